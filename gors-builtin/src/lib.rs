@@ -23,6 +23,8 @@ pub type int16 = i16;
 pub type int32 = i32;
 pub type int64 = i64;
 pub type rune = i32;
+/// Go strings are Rust strings, so they only hold valid UTF-8: bytes that
+/// are not convert to an empty string, `string([]byte{0xff})` being `""`.
 pub type string = std::string::String;
 pub type uint = usize;
 pub type uint8 = u8;
@@ -35,7 +37,7 @@ pub trait comparable {}
 
 impl<T: Eq> comparable for T {}
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct GorsInterfaceKey {
     type_name: &'static str,
     data: usize,
@@ -77,10 +79,16 @@ pub trait GorsAnyComparable: Send + Sync {
     fn clone_raw_any_send(&self) -> Box<dyn Any + Send>;
     fn clone_raw_any_send_sync(&self) -> Box<dyn Any + Send + Sync>;
     fn eq_any(&self, other: &dyn Any) -> bool;
+    fn hash_any(&self, state: &mut dyn std::hash::Hasher);
 }
 
+/// A comparable value in an interface, with the function hashing it: the
+/// hash of a value whose type is not `GorsHash` is left to its type.
 #[derive(Clone)]
-pub struct GorsComparableAny<T: Any + Clone + PartialEq + Send + Sync>(pub T);
+pub struct GorsComparableAny<T: Any + Clone + PartialEq + Send + Sync>(
+    pub T,
+    pub fn(&T, &mut dyn std::hash::Hasher),
+);
 
 impl<T> GorsAnyComparable for GorsComparableAny<T>
 where
@@ -91,17 +99,15 @@ where
     }
 
     fn clone_comparable_any(&self) -> Box<dyn Any> {
-        Box::new(Box::new(Self(self.0.clone())) as Box<dyn GorsAnyComparable>) as Box<dyn Any>
+        Box::new(Box::new(self.clone()) as Box<dyn GorsAnyComparable>) as Box<dyn Any>
     }
 
     fn clone_comparable_any_send(&self) -> Box<dyn Any + Send> {
-        Box::new(Box::new(Self(self.0.clone())) as Box<dyn GorsAnyComparable>)
-            as Box<dyn Any + Send>
+        Box::new(Box::new(self.clone()) as Box<dyn GorsAnyComparable>) as Box<dyn Any + Send>
     }
 
     fn clone_comparable_any_send_sync(&self) -> Box<dyn Any + Send + Sync> {
-        Box::new(Box::new(Self(self.0.clone())) as Box<dyn GorsAnyComparable>)
-            as Box<dyn Any + Send + Sync>
+        Box::new(Box::new(self.clone()) as Box<dyn GorsAnyComparable>) as Box<dyn Any + Send + Sync>
     }
 
     fn clone_raw_any(&self) -> Box<dyn Any> {
@@ -121,30 +127,216 @@ where
             .and_then(|other| other.downcast_ref::<T>())
             .is_some_and(|other| self.0 == *other)
     }
+
+    fn hash_any(&self, state: &mut dyn std::hash::Hasher) {
+        (self.1)(&self.0, &mut *state);
+    }
 }
 
-pub fn box_any_comparable<T>(value: T) -> Box<dyn Any>
+pub fn box_any_comparable<T>(value: T) -> GorsAny
 where
     T: Any + Clone + PartialEq + Send + Sync,
 {
-    Box::new(Box::new(GorsComparableAny(value)) as Box<dyn GorsAnyComparable>) as Box<dyn Any>
+    box_comparable(value, |_, _| {})
 }
 
 pub fn box_any_comparable_send_sync<T>(value: T) -> Box<dyn Any + Send + Sync>
 where
     T: Any + Clone + PartialEq + Send + Sync,
 {
-    Box::new(Box::new(GorsComparableAny(value)) as Box<dyn GorsAnyComparable>)
+    Box::new(Box::new(GorsComparableAny(value, |_, _| {})) as Box<dyn GorsAnyComparable>)
         as Box<dyn Any + Send + Sync>
 }
 
+fn box_comparable<T>(value: T, hash: fn(&T, &mut dyn std::hash::Hasher)) -> GorsAny
+where
+    T: Any + Clone + PartialEq + Send + Sync,
+{
+    let mut slot = Some(value);
+    if let Some(inline) = take_inline_any(&mut slot) {
+        return inline;
+    }
+    match slot {
+        Some(value) => GorsAny::Boxed(Box::new(
+            Box::new(GorsComparableAny(value, hash)) as Box<dyn GorsAnyComparable>
+        )),
+        None => GorsAny::Nil,
+    }
+}
+
+/// Comparable Go values, hashed consistently with `==` so that interface
+/// values holding them hash by value.
+pub trait GorsHash {
+    fn gors_hash(&self, state: &mut dyn std::hash::Hasher);
+}
+
+macro_rules! gors_hash_with_hash {
+    ($($ty:ty),*) => {
+        $(
+            impl GorsHash for $ty {
+                fn gors_hash(&self, mut state: &mut dyn std::hash::Hasher) {
+                    self.hash(&mut state);
+                }
+            }
+        )*
+    };
+}
+
+gors_hash_with_hash!(
+    bool,
+    isize,
+    i8,
+    i16,
+    i32,
+    i64,
+    usize,
+    u8,
+    u16,
+    u32,
+    u64,
+    std::string::String,
+    &'static str,
+    GorsAny
+);
+
+// 0 and -0 are equal, so floats hash with the sign of zero dropped.
+impl GorsHash for f32 {
+    fn gors_hash(&self, state: &mut dyn std::hash::Hasher) {
+        state.write_u32((self + 0.0).to_bits());
+    }
+}
+
+impl GorsHash for f64 {
+    fn gors_hash(&self, state: &mut dyn std::hash::Hasher) {
+        state.write_u64((self + 0.0).to_bits());
+    }
+}
+
+impl<T: GorsHash, const N: usize> GorsHash for [T; N] {
+    fn gors_hash(&self, state: &mut dyn std::hash::Hasher) {
+        for value in self {
+            value.gors_hash(&mut *state);
+        }
+    }
+}
+
+impl<T> GorsHash for GorsPtr<T> {
+    fn gors_hash(&self, mut state: &mut dyn std::hash::Hasher) {
+        self.interface_key().hash(&mut state);
+    }
+}
+
+impl<T> GorsHash for Chan<T> {
+    fn gors_hash(&self, state: &mut dyn std::hash::Hasher) {
+        state.write_usize(
+            self.inner
+                .as_ref()
+                .map_or(0, |inner| Arc::as_ptr(inner).addr()),
+        );
+    }
+}
+
+/// A field of a struct hashing its fields.
+///
+/// `(&HashField(&self.f)).hash_field(state)` hashes the field if its type
+/// is `GorsHash`, with `HashWriteField`, and skips it otherwise, with
+/// `HashSkipField`. Skipping keeps hashes consistent with `==`.
+pub struct HashField<'a, T>(pub &'a T);
+
+pub trait HashWriteField {
+    fn hash_field(&self, state: &mut dyn std::hash::Hasher);
+}
+
+impl<T: GorsHash> HashWriteField for HashField<'_, T> {
+    fn hash_field(&self, state: &mut dyn std::hash::Hasher) {
+        self.0.gors_hash(&mut *state);
+    }
+}
+
+pub trait HashSkipField {
+    fn hash_field(&self, state: &mut dyn std::hash::Hasher);
+}
+
+impl<T> HashSkipField for &HashField<'_, T> {
+    fn hash_field(&self, _state: &mut dyn std::hash::Hasher) {}
+}
+
+/// A comparable value to store in an interface.
+///
+/// `(&ComparableProbe::new(value)).box_any_comparable()` stores a value
+/// hashing with its `GorsHash` impl when it has one, with
+/// `ComparableHashed`, and one hashing by type only otherwise, with
+/// `ComparableUnhashed`.
+pub struct ComparableProbe<T>(std::cell::Cell<Option<T>>);
+
+impl<T> ComparableProbe<T> {
+    pub const fn new(value: T) -> Self {
+        Self(std::cell::Cell::new(Some(value)))
+    }
+}
+
+pub trait ComparableHashed {
+    fn box_any_comparable(&self) -> GorsAny;
+    fn box_any_comparable_send_sync(&self) -> Box<dyn Any + Send + Sync>;
+}
+
+impl<T> ComparableHashed for ComparableProbe<T>
+where
+    T: GorsHash + Any + Clone + PartialEq + Send + Sync,
+{
+    fn box_any_comparable(&self) -> GorsAny {
+        self.0.take().map_or(GorsAny::Nil, |value| {
+            box_comparable(value, |value, state| value.gors_hash(&mut *state))
+        })
+    }
+
+    fn box_any_comparable_send_sync(&self) -> Box<dyn Any + Send + Sync> {
+        match self.0.take() {
+            Some(value) => Box::new(Box::new(GorsComparableAny(value, |value, state| {
+                value.gors_hash(&mut *state);
+            })) as Box<dyn GorsAnyComparable>),
+            None => Box::new(()),
+        }
+    }
+}
+
+pub trait ComparableUnhashed {
+    fn box_any_comparable(&self) -> GorsAny;
+    fn box_any_comparable_send_sync(&self) -> Box<dyn Any + Send + Sync>;
+}
+
+impl<T> ComparableUnhashed for &ComparableProbe<T>
+where
+    T: Any + Clone + PartialEq + Send + Sync,
+{
+    fn box_any_comparable(&self) -> GorsAny {
+        self.0.take().map_or(GorsAny::Nil, box_any_comparable)
+    }
+
+    fn box_any_comparable_send_sync(&self) -> Box<dyn Any + Send + Sync> {
+        match self.0.take() {
+            Some(value) => box_any_comparable_send_sync(value),
+            None => Box::new(()),
+        }
+    }
+}
+
 fn comparable_any(value: &dyn Any) -> Option<&dyn GorsAnyComparable> {
-    value
+    interface_payload(value)
         .downcast_ref::<Box<dyn GorsAnyComparable>>()
         .map(|value| &**value)
 }
 
+/// The dynamic value of an interface passed as a `GorsAny` rather than
+/// through its `Deref`.
+fn interface_payload(value: &dyn Any) -> &dyn Any {
+    value
+        .downcast_ref::<GorsAny>()
+        .map_or(value, GorsAny::payload)
+}
+
 fn comparable_any_payload(value: &dyn Any) -> Option<&dyn Any> {
+    let value = interface_payload(value);
     comparable_any(value)
         .map(GorsAnyComparable::as_any)
         .or(Some(value))
@@ -158,6 +350,267 @@ pub fn any_downcast_ref<T: Any>(value: &dyn Any) -> Option<&T> {
     comparable_any_payload(value).and_then(|value| value.downcast_ref::<T>())
 }
 
+/// Moves the value out of `slot`, which holds an `Option` of unknown type,
+/// if it is a `T`.
+fn take_any<T: Any>(slot: &mut dyn Any) -> Option<T> {
+    slot.downcast_mut::<Option<T>>().and_then(Option::take)
+}
+
+/// Moves a value of a basic kind out of `slot`, an `Option` of unknown
+/// type, into an inline `GorsAny` variant. Not an associated function:
+/// builtin pruning reads `GorsAny::f(value)` as a trait call on `value`.
+fn take_inline_any(slot: &mut dyn Any) -> Option<GorsAny> {
+    macro_rules! inline_if {
+        ($($variant:ident($ty:ty)),*) => {
+            $(
+                if let Some(value) = take_any::<$ty>(slot) {
+                    return Some(GorsAny::$variant(value));
+                }
+            )*
+        };
+    }
+    inline_if!(
+        Bool(bool),
+        Int(isize),
+        Int8(i8),
+        Int16(i16),
+        Int32(i32),
+        Int64(i64),
+        Uint(usize),
+        Uint8(u8),
+        Uint16(u16),
+        Uint32(u32),
+        Uint64(u64),
+        Float32(f32),
+        Float64(f64),
+        String(std::string::String)
+    );
+    None
+}
+
+/// The value of an interface type.
+///
+/// The basic kinds are held inline, so storing one in an interface does not
+/// allocate and comparing two of them is a match on the variants. The other
+/// dynamic types are boxed, comparable ones behind a `GorsAnyComparable`.
+#[derive(Default)]
+pub enum GorsAny {
+    #[default]
+    Nil,
+    Bool(bool),
+    Int(isize),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Uint(usize),
+    Uint8(u8),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    Float32(f32),
+    Float64(f64),
+    String(std::string::String),
+    Boxed(Box<dyn Any>),
+}
+
+impl GorsAny {
+    /// Store `value` in an interface. Nil stays nil and interface values are
+    /// passed through rather than nested.
+    pub fn new<T: Any>(value: T) -> Self {
+        let mut value = Some(value);
+        if let Some(inline) = take_inline_any(&mut value) {
+            return inline;
+        }
+        if let Some(value) = take_any::<Self>(&mut value) {
+            return value;
+        }
+        if let Some(value) = take_any::<Box<dyn Any>>(&mut value) {
+            return Self::from_box(value);
+        }
+        if take_any::<()>(&mut value).is_some() {
+            return Self::Nil;
+        }
+        match value {
+            Some(value) => Self::Boxed(Box::new(value)),
+            None => Self::Nil,
+        }
+    }
+
+    pub fn from_box(value: Box<dyn Any>) -> Self {
+        if value.is::<()>() {
+            return Self::Nil;
+        }
+        // Basic values are inline, so that they hash the same however they
+        // were boxed.
+        if let Some(inline) = comparable_any_payload(value.as_ref()).and_then(Self::inline_ref) {
+            return inline;
+        }
+        match value.downcast::<Self>() {
+            Ok(value) => *value,
+            Err(value) => Self::Boxed(value),
+        }
+    }
+
+    pub const fn is_nil(&self) -> bool {
+        matches!(self, Self::Nil)
+    }
+
+    /// The dynamic value, as `Box<dyn Any>::as_ref` gives it.
+    #[allow(clippy::should_implement_trait)]
+    pub fn as_ref(&self) -> &dyn Any {
+        self.payload()
+    }
+
+    /// The dynamic value, `()` for nil.
+    pub fn payload(&self) -> &dyn Any {
+        match self {
+            Self::Nil => &(),
+            Self::Bool(value) => value,
+            Self::Int(value) => value,
+            Self::Int8(value) => value,
+            Self::Int16(value) => value,
+            Self::Int32(value) => value,
+            Self::Int64(value) => value,
+            Self::Uint(value) => value,
+            Self::Uint8(value) => value,
+            Self::Uint16(value) => value,
+            Self::Uint32(value) => value,
+            Self::Uint64(value) => value,
+            Self::Float32(value) => value,
+            Self::Float64(value) => value,
+            Self::String(value) => value,
+            Self::Boxed(value) => value.as_ref(),
+        }
+    }
+
+    fn inline_ref(value: &dyn Any) -> Option<Self> {
+        macro_rules! inline_if {
+            ($($variant:ident($ty:ty)),*) => {
+                $(
+                    if let Some(value) = value.downcast_ref::<$ty>() {
+                        return Some(Self::$variant(value.clone()));
+                    }
+                )*
+            };
+        }
+        inline_if!(
+            Bool(bool),
+            Int(isize),
+            Int8(i8),
+            Int16(i16),
+            Int32(i32),
+            Int64(i64),
+            Uint(usize),
+            Uint8(u8),
+            Uint16(u16),
+            Uint32(u32),
+            Uint64(u64),
+            Float32(f32),
+            Float64(f64),
+            String(std::string::String)
+        );
+        None
+    }
+
+    fn into_send(self) -> Box<dyn Any + Send> {
+        match self {
+            Self::Boxed(value) => any_box_to_send(value),
+            value => clone_any_send_ref(value.payload()),
+        }
+    }
+}
+
+impl Clone for GorsAny {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Nil => Self::Nil,
+            Self::Bool(value) => Self::Bool(*value),
+            Self::Int(value) => Self::Int(*value),
+            Self::Int8(value) => Self::Int8(*value),
+            Self::Int16(value) => Self::Int16(*value),
+            Self::Int32(value) => Self::Int32(*value),
+            Self::Int64(value) => Self::Int64(*value),
+            Self::Uint(value) => Self::Uint(*value),
+            Self::Uint8(value) => Self::Uint8(*value),
+            Self::Uint16(value) => Self::Uint16(*value),
+            Self::Uint32(value) => Self::Uint32(*value),
+            Self::Uint64(value) => Self::Uint64(*value),
+            Self::Float32(value) => Self::Float32(*value),
+            Self::Float64(value) => Self::Float64(*value),
+            Self::String(value) => Self::String(value.clone()),
+            Self::Boxed(value) => clone_any_ref(value.as_ref()),
+        }
+    }
+}
+
+impl PartialEq for GorsAny {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Nil, Self::Nil) => true,
+            (Self::Bool(left), Self::Bool(right)) => left == right,
+            (Self::Int(left), Self::Int(right)) => left == right,
+            (Self::Int8(left), Self::Int8(right)) => left == right,
+            (Self::Int16(left), Self::Int16(right)) => left == right,
+            (Self::Int32(left), Self::Int32(right)) => left == right,
+            (Self::Int64(left), Self::Int64(right)) => left == right,
+            (Self::Uint(left), Self::Uint(right)) => left == right,
+            (Self::Uint8(left), Self::Uint8(right)) => left == right,
+            (Self::Uint16(left), Self::Uint16(right)) => left == right,
+            (Self::Uint32(left), Self::Uint32(right)) => left == right,
+            (Self::Uint64(left), Self::Uint64(right)) => left == right,
+            (Self::Float32(left), Self::Float32(right)) => left == right,
+            (Self::Float64(left), Self::Float64(right)) => left == right,
+            (Self::String(left), Self::String(right)) => left == right,
+            (Self::Boxed(_), _) | (_, Self::Boxed(_)) => any_eq(self.payload(), other.payload()),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for GorsAny {}
+
+/// Hashes consistently with `==`: a dynamic type which is not comparable
+/// panics, as `GorsInterfaceKey::non_comparable` does.
+impl std::hash::Hash for GorsAny {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        // 0 and -0 are equal, so floats hash with the sign of zero dropped.
+        match self {
+            Self::Nil => {}
+            Self::Bool(value) => value.hash(state),
+            Self::Int(value) => value.hash(state),
+            Self::Int8(value) => value.hash(state),
+            Self::Int16(value) => value.hash(state),
+            Self::Int32(value) => value.hash(state),
+            Self::Int64(value) => value.hash(state),
+            Self::Uint(value) => value.hash(state),
+            Self::Uint8(value) => value.hash(state),
+            Self::Uint16(value) => value.hash(state),
+            Self::Uint32(value) => value.hash(state),
+            Self::Uint64(value) => value.hash(state),
+            Self::Float32(value) => (value + 0.0).to_bits().hash(state),
+            Self::Float64(value) => (value + 0.0).to_bits().hash(state),
+            Self::String(value) => value.hash(state),
+            Self::Boxed(value) => {
+                let Some(comparable) = comparable_any(value.as_ref()) else {
+                    panic_value("hash of unhashable type");
+                };
+                comparable.as_any().type_id().hash(state);
+                comparable.hash_any(&mut *state);
+            }
+        }
+    }
+}
+
+impl std::ops::Deref for GorsAny {
+    type Target = dyn Any;
+
+    fn deref(&self) -> &dyn Any {
+        self.payload()
+    }
+}
+
 pub trait error: Send + Sync {
     fn __gors_as_any(&self) -> Option<&dyn Any>;
     fn __gors_interface_key(&self) -> GorsInterfaceKey;
@@ -198,6 +651,229 @@ impl error for __GorsStringError {
     }
 }
 
+/// A Go `runtime.Error`: the value the runtime panics with on a failed
+/// bounds check, a division by zero, a nil dereference...
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeError(std::string::String);
+
+impl RuntimeError {
+    /// An error reported with Go's `runtime error: ` prefix.
+    pub fn new(message: &str) -> Self {
+        Self(format!("runtime error: {message}"))
+    }
+
+    /// An error reported as is, like the misuses of channels and nil maps.
+    pub fn plain(message: &str) -> Self {
+        Self(message.to_string())
+    }
+
+    /// `index` out of range. Negative Go indexes wrapped around when
+    /// converted to `usize`.
+    pub fn index(index: usize, len: usize) -> Self {
+        match isize::try_from(index) {
+            Ok(index) => Self::new(&format!("index out of range [{index}] with length {len}")),
+            Err(_) => Self::new(&format!("index out of range [{}]", index.cast_signed())),
+        }
+    }
+
+    /// `s[:high]` beyond the capacity of a slice.
+    pub fn slice_high(high: usize, cap: usize) -> Self {
+        Self::new(&format!(
+            "slice bounds out of range [:{high}] with capacity {cap}"
+        ))
+    }
+
+    /// `s[:high]` beyond the length of a string.
+    pub fn string_high(high: usize, len: usize) -> Self {
+        Self::new(&format!(
+            "slice bounds out of range [:{high}] with length {len}"
+        ))
+    }
+
+    /// `s[low:high]` with `low` above `high`.
+    pub fn slice_low(low: usize, high: usize) -> Self {
+        Self::new(&format!("slice bounds out of range [{low}:{high}]"))
+    }
+
+    /// The failed type assertion `x.(asserted)` of the `value` an `interface`
+    /// holds. Only dynamic types of basic kinds can be named.
+    pub fn type_assertion(interface: &str, value: Option<&dyn Any>, asserted: &str) -> Self {
+        let value = value
+            .and_then(comparable_any_payload)
+            .filter(|value| !value.is::<()>());
+        let Some(value) = value else {
+            return Self::plain(&format!(
+                "interface conversion: {interface} is nil, not {asserted}"
+            ));
+        };
+        match basic_type_name(value) {
+            Some(dynamic) => Self::plain(&format!(
+                "interface conversion: {interface} is {dynamic}, not {asserted}"
+            )),
+            None => Self::plain(&format!(
+                "interface conversion: {interface} is not {asserted}"
+            )),
+        }
+    }
+
+    pub fn divide() -> Self {
+        Self::new("integer divide by zero")
+    }
+
+    pub fn nil_dereference() -> Self {
+        Self::new("invalid memory address or nil pointer dereference")
+    }
+
+    pub fn nil_map() -> Self {
+        Self::plain("assignment to entry in nil map")
+    }
+
+    /// The `*runtime.PanicNilError` of `panic(nil)`.
+    pub fn panic_nil() -> Self {
+        Self::plain("panic called with nil argument")
+    }
+
+    pub fn RuntimeError(&self) {}
+}
+
+impl error for RuntimeError {
+    fn __gors_as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn __gors_interface_key(&self) -> GorsInterfaceKey {
+        GorsInterfaceKey::non_comparable()
+    }
+
+    fn Error(&self) -> std::string::String {
+        self.0.clone()
+    }
+}
+
+/// The Go name of the type of `value`, if it is a basic one.
+fn basic_type_name(value: &dyn Any) -> Option<&'static str> {
+    let names = [
+        (TypeId::of::<bool>(), "bool"),
+        (TypeId::of::<isize>(), "int"),
+        (TypeId::of::<i8>(), "int8"),
+        (TypeId::of::<i16>(), "int16"),
+        (TypeId::of::<i32>(), "int32"),
+        (TypeId::of::<i64>(), "int64"),
+        (TypeId::of::<usize>(), "uint"),
+        (TypeId::of::<u8>(), "uint8"),
+        (TypeId::of::<u16>(), "uint16"),
+        (TypeId::of::<u32>(), "uint32"),
+        (TypeId::of::<u64>(), "uint64"),
+        (TypeId::of::<f32>(), "float32"),
+        (TypeId::of::<f64>(), "float64"),
+        (TypeId::of::<std::string::String>(), "string"),
+    ];
+    let type_id = value.type_id();
+    names
+        .into_iter()
+        .find_map(|(id, name)| (id == type_id).then_some(name))
+}
+
+/// Panic with a run-time error, as an `error` value.
+pub fn panic_runtime_error(error: RuntimeError) -> ! {
+    r#panic(Box::new(error) as Box<dyn error>)
+}
+
+/// The bounds of a Go slice expression `s[low:high]`, indexing slices and
+/// strings with Go's run-time checks instead of Rust's.
+pub struct SliceBounds<R>(R);
+
+#[inline]
+pub const fn bounds<R: std::ops::RangeBounds<usize>>(range: R) -> SliceBounds<R> {
+    SliceBounds(range)
+}
+
+impl<R: std::ops::RangeBounds<usize>> SliceBounds<R> {
+    /// The range of these bounds in a sequence of `len` elements; a string
+    /// reports its length where a slice reports its capacity.
+    fn checked(&self, len: usize, is_string: bool) -> std::ops::Range<usize> {
+        use std::ops::Bound;
+
+        let high = match self.0.end_bound() {
+            Bound::Included(high) => high.saturating_add(1),
+            Bound::Excluded(high) => *high,
+            Bound::Unbounded => len,
+        };
+        let low = match self.0.start_bound() {
+            Bound::Included(low) => *low,
+            Bound::Excluded(low) => low.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        if high > len {
+            panic_runtime_error(if is_string {
+                RuntimeError::string_high(high, len)
+            } else {
+                RuntimeError::slice_high(high, len)
+            });
+        }
+        if low > high {
+            panic_runtime_error(RuntimeError::slice_low(low, high));
+        }
+        low..high
+    }
+}
+
+impl<T, R: std::ops::RangeBounds<usize>> std::ops::Index<SliceBounds<R>> for [T] {
+    type Output = [T];
+
+    fn index(&self, bounds: SliceBounds<R>) -> &[T] {
+        self.get(bounds.checked(self.len(), false))
+            .unwrap_or_default()
+    }
+}
+
+impl<T, R: std::ops::RangeBounds<usize>> std::ops::IndexMut<SliceBounds<R>> for [T] {
+    fn index_mut(&mut self, bounds: SliceBounds<R>) -> &mut [T] {
+        let range = bounds.checked(self.len(), false);
+        self.get_mut(range).unwrap_or_default()
+    }
+}
+
+impl<T, R: std::ops::RangeBounds<usize>> std::ops::Index<SliceBounds<R>> for Vec<T> {
+    type Output = [T];
+
+    fn index(&self, bounds: SliceBounds<R>) -> &[T] {
+        std::ops::Index::index(self.as_slice(), bounds)
+    }
+}
+
+impl<T, R: std::ops::RangeBounds<usize>> std::ops::IndexMut<SliceBounds<R>> for Vec<T> {
+    fn index_mut(&mut self, bounds: SliceBounds<R>) -> &mut [T] {
+        std::ops::IndexMut::index_mut(self.as_mut_slice(), bounds)
+    }
+}
+
+impl<R: std::ops::RangeBounds<usize>> std::ops::Index<SliceBounds<R>> for str {
+    type Output = Self;
+
+    fn index(&self, bounds: SliceBounds<R>) -> &Self {
+        self.get(bounds.checked(self.len(), true))
+            .unwrap_or_default()
+    }
+}
+
+impl<R: std::ops::RangeBounds<usize>> std::ops::Index<SliceBounds<R>> for std::string::String {
+    type Output = str;
+
+    fn index(&self, bounds: SliceBounds<R>) -> &str {
+        std::ops::Index::index(self.as_str(), bounds)
+    }
+}
+
+/// `panic(nil)`: panic with a `*runtime.PanicNilError`, or with nil like
+/// before Go 1.21 under `GODEBUG=panicnil=1`.
+pub fn panic_nil() -> ! {
+    if godebug("panicnil").as_deref() == Some("1") {
+        r#panic(())
+    }
+    panic_runtime_error(RuntimeError::panic_nil())
+}
+
 impl Default for Box<dyn error> {
     fn default() -> Self {
         Box::new(__GorsNooperror)
@@ -249,13 +925,17 @@ pub fn error_string(value: &dyn error) -> std::string::String {
     }
 }
 
-pub fn clone_any(value: &dyn Any) -> Box<dyn Any> {
+pub fn clone_any(value: &dyn Any) -> GorsAny {
     clone_any_ref(value)
 }
 
-pub fn clone_any_ref(value: &dyn Any) -> Box<dyn Any> {
+pub fn clone_any_ref(value: &dyn Any) -> GorsAny {
+    if let Some(v) = value.downcast_ref::<GorsAny>() {
+        return v.clone();
+    }
     if let Some(value) = comparable_any(value) {
-        return value.clone_comparable_any();
+        return GorsAny::inline_ref(value.as_any())
+            .unwrap_or_else(|| GorsAny::Boxed(value.clone_comparable_any()));
     }
     if let Some(v) = value.downcast_ref::<Box<dyn Any>>() {
         return clone_any_ref(v.as_ref());
@@ -266,39 +946,31 @@ pub fn clone_any_ref(value: &dyn Any) -> Box<dyn Any> {
     if let Some(v) = value.downcast_ref::<Box<dyn Any + Send + Sync>>() {
         return clone_any_ref(v.as_ref());
     }
+    if let Some(inline) = GorsAny::inline_ref(value) {
+        return inline;
+    }
 
     macro_rules! clone_if {
         ($ty:ty) => {
             if let Some(v) = value.downcast_ref::<$ty>() {
-                return Box::new(v.clone()) as Box<dyn Any>;
+                return GorsAny::Boxed(Box::new(v.clone()));
             }
         };
     }
 
     clone_if!(GorsReflectValue);
-    clone_if!(std::string::String);
     clone_if!(&'static str);
-    clone_if!(bool);
-    clone_if!(isize);
-    clone_if!(i8);
-    clone_if!(i16);
-    clone_if!(i32);
-    clone_if!(i64);
-    clone_if!(usize);
-    clone_if!(u8);
-    clone_if!(u16);
-    clone_if!(u32);
-    clone_if!(u64);
-    clone_if!(f32);
-    clone_if!(f64);
     clone_if!(Vec<u8>);
     clone_if!(Vec<std::string::String>);
     clone_if!(Box<dyn error>);
 
-    Box::new(())
+    GorsAny::Nil
 }
 
 pub fn clone_any_send_ref(value: &dyn Any) -> Box<dyn Any + Send> {
+    if let Some(v) = value.downcast_ref::<GorsAny>() {
+        return clone_any_send_ref(v.payload());
+    }
     if let Some(value) = comparable_any(value) {
         return value.clone_comparable_any_send();
     }
@@ -344,6 +1016,9 @@ pub fn clone_any_send_ref(value: &dyn Any) -> Box<dyn Any + Send> {
 }
 
 pub fn clone_any_send_sync(value: &dyn Any) -> Box<dyn Any + Send + Sync> {
+    if let Some(v) = value.downcast_ref::<GorsAny>() {
+        return clone_any_send_sync(v.payload());
+    }
     if let Some(value) = comparable_any(value) {
         return value.clone_comparable_any_send_sync();
     }
@@ -463,8 +1138,8 @@ fn lock_reflect_ops(
     ops.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
-pub fn reflect_slice_any<T: 'static + Send>(slice: Arc<Mutex<Vec<T>>>) -> Box<dyn Any> {
-    Box::new(GorsReflectValue::slice(slice)) as Box<dyn Any>
+pub fn reflect_slice_any<T: 'static + Send>(slice: Arc<Mutex<Vec<T>>>) -> GorsAny {
+    GorsAny::Boxed(Box::new(GorsReflectValue::slice(slice)))
 }
 
 pub fn reflect_value_kind(value: &dyn Any) -> __GorsReflectKind {
@@ -513,6 +1188,9 @@ pub fn reflect_value_swapper(value: &dyn Any) -> GorsReflectSwapper {
 }
 
 pub fn reflect_type_comparable(value: &dyn Any) -> bool {
+    if let Some(value) = value.downcast_ref::<GorsAny>() {
+        return reflect_type_comparable(value.payload());
+    }
     if let Some(value) = value.downcast_ref::<Box<dyn Any>>() {
         return reflect_type_comparable(value.as_ref());
     }
@@ -529,21 +1207,49 @@ pub fn reflect_type_comparable(value: &dyn Any) -> bool {
         || value.is::<Vec<u8>>()
         || value.is::<Vec<std::string::String>>()
         || value.is::<Vec<Box<dyn Any>>>()
+        || value.is::<Vec<GorsAny>>()
     {
         return false;
     }
     true
 }
 
+/// The dynamic value of an interface, out of the `GorsAny` or the boxes it
+/// may be held in.
+fn interface_value(value: &dyn Any) -> &dyn Any {
+    let mut value = value;
+    loop {
+        if let Some(inner) = value.downcast_ref::<GorsAny>() {
+            value = inner.payload();
+        } else if let Some(inner) = value.downcast_ref::<Box<dyn Any>>() {
+            value = inner.as_ref();
+        } else if let Some(inner) = value.downcast_ref::<Box<dyn Any + Send>>() {
+            value = inner.as_ref();
+        } else if let Some(inner) = value.downcast_ref::<Box<dyn Any + Send + Sync>>() {
+            value = inner.as_ref();
+        } else {
+            return value;
+        }
+    }
+}
+
 pub fn any_eq(left: &dyn Any, right: &dyn Any) -> bool {
+    if let (Some(left), Some(right)) = (
+        left.downcast_ref::<GorsAny>(),
+        right.downcast_ref::<GorsAny>(),
+    ) {
+        return left == right;
+    }
+    let left = interface_value(left);
+    let right = interface_value(right);
     if interface_is_nil(left) || interface_is_nil(right) {
         return interface_is_nil(left) && interface_is_nil(right);
     }
-    if let Some(left) = comparable_any(left) {
-        return left.eq_any(right);
+    if let Some(comparable) = comparable_any(left) {
+        return comparable.eq_any(right);
     }
-    if let Some(right) = comparable_any(right) {
-        return right.eq_any(left);
+    if let Some(comparable) = comparable_any(right) {
+        return comparable.eq_any(left);
     }
 
     macro_rules! eq_if {
@@ -1031,24 +1737,560 @@ impl<T> PartialEq for GorsPtr<T> {
 
 impl<T> Eq for GorsPtr<T> {}
 
-static RECOVER_PAYLOAD: Mutex<Option<Box<dyn Any + Send>>> = Mutex::new(None);
+/// Values which may hold `GorsPtr`s, which the cycle collector follows to
+/// find the pointers between the objects it tracks.
+pub trait GorsTrace {
+    fn trace(&self, tracer: &mut GcTracer);
+}
 
-fn recover_payload_lock() -> MutexGuard<'static, Option<Box<dyn Any + Send>>> {
-    match RECOVER_PAYLOAD.lock() {
-        Ok(guard) => guard,
-        Err(poisoned) => poisoned.into_inner(),
+/// The addresses of the objects the values traced point to.
+#[derive(Default)]
+pub struct GcTracer {
+    edges: Vec<usize>,
+}
+
+impl<T> GorsTrace for GorsPtr<T> {
+    fn trace(&self, tracer: &mut GcTracer) {
+        if self.inner.is_some() {
+            tracer.edges.push(self.ptr_id().addr());
+        }
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum __GorsReflectKind {
-    Invalid,
-    Bool,
-    Int,
-    Int8,
-    Int16,
-    Int32,
-    Int64,
+impl<T: GorsTrace> GorsTrace for Vec<T> {
+    fn trace(&self, tracer: &mut GcTracer) {
+        for value in self {
+            value.trace(tracer);
+        }
+    }
+}
+
+impl<T: GorsTrace, const N: usize> GorsTrace for [T; N] {
+    fn trace(&self, tracer: &mut GcTracer) {
+        for value in self {
+            value.trace(tracer);
+        }
+    }
+}
+
+impl<T: GorsTrace> GorsTrace for Option<T> {
+    fn trace(&self, tracer: &mut GcTracer) {
+        if let Some(value) = self {
+            value.trace(tracer);
+        }
+    }
+}
+
+impl<T: GorsTrace> GorsTrace for Box<T> {
+    fn trace(&self, tracer: &mut GcTracer) {
+        (**self).trace(tracer);
+    }
+}
+
+impl<K, V: GorsTrace> GorsTrace for GorsMap<K, V> {
+    fn trace(&self, tracer: &mut GcTracer) {
+        for value in self.entries.values() {
+            value.trace(tracer);
+        }
+    }
+}
+
+/// A field of a struct tracing its fields.
+///
+/// `(&GcField(&self.f)).trace_field(tracer)` traces the field if its type
+/// is `GorsTrace`, with `GcTraceField`, and skips it otherwise, with
+/// `GcSkipField`.
+pub struct GcField<'a, T>(pub &'a T);
+
+pub trait GcTraceField {
+    fn trace_field(&self, tracer: &mut GcTracer);
+}
+
+impl<T: GorsTrace> GcTraceField for GcField<'_, T> {
+    fn trace_field(&self, tracer: &mut GcTracer) {
+        self.0.trace(tracer);
+    }
+}
+
+pub trait GcSkipField {
+    fn trace_field(&self, tracer: &mut GcTracer);
+}
+
+impl<T> GcSkipField for &GcField<'_, T> {
+    fn trace_field(&self, _tracer: &mut GcTracer) {}
+}
+
+/// A value to allocate a `GorsPtr` for.
+///
+/// `(&GcProbe::new(value)).gors_ptr()` allocates an object the cycle
+/// collector tracks when the type of the value allows it, with
+/// `GcTracedPtr`, and an untracked one otherwise, with `GcUntracedPtr`.
+pub struct GcProbe<T>(std::cell::Cell<Option<T>>);
+
+impl<T> GcProbe<T> {
+    pub const fn new(value: T) -> Self {
+        Self(std::cell::Cell::new(Some(value)))
+    }
+}
+
+pub trait GcTracedPtr<T> {
+    fn gors_ptr(&self) -> GorsPtr<T>;
+}
+
+impl<T: GorsTrace + Default + Send + 'static> GcTracedPtr<T> for GcProbe<T> {
+    fn gors_ptr(&self) -> GorsPtr<T> {
+        GorsPtr::new_traced(self.0.take().unwrap_or_default())
+    }
+}
+
+pub trait GcUntracedPtr<T> {
+    fn gors_ptr(&self) -> GorsPtr<T>;
+}
+
+impl<T> GcUntracedPtr<T> for &GcProbe<T> {
+    fn gors_ptr(&self) -> GorsPtr<T> {
+        self.0.take().map_or_else(GorsPtr::nil, GorsPtr::new)
+    }
+}
+
+/// An object the cycle collector tracks.
+trait GcObject: Send + Sync {
+    /// Trace the pointers of the object, unless it is locked.
+    fn trace_object(&self, tracer: &mut GcTracer) -> bool;
+    /// Drop the value of the object, which frees the garbage cycles it is
+    /// part of.
+    fn clear_object(&self);
+}
+
+impl<T: GorsTrace + Default + Send> GcObject for Mutex<T> {
+    fn trace_object(&self, tracer: &mut GcTracer) -> bool {
+        match self.try_lock() {
+            Ok(value) => value.trace(tracer),
+            Err(std::sync::TryLockError::Poisoned(poisoned)) => poisoned.into_inner().trace(tracer),
+            Err(std::sync::TryLockError::WouldBlock) => return false,
+        }
+        true
+    }
+
+    fn clear_object(&self) {
+        let value = match self.try_lock() {
+            Ok(mut value) => std::mem::take(&mut *value),
+            Err(std::sync::TryLockError::Poisoned(poisoned)) => {
+                std::mem::take(&mut *poisoned.into_inner())
+            }
+            Err(std::sync::TryLockError::WouldBlock) => return,
+        };
+        drop(value);
+    }
+}
+
+/// The objects the cycle collector tracks, and the number of them to
+/// collect at next.
+struct GcHeap {
+    objects: Vec<std::sync::Weak<dyn GcObject>>,
+    next_collection: usize,
+}
+
+const GC_MIN_OBJECTS: usize = 4096;
+
+static GC_HEAP: Mutex<GcHeap> = Mutex::new(GcHeap {
+    objects: Vec::new(),
+    next_collection: GC_MIN_OBJECTS,
+});
+
+impl<T: GorsTrace + Default + Send + 'static> GorsPtr<T> {
+    /// `GorsPtr::new` for the values which may take part in cycles: the
+    /// cycle collector tracks the object, and runs once the number of
+    /// objects tracked doubled since the last collection.
+    pub fn new_traced(value: T) -> Self {
+        let inner = Arc::new(Mutex::new(value));
+        let object = Arc::downgrade(&inner);
+        let object: std::sync::Weak<dyn GcObject> = object;
+        let mut heap = lock_scheduler(&GC_HEAP);
+        heap.objects.push(object);
+        let collect = heap.objects.len() >= heap.next_collection;
+        drop(heap);
+        if collect {
+            collect_cycles();
+            run_finalizer_hook();
+        }
+        Self::from_arc(inner)
+    }
+}
+
+/// Set while a collection waits for the running goroutines to stop at
+/// their next `yield_point`.
+static GC_STOP_REQUESTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// The collector side of `yield_point`, installed by the first collection.
+/// Going through a hook keeps the collector out of programs which never
+/// allocate tracked objects.
+static GC_SAFEPOINT_HOOK: std::sync::OnceLock<fn()> = std::sync::OnceLock::new();
+
+/// Whether a collection is in progress, and the number of goroutines
+/// stopped at a safepoint for it.
+struct GcStop {
+    requested: bool,
+    stopped: usize,
+}
+
+static GC_STOP: Mutex<GcStop> = Mutex::new(GcStop {
+    requested: false,
+    stopped: 0,
+});
+
+/// The goroutines stopped at a safepoint wait here for the collection to
+/// finish.
+static GC_RESUME: Condvar = Condvar::new();
+
+/// How many times a collection checks whether the running goroutines
+/// stopped, waiting `GC_STOP_BACKOFF` longer after each check.
+const GC_STOP_ATTEMPTS: u32 = 20;
+
+const GC_STOP_BACKOFF: std::time::Duration = std::time::Duration::from_micros(100);
+
+/// Free the cycles of tracked objects no longer reachable from the
+/// program, and return the number of objects freed.
+///
+/// Collection stops the world: the running goroutines stop at their next
+/// `yield_point`, and the collector holds the goroutine registry, so that
+/// none of the others resumes. A goroutine which does not reach a safepoint
+/// in time delays the collection until `GC_MIN_OBJECTS` more objects are
+/// tracked.
+pub fn collect_cycles() -> usize {
+    let mut stop = lock_scheduler(&GC_STOP);
+    if stop.requested {
+        // Another goroutine collects: wait for it like at a safepoint.
+        drop(stop);
+        gc_safepoint();
+        return 0;
+    }
+    stop.requested = true;
+    drop(stop);
+    let _ = GC_SAFEPOINT_HOOK.set(gc_safepoint);
+    GC_STOP_REQUESTED.store(true, std::sync::atomic::Ordering::SeqCst);
+    let registry = stop_the_world();
+    let mut heap = lock_scheduler(&GC_HEAP);
+    let garbage = if registry.is_some() {
+        NUM_GC.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        tracked_garbage(&mut heap)
+    } else {
+        heap.next_collection = heap.objects.len().saturating_add(GC_MIN_OBJECTS);
+        Vec::new()
+    };
+    drop(heap);
+    GC_STOP_REQUESTED.store(false, std::sync::atomic::Ordering::SeqCst);
+    lock_scheduler(&GC_STOP).requested = false;
+    GC_RESUME.notify_all();
+    drop(registry);
+    // Dropping the values of the garbage objects frees them.
+    for object in &garbage {
+        object.clear_object();
+    }
+    garbage.len()
+}
+
+/// Wait for the goroutines running besides the calling one to stop at a
+/// safepoint, checking `GC_STOP_ATTEMPTS` times with a linear backoff, and
+/// return the goroutine registry, locked. `None` if some did not stop.
+fn stop_the_world() -> Option<MutexGuard<'static, std::collections::BTreeMap<u64, GoroutineRecord>>>
+{
+    let current = goroutine_id().get();
+    for attempt in 1..=GC_STOP_ATTEMPTS {
+        let registry = lock_scheduler(&GOROUTINE_REGISTRY);
+        let running = registry
+            .iter()
+            .filter(|&(&id, record)| id != current && record.status == GoroutineStatus::Running)
+            .count();
+        if lock_scheduler(&GC_STOP).stopped >= running {
+            return Some(registry);
+        }
+        // Goroutines changing status wait for the registry: let them.
+        drop(registry);
+        std::thread::sleep(GC_STOP_BACKOFF * attempt);
+    }
+    None
+}
+
+/// Stop the calling goroutine until the collection in progress finishes.
+fn gc_safepoint() {
+    let mut stop = lock_scheduler(&GC_STOP);
+    if !stop.requested {
+        return;
+    }
+    stop.stopped += 1;
+    while stop.requested {
+        stop = wait_scheduler(&GC_RESUME, stop);
+    }
+    stop.stopped -= 1;
+}
+
+/// The objects of `heap` which are garbage.
+fn tracked_garbage(heap: &mut GcHeap) -> Vec<Arc<dyn GcObject>> {
+    heap.objects.retain(|object| object.strong_count() > 0);
+    let objects: Vec<Arc<dyn GcObject>> = heap
+        .objects
+        .iter()
+        .filter_map(std::sync::Weak::upgrade)
+        .collect();
+    let garbage = cycle_garbage(&objects);
+    let live = garbage.iter().filter(|&&garbage| !garbage).count();
+    heap.next_collection = live.saturating_mul(2).max(GC_MIN_OBJECTS);
+    objects
+        .into_iter()
+        .zip(garbage)
+        .filter_map(|(object, garbage)| garbage.then_some(object))
+        .collect()
+}
+
+/// Whether each object is garbage: only referenced by other objects which
+/// are garbage. The objects referenced from elsewhere, by the stacks, the
+/// globals or values the collector does not trace, are live, as are the
+/// objects they point to; `objects` holds one reference to each.
+fn cycle_garbage(objects: &[Arc<dyn GcObject>]) -> Vec<bool> {
+    let index: HashMap<usize, usize> = objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| (Arc::as_ptr(object).cast::<()>().addr(), i))
+        .collect();
+    let mut pointers: Vec<Vec<usize>> = Vec::with_capacity(objects.len());
+    let mut internal = vec![0usize; objects.len()];
+    let mut live = vec![false; objects.len()];
+    for (object, live) in objects.iter().zip(&mut live) {
+        let mut tracer = GcTracer::default();
+        // The objects in use by another thread are live.
+        *live = !object.trace_object(&mut tracer);
+        let targets: Vec<usize> = tracer
+            .edges
+            .iter()
+            .filter_map(|edge| index.get(edge).copied())
+            .collect();
+        for &target in &targets {
+            if let Some(count) = internal.get_mut(target) {
+                *count += 1;
+            }
+        }
+        pointers.push(targets);
+    }
+    let mut pending = Vec::new();
+    for (i, ((object, &internal), live)) in objects.iter().zip(&internal).zip(&mut live).enumerate()
+    {
+        *live |= Arc::strong_count(object) > internal + 1;
+        if *live {
+            pending.push(i);
+        }
+    }
+    while let Some(i) = pending.pop() {
+        for &target in pointers.get(i).into_iter().flatten() {
+            if let Some(live) = live.get_mut(target).filter(|live| !**live) {
+                *live = true;
+                pending.push(target);
+            }
+        }
+    }
+    live.into_iter().map(|live| !live).collect()
+}
+
+/// An object with a finalizer: the registry holds a reference to it until
+/// the finalizer runs, so the object lives on meanwhile.
+trait Finalizer: Send {
+    /// The address of the object.
+    fn object(&self) -> usize;
+    /// Whether nothing but the finalizer refers to the object anymore.
+    fn unreachable(&self) -> bool;
+    fn run(self: Box<Self>);
+}
+
+struct PendingFinalizer<T> {
+    object: Arc<Mutex<T>>,
+    finalizer: Box<dyn FnOnce(GorsPtr<T>) + Send>,
+}
+
+impl<T: Send + 'static> Finalizer for PendingFinalizer<T> {
+    fn object(&self) -> usize {
+        Arc::as_ptr(&self.object).addr()
+    }
+
+    fn unreachable(&self) -> bool {
+        Arc::strong_count(&self.object) == 1
+    }
+
+    fn run(self: Box<Self>) {
+        (self.finalizer)(GorsPtr::from_arc(self.object));
+    }
+}
+
+static FINALIZERS: Mutex<Vec<Box<dyn Finalizer>>> = Mutex::new(Vec::new());
+
+/// The finalizer side of `gc`, installed by the first `set_finalizer`.
+/// Going through a hook keeps the scheduler out of programs which only
+/// call `runtime.GC`.
+static FINALIZER_HOOK: std::sync::OnceLock<fn()> = std::sync::OnceLock::new();
+
+/// What `runtime.SetFinalizer` accepts as a finalizer for a `*T`: a
+/// function literal, or a function value, which may be nil.
+pub trait FinalizerFunc<T>: Send + 'static {
+    fn into_finalizer(self) -> Option<Box<dyn FnOnce(GorsPtr<T>) + Send>>;
+}
+
+impl<T, F: FnOnce(GorsPtr<T>) + Send + 'static> FinalizerFunc<T> for F {
+    fn into_finalizer(self) -> Option<Box<dyn FnOnce(GorsPtr<T>) + Send>> {
+        Some(Box::new(self))
+    }
+}
+
+/// The untyped `nil`.
+impl<T> FinalizerFunc<T> for Option<()> {
+    fn into_finalizer(self) -> Option<Box<dyn FnOnce(GorsPtr<T>) + Send>> {
+        None
+    }
+}
+
+/// The function value of a `func(*T)`.
+pub type GorsFinalizerValue<T> = Arc<Mutex<Option<Arc<dyn Fn(GorsPtr<T>) + Send + Sync>>>>;
+
+impl<T: 'static> FinalizerFunc<T> for GorsFinalizerValue<T> {
+    fn into_finalizer(self) -> Option<Box<dyn FnOnce(GorsPtr<T>) + Send>> {
+        let func = lock_func(&self).clone()?;
+        Some(Box::new(move |obj| func(obj)))
+    }
+}
+
+/// `runtime.SetFinalizer`: call `finalizer` with `obj` once a collection
+/// finds that the program no longer refers to it. A nil finalizer removes
+/// the one set on `obj`.
+///
+/// Collections are `runtime.GC` and the automatic ones of the cycle
+/// collector, so the finalizers of a program which allocates no values
+/// that may take part in cycles only run on `runtime.GC`.
+pub fn set_finalizer<T: Send + 'static>(obj: &GorsPtr<T>, finalizer: impl FinalizerFunc<T>) {
+    let object = match &obj.inner {
+        Some(GorsPtrInner::Direct(object)) => object.clone(),
+        Some(GorsPtrInner::Projected(_)) => {
+            throw("runtime.SetFinalizer: pointer not at beginning of allocated block")
+        }
+        None => throw("runtime.SetFinalizer: pointer not in allocated block"),
+    };
+    let address = Arc::as_ptr(&object).addr();
+    let mut finalizers = lock_scheduler(&FINALIZERS);
+    let set = finalizers.iter().any(|pending| pending.object() == address);
+    let Some(finalizer) = finalizer.into_finalizer() else {
+        finalizers.retain(|pending| pending.object() != address);
+        return;
+    };
+    if set {
+        drop(finalizers);
+        throw("runtime.SetFinalizer: finalizer already set");
+    }
+    finalizers.push(Box::new(PendingFinalizer { object, finalizer }));
+    drop(finalizers);
+    let _ = FINALIZER_HOOK.set(run_finalizers);
+}
+
+/// `runtime.KeepAlive`: keep `x` referenced up to the call.
+pub fn keep_alive<T>(x: T) {
+    drop(std::hint::black_box(x));
+}
+
+/// Run the finalizers of the objects which became unreachable, one after
+/// the other on a goroutine of their own, like Go.
+fn run_finalizers() {
+    let mut finalizers = lock_scheduler(&FINALIZERS);
+    let (ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut *finalizers)
+        .into_iter()
+        .partition(|finalizer| finalizer.unreachable());
+    *finalizers = pending;
+    drop(finalizers);
+    if !ready.is_empty() {
+        go(move || {
+            for finalizer in ready {
+                finalizer.run();
+            }
+        });
+    }
+}
+
+/// `runtime.GC`: collect the unreachable cycles, then run the finalizers
+/// of the objects nothing refers to anymore.
+pub fn gc() {
+    collect_cycles();
+    run_finalizer_hook();
+}
+
+fn run_finalizer_hook() {
+    if let Some(hook) = FINALIZER_HOOK.get() {
+        hook();
+    }
+}
+
+/// The bytes allocated and not yet freed.
+static HEAP_ALLOC: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+static TOTAL_ALLOC: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+static MALLOCS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+static FREES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+static NUM_GC: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+/// Count an allocation of `size` bytes. The global allocator of programs
+/// reading memory statistics calls it, so it must not allocate.
+pub fn record_alloc(size: usize) {
+    let size = u64::try_from(size).unwrap_or(u64::MAX);
+    MALLOCS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    TOTAL_ALLOC.fetch_add(size, std::sync::atomic::Ordering::Relaxed);
+    HEAP_ALLOC.fetch_add(size, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Count the release of an allocation of `size` bytes.
+pub fn record_free(size: usize) {
+    let size = u64::try_from(size).unwrap_or(u64::MAX);
+    FREES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    HEAP_ALLOC.fetch_sub(size, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Count the resizing of an allocation from `old_size` to `new_size`
+/// bytes, which allocates no object and frees none.
+pub fn record_realloc(old_size: usize, new_size: usize) {
+    let old_size = u64::try_from(old_size).unwrap_or(u64::MAX);
+    let new_size = u64::try_from(new_size).unwrap_or(u64::MAX);
+    if new_size >= old_size {
+        let grown = new_size - old_size;
+        TOTAL_ALLOC.fetch_add(grown, std::sync::atomic::Ordering::Relaxed);
+        HEAP_ALLOC.fetch_add(grown, std::sync::atomic::Ordering::Relaxed);
+    } else {
+        HEAP_ALLOC.fetch_sub(old_size - new_size, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// The allocation counters `runtime.ReadMemStats` reports.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemStatsSample {
+    pub heap_alloc: u64,
+    pub total_alloc: u64,
+    pub mallocs: u64,
+    pub frees: u64,
+    pub num_gc: u32,
+}
+
+pub fn read_mem_stats() -> MemStatsSample {
+    let load =
+        |counter: &std::sync::atomic::AtomicU64| counter.load(std::sync::atomic::Ordering::Relaxed);
+    MemStatsSample {
+        heap_alloc: load(&HEAP_ALLOC),
+        total_alloc: load(&TOTAL_ALLOC),
+        mallocs: load(&MALLOCS),
+        frees: load(&FREES),
+        num_gc: NUM_GC.load(std::sync::atomic::Ordering::Relaxed),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum __GorsReflectKind {
+    Invalid,
+    Bool,
+    Int,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
     Uint,
     Uint8,
     Uint16,
@@ -1087,6 +2329,12 @@ impl __GorsReflectKindValue for Box<dyn Any> {
     }
 }
 
+impl __GorsReflectKindValue for GorsAny {
+    fn __gors_reflect_kind(&self) -> __GorsReflectKind {
+        reflect_kind_of_any(self.payload())
+    }
+}
+
 impl __GorsReflectKindValue for r#bool {
     fn __gors_reflect_kind(&self) -> __GorsReflectKind {
         __GorsReflectKind::Bool
@@ -1201,7 +2449,7 @@ impl<T, const N: usize> __GorsReflectKindValue for [T; N] {
     }
 }
 
-impl<K, V> __GorsReflectKindValue for HashMap<K, V> {
+impl<K, V> __GorsReflectKindValue for GorsMap<K, V> {
     fn __gors_reflect_kind(&self) -> __GorsReflectKind {
         __GorsReflectKind::Map
     }
@@ -1267,6 +2515,32 @@ pub fn lock_func<T: ?Sized>(func: &Arc<Mutex<T>>) -> MutexGuard<'_, T> {
     func.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
+/// Stands in for `Mutex` in programs that never start a goroutine: it has
+/// the same `lock` API but only borrows a `RefCell`, and never poisons.
+#[derive(Default)]
+pub struct LocalMutex<T>(std::cell::RefCell<T>);
+
+#[allow(dead_code)]
+impl<T> LocalMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self(std::cell::RefCell::new(value))
+    }
+
+    pub fn lock(&self) -> std::sync::LockResult<std::cell::RefMut<'_, T>> {
+        Ok(self.0.borrow_mut())
+    }
+}
+
+pub fn lock_local_func<T>(func: &std::rc::Rc<LocalMutex<T>>) -> std::cell::RefMut<'_, T> {
+    func.0.borrow_mut()
+}
+
+/// Reads a package variable lowered to a thread-local in programs that
+/// never start a goroutine.
+pub fn thread_global<T: ?Sized>(global: &'static std::thread::LocalKey<&'static T>) -> &'static T {
+    global.with(|value| *value)
+}
+
 pub trait ByteSeq {
     fn byte_at(&self, index: usize) -> u8;
     fn byte_slice(&self, start: usize, end: usize) -> Vec<u8>;
@@ -1385,7 +2659,7 @@ impl<T, const N: usize> Len for GorsPtr<[T; N]> {
     }
 }
 
-impl<K, V> Len for HashMap<K, V> {
+impl<K, V> Len for GorsMap<K, V> {
     fn len_value(&self) -> usize {
         self.len()
     }
@@ -1605,7 +2879,7 @@ where
 }
 
 #[inline]
-pub fn delete<K: Hash + Eq, V>(m: &mut HashMap<K, V>, key: &K) {
+pub fn delete<K: Hash + Eq, V>(m: &mut GorsMap<K, V>, key: &K) {
     m.remove(key);
 }
 
@@ -1629,7 +2903,7 @@ impl<T: Default> Clear for [T] {
     }
 }
 
-impl<K, V> Clear for HashMap<K, V> {
+impl<K, V> Clear for GorsMap<K, V> {
     fn clear_value(&mut self) {
         self.clear();
     }
@@ -1661,134 +2935,481 @@ pub fn make_vec_cap<T>(cap: usize) -> Vec<T> {
 }
 
 #[inline]
-pub fn make_map<K, V>() -> HashMap<K, V> {
-    HashMap::new()
-}
-
-#[inline]
-pub fn make_map_cap<K, V>(cap: usize) -> HashMap<K, V> {
-    HashMap::with_capacity(cap)
-}
-
-#[inline]
-pub fn make_chan<T>(capacity: usize) -> Chan<T> {
-    Chan::new(capacity)
+pub fn make_map<K, V>() -> GorsMap<K, V> {
+    GorsMap::new()
 }
 
 #[inline]
-pub fn max<T: PartialOrd>(a: T, b: T) -> T {
-    if a >= b { a } else { b }
+pub fn make_map_cap<K, V>(cap: usize) -> GorsMap<K, V> {
+    GorsMap::with_capacity(cap)
 }
 
-#[inline]
-pub fn max3<T: PartialOrd>(a: T, b: T, c: T) -> T {
-    max(max(a, b), c)
+/// A Go map. The zero value is the nil map: it reads like an empty map but
+/// panics on assignment, and only a `make` or a literal allocates it.
+///
+/// Like Go, every iteration starts at a random entry. `GORS_MAPORDER`
+/// makes iterations reproducible instead: `sorted` visits the keys in
+/// order, and `insertion` in the order they were added.
+#[derive(Clone, Debug)]
+pub struct GorsMap<K, V> {
+    entries: HashMap<K, V>,
+    nil: bool,
+    /// When the keys were added, by hash, with `GORS_MAPORDER=insertion`.
+    inserted: HashMap<u64, u64>,
+    insertions: u64,
 }
 
-#[inline]
-pub fn min<T: PartialOrd>(a: T, b: T) -> T {
-    if a <= b { a } else { b }
-}
+impl<K, V> GorsMap<K, V> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::from_entries(HashMap::new())
+    }
 
-#[inline]
-pub fn min3<T: PartialOrd>(a: T, b: T, c: T) -> T {
-    min(min(a, b), c)
-}
+    #[inline]
+    pub fn with_capacity(cap: usize) -> Self {
+        Self::from_entries(HashMap::with_capacity(cap))
+    }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct Complex64 {
-    pub re: f32,
-    pub im: f32,
-}
+    #[inline]
+    pub fn from_entries(entries: HashMap<K, V>) -> Self {
+        Self {
+            entries,
+            nil: false,
+            inserted: HashMap::new(),
+            insertions: 0,
+        }
+    }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct Complex128 {
-    pub re: f64,
-    pub im: f64,
-}
+    #[inline]
+    pub const fn is_nil(&self) -> bool {
+        self.nil
+    }
 
-impl std::fmt::Display for Complex64 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}{:+}i)", self.re, self.im)
+    fn allocated(&mut self) -> &mut HashMap<K, V> {
+        if self.nil {
+            panic_runtime_error(RuntimeError::nil_map());
+        }
+        &mut self.entries
     }
 }
 
-impl std::fmt::Display for Complex128 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}{:+}i)", self.re, self.im)
+impl<K: Hash + Eq, V> GorsMap<K, V> {
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.record_insertion(&key);
+        self.allocated().insert(key, value)
     }
-}
 
-macro_rules! impl_complex_ops {
-    ($ty:ty) => {
-        impl std::ops::Add for $ty {
-            type Output = Self;
+    #[inline]
+    pub fn entry(&mut self, key: K) -> std::collections::hash_map::Entry<'_, K, V> {
+        self.record_insertion(&key);
+        self.allocated().entry(key)
+    }
 
-            fn add(self, rhs: Self) -> Self {
-                Self {
-                    re: self.re + rhs.re,
-                    im: self.im + rhs.im,
-                }
-            }
+    pub fn iter(&self) -> MapIter<std::collections::hash_map::Iter<'_, K, V>> {
+        if let Some(entries) = self.ordered(self.entries.iter(), |&(key, _)| key) {
+            return MapIter::Ordered(entries.into_iter());
         }
+        let start = cheaprand_n(self.entries.len());
+        MapIter::Rotated(
+            self.entries
+                .iter()
+                .skip(start)
+                .chain(self.entries.iter().take(start)),
+        )
+    }
 
-        impl std::ops::Sub for $ty {
-            type Output = Self;
+    pub fn iter_mut(&mut self) -> MapIter<std::collections::hash_map::IterMut<'_, K, V>> {
+        let hasher = self.entries.hasher().clone();
+        let mut entries: Vec<_> = self.entries.iter_mut().collect();
+        sort_map_entries(&mut entries, |(key, _)| key, &hasher, &self.inserted);
+        MapIter::Ordered(entries.into_iter())
+    }
 
-            fn sub(self, rhs: Self) -> Self {
-                Self {
-                    re: self.re - rhs.re,
-                    im: self.im - rhs.im,
-                }
-            }
-        }
+    pub fn keys(&self) -> MapKeys<'_, K, V> {
+        self.iter().map(|(key, _)| key)
+    }
 
-        impl std::ops::Mul for $ty {
-            type Output = Self;
+    pub fn values(&self) -> MapValues<'_, K, V> {
+        self.iter().map(|(_, value)| value)
+    }
 
-            fn mul(self, rhs: Self) -> Self {
-                Self {
-                    re: self.re.mul_add(rhs.re, -(self.im * rhs.im)),
-                    im: self.re.mul_add(rhs.im, self.im * rhs.re),
-                }
-            }
+    /// The entries in the order `GORS_MAPORDER` asks for, if any.
+    fn ordered<T>(&self, entries: impl Iterator<Item = T>, key: fn(&T) -> &K) -> Option<Vec<T>> {
+        if map_order() == MapOrder::Random {
+            return None;
         }
+        let mut entries: Vec<T> = entries.collect();
+        sort_map_entries(&mut entries, key, self.entries.hasher(), &self.inserted);
+        Some(entries)
+    }
 
-        impl std::ops::Div for $ty {
-            type Output = Self;
+    fn record_insertion(&mut self, key: &K) {
+        if map_order() != MapOrder::Insertion || self.nil || self.entries.contains_key(key) {
+            return;
+        }
+        // The entries of deleted keys are dropped once they outnumber the
+        // live ones.
+        if self.inserted.len() > 2 * self.entries.len() {
+            let live: std::collections::HashSet<u64> = self
+                .entries
+                .keys()
+                .map(|key| std::hash::BuildHasher::hash_one(self.entries.hasher(), key))
+                .collect();
+            self.inserted.retain(|hash, _| live.contains(hash));
+        }
+        let hash = std::hash::BuildHasher::hash_one(self.entries.hasher(), key);
+        self.inserted.insert(hash, self.insertions);
+        self.insertions += 1;
+    }
+}
 
-            fn div(self, rhs: Self) -> Self {
-                let denom = rhs.re.mul_add(rhs.re, rhs.im * rhs.im);
-                Self {
-                    re: self.re.mul_add(rhs.re, self.im * rhs.im) / denom,
-                    im: self.im.mul_add(rhs.re, -(self.re * rhs.im)) / denom,
-                }
-            }
+impl<K, V> Default for GorsMap<K, V> {
+    fn default() -> Self {
+        Self {
+            nil: true,
+            ..Self::new()
         }
-    };
+    }
 }
 
-impl_complex_ops!(Complex64);
-impl_complex_ops!(Complex128);
+impl<K, V> std::ops::Deref for GorsMap<K, V> {
+    type Target = HashMap<K, V>;
 
-#[inline]
-pub const fn complex64(re: f32, im: f32) -> Complex64 {
-    Complex64 { re, im }
+    fn deref(&self) -> &HashMap<K, V> {
+        &self.entries
+    }
 }
 
-#[inline]
-pub const fn complex128(re: f64, im: f64) -> Complex128 {
-    Complex128 { re, im }
+impl<K, V> std::ops::DerefMut for GorsMap<K, V> {
+    fn deref_mut(&mut self) -> &mut HashMap<K, V> {
+        &mut self.entries
+    }
 }
 
-#[inline]
-pub const fn complex(re: f64, im: f64) -> Complex128 {
-    complex128(re, im)
+impl<K: Hash + Eq, V: PartialEq> PartialEq for GorsMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_nil() == other.is_nil() && **self == **other
+    }
 }
 
-pub trait Complex64Value {
-    fn complex64_value(self) -> Complex64;
-}
+impl<K: Hash + Eq, V: Eq> Eq for GorsMap<K, V> {}
+
+impl<K: Hash + Eq, V, const N: usize> From<[(K, V); N]> for GorsMap<K, V> {
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for GorsMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        let entries = entries.into_iter();
+        let mut map = Self::with_capacity(entries.size_hint().0);
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K: Hash + Eq, V> IntoIterator for GorsMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = MapIter<std::collections::hash_map::IntoIter<K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let hasher = self.entries.hasher().clone();
+        let mut entries: Vec<_> = self.entries.into_iter().collect();
+        sort_map_entries(&mut entries, |(key, _)| key, &hasher, &self.inserted);
+        MapIter::Ordered(entries.into_iter())
+    }
+}
+
+impl<'a, K: Hash + Eq, V> IntoIterator for &'a GorsMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = MapIter<std::collections::hash_map::Iter<'a, K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Hash + Eq, V> IntoIterator for &'a mut GorsMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = MapIter<std::collections::hash_map::IterMut<'a, K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub type MapKeys<'a, K, V> = std::iter::Map<
+    MapIter<std::collections::hash_map::Iter<'a, K, V>>,
+    fn((&'a K, &'a V)) -> &'a K,
+>;
+
+pub type MapValues<'a, K, V> = std::iter::Map<
+    MapIter<std::collections::hash_map::Iter<'a, K, V>>,
+    fn((&'a K, &'a V)) -> &'a V,
+>;
+
+/// The entries of a map, in the order an iteration visits them.
+pub enum MapIter<I: Iterator> {
+    /// The entries from a random one on, wrapping around.
+    Rotated(std::iter::Chain<std::iter::Skip<I>, std::iter::Take<I>>),
+    Ordered(std::vec::IntoIter<I::Item>),
+}
+
+impl<I: Iterator> Iterator for MapIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        match self {
+            Self::Rotated(entries) => entries.next(),
+            Self::Ordered(entries) => entries.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Rotated(entries) => entries.size_hint(),
+            Self::Ordered(entries) => entries.size_hint(),
+        }
+    }
+}
+
+/// Put the entries of a map in the order of an iteration.
+fn sort_map_entries<K: Hash, T>(
+    entries: &mut [T],
+    key: fn(&T) -> &K,
+    hasher: &std::collections::hash_map::RandomState,
+    inserted: &HashMap<u64, u64>,
+) {
+    match map_order() {
+        MapOrder::Random => entries.rotate_left(cheaprand_n(entries.len())),
+        MapOrder::Sorted => entries.sort_by_cached_key(|entry| MapKeyOrder::of(key(entry))),
+        MapOrder::Insertion => entries.sort_by_cached_key(|entry| {
+            let hash = std::hash::BuildHasher::hash_one(hasher, key(entry));
+            inserted.get(&hash).copied().unwrap_or(u64::MAX)
+        }),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MapOrder {
+    Random,
+    Sorted,
+    Insertion,
+}
+
+/// The order maps are iterated in, from `GORS_MAPORDER`.
+fn map_order() -> MapOrder {
+    static MAP_ORDER: std::sync::OnceLock<MapOrder> = std::sync::OnceLock::new();
+    *MAP_ORDER.get_or_init(|| match std::env::var("GORS_MAPORDER").as_deref() {
+        Ok("sorted") => MapOrder::Sorted,
+        Ok("insertion") => MapOrder::Insertion,
+        _ => MapOrder::Random,
+    })
+}
+
+/// A key of a map as the sequence of values it hashes, which sort like
+/// the integers, strings and booleans they come from. Other keys get an
+/// arbitrary but reproducible order.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct MapKeyOrder(Vec<u16>);
+
+impl MapKeyOrder {
+    fn of<K: Hash + ?Sized>(key: &K) -> Self {
+        let mut order = Self(Vec::new());
+        key.hash(&mut order);
+        order
+    }
+
+    fn push_be_bytes(&mut self, bytes: &[u8]) {
+        self.0.extend(bytes.iter().map(|&byte| u16::from(byte)));
+    }
+}
+
+/// Strings hash their bytes then `0xff`: the bytes sort after it, so that
+/// prefixes sort first.
+impl std::hash::Hasher for MapKeyOrder {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0
+            .extend(bytes.iter().map(|&byte| 0x100 + u16::from(byte)));
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.push_be_bytes(&i.to_be_bytes());
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.push_be_bytes(&i.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.push_be_bytes(&i.to_be_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.push_be_bytes(&i.to_be_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.push_be_bytes(&i.to_be_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.push_be_bytes(&i.to_be_bytes());
+    }
+
+    // Flipping the sign bit sorts negative integers first.
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i.cast_unsigned() ^ (1 << 7));
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i.cast_unsigned() ^ (1 << 15));
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i.cast_unsigned() ^ (1 << 31));
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i.cast_unsigned() ^ (1 << 63));
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i.cast_unsigned() ^ (1 << 127));
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_usize(i.cast_unsigned() ^ (1 << (usize::BITS - 1)));
+    }
+}
+
+#[inline]
+pub fn make_chan<T>(capacity: usize) -> Chan<T> {
+    Chan::new(capacity)
+}
+
+#[inline]
+pub fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if a >= b { a } else { b }
+}
+
+#[inline]
+pub fn max3<T: PartialOrd>(a: T, b: T, c: T) -> T {
+    max(max(a, b), c)
+}
+
+#[inline]
+pub fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if a <= b { a } else { b }
+}
+
+#[inline]
+pub fn min3<T: PartialOrd>(a: T, b: T, c: T) -> T {
+    min(min(a, b), c)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex64 {
+    pub re: f32,
+    pub im: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex128 {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl std::fmt::Display for Complex64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}{:+}i)", self.re, self.im)
+    }
+}
+
+impl std::fmt::Display for Complex128 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}{:+}i)", self.re, self.im)
+    }
+}
+
+macro_rules! impl_complex_ops {
+    ($ty:ty) => {
+        impl std::ops::Add for $ty {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self {
+                    re: self.re + rhs.re,
+                    im: self.im + rhs.im,
+                }
+            }
+        }
+
+        impl std::ops::Sub for $ty {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self {
+                    re: self.re - rhs.re,
+                    im: self.im - rhs.im,
+                }
+            }
+        }
+
+        impl std::ops::Mul for $ty {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                Self {
+                    re: self.re.mul_add(rhs.re, -(self.im * rhs.im)),
+                    im: self.re.mul_add(rhs.im, self.im * rhs.re),
+                }
+            }
+        }
+
+        impl std::ops::Div for $ty {
+            type Output = Self;
+
+            fn div(self, rhs: Self) -> Self {
+                let denom = rhs.re.mul_add(rhs.re, rhs.im * rhs.im);
+                Self {
+                    re: self.re.mul_add(rhs.re, self.im * rhs.im) / denom,
+                    im: self.im.mul_add(rhs.re, -(self.re * rhs.im)) / denom,
+                }
+            }
+        }
+    };
+}
+
+impl_complex_ops!(Complex64);
+impl_complex_ops!(Complex128);
+
+#[inline]
+pub const fn complex64(re: f32, im: f32) -> Complex64 {
+    Complex64 { re, im }
+}
+
+#[inline]
+pub const fn complex128(re: f64, im: f64) -> Complex128 {
+    Complex128 { re, im }
+}
+
+#[inline]
+pub const fn complex(re: f64, im: f64) -> Complex128 {
+    complex128(re, im)
+}
+
+pub trait Complex64Value {
+    fn complex64_value(self) -> Complex64;
+}
 
 pub trait Complex128Value {
     fn complex128_value(self) -> Complex128;
@@ -1965,6 +3586,31 @@ struct ChanInner<T> {
     buf: VecDeque<T>,
     capacity: usize,
     closed: bool,
+    /// Number of values received from `buf` so far, so that blocked senders
+    /// of unbuffered channels can tell when theirs has been taken.
+    taken: u64,
+    receivers: ChanWaiters,
+    senders: ChanWaiters,
+    /// Values offered by blocked `select` statements, for receivers to take.
+    offers: VecDeque<SendOffer<T>>,
+}
+
+/// Goroutines waiting on one of the condition variables of a channel.
+#[derive(Default)]
+struct ChanWaiters {
+    /// Blocked, and not notified since.
+    waiting: usize,
+    /// Notified, and not woken up yet.
+    notified: usize,
+    /// `select` statements waiting for the same operation.
+    selects: Vec<Arc<SelectWaiter>>,
+}
+
+/// A send case of a blocked `select` statement.
+struct SendOffer<T> {
+    select: Arc<SelectWaiter>,
+    case: usize,
+    value: T,
 }
 
 type ChanState<T> = Arc<(Mutex<ChanInner<T>>, Condvar, Condvar)>;
@@ -2012,6 +3658,10 @@ impl<T> Chan<T> {
                     buf: VecDeque::with_capacity(capacity),
                     capacity,
                     closed: false,
+                    taken: 0,
+                    receivers: ChanWaiters::default(),
+                    senders: ChanWaiters::default(),
+                    offers: VecDeque::new(),
                 }),
                 Condvar::new(),
                 Condvar::new(),
@@ -2020,53 +3670,77 @@ impl<T> Chan<T> {
     }
 
     #[allow(clippy::significant_drop_tightening)]
+    #[track_caller]
     pub fn send(&self, val: T) {
+        yield_point();
         let Some(inner) = self.inner.as_ref() else {
             let _ = val;
-            loop {
-                std::thread::park();
-            }
+            park_goroutine(GoroutineStatus::ChanSendNilChan);
         };
         let (lock, rx_cv, tx_cv) = &**inner;
         let mut inner = lock_chan(lock);
-        if inner.closed {
-            return;
+        self.race_synchronize();
+        while !inner.closed && inner.capacity > 0 && inner.buf.len() >= inner.capacity {
+            inner = wait_chan(
+                lock,
+                tx_cv,
+                inner,
+                |inner| &mut inner.senders,
+                GoroutineStatus::ChanSend,
+            );
         }
-        while inner.capacity > 0 && inner.buf.len() >= inner.capacity {
-            inner = wait_chan(tx_cv, inner);
-            if inner.closed {
-                return;
-            }
+        if inner.closed {
+            drop(inner);
+            panic_runtime_error(RuntimeError::plain("send on closed channel"));
         }
+        inner.buf.push_back(val);
+        notify_chan(&mut inner.receivers, rx_cv);
         if inner.capacity == 0 {
-            inner.buf.push_back(val);
-            rx_cv.notify_one();
-            while !inner.buf.is_empty() && !inner.closed {
-                inner = wait_chan(tx_cv, inner);
+            let sent = inner.taken + inner.buf.len() as u64;
+            while inner.taken < sent {
+                if inner.closed {
+                    drop(inner);
+                    panic_runtime_error(RuntimeError::plain("send on closed channel"));
+                }
+                inner = wait_chan(
+                    lock,
+                    tx_cv,
+                    inner,
+                    |inner| &mut inner.senders,
+                    GoroutineStatus::ChanSend,
+                );
             }
-        } else {
-            inner.buf.push_back(val);
-            rx_cv.notify_one();
+            self.race_synchronize();
         }
     }
 
+    #[track_caller]
     pub fn recv(&self) -> Option<T> {
+        yield_point();
         let Some(inner) = self.inner.as_ref() else {
-            loop {
-                std::thread::park();
-            }
+            park_goroutine(GoroutineStatus::ChanReceiveNilChan);
         };
         let (lock, rx_cv, tx_cv) = &**inner;
         let mut inner = lock_chan(lock);
         loop {
-            if let Some(val) = inner.buf.pop_front() {
-                tx_cv.notify_one();
-                return Some(val);
-            }
-            if inner.closed {
-                return None;
+            let received = pop_value(&mut inner, tx_cv).or_else(|| {
+                if inner.closed {
+                    None
+                } else {
+                    take_offer(&mut inner, None)
+                }
+            });
+            if received.is_some() || inner.closed {
+                self.race_synchronize();
+                return received;
             }
-            inner = wait_chan(rx_cv, inner);
+            inner = wait_chan(
+                lock,
+                rx_cv,
+                inner,
+                |inner| &mut inner.receivers,
+                GoroutineStatus::ChanReceive,
+            );
         }
     }
 
@@ -2076,12 +3750,17 @@ impl<T> Chan<T> {
         };
         let (lock, rx_cv, _) = &**inner;
         let mut inner = lock_chan(lock);
-        if inner.closed || inner.capacity == 0 || inner.buf.len() >= inner.capacity {
+        if inner.closed {
+            drop(inner);
+            panic_runtime_error(RuntimeError::plain("send on closed channel"));
+        }
+        if inner.capacity == 0 || inner.buf.len() >= inner.capacity {
             return Err(val);
         }
+        self.race_synchronize();
         inner.buf.push_back(val);
+        notify_chan(&mut inner.receivers, rx_cv);
         drop(inner);
-        rx_cv.notify_one();
         Ok(())
     }
 
@@ -2094,14 +3773,14 @@ impl<T> Chan<T> {
         };
         let (lock, _, tx_cv) = &**inner;
         let mut inner = lock_chan(lock);
-        if let Some(val) = inner.buf.pop_front() {
+        self.race_synchronize();
+        if let Some(val) = pop_value(&mut inner, tx_cv) {
             drop(inner);
-            tx_cv.notify_one();
             Ok(val)
         } else if inner.closed {
             Ok(T::default())
         } else {
-            Err(TryRecvError::Empty)
+            take_offer(&mut inner, None).ok_or(TryRecvError::Empty)
         }
     }
 
@@ -2112,17 +3791,18 @@ impl<T> Chan<T> {
         let inner = self.inner.as_ref()?;
         let (lock, _, tx_cv) = &**inner;
         let mut inner = lock_chan(lock);
-        if let Some(val) = inner.buf.pop_front() {
+        self.race_synchronize();
+        if let Some(val) = pop_value(&mut inner, tx_cv) {
             drop(inner);
-            tx_cv.notify_one();
             Some((val, true))
         } else if inner.closed {
             Some((T::default(), false))
         } else {
-            None
+            take_offer(&mut inner, None).map(|val| (val, true))
         }
     }
 
+    #[track_caller]
     pub fn recv_with_ok(&self) -> (T, bool)
     where
         T: Default,
@@ -2136,16 +3816,23 @@ impl<T> Chan<T> {
     #[allow(clippy::significant_drop_tightening)]
     pub fn close(&self) {
         let Some(inner) = self.inner.as_ref() else {
-            panic_value("close of nil channel");
+            panic_runtime_error(RuntimeError::plain("close of nil channel"));
         };
         let (lock, rx_cv, tx_cv) = &**inner;
         let mut inner = lock_chan(lock);
         if inner.closed {
-            return;
+            drop(inner);
+            panic_runtime_error(RuntimeError::plain("close of closed channel"));
         }
+        self.race_synchronize();
         inner.closed = true;
-        rx_cv.notify_all();
-        tx_cv.notify_all();
+        if inner.capacity == 0 {
+            // Values still pending on an unbuffered channel belong to blocked
+            // senders, which panic instead of completing their send.
+            inner.buf.clear();
+        }
+        notify_all_chan(&mut inner.receivers, rx_cv);
+        notify_all_chan(&mut inner.senders, tx_cv);
     }
 
     pub fn len(&self) -> usize {
@@ -2175,6 +3862,15 @@ impl<T> Chan<T> {
     pub fn is_nil(&self) -> bool {
         self.inner.is_none()
     }
+
+    /// Order the calling goroutine after the earlier operations on the
+    /// channel, and before the later ones, for the race detector. This
+    /// orders more than Go does: concurrent receives are ordered too.
+    fn race_synchronize(&self) {
+        if let Some(inner) = &self.inner {
+            race_synchronize(Arc::as_ptr(inner).addr());
+        }
+    }
 }
 
 pub struct ChanIter<T>(Chan<T>);
@@ -2182,6 +3878,7 @@ pub struct ChanIter<T>(Chan<T>);
 impl<T: Default> Iterator for ChanIter<T> {
     type Item = T;
 
+    #[track_caller]
     fn next(&mut self) -> Option<T> {
         let (val, ok) = self.0.recv_with_ok();
         ok.then_some(val)
@@ -2204,14 +3901,113 @@ fn lock_chan<T>(lock: &Mutex<ChanInner<T>>) -> MutexGuard<'_, ChanInner<T>> {
     }
 }
 
+/// Wait on `cvar`, without holding a processor, so other goroutines run
+/// meanwhile. The channel is unlocked while the processor is reacquired,
+/// and callers re-check its state anyway.
+///
+/// `waiters` selects the count of goroutines waiting on `cvar`: the
+/// goroutine is blocked, in `status`, until notified through `notify_chan`,
+/// or woken up spuriously.
+#[track_caller]
 fn wait_chan<'a, T>(
+    lock: &'a Mutex<ChanInner<T>>,
     cvar: &Condvar,
-    guard: MutexGuard<'a, ChanInner<T>>,
+    mut guard: MutexGuard<'a, ChanInner<T>>,
+    waiters: fn(&mut ChanInner<T>) -> &mut ChanWaiters,
+    status: GoroutineStatus,
 ) -> MutexGuard<'a, ChanInner<T>> {
-    match cvar.wait(guard) {
+    waiters(&mut guard).waiting += 1;
+    block_goroutine(status);
+    let released = release_processor();
+    let mut guard = match cvar.wait(guard) {
         Ok(guard) => guard,
         Err(poisoned) => poisoned.into_inner(),
+    };
+    let woken = waiters(&mut guard);
+    if woken.notified > 0 {
+        // Possibly another waiter's notification, which then counts as
+        // spurious itself.
+        woken.notified -= 1;
+    } else {
+        woken.waiting -= 1;
+        unblock_goroutines(1);
+    }
+    if !released {
+        set_goroutine_status(GoroutineStatus::Running);
+        return guard;
+    }
+    drop(guard);
+    acquire_processor();
+    set_goroutine_status(GoroutineStatus::Running);
+    lock_chan(lock)
+}
+
+/// Wake up one goroutine waiting on `cvar`, which `waiters` counts, and the
+/// `select` statements waiting for the same operation.
+fn notify_chan(waiters: &mut ChanWaiters, cvar: &Condvar) {
+    if waiters.waiting > 0 {
+        waiters.waiting -= 1;
+        waiters.notified += 1;
+        unblock_goroutines(1);
+    }
+    cvar.notify_one();
+    for select in &waiters.selects {
+        wake_select(select);
+    }
+}
+
+/// Wake up every goroutine waiting on `cvar`, which `waiters` counts, and
+/// the `select` statements waiting for the same operation.
+fn notify_all_chan(waiters: &mut ChanWaiters, cvar: &Condvar) {
+    let woken = std::mem::take(&mut waiters.waiting);
+    waiters.notified += woken;
+    unblock_goroutines(woken);
+    cvar.notify_all();
+    for select in &waiters.selects {
+        wake_select(select);
+    }
+}
+
+/// Receive the next value of `buf`, and wake up the senders waiting on it.
+/// Blocked senders of an unbuffered channel each wait for their own value to
+/// be taken, so they are all woken up.
+fn pop_value<T>(inner: &mut ChanInner<T>, tx_cv: &Condvar) -> Option<T> {
+    let val = inner.buf.pop_front()?;
+    inner.taken += 1;
+    if inner.capacity == 0 {
+        notify_all_chan(&mut inner.senders, tx_cv);
+    } else {
+        notify_chan(&mut inner.senders, tx_cv);
+    }
+    Some(val)
+}
+
+/// Take the value offered by the first blocked `select` which can still
+/// choose its send case, other than `taker`. Offers of `select` statements
+/// which chose another case are dropped.
+fn take_offer<T>(inner: &mut ChanInner<T>, taker: Option<&SelectWaiter>) -> Option<T> {
+    let mut index = 0;
+    while let Some(offer) = inner.offers.get(index) {
+        if taker.is_some_and(|taker| std::ptr::eq(taker, Arc::as_ptr(&offer.select))) {
+            index += 1;
+            continue;
+        }
+        match claim_select(&offer.select, offer.case) {
+            Claim::Won => return inner.offers.remove(index).map(|offer| offer.value),
+            Claim::Busy => {
+                // Both are polling their cases: let the taker poll again
+                // once the offering `select` blocks.
+                if let Some(taker) = taker {
+                    wake_select(taker);
+                }
+                index += 1;
+            }
+            Claim::Lost => {
+                inner.offers.remove(index);
+            }
+        }
     }
+    None
 }
 
 #[inline]
@@ -2220,11 +4016,13 @@ pub fn close<T>(ch: &Chan<T>) {
 }
 
 #[inline]
+#[track_caller]
 pub fn send<T>(ch: &Chan<T>, value: T) {
     ch.send(value);
 }
 
 #[inline]
+#[track_caller]
 pub fn recv<T: Default>(ch: &Chan<T>) -> T {
     ch.recv_with_ok().0
 }
@@ -2234,16 +4032,1065 @@ pub fn recv_with_ok<T: Default>(ch: &Chan<T>) -> (T, bool) {
     ch.recv_with_ok()
 }
 
-#[inline]
-#[allow(clippy::panic)]
-pub fn r#panic<T: Any + Send + 'static>(value: T) -> ! {
-    std::panic::panic_any(value)
-}
+/// One case of a `select` statement.
+pub trait SelectCase {
+    /// Complete the operation of case `case` if it can proceed now.
+    fn poll(&mut self, select: &Arc<SelectWaiter>, case: usize) -> bool;
 
-#[inline]
-pub fn panic_value<T: Any + Send + 'static>(value: T) -> ! {
-    r#panic(value)
-}
+    /// Have the channel wake `select` up when the operation may proceed.
+    fn register(&mut self, select: &Arc<SelectWaiter>, case: usize);
+
+    /// Undo `register`.
+    fn withdraw(&mut self, select: &Arc<SelectWaiter>);
+
+    /// Called on the chosen case once the `select` no longer waits on any
+    /// channel, to raise the panics of the operation.
+    fn finish(&mut self) {}
+}
+
+/// A receive case of a `select` statement.
+pub struct SelectRecv<T> {
+    chan: Chan<T>,
+    received: Option<(T, bool)>,
+}
+
+impl<T> SelectRecv<T> {
+    pub fn new(chan: &Chan<T>) -> Self {
+        Self {
+            chan: chan.clone(),
+            received: None,
+        }
+    }
+
+    /// The received value, and whether the channel was still open.
+    pub fn received(&mut self) -> (T, bool)
+    where
+        T: Default,
+    {
+        self.received.take().unwrap_or_default()
+    }
+}
+
+impl<T: Default> SelectCase for SelectRecv<T> {
+    fn poll(&mut self, select: &Arc<SelectWaiter>, _case: usize) -> bool {
+        let Some(inner) = self.chan.inner.as_ref() else {
+            return false;
+        };
+        let (lock, _, tx_cv) = &**inner;
+        let mut inner = lock_chan(lock);
+        self.chan.race_synchronize();
+        self.received = if let Some(val) = pop_value(&mut inner, tx_cv) {
+            Some((val, true))
+        } else if inner.closed {
+            Some((T::default(), false))
+        } else {
+            take_offer(&mut inner, Some(select)).map(|val| (val, true))
+        };
+        self.received.is_some()
+    }
+
+    fn register(&mut self, select: &Arc<SelectWaiter>, _case: usize) {
+        let Some(inner) = self.chan.inner.as_ref() else {
+            return;
+        };
+        let (lock, _, _) = &**inner;
+        lock_chan(lock).receivers.selects.push(Arc::clone(select));
+    }
+
+    fn withdraw(&mut self, select: &Arc<SelectWaiter>) {
+        let Some(inner) = self.chan.inner.as_ref() else {
+            return;
+        };
+        let (lock, _, _) = &**inner;
+        lock_chan(lock)
+            .receivers
+            .selects
+            .retain(|waiter| !Arc::ptr_eq(waiter, select));
+    }
+}
+
+/// A send case of a `select` statement.
+pub struct SelectSend<T> {
+    chan: Chan<T>,
+    /// The value to send, until offered to receivers by `register`.
+    value: Option<T>,
+    /// Whether the case was chosen because the channel is closed.
+    closed: bool,
+}
+
+impl<T> SelectSend<T> {
+    pub fn new(chan: &Chan<T>, value: T) -> Self {
+        Self {
+            chan: chan.clone(),
+            value: Some(value),
+            closed: false,
+        }
+    }
+}
+
+impl<T> SelectCase for SelectSend<T> {
+    fn poll(&mut self, select: &Arc<SelectWaiter>, case: usize) -> bool {
+        let Some(inner) = self.chan.inner.as_ref() else {
+            return false;
+        };
+        let (lock, rx_cv, _) = &**inner;
+        let mut inner = lock_chan(lock);
+        self.chan.race_synchronize();
+        if inner.closed {
+            self.closed = true;
+            return true;
+        }
+        let ready = if inner.capacity == 0 {
+            inner.receivers.waiting > 0
+        } else {
+            inner.buf.len() < inner.capacity
+        };
+        if !ready {
+            return false;
+        }
+        let offered = inner
+            .offers
+            .iter()
+            .position(|offer| Arc::ptr_eq(&offer.select, select) && offer.case == case);
+        let value = self.value.take().or_else(|| {
+            offered
+                .and_then(|index| inner.offers.remove(index))
+                .map(|offer| offer.value)
+        });
+        if let Some(value) = value {
+            inner.buf.push_back(value);
+            notify_chan(&mut inner.receivers, rx_cv);
+        }
+        true
+    }
+
+    fn register(&mut self, select: &Arc<SelectWaiter>, case: usize) {
+        let Some(inner) = self.chan.inner.as_ref() else {
+            return;
+        };
+        let (lock, rx_cv, _) = &**inner;
+        let mut inner = lock_chan(lock);
+        self.chan.race_synchronize();
+        if let Some(value) = self.value.take() {
+            inner.offers.push_back(SendOffer {
+                select: Arc::clone(select),
+                case,
+                value,
+            });
+        }
+        inner.senders.selects.push(Arc::clone(select));
+        notify_chan(&mut inner.receivers, rx_cv);
+    }
+
+    fn withdraw(&mut self, select: &Arc<SelectWaiter>) {
+        let Some(inner) = self.chan.inner.as_ref() else {
+            return;
+        };
+        let (lock, _, _) = &**inner;
+        let mut inner = lock_chan(lock);
+        inner
+            .offers
+            .retain(|offer| !Arc::ptr_eq(&offer.select, select));
+        inner
+            .senders
+            .selects
+            .retain(|waiter| !Arc::ptr_eq(waiter, select));
+    }
+
+    fn finish(&mut self) {
+        if self.closed {
+            panic_runtime_error(RuntimeError::plain("send on closed channel"));
+        }
+        self.chan.race_synchronize();
+    }
+}
+
+/// A goroutine running a `select` statement.
+pub struct SelectWaiter {
+    state: Mutex<SelectState>,
+    wakeup: Condvar,
+}
+
+struct SelectState {
+    /// The case completed on behalf of the blocked goroutine.
+    chosen: Option<usize>,
+    /// Set while the goroutine polls its cases, when they cannot be chosen
+    /// on its behalf.
+    polling: bool,
+    /// Whether some case may have become ready since the last poll.
+    dirty: bool,
+    /// Whether the goroutine is blocked.
+    asleep: bool,
+}
+
+enum Claim {
+    Won,
+    /// The `select` is polling its cases. It polls them again afterwards.
+    Busy,
+    /// The `select` chose another case.
+    Lost,
+}
+
+/// Run a `select` statement, and return the index of the chosen case.
+///
+/// One of the operations which can proceed is completed, chosen uniformly
+/// at random. When none can, the default case is chosen if there is one,
+/// with index `cases.len()`, or the goroutine blocks until one can.
+///
+/// A blocked `select` registers with the channels of all its cases, and
+/// offers the values of its send cases to receivers. The goroutine
+/// completing one of its operations chooses that case on its behalf, and
+/// wakes it up.
+#[track_caller]
+pub fn select(cases: &mut [&mut dyn SelectCase], has_default: bool) -> usize {
+    yield_point();
+    let select = Arc::new(SelectWaiter {
+        state: Mutex::new(SelectState {
+            chosen: None,
+            polling: true,
+            dirty: false,
+            asleep: false,
+        }),
+        wakeup: Condvar::new(),
+    });
+    if let Some(case) = poll_select(cases, &select) {
+        finish_select(cases, case);
+        return case;
+    }
+    if has_default {
+        return cases.len();
+    }
+    for (case, op) in cases.iter_mut().enumerate() {
+        op.register(&select, case);
+    }
+    let chosen = loop {
+        if let Some(case) = poll_select(cases, &select) {
+            break case;
+        }
+        let mut state = lock_select(&select);
+        if std::mem::take(&mut state.dirty) {
+            drop(state);
+            std::thread::yield_now();
+            continue;
+        }
+        state.polling = false;
+        state.asleep = true;
+        block_goroutine(GoroutineStatus::Select);
+        let released = release_processor();
+        while state.asleep {
+            state = match select.wakeup.wait(state) {
+                Ok(state) => state,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        let chosen = state.chosen;
+        state.polling = chosen.is_none();
+        state.dirty = false;
+        drop(state);
+        if released {
+            acquire_processor();
+        }
+        set_goroutine_status(GoroutineStatus::Running);
+        if let Some(case) = chosen {
+            break case;
+        }
+    };
+    for op in cases.iter_mut() {
+        op.withdraw(&select);
+    }
+    finish_select(cases, chosen);
+    chosen
+}
+
+fn finish_select(cases: &mut [&mut dyn SelectCase], chosen: usize) {
+    if let Some(op) = cases.get_mut(chosen) {
+        op.finish();
+    }
+}
+
+/// Poll the cases in a random order, so that each ready one is as likely
+/// to be chosen.
+fn poll_select(cases: &mut [&mut dyn SelectCase], select: &Arc<SelectWaiter>) -> Option<usize> {
+    let mut order = (0..cases.len()).collect::<Vec<_>>();
+    for i in (1..order.len()).rev() {
+        order.swap(i, cheaprand_n(i + 1));
+    }
+    order
+        .into_iter()
+        .find(|&case| cases.get_mut(case).is_some_and(|op| op.poll(select, case)))
+}
+
+fn lock_select(select: &SelectWaiter) -> MutexGuard<'_, SelectState> {
+    match select.state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Choose `case` on behalf of a blocked `select`.
+#[allow(clippy::significant_drop_tightening)]
+fn claim_select(select: &SelectWaiter, case: usize) -> Claim {
+    let mut state = lock_select(select);
+    if state.chosen.is_some() {
+        return Claim::Lost;
+    }
+    if state.polling {
+        state.dirty = true;
+        return Claim::Busy;
+    }
+    state.chosen = Some(case);
+    rouse_select(select, &mut state);
+    Claim::Won
+}
+
+/// Have a `select` poll its cases again.
+#[allow(clippy::significant_drop_tightening)]
+fn wake_select(select: &SelectWaiter) {
+    let mut state = lock_select(select);
+    state.dirty = true;
+    rouse_select(select, &mut state);
+}
+
+fn rouse_select(select: &SelectWaiter, state: &mut SelectState) {
+    if std::mem::take(&mut state.asleep) {
+        unblock_goroutines(1);
+        select.wakeup.notify_one();
+    }
+}
+
+/// A pseudo-random number below `n`, from a per-thread xorshift generator.
+fn cheaprand_n(n: usize) -> usize {
+    thread_local! {
+        static STATE: std::cell::Cell<u64> = std::cell::Cell::new(
+            std::hash::BuildHasher::hash_one(&std::collections::hash_map::RandomState::new(), 0) | 1,
+        );
+    }
+    let x = STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        x
+    });
+    let n = u64::try_from(n).unwrap_or(u64::MAX).max(1);
+    usize::try_from(x % n).unwrap_or_default()
+}
+
+/// A goroutine waiting to run.
+type Goroutine = Box<dyn FnOnce() + Send + 'static>;
+
+/// Upper bound on `GOMAXPROCS`, so the processor table is allocated once.
+const MAX_PROCS: usize = 256;
+
+/// How often `sysmon` samples the processors. A processor which does not
+/// schedule for one period while other goroutines are runnable is asked to
+/// yield, and retaken after a second one.
+const SYSMON_PERIOD: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Set while some goroutine should give its processor up at its next
+/// `yield_point`.
+static PREEMPT_REQUESTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// The scheduler side of `yield_point`, installed when the scheduler starts.
+/// Going through a hook keeps the scheduler out of programs which never
+/// start a goroutine.
+static PREEMPT_HOOK: std::sync::OnceLock<fn()> = std::sync::OnceLock::new();
+
+/// `GOMAXPROCS` set before the scheduler started, or 0.
+static REQUESTED_PROCS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+static SCHEDULER: std::sync::OnceLock<Scheduler> = std::sync::OnceLock::new();
+
+/// One live goroutine in `GOROUTINES`.
+const LIVE_GOROUTINE: u64 = 1 << 32;
+
+/// One blocked goroutine in `GOROUTINES`.
+const BLOCKED_GOROUTINE: u64 = 1;
+
+/// The number of live goroutines, the main one included, in the high half,
+/// and of goroutines blocked in a channel operation or `select {}` in the
+/// low half. A single load then tells whether all goroutines are asleep.
+static GOROUTINES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(LIVE_GOROUTINE);
+
+/// A logical processor (Go's P): the right to run goroutines, with a local
+/// run queue. Worker threads (Go's Ms) hold at most one processor at a time,
+/// and release it while blocked.
+struct Processor {
+    runq: Mutex<VecDeque<Goroutine>>,
+    /// Whether a thread holds the processor. Only changed under the
+    /// scheduler state lock.
+    held: std::sync::atomic::AtomicBool,
+    /// Bumped each time the processor is claimed or retaken, so a thread
+    /// whose processor was retaken notices at its next scheduling point.
+    generation: std::sync::atomic::AtomicU64,
+    /// Bumped each time the processor schedules.
+    tick: std::sync::atomic::AtomicU64,
+    /// Set by `sysmon` or `gomaxprocs` to ask the holder to yield.
+    preempt: std::sync::atomic::AtomicBool,
+}
+
+struct SchedulerState {
+    maxprocs: usize,
+    global: VecDeque<Goroutine>,
+    idle: Vec<usize>,
+    /// Processors handed over by `gosched` to a worker thread, to run the
+    /// goroutines queued behind the yielding one.
+    reserved: Vec<usize>,
+    idle_workers: usize,
+    /// Idle workers notified but not awake yet.
+    waking_workers: usize,
+    starting_workers: usize,
+    /// Worker threads started, idle or not.
+    workers: usize,
+    /// Goroutines resuming after a blocking operation wait for a processor
+    /// in order, ahead of idle worker threads. Each waits on its own
+    /// condition variable, so handing a processor over wakes the next one
+    /// only.
+    resuming: VecDeque<Arc<Condvar>>,
+}
+
+struct Scheduler {
+    procs: Vec<Processor>,
+    maxprocs: std::sync::atomic::AtomicUsize,
+    state: Mutex<SchedulerState>,
+    /// Idle worker threads wait here for runnable goroutines.
+    work: Condvar,
+}
+
+/// Start `f` as a goroutine.
+///
+/// Goroutines are queued on the processor of the calling goroutine and run
+/// by a pool of worker threads, which steal from each other's queues and
+/// have stacks of `worker_stack_size` bytes. At most `GOMAXPROCS` of them
+/// run at once. Only runnable goroutines are multiplexed: a goroutine
+/// blocked in a channel or `sync` operation keeps its worker thread, but
+/// not its processor, until it resumes, so a program needs a thread for
+/// each goroutine blocked at the same time.
+#[track_caller]
+pub fn go<F: FnOnce() + Send + 'static>(f: F) {
+    let sched = scheduler();
+    GOROUTINES.fetch_add(LIVE_GOROUTINE, std::sync::atomic::Ordering::SeqCst);
+    let id = LAST_GOROUTINE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+    register_goroutine(id, GoroutineStatus::Runnable);
+    let goroutine: Goroutine = Box::new(move || {
+        goroutine_id().set(id);
+        set_goroutine_status(GoroutineStatus::Running);
+        f();
+    });
+    match owned_processor(sched)
+        .filter(|&p| p < sched.maxprocs.load(std::sync::atomic::Ordering::Relaxed))
+        .and_then(|p| sched.procs.get(p))
+    {
+        Some(proc) => lock_scheduler(&proc.runq).push_back(goroutine),
+        None => lock_scheduler(&sched.state).global.push_back(goroutine),
+    }
+    let mut state = lock_scheduler(&sched.state);
+    wake_worker(sched, &mut state);
+}
+
+/// `runtime.NumGoroutine`: the number of goroutines that exist, the main
+/// one included.
+pub fn num_goroutine() -> isize {
+    let goroutines = GOROUTINES.load(std::sync::atomic::Ordering::SeqCst);
+    isize::try_from(goroutines / LIVE_GOROUTINE).unwrap_or(isize::MAX)
+}
+
+/// `runtime.Gosched`: let the other runnable goroutines run first.
+#[track_caller]
+pub fn gosched() {
+    let (Some(sched), Some((p, generation))) = (SCHEDULER.get(), current_processor()) else {
+        std::thread::yield_now();
+        return;
+    };
+    let Some(proc) = sched.procs.get(p) else {
+        return;
+    };
+    let mut state = lock_scheduler(&sched.state);
+    let owned = processor_is_owned(proc, generation);
+    let retired = p >= state.maxprocs;
+    let waiting = !state.resuming.is_empty();
+    let runnable = has_work(sched, &state);
+    if owned && !retired && !waiting && !runnable {
+        proc.tick.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        return;
+    }
+
+    set_current_processor(None);
+    if owned && !retired && !waiting {
+        proc.held.store(false, std::sync::atomic::Ordering::Release);
+        state.reserved.push(p);
+        wake_worker(sched, &mut state);
+    } else if owned {
+        put_processor(sched, &mut state, p, generation);
+    }
+    drop(state);
+    // Runnable until it gets a processor back, the goroutine does not keep
+    // a collection from stopping the world meanwhile.
+    set_goroutine_status(GoroutineStatus::Runnable);
+    acquire_processor();
+    set_goroutine_status(GoroutineStatus::Running);
+}
+
+/// A cooperative preemption point, emitted at loop back-edges. Goroutines
+/// also stop here for the cycle collector.
+#[inline]
+pub fn yield_point() {
+    if PREEMPT_REQUESTED.load(std::sync::atomic::Ordering::Relaxed)
+        && let Some(hook) = PREEMPT_HOOK.get()
+    {
+        hook();
+    }
+    if GC_STOP_REQUESTED.load(std::sync::atomic::Ordering::Relaxed)
+        && let Some(hook) = GC_SAFEPOINT_HOOK.get()
+    {
+        hook();
+    }
+}
+
+/// `runtime.GOMAXPROCS`: set the number of processors when `n` is
+/// positive, and return the previous setting.
+#[allow(clippy::significant_drop_tightening)]
+pub fn gomaxprocs(n: isize) -> isize {
+    let requested = usize::try_from(n)
+        .ok()
+        .filter(|&n| n > 0)
+        .map(|n| n.min(MAX_PROCS));
+    let previous = if let Some(sched) = SCHEDULER.get() {
+        let mut state = lock_scheduler(&sched.state);
+        let previous = state.maxprocs;
+        if let Some(n) = requested {
+            resize_processors(sched, &mut state, n);
+        }
+        previous
+    } else {
+        let previous = default_maxprocs();
+        if let Some(n) = requested {
+            REQUESTED_PROCS.store(n, std::sync::atomic::Ordering::Relaxed);
+        }
+        previous
+    };
+    isize::try_from(previous).unwrap_or(isize::MAX)
+}
+
+/// Block the calling goroutine forever, as `select {}` does.
+#[track_caller]
+pub fn block_forever() -> ! {
+    park_goroutine(GoroutineStatus::SelectNoCases)
+}
+
+/// Block the calling goroutine forever in `status`, as operations on nil
+/// channels do.
+#[track_caller]
+fn park_goroutine(status: GoroutineStatus) -> ! {
+    block_goroutine(status);
+    release_processor();
+    loop {
+        std::thread::park();
+    }
+}
+
+fn scheduler() -> &'static Scheduler {
+    SCHEDULER.get_or_init(|| {
+        let maxprocs = default_maxprocs();
+        let procs = (0..MAX_PROCS)
+            .map(|p| Processor {
+                runq: Mutex::new(VecDeque::new()),
+                held: std::sync::atomic::AtomicBool::new(p == 0),
+                generation: std::sync::atomic::AtomicU64::new(1),
+                tick: std::sync::atomic::AtomicU64::new(0),
+                preempt: std::sync::atomic::AtomicBool::new(false),
+            })
+            .collect();
+        // The thread starting the first goroutine, normally the main one,
+        // runs on the first processor.
+        set_current_processor(Some((0, 1)));
+        let _ = PREEMPT_HOOK.set(preempt);
+        let _ = std::thread::Builder::new()
+            .name("gors-sysmon".to_string())
+            .spawn(sysmon);
+        Scheduler {
+            procs,
+            maxprocs: std::sync::atomic::AtomicUsize::new(maxprocs),
+            state: Mutex::new(SchedulerState {
+                maxprocs,
+                global: VecDeque::new(),
+                idle: (1..maxprocs).rev().collect(),
+                reserved: Vec::new(),
+                idle_workers: 0,
+                waking_workers: 0,
+                starting_workers: 0,
+                workers: 0,
+                resuming: VecDeque::new(),
+            }),
+            work: Condvar::new(),
+        }
+    })
+}
+
+/// Count the calling goroutine as blocked in `status`, and die like Go if
+/// it was the last one running.
+#[track_caller]
+fn block_goroutine(status: GoroutineStatus) {
+    set_goroutine_status(status);
+    let goroutines = GOROUTINES.fetch_add(BLOCKED_GOROUTINE, std::sync::atomic::Ordering::SeqCst)
+        + BLOCKED_GOROUTINE;
+    check_deadlock(goroutines);
+}
+
+/// Count `n` blocked goroutines as runnable again.
+fn unblock_goroutines(n: usize) {
+    let n = u64::try_from(n).unwrap_or(u64::MAX);
+    GOROUTINES.fetch_sub(n * BLOCKED_GOROUTINE, std::sync::atomic::Ordering::SeqCst);
+}
+
+fn exit_goroutine() {
+    lock_scheduler(&GOROUTINE_REGISTRY).remove(&goroutine_id().get());
+    let goroutines =
+        GOROUTINES.fetch_sub(LIVE_GOROUTINE, std::sync::atomic::Ordering::SeqCst) - LIVE_GOROUTINE;
+    check_deadlock(goroutines);
+}
+
+fn check_deadlock(goroutines: u64) {
+    if !MAIN_STARTED.load(std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+    let main_exited = MAIN_EXITED.load(std::sync::atomic::Ordering::SeqCst);
+    if let Some(message) = deadlock(goroutines, main_exited) {
+        throw(message);
+    }
+}
+
+/// The fatal error of a program with `goroutines` live and blocked ones, if
+/// none of them can run anymore.
+fn deadlock(goroutines: u64, main_exited: bool) -> Option<&'static str> {
+    if goroutines < LIVE_GOROUTINE && main_exited {
+        return Some("no goroutines (main called runtime.Goexit) - deadlock!");
+    }
+    all_goroutines_asleep(goroutines).then_some("all goroutines are asleep - deadlock!")
+}
+
+/// Whether `run_main` started the program. Only then is every thread
+/// blocking on channels a counted goroutine: tests block from their own
+/// threads.
+static MAIN_STARTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Whether the main goroutine called `runtime.Goexit`.
+static MAIN_EXITED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Terminate the main goroutine after `runtime.Goexit`. The program keeps
+/// running the other goroutines, and crashes once none is left.
+fn main_goexit() -> ! {
+    MAIN_EXITED.store(true, std::sync::atomic::Ordering::SeqCst);
+    exit_goroutine();
+    loop {
+        std::thread::park();
+    }
+}
+
+/// Report a fatal error of the runtime, with the stacks `GOTRACEBACK` asks
+/// for, then crash.
+fn throw(message: &str) -> ! {
+    ::std::eprintln!("fatal error: {message}");
+    if traceback().level > 0 {
+        let dump = goroutine_dump(None);
+        if !dump.is_empty() {
+            ::std::eprint!("\n{dump}");
+        }
+    }
+    crash_exit()
+}
+
+/// Whether every live goroutine is blocked. A goroutine being notified
+/// counts as runnable, so nothing can wake any of them up anymore.
+fn all_goroutines_asleep(goroutines: u64) -> bool {
+    goroutines / LIVE_GOROUTINE == goroutines % LIVE_GOROUTINE
+}
+
+fn default_maxprocs() -> usize {
+    let requested = REQUESTED_PROCS.load(std::sync::atomic::Ordering::Relaxed);
+    if requested > 0 {
+        return requested;
+    }
+    std::env::var("GOMAXPROCS")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .or_else(|| {
+            std::thread::available_parallelism()
+                .ok()
+                .map(std::num::NonZeroUsize::get)
+        })
+        .unwrap_or(1)
+        .min(MAX_PROCS)
+}
+
+fn lock_scheduler<T>(lock: &Mutex<T>) -> MutexGuard<'_, T> {
+    match lock.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn wait_scheduler<'a, T>(cvar: &Condvar, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+    match cvar.wait(guard) {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// The processor of the calling thread, with the generation it was claimed
+/// at.
+fn with_current_processor<R>(f: impl FnOnce(&std::cell::Cell<Option<(usize, u64)>>) -> R) -> R {
+    thread_local! {
+        static CURRENT_PROCESSOR: std::cell::Cell<Option<(usize, u64)>> =
+            const { std::cell::Cell::new(None) };
+    }
+    CURRENT_PROCESSOR.with(f)
+}
+
+fn current_processor() -> Option<(usize, u64)> {
+    with_current_processor(std::cell::Cell::get)
+}
+
+fn set_current_processor(current: Option<(usize, u64)>) {
+    with_current_processor(|cell| cell.set(current));
+}
+
+fn processor_is_owned(proc: &Processor, generation: u64) -> bool {
+    proc.held.load(std::sync::atomic::Ordering::Acquire)
+        && proc.generation.load(std::sync::atomic::Ordering::Acquire) == generation
+}
+
+/// The processor of the calling thread, unless it was retaken.
+fn owned_processor(sched: &Scheduler) -> Option<usize> {
+    let (p, generation) = current_processor()?;
+    sched
+        .procs
+        .get(p)
+        .is_some_and(|proc| processor_is_owned(proc, generation))
+        .then_some(p)
+}
+
+fn has_work(sched: &Scheduler, state: &SchedulerState) -> bool {
+    !state.global.is_empty()
+        || sched
+            .procs
+            .iter()
+            .take(state.maxprocs)
+            .any(|proc| !lock_scheduler(&proc.runq).is_empty())
+}
+
+/// Make sure a worker thread picks up the queued goroutines, if a
+/// processor is free to run them.
+fn wake_worker(sched: &'static Scheduler, state: &mut SchedulerState) {
+    if state.idle.is_empty() && state.reserved.is_empty() {
+        return;
+    }
+    if state.idle_workers > state.waking_workers {
+        state.waking_workers += 1;
+        sched.work.notify_one();
+    } else if state.starting_workers == 0 {
+        start_worker(sched, &mut *state);
+    }
+}
+
+/// Start a worker thread, or die like Go when the system refuses to create
+/// one: the goroutines blocked on the other worker threads could be waiting
+/// for the queued ones.
+fn start_worker(sched: &'static Scheduler, state: &mut SchedulerState) {
+    let spawned = std::thread::Builder::new()
+        .name("gors-worker".to_string())
+        .stack_size(worker_stack_size())
+        .spawn(move || worker(sched));
+    if let Err(err) = spawned {
+        newosproc_failed(state.workers + 1, &err);
+    }
+    state.starting_workers += 1;
+    state.workers += 1;
+}
+
+/// Die like Go when the system refuses to create a thread, with `threads`
+/// of them running already. There are no goroutine stacks to go with it:
+/// a system out of threads is usually out of memory mappings too, so the
+/// dump could not be allocated.
+fn newosproc_failed(threads: usize, err: &std::io::Error) -> ! {
+    let errno = err.raw_os_error().unwrap_or_default();
+    ::std::eprintln!(
+        "runtime: failed to create new OS thread (have {threads} already; errno={errno})"
+    );
+    if err.kind() == std::io::ErrorKind::WouldBlock {
+        ::std::eprintln!("runtime: may need to increase max user processes (ulimit -u)");
+    }
+    ::std::eprintln!("fatal error: newosproc");
+    crash_exit()
+}
+
+fn claim_processor(sched: &Scheduler, p: usize) {
+    let Some(proc) = sched.procs.get(p) else {
+        return;
+    };
+    let generation = proc
+        .generation
+        .fetch_add(1, std::sync::atomic::Ordering::AcqRel)
+        + 1;
+    proc.held.store(true, std::sync::atomic::Ordering::Release);
+    proc.preempt
+        .store(false, std::sync::atomic::Ordering::Relaxed);
+    proc.tick.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    set_current_processor(Some((p, generation)));
+}
+
+/// Give processor `p` back, unless it was retaken since it was claimed at
+/// `generation`, and hand it to whoever needs it.
+fn put_processor(sched: &'static Scheduler, state: &mut SchedulerState, p: usize, generation: u64) {
+    let Some(proc) = sched.procs.get(p) else {
+        return;
+    };
+    if !processor_is_owned(proc, generation) {
+        return;
+    }
+    proc.held.store(false, std::sync::atomic::Ordering::Release);
+    if p < state.maxprocs {
+        state.idle.push(p);
+    } else {
+        let retired = lock_scheduler(&proc.runq).drain(..).collect::<Vec<_>>();
+        state.global.extend(retired);
+    }
+    hand_off(sched, &mut *state);
+}
+
+fn hand_off(sched: &'static Scheduler, state: &mut SchedulerState) {
+    if let Some(next) = state.resuming.front() {
+        next.notify_one();
+    } else if has_work(sched, state) {
+        wake_worker(sched, &mut *state);
+    }
+}
+
+/// Release the processor of the calling thread before it blocks. Returns
+/// whether it had one, and so must call `acquire_processor` once woken.
+fn release_processor() -> bool {
+    let (Some(sched), Some((p, generation))) = (SCHEDULER.get(), current_processor()) else {
+        return false;
+    };
+    set_current_processor(None);
+    let mut state = lock_scheduler(&sched.state);
+    put_processor(sched, &mut state, p, generation);
+    true
+}
+
+/// Wait for a processor, in the order goroutines asked for one.
+fn acquire_processor() {
+    let Some(sched) = SCHEDULER.get() else {
+        return;
+    };
+    let turn = Arc::new(Condvar::new());
+    let mut state = lock_scheduler(&sched.state);
+    state.resuming.push_back(Arc::clone(&turn));
+    loop {
+        if state
+            .resuming
+            .front()
+            .is_some_and(|next| Arc::ptr_eq(next, &turn))
+            && let Some(p) = state.idle.pop()
+        {
+            state.resuming.pop_front();
+            claim_processor(sched, p);
+            hand_off(sched, &mut state);
+            return;
+        }
+        state = wait_scheduler(&turn, state);
+    }
+}
+
+fn worker(sched: &'static Scheduler) {
+    set_stack_limit(worker_stack_size());
+    let mut state = lock_scheduler(&sched.state);
+    state.starting_workers -= 1;
+    loop {
+        let p = if let Some(p) = state.reserved.pop() {
+            p
+        } else if state.resuming.is_empty()
+            && has_work(sched, &state)
+            && let Some(p) = state.idle.pop()
+        {
+            p
+        } else {
+            state.idle_workers += 1;
+            state = wait_scheduler(&sched.work, state);
+            state.idle_workers -= 1;
+            state.waking_workers = state.waking_workers.saturating_sub(1);
+            continue;
+        };
+        claim_processor(sched, p);
+        drop(state);
+        run_processor(sched);
+        state = lock_scheduler(&sched.state);
+    }
+}
+
+/// Run goroutines on the processor of the calling worker thread until none
+/// is left, or a resuming goroutine is waiting for the processor.
+#[allow(clippy::significant_drop_tightening)]
+fn run_processor(sched: &'static Scheduler) {
+    while let Some(p) = owned_processor(sched) {
+        let goroutine = {
+            let mut state = lock_scheduler(&sched.state);
+            if p >= state.maxprocs || !state.resuming.is_empty() {
+                break;
+            }
+            let goroutine = find_goroutine(sched, &mut state, p);
+            if goroutine.is_some() && has_work(sched, &state) {
+                wake_worker(sched, &mut state);
+            }
+            goroutine
+        };
+        let Some(goroutine) = goroutine else {
+            break;
+        };
+        if let Some(proc) = sched.procs.get(p) {
+            proc.tick.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        // An unrecovered panic crashes the program, but not the tests
+        // sharing the process.
+        if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(goroutine))
+            && !payload.is::<Goexit>()
+            && !cfg!(test)
+        {
+            fatal_panic(go_panic_payload(payload).as_ref());
+        }
+        with_goroutine_panics(|panics| *panics = GoroutinePanics::default());
+        exit_goroutine();
+    }
+    release_processor();
+}
+
+/// The next goroutine for processor `p`: from its own queue, then the
+/// global one, then half of another processor's queue.
+fn find_goroutine(sched: &Scheduler, state: &mut SchedulerState, p: usize) -> Option<Goroutine> {
+    let own = sched.procs.get(p)?;
+    let local = lock_scheduler(&own.runq).pop_front();
+    if let Some(goroutine) = local.or_else(|| state.global.pop_front()) {
+        return Some(goroutine);
+    }
+    let maxprocs = state.maxprocs;
+    let victims = sched.procs.iter().take(maxprocs).cycle().skip(p + 1);
+    for victim in victims.take(maxprocs.saturating_sub(1)) {
+        let mut stolen = {
+            let mut runq = lock_scheduler(&victim.runq);
+            let half = runq.len() - runq.len() / 2;
+            runq.drain(..half).collect::<VecDeque<_>>()
+        };
+        if let Some(goroutine) = stolen.pop_front() {
+            lock_scheduler(&own.runq).extend(stolen);
+            return Some(goroutine);
+        }
+    }
+    None
+}
+
+fn resize_processors(sched: &'static Scheduler, state: &mut SchedulerState, n: usize) {
+    let previous = state.maxprocs;
+    state.maxprocs = n;
+    sched
+        .maxprocs
+        .store(n, std::sync::atomic::Ordering::Relaxed);
+    state.idle.retain(|&p| p < n);
+    for (p, proc) in sched.procs.iter().enumerate().take(previous.max(n)) {
+        let held = proc.held.load(std::sync::atomic::Ordering::Acquire);
+        if p >= n && held {
+            proc.preempt
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            PREEMPT_REQUESTED.store(true, std::sync::atomic::Ordering::Relaxed);
+        } else if p >= n {
+            let retired = lock_scheduler(&proc.runq).drain(..).collect::<Vec<_>>();
+            state.global.extend(retired);
+        } else if p >= previous && !held && !state.reserved.contains(&p) {
+            state.idle.push(p);
+        }
+    }
+    hand_off(sched, &mut *state);
+}
+
+/// Installed as `PREEMPT_HOOK`: yield if asked to, or if the processor of
+/// the calling thread was retaken or retired.
+fn preempt() {
+    let (Some(sched), Some((p, generation))) = (SCHEDULER.get(), current_processor()) else {
+        return;
+    };
+    let Some(proc) = sched.procs.get(p) else {
+        return;
+    };
+    if proc
+        .preempt
+        .swap(false, std::sync::atomic::Ordering::Relaxed)
+        || !processor_is_owned(proc, generation)
+        || p >= sched.maxprocs.load(std::sync::atomic::Ordering::Relaxed)
+    {
+        gosched();
+    }
+}
+
+/// Background monitor preempting goroutines which hold a processor for too
+/// long while others are runnable, and retaking processors from threads
+/// stuck outside of the scheduler.
+#[allow(clippy::significant_drop_tightening)]
+fn sysmon() {
+    let mut samples = vec![(0_u64, 0_u32); MAX_PROCS];
+    loop {
+        std::thread::sleep(SYSMON_PERIOD);
+        let Some(sched) = SCHEDULER.get() else {
+            continue;
+        };
+        let mut state = lock_scheduler(&sched.state);
+        let contended = !state.resuming.is_empty() || has_work(sched, &state);
+        let mut preempting = false;
+        for (p, (proc, sample)) in sched.procs.iter().zip(samples.iter_mut()).enumerate() {
+            let tick = proc.tick.load(std::sync::atomic::Ordering::Relaxed);
+            let held = proc.held.load(std::sync::atomic::Ordering::Acquire);
+            if held && p >= state.maxprocs {
+                preempting = true;
+            }
+            if !held || !contended || tick != sample.0 {
+                *sample = (tick, 0);
+                if p < state.maxprocs {
+                    proc.preempt
+                        .store(false, std::sync::atomic::Ordering::Relaxed);
+                }
+                continue;
+            }
+            sample.1 += 1;
+            if sample.1 == 1 {
+                proc.preempt
+                    .store(true, std::sync::atomic::Ordering::Relaxed);
+                preempting = true;
+                continue;
+            }
+            let generation = proc
+                .generation
+                .fetch_add(1, std::sync::atomic::Ordering::AcqRel)
+                + 1;
+            put_processor(sched, &mut state, p, generation);
+            *sample = (tick, 0);
+        }
+        PREEMPT_REQUESTED.store(preempting, std::sync::atomic::Ordering::Relaxed);
+        if contended {
+            hand_off(sched, &mut state);
+        }
+    }
+}
+
+#[inline]
+#[allow(clippy::panic)]
+pub fn r#panic<T: Any + Send + 'static>(value: T) -> ! {
+    std::panic::panic_any(value)
+}
+
+#[inline]
+pub fn panic_value<T: Any + Send + 'static>(value: T) -> ! {
+    r#panic(value)
+}
 
 fn any_box_to_send(value: Box<dyn Any>) -> Box<dyn Any + Send> {
     macro_rules! move_if {
@@ -2281,36 +5128,1842 @@ fn any_box_to_send(value: Box<dyn Any>) -> Box<dyn Any + Send> {
 }
 
 #[inline]
-pub fn panic_any_payload(value: Box<dyn Any>) -> ! {
-    std::panic::resume_unwind(any_box_to_send(value))
-}
-
-#[inline]
-pub fn set_recover_payload<T: Any + Send + 'static>(value: T) {
-    *recover_payload_lock() = Some(Box::new(value));
+pub fn panic_any_payload(value: GorsAny) -> ! {
+    std::panic::resume_unwind(value.into_send())
+}
+
+type PanicPayload = Box<dyn Any + Send>;
+
+/// The panics of the calling goroutine. A goroutine runs on a single thread
+/// from start to finish, so they are kept in a thread local.
+#[derive(Default)]
+struct GoroutinePanics {
+    /// The panic caught by the body of the function about to run its
+    /// deferred calls.
+    pending: Option<PanicPayload>,
+    /// Each function running its deferred calls, innermost last.
+    deferring: Vec<DeferringFrame>,
+    /// The panics still on the panic stack of the goroutine below the one
+    /// unwinding, oldest first: printed ahead of it if it is not recovered.
+    earlier: Vec<EarlierPanic>,
+}
+
+/// A function running its deferred calls.
+struct DeferringFrame {
+    /// Its panic. Recovering it clears it.
+    panic: Option<PanicPayload>,
+    /// The panic recovered by the deferred call running, if any.
+    recovered: Option<EarlierPanic>,
+    /// The length of `GoroutinePanics::earlier` when the frame started.
+    earlier: usize,
+}
+
+/// A panic aborted by a later one, or recovered by a deferred call which
+/// then panicked.
+struct EarlierPanic {
+    message: std::string::String,
+    type_id: TypeId,
+    recovered: bool,
+}
+
+impl EarlierPanic {
+    fn new(payload: &(dyn Any + Send), recovered: bool) -> Self {
+        let value = panic_value_ref(payload);
+        Self {
+            message: describe_panic(value),
+            type_id: value.type_id(),
+            recovered,
+        }
+    }
 }
 
-#[inline]
-pub fn set_recover_payload_any(value: Box<dyn Any>) {
-    *recover_payload_lock() = Some(any_box_to_send(value));
+fn with_goroutine_panics<R>(f: impl FnOnce(&mut GoroutinePanics) -> R) -> R {
+    thread_local! {
+        static GOROUTINE_PANICS: std::cell::RefCell<GoroutinePanics> =
+            std::cell::RefCell::new(GoroutinePanics::default());
+    }
+    GOROUTINE_PANICS.with(|panics| f(&mut panics.borrow_mut()))
+}
+
+/// The payload `runtime.Goexit` unwinds with. It runs the deferred calls
+/// like a panic, but cannot be recovered.
+struct Goexit;
+
+/// `runtime.Goexit`: run the deferred calls of the calling goroutine, then
+/// terminate it.
+pub fn goexit() -> ! {
+    std::panic::resume_unwind(Box::new(Goexit))
+}
+
+/// Run the main goroutine on a thread with a goroutine stack, and crash the
+/// program like Go if it panics.
+pub fn run_main(main: impl FnOnce() + Send + 'static) {
+    register_goroutine(1, GoroutineStatus::Running);
+    MAIN_STARTED.store(true, std::sync::atomic::Ordering::SeqCst);
+    // Panics are reported once known to be unrecovered, by `fatal_panic`,
+    // with the stack they started unwinding from.
+    std::panic::set_hook(Box::new(|_| {
+        let stack = std::backtrace::Backtrace::force_capture();
+        with_panic_stack(|panic_stack| *panic_stack = Some(stack));
+    }));
+    let spawned = std::thread::Builder::new()
+        .name("gors-main".to_string())
+        .stack_size(goroutine_stack_size())
+        .spawn(move || {
+            set_stack_limit(goroutine_stack_size());
+            if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(main)) {
+                if payload.is::<Goexit>() {
+                    main_goexit();
+                }
+                fatal_panic(go_panic_payload(payload).as_ref());
+            }
+        });
+    match spawned {
+        Ok(main) => {
+            let _ = main.join();
+            finish_race_detection();
+        }
+        Err(err) => newosproc_failed(1, &err),
+    }
 }
 
-#[inline]
-pub fn set_recover_payload_box(value: Box<dyn Any + Send>) {
-    *recover_payload_lock() = Some(value);
+/// The size of the stack the main goroutine runs on, and the limit stack
+/// overflows report.
+///
+/// It is `GORS_STACK_SIZE` bytes, with an optional `K`, `M` or `G` suffix,
+/// or Go's maximum stack size. Thread stacks are reserved up front but only
+/// committed as they are used.
+pub fn goroutine_stack_size() -> usize {
+    static STACK_SIZE: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+    *STACK_SIZE.get_or_init(|| {
+        configured_stack_size().unwrap_or(if cfg!(target_pointer_width = "64") {
+            1_000_000_000
+        } else {
+            250_000_000
+        })
+    })
+}
+
+/// The size of the stacks worker threads run goroutines on.
+///
+/// It is `GORS_STACK_SIZE` too when that is set. Otherwise it is smaller
+/// than Go's maximum, because every worker thread reserves its whole stack
+/// and a program has one for each goroutine blocked at the same time: 10,000
+/// blocked goroutines reserve 2.5 TiB of address space on 64-bit targets.
+/// Only the pages a goroutine touches are committed, but the reservations
+/// count against `ulimit -v` and strict overcommit, where programs blocking
+/// many goroutines need a smaller `GORS_STACK_SIZE`.
+fn worker_stack_size() -> usize {
+    configured_stack_size().unwrap_or(if cfg!(target_pointer_width = "64") {
+        256 << 20
+    } else {
+        64 << 20
+    })
+}
+
+fn configured_stack_size() -> Option<usize> {
+    std::env::var("GORS_STACK_SIZE")
+        .ok()
+        .and_then(|value| parse_stack_size(&value))
+}
+
+/// The value of the `name` setting of `GODEBUG`, a comma-separated list of
+/// `name=value` settings where the last one wins.
+fn godebug(name: &str) -> Option<std::string::String> {
+    godebug_setting(&std::env::var("GODEBUG").ok()?, name)
+}
+
+fn godebug_setting(settings: &str, name: &str) -> Option<std::string::String> {
+    settings
+        .split(',')
+        .rev()
+        .filter_map(|setting| setting.split_once('='))
+        .find(|(setting, _)| setting.trim() == name)
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// How crashes print the goroutine stacks, from `GOTRACEBACK`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Traceback {
+    /// 0 prints no stacks.
+    level: u32,
+    /// Whether the stacks of the other goroutines are printed too.
+    all: bool,
+    /// Whether the program aborts, to dump core, rather than exit.
+    crash: bool,
+}
+
+impl Traceback {
+    fn parse(value: &str) -> Self {
+        let (level, all, crash) = match value {
+            "none" => (0, false, false),
+            "single" | "" => (1, false, false),
+            "all" => (1, true, false),
+            "system" => (2, true, false),
+            "crash" => (2, true, true),
+            _ => (value.parse().unwrap_or(0), true, false),
+        };
+        Self { level, all, crash }
+    }
+}
+
+fn traceback() -> Traceback {
+    static TRACEBACK: std::sync::OnceLock<Traceback> = std::sync::OnceLock::new();
+    *TRACEBACK.get_or_init(|| Traceback::parse(&std::env::var("GOTRACEBACK").unwrap_or_default()))
+}
+
+/// Terminate the program after a fatal error: exit with status 2, or
+/// abort under `GOTRACEBACK=crash`.
+fn crash_exit() -> ! {
+    if traceback().crash {
+        std::process::abort()
+    }
+    std::process::exit(2)
+}
+
+fn parse_stack_size(value: &str) -> Option<usize> {
+    let value = value.trim();
+    let (digits, unit) = match value.char_indices().last()? {
+        (end, 'k' | 'K') => (value.get(..end)?, 1 << 10),
+        (end, 'm' | 'M') => (value.get(..end)?, 1 << 20),
+        (end, 'g' | 'G') => (value.get(..end)?, 1 << 30),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .filter(|&n| n > 0)
+}
+
+/// What the program prints when a goroutine overflows its stack, before
+/// exiting with status 2. Like Go, it reports the maximum stack size, even
+/// for goroutines on the smaller stacks of worker threads.
+fn stack_overflow_message() -> std::string::String {
+    format!(
+        "runtime: goroutine stack exceeds {}-byte limit\nfatal error: stack overflow\n",
+        goroutine_stack_size()
+    )
+}
+
+/// How far short of the end of its stack a thread reports an overflow, to
+/// leave room for the frames between two checks and for the report.
+const STACK_HEADROOM: usize = 256 << 10;
+
+/// The lowest address the stack of the calling thread may grow down to, or
+/// 0 on threads the runtime did not start, which are never checked.
+fn stack_limit() -> &'static std::thread::LocalKey<std::cell::Cell<usize>> {
+    thread_local! {
+        static STACK_LIMIT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+    &STACK_LIMIT
+}
+
+/// The address of the top of the stack of the calling thread, roughly.
+#[inline(always)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::ptr::addr_of!(marker).addr()
+}
+
+/// Record that the calling thread, which has barely used its stack yet,
+/// runs on a stack of `size` bytes.
+fn set_stack_limit(size: usize) {
+    let headroom = STACK_HEADROOM.min(size / 4);
+    stack_limit().set(stack_position().saturating_sub(size - headroom));
 }
 
+/// Crash like Go when the calling goroutine has used up its stack.
+///
+/// Generated functions call it on entry, so deep recursion stops here
+/// instead of on the guard page, where the Rust runtime would abort with a
+/// message of its own.
 #[inline]
-pub fn recover() -> Box<dyn Any + Send> {
-    recover_payload_lock()
-        .take()
-        .unwrap_or_else(|| Box::new(()))
+pub fn check_stack() {
+    if stack_position() < stack_limit().get() {
+        stack_overflow();
+    }
+}
+
+#[cold]
+fn stack_overflow() -> ! {
+    ::std::eprint!("{}", stack_overflow_message());
+    crash_exit()
+}
+
+/// Print the unrecovered panic unwinding the calling goroutine, along with
+/// the earlier panics still on its panic stack and the stacks `GOTRACEBACK`
+/// asks for, then crash.
+fn fatal_panic(payload: &(dyn Any + Send)) -> ! {
+    static CRASHING: Mutex<()> = Mutex::new(());
+    // The first goroutine to crash reports; the others wait for the exit.
+    let _crashing = CRASHING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let report = with_goroutine_panics(|panics| panic_report(&panics.earlier, payload));
+    let goroutine = goroutine_id().get();
+    let record = running_goroutine(goroutine);
+    let stack = with_panic_stack(Option::take);
+    ::std::eprintln!("{report}");
+    let traceback = traceback();
+    if traceback.level > 0 {
+        let mut dump = std::string::String::new();
+        write_goroutine(&mut dump, goroutine, record, stack.as_ref());
+        let others = if traceback.all {
+            goroutine_dump(Some(goroutine))
+        } else {
+            std::string::String::new()
+        };
+        if !others.is_empty() {
+            dump.push('\n');
+            dump.push_str(&others);
+        }
+        ::std::eprint!("\n{dump}");
+    }
+    crash_exit()
+}
+
+/// The `panic: ...` lines Go prints for an unrecovered panic.
+fn panic_report(earlier: &[EarlierPanic], payload: &(dyn Any + Send)) -> std::string::String {
+    let last = EarlierPanic::new(payload, false);
+    let mut lines: Vec<(&EarlierPanic, &str)> = Vec::new();
+    for panic in earlier.iter().chain(std::iter::once(&last)) {
+        // A recovered value panicking again is printed once.
+        if let Some((previous, annotation)) = lines.last_mut()
+            && *annotation == " [recovered]"
+            && previous.type_id == panic.type_id
+            && previous.message == panic.message
+        {
+            *annotation = " [recovered, repanicked]";
+            continue;
+        }
+        let annotation = if panic.recovered { " [recovered]" } else { "" };
+        lines.push((panic, annotation));
+    }
+    let mut report = std::string::String::new();
+    for (i, (panic, annotation)) in lines.into_iter().enumerate() {
+        if i > 0 {
+            report.push_str("\n\t");
+        }
+        report.push_str("panic: ");
+        report.push_str(&panic.message);
+        report.push_str(annotation);
+    }
+    report
+}
+
+/// The value a panic payload holds, out of the boxes `any` values and
+/// resumed panics wrap it in.
+fn panic_value_ref(payload: &dyn Any) -> &dyn Any {
+    let mut value = payload;
+    loop {
+        if let Some(inner) = value.downcast_ref::<Box<dyn GorsAnyComparable>>() {
+            value = inner.as_any();
+        } else if let Some(inner) = value.downcast_ref::<Box<dyn Any>>() {
+            value = inner.as_ref();
+        } else if let Some(inner) = value.downcast_ref::<Box<dyn Any + Send>>() {
+            value = inner.as_ref();
+        } else if let Some(inner) = value.downcast_ref::<Box<dyn Any + Send + Sync>>() {
+            value = inner.as_ref();
+        } else {
+            return value;
+        }
+    }
+}
+
+/// Format a panic value as Go prints it: errors through their `Error`
+/// method, and the other values as the `print` builtin does.
+fn describe_panic(value: &dyn Any) -> std::string::String {
+    macro_rules! describe_if {
+        ($ty:ty, $describe:expr) => {
+            if let Some(v) = value.downcast_ref::<$ty>() {
+                return $describe(v);
+            }
+        };
+    }
+
+    describe_if!(std::string::String, std::string::String::clone);
+    describe_if!(&'static str, |v: &&str| (*v).to_string());
+    describe_if!(Box<dyn error>, <Box<dyn error> as error>::Error);
+    describe_if!(bool, ToString::to_string);
+    describe_if!(isize, ToString::to_string);
+    describe_if!(i8, ToString::to_string);
+    describe_if!(i16, ToString::to_string);
+    describe_if!(i32, ToString::to_string);
+    describe_if!(i64, ToString::to_string);
+    describe_if!(usize, ToString::to_string);
+    describe_if!(u8, ToString::to_string);
+    describe_if!(u16, ToString::to_string);
+    describe_if!(u32, ToString::to_string);
+    describe_if!(u64, ToString::to_string);
+    describe_if!(f32, |v: &f32| go_print_float(f64::from(*v)));
+    describe_if!(f64, |v: &f64| go_print_float(*v));
+    if value.is::<()>() {
+        return "nil".to_string();
+    }
+    let descriptions = PANIC_DESCRIPTIONS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    descriptions
+        .iter()
+        .find_map(|(_, describe)| describe(value))
+        .unwrap_or_else(|| {
+            format!(
+                "(unknown type) {:p}",
+                std::ptr::from_ref(value).cast::<()>()
+            )
+        })
+}
+
+/// Format a panic value of the named Go type `type_name` as Go does: the
+/// values of basic underlying types like `main.T(1)` or `main.T("text")`,
+/// the other values like `(main.T) 0xc000012345`.
+pub fn describe_named_panic(type_name: &str, value: &dyn Any) -> std::string::String {
+    if let Some(text) = value.downcast_ref::<std::string::String>() {
+        return format!("{type_name}(\"{text}\")");
+    }
+    let basic = [
+        TypeId::of::<bool>(),
+        TypeId::of::<isize>(),
+        TypeId::of::<i8>(),
+        TypeId::of::<i16>(),
+        TypeId::of::<i32>(),
+        TypeId::of::<i64>(),
+        TypeId::of::<usize>(),
+        TypeId::of::<u8>(),
+        TypeId::of::<u16>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<u64>(),
+        TypeId::of::<f32>(),
+        TypeId::of::<f64>(),
+    ];
+    if basic.contains(&value.type_id()) {
+        return format!("{type_name}({})", describe_panic(value));
+    }
+    format!("({type_name}) {:p}", std::ptr::from_ref(value).cast::<()>())
+}
+
+/// Format a float as the `print` builtin does, e.g. `+1.500000e+000`.
+fn go_print_float(v: f64) -> std::string::String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v > 0.0 { "+Inf" } else { "-Inf" }.to_string();
+    }
+    let formatted = format!("{v:+.6e}");
+    let (mantissa, exponent) = formatted
+        .split_once('e')
+        .unwrap_or((formatted.as_str(), "0"));
+    let (exponent_sign, exponent) = match exponent.strip_prefix('-') {
+        Some(exponent) => ('-', exponent),
+        None => ('+', exponent),
+    };
+    format!("{mantissa}e{exponent_sign}{exponent:0>3}")
+}
+
+type PanicDescription = Box<dyn Fn(&dyn Any) -> Option<std::string::String> + Send + Sync>;
+
+/// How to print the panic values of the types registered by
+/// `with_panic_description`.
+static PANIC_DESCRIPTIONS: Mutex<Vec<(TypeId, PanicDescription)>> = Mutex::new(Vec::new());
+
+/// Print `value`, and the other values of its type, with `describe` if they
+/// crash the program as a panic value. Go prints errors with their `Error`
+/// method.
+pub fn with_panic_description<T: Any + Send>(
+    value: T,
+    describe: fn(&T) -> std::string::String,
+) -> T {
+    let mut descriptions = PANIC_DESCRIPTIONS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !descriptions
+        .iter()
+        .any(|(type_id, _)| *type_id == TypeId::of::<T>())
+    {
+        let description: PanicDescription =
+            Box::new(move |value| value.downcast_ref::<T>().map(describe));
+        descriptions.push((TypeId::of::<T>(), description));
+    }
+    drop(descriptions);
+    value
+}
+
+/// The ID of the goroutine running on the calling thread. The main one is 1.
+fn goroutine_id() -> &'static std::thread::LocalKey<std::cell::Cell<u64>> {
+    thread_local! {
+        static GOROUTINE_ID: std::cell::Cell<u64> = const { std::cell::Cell::new(1) };
+    }
+    &GOROUTINE_ID
+}
+
+/// The ID of the last goroutine started.
+static LAST_GOROUTINE_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// What a goroutine is doing, as goroutine dumps show it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GoroutineStatus {
+    Runnable,
+    Running,
+    ChanReceive,
+    ChanSend,
+    ChanReceiveNilChan,
+    ChanSendNilChan,
+    Select,
+    SelectNoCases,
+    Sleep,
+    Semacquire,
+    SyncWaitGroupWait,
+    SyncMutexLock,
+    SyncRWMutexRLock,
+    SyncRWMutexLock,
+    SyncCondWait,
+}
+
+impl GoroutineStatus {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Runnable => "runnable",
+            Self::Running => "running",
+            Self::ChanReceive => "chan receive",
+            Self::ChanSend => "chan send",
+            Self::ChanReceiveNilChan => "chan receive (nil chan)",
+            Self::ChanSendNilChan => "chan send (nil chan)",
+            Self::Select => "select",
+            Self::SelectNoCases => "select (no cases)",
+            Self::Sleep => "sleep",
+            Self::Semacquire => "semacquire",
+            Self::SyncWaitGroupWait => "sync.WaitGroup.Wait",
+            Self::SyncMutexLock => "sync.Mutex.Lock",
+            Self::SyncRWMutexRLock => "sync.RWMutex.RLock",
+            Self::SyncRWMutexLock => "sync.RWMutex.Lock",
+            Self::SyncCondWait => "sync.Cond.Wait",
+        }
+    }
+}
+
+/// A live goroutine, as goroutine dumps show it.
+#[derive(Clone, Copy)]
+struct GoroutineRecord {
+    status: GoroutineStatus,
+    /// The goroutine which started this one.
+    creator: u64,
+    /// The `go` statement which started the goroutine.
+    go_statement: Option<&'static std::panic::Location<'static>>,
+    /// The operation the goroutine stopped running at. The stack of a
+    /// goroutine can only be captured by the goroutine itself, which costs
+    /// too much to do at every blocking operation, so the dumps printed by
+    /// other goroutines show this frame only.
+    stopped_at: Option<&'static std::panic::Location<'static>>,
+}
+
+/// The live goroutines, by ID.
+static GOROUTINE_REGISTRY: Mutex<std::collections::BTreeMap<u64, GoroutineRecord>> =
+    Mutex::new(std::collections::BTreeMap::new());
+
+/// Register goroutine `id`, started by the calling goroutine.
+#[track_caller]
+fn register_goroutine(id: u64, status: GoroutineStatus) {
+    let record = GoroutineRecord {
+        status,
+        creator: goroutine_id().get(),
+        go_statement: (id != 1).then_some(std::panic::Location::caller()),
+        stopped_at: None,
+    };
+    lock_scheduler(&GOROUTINE_REGISTRY).insert(id, record);
+}
+
+/// The record of goroutine `id`, which the calling thread runs.
+fn running_goroutine(id: u64) -> GoroutineRecord {
+    let record = lock_scheduler(&GOROUTINE_REGISTRY).get(&id).copied();
+    GoroutineRecord {
+        status: GoroutineStatus::Running,
+        ..record.unwrap_or(GoroutineRecord {
+            status: GoroutineStatus::Running,
+            creator: 0,
+            go_statement: None,
+            stopped_at: None,
+        })
+    }
+}
+
+/// Record what the calling goroutine is doing, and where in the Go code it
+/// stopped running unless it runs. The blocking operations of the runtime
+/// track their caller, so the location is the one of the Go operation.
+#[track_caller]
+fn set_goroutine_status(status: GoroutineStatus) {
+    let stopped_at = (status != GoroutineStatus::Running).then_some(std::panic::Location::caller());
+    if let Some(record) = lock_scheduler(&GOROUTINE_REGISTRY).get_mut(&goroutine_id().get()) {
+        record.status = status;
+        record.stopped_at = stopped_at;
+    }
+}
+
+/// `time.Sleep`: pause the calling goroutine for `ns` nanoseconds, while
+/// the other goroutines run.
+#[track_caller]
+pub fn sleep(ns: i64) {
+    let Some(ns) = u64::try_from(ns).ok().filter(|&ns| ns > 0) else {
+        return;
+    };
+    set_goroutine_status(GoroutineStatus::Sleep);
+    let released = release_processor();
+    std::thread::sleep(std::time::Duration::from_nanos(ns));
+    if released {
+        acquire_processor();
+    }
+    set_goroutine_status(GoroutineStatus::Running);
+}
+
+/// What a goroutine acquiring one of `sync`'s semaphores waits for.
+#[derive(Clone, Copy)]
+pub enum SemaWait {
+    Semacquire,
+    WaitGroup,
+    Mutex,
+    RWMutexR,
+    RWMutex,
+}
+
+impl SemaWait {
+    const fn status(self) -> GoroutineStatus {
+        match self {
+            Self::Semacquire => GoroutineStatus::Semacquire,
+            Self::WaitGroup => GoroutineStatus::SyncWaitGroupWait,
+            Self::Mutex => GoroutineStatus::SyncMutexLock,
+            Self::RWMutexR => GoroutineStatus::SyncRWMutexRLock,
+            Self::RWMutex => GoroutineStatus::SyncRWMutexLock,
+        }
+    }
+}
+
+/// A semaphore of `sync`. Its count lives in the runtime rather than in the
+/// word `sync` passes, which `sync` does not read.
+#[derive(Default)]
+struct Semaphore {
+    count: u32,
+    /// Goroutines blocked in `semacquire`.
+    waiting: usize,
+    /// Waiting goroutines notified by `semrelease` and not awake yet.
+    notified: usize,
+    wakeup: Arc<Condvar>,
+}
+
+/// A notify list of `sync.Cond`: tickets are handed out in order, and a
+/// waiter returns once its ticket was notified.
+#[derive(Default)]
+struct NotifyList {
+    wait: u32,
+    notify: u32,
+    /// The tickets of the goroutines blocked in `notify_list_wait`.
+    sleeping: Vec<u32>,
+    wakeup: Arc<Condvar>,
+}
+
+/// Whether ticket `t` of a notify list is below `notify`, the counters
+/// wrapping around.
+const fn ticket_notified(notify: u32, t: u32) -> bool {
+    t.wrapping_sub(notify) >= 1 << 31
+}
+
+#[derive(Default)]
+struct SyncWaits {
+    semaphores: std::collections::BTreeMap<GorsInterfaceKey, Semaphore>,
+    notify_lists: std::collections::BTreeMap<GorsInterfaceKey, NotifyList>,
+}
+
+/// The semaphores and notify lists of `sync`, keyed by the address of the
+/// word they are for. Idle ones are dropped.
+static SYNC_WAITS: Mutex<SyncWaits> = Mutex::new(SyncWaits {
+    semaphores: std::collections::BTreeMap::new(),
+    notify_lists: std::collections::BTreeMap::new(),
+});
+
+/// `sync.runtime_Semacquire` and its variants: wait until the semaphore at
+/// `sema` is positive, then decrement it. The goroutine gives its processor
+/// up while it waits.
+#[track_caller]
+pub fn semacquire<T>(sema: &GorsPtr<T>, reason: SemaWait) {
+    let key = sema.interface_key();
+    let mut waits = lock_scheduler(&SYNC_WAITS);
+    let mut released = false;
+    loop {
+        let semaphore = waits.semaphores.entry(key).or_default();
+        if semaphore.count > 0 {
+            semaphore.count -= 1;
+            if semaphore.count == 0 && semaphore.waiting == 0 && semaphore.notified == 0 {
+                waits.semaphores.remove(&key);
+            }
+            break;
+        }
+        semaphore.waiting += 1;
+        let wakeup = Arc::clone(&semaphore.wakeup);
+        block_goroutine(reason.status());
+        released |= release_processor();
+        waits = wait_scheduler(&wakeup, waits);
+        let semaphore = waits.semaphores.entry(key).or_default();
+        if semaphore.notified > 0 {
+            semaphore.notified -= 1;
+        } else {
+            semaphore.waiting -= 1;
+            unblock_goroutines(1);
+        }
+    }
+    drop(waits);
+    if released {
+        acquire_processor();
+    }
+    set_goroutine_status(GoroutineStatus::Running);
+}
+
+/// `sync.runtime_Semrelease`: increment the semaphore at `sema`, waking up
+/// one of the goroutines waiting for it.
+pub fn semrelease<T>(sema: &GorsPtr<T>) {
+    let mut waits = lock_scheduler(&SYNC_WAITS);
+    let semaphore = waits.semaphores.entry(sema.interface_key()).or_default();
+    semaphore.count += 1;
+    if semaphore.waiting > 0 {
+        semaphore.waiting -= 1;
+        semaphore.notified += 1;
+        unblock_goroutines(1);
+        semaphore.wakeup.notify_one();
+    }
+    drop(waits);
+}
+
+/// `sync.runtime_notifyListAdd`: the next ticket of the notify list at
+/// `list`.
+pub fn notify_list_add<T>(list: &GorsPtr<T>) -> u32 {
+    let mut waits = lock_scheduler(&SYNC_WAITS);
+    let list = waits.notify_lists.entry(list.interface_key()).or_default();
+    let ticket = list.wait;
+    list.wait = list.wait.wrapping_add(1);
+    drop(waits);
+    ticket
+}
+
+/// `sync.runtime_notifyListWait`: wait until ticket `t` of the notify list
+/// at `list` is notified.
+#[track_caller]
+pub fn notify_list_wait<T>(list: &GorsPtr<T>, t: u32) {
+    let key = list.interface_key();
+    let mut waits = lock_scheduler(&SYNC_WAITS);
+    // A list is only dropped once all its tickets are notified.
+    let Some(list) = waits
+        .notify_lists
+        .get_mut(&key)
+        .filter(|list| !ticket_notified(list.notify, t))
+    else {
+        return;
+    };
+    list.sleeping.push(t);
+    let wakeup = Arc::clone(&list.wakeup);
+    // The notifier counts the goroutine as runnable again.
+    block_goroutine(GoroutineStatus::SyncCondWait);
+    let released = release_processor();
+    while !waits
+        .notify_lists
+        .get(&key)
+        .is_none_or(|list| ticket_notified(list.notify, t))
+    {
+        waits = wait_scheduler(&wakeup, waits);
+    }
+    drop(waits);
+    if released {
+        acquire_processor();
+    }
+    set_goroutine_status(GoroutineStatus::Running);
+}
+
+/// `sync.runtime_notifyListNotifyOne`: notify the oldest ticket of the
+/// notify list at `list` not notified yet.
+pub fn notify_list_notify_one<T>(list: &GorsPtr<T>) {
+    notify_list(list, false);
+}
+
+/// `sync.runtime_notifyListNotifyAll`: notify every ticket of the notify
+/// list at `list`.
+pub fn notify_list_notify_all<T>(list: &GorsPtr<T>) {
+    notify_list(list, true);
+}
+
+fn notify_list<T>(list: &GorsPtr<T>, all: bool) {
+    let key = list.interface_key();
+    let mut waits = lock_scheduler(&SYNC_WAITS);
+    let Some(list) = waits.notify_lists.get_mut(&key) else {
+        return;
+    };
+    if list.wait == list.notify {
+        return;
+    }
+    list.notify = if all {
+        list.wait
+    } else {
+        list.notify.wrapping_add(1)
+    };
+    let notify = list.notify;
+    let sleeping = list.sleeping.len();
+    list.sleeping.retain(|&t| !ticket_notified(notify, t));
+    unblock_goroutines(sleeping - list.sleeping.len());
+    list.wakeup.notify_all();
+    if list.wait == list.notify && list.sleeping.is_empty() {
+        waits.notify_lists.remove(&key);
+    }
+}
+
+/// `runtime.Stack`: format the stack of the calling goroutine into `buf`.
+///
+/// The stacks of the other goroutines follow if `all` is set. Return the
+/// number of bytes written; the dump is truncated to fit `buf`.
+pub fn goroutine_stack(buf: &mut [u8], all: bool) -> isize {
+    let current = goroutine_id().get();
+    let stack = std::backtrace::Backtrace::force_capture();
+    let mut dump = std::string::String::new();
+    write_goroutine(&mut dump, current, running_goroutine(current), Some(&stack));
+    let others = if all {
+        goroutine_dump(Some(current))
+    } else {
+        std::string::String::new()
+    };
+    if !others.is_empty() {
+        dump.push('\n');
+        dump.push_str(&others);
+    }
+    let len = dump.len().min(buf.len());
+    if let (Some(dst), Some(src)) = (buf.get_mut(..len), dump.as_bytes().get(..len)) {
+        dst.copy_from_slice(src);
+    }
+    isize::try_from(len).unwrap_or(isize::MAX)
+}
+
+/// The stacks of the live goroutines but `except`, separated by blank
+/// lines.
+fn goroutine_dump(except: Option<u64>) -> std::string::String {
+    let records: Vec<(u64, GoroutineRecord)> = lock_scheduler(&GOROUTINE_REGISTRY)
+        .iter()
+        .filter(|&(&id, _)| Some(id) != except)
+        .map(|(&id, &record)| (id, record))
+        .collect();
+    let mut dump = std::string::String::new();
+    for (id, record) in records {
+        if !dump.is_empty() {
+            dump.push('\n');
+        }
+        write_goroutine(&mut dump, id, record, None);
+    }
+    dump
 }
 
+/// The race detector side of the synchronization operations of the
+/// runtime, installed by `enable_race_detection`. Going through a hook
+/// keeps the detector out of programs built without `-race`.
+static RACE_SYNC_HOOK: std::sync::OnceLock<fn(usize)> = std::sync::OnceLock::new();
+
+/// The number of data races reported.
+static RACES_REPORTED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Turn the race detector on. Programs built with `-race` call it before
+/// anything else.
+pub fn enable_race_detection() {
+    let _ = RACE_SYNC_HOOK.set(race_acquire_release);
+}
+
+/// Order the calling goroutine after the earlier operations of the other
+/// goroutines on the synchronization object at `addr`, and before their
+/// later ones, when races are detected.
 #[inline]
-pub fn resume_unrecovered_panic() {
-    let payload = recover_payload_lock().take();
-    if let Some(payload) = payload {
+fn race_synchronize(addr: usize) {
+    if let Some(hook) = RACE_SYNC_HOOK.get() {
+        hook(addr);
+    }
+}
+
+/// Exit with status 66 if data races were reported, as programs built
+/// with `-race` do.
+fn finish_race_detection() {
+    let races = RACES_REPORTED.load(std::sync::atomic::Ordering::SeqCst);
+    if races > 0 {
+        ::std::eprintln!("Found {races} data race(s)");
+        std::process::exit(66);
+    }
+}
+
+/// The memory of a variable or of a pointed-to value, whose accesses the
+/// race detector checks in programs built with `-race`.
+pub trait RaceAccess {
+    /// The address the accesses are tracked at.
+    fn race_addr(&self) -> usize;
+}
+
+/// Check a read of `field` of `cell` by Go function `function`, or of the
+/// whole value when `field` is empty.
+#[track_caller]
+pub fn race_read<'a, C: RaceAccess + ?Sized>(
+    cell: &'a C,
+    field: &'static str,
+    function: &'static str,
+) -> &'a C {
+    race_access(cell.race_addr(), field, function, false);
+    cell
+}
+
+/// Check a write of `field` of `cell` by Go function `function`, or of the
+/// whole value when `field` is empty.
+#[track_caller]
+pub fn race_write<'a, C: RaceAccess + ?Sized>(
+    cell: &'a C,
+    field: &'static str,
+    function: &'static str,
+) -> &'a C {
+    race_access(cell.race_addr(), field, function, true);
+    cell
+}
+
+/// Access `cell` as a synchronization object, like the atomics and locks of
+/// `sync` do.
+pub fn race_sync<C: RaceAccess + ?Sized>(cell: &C) -> &C {
+    race_synchronize(cell.race_addr());
+    cell
+}
+
+impl<T: RaceAccess + ?Sized> RaceAccess for &T {
+    fn race_addr(&self) -> usize {
+        (**self).race_addr()
+    }
+}
+
+impl<T: RaceAccess + ?Sized> RaceAccess for &mut T {
+    fn race_addr(&self) -> usize {
+        (**self).race_addr()
+    }
+}
+
+impl<T: ?Sized> RaceAccess for Mutex<T> {
+    fn race_addr(&self) -> usize {
+        std::ptr::from_ref(self).addr()
+    }
+}
+
+impl<T: RaceAccess + ?Sized> RaceAccess for Arc<T> {
+    fn race_addr(&self) -> usize {
+        (**self).race_addr()
+    }
+}
+
+impl<T: RaceAccess> RaceAccess for std::sync::LazyLock<T> {
+    fn race_addr(&self) -> usize {
+        (**self).race_addr()
+    }
+}
+
+impl<T> RaceAccess for GorsPtr<T> {
+    fn race_addr(&self) -> usize {
+        self.ptr_id().addr()
+    }
+}
+
+/// Forget the accesses to the memory `value` was just allocated at, which
+/// earlier values at the same address were subject to.
+pub fn race_new<T: RaceAccess>(value: T) -> T {
+    if RACE_SYNC_HOOK.get().is_some() {
+        let addr = value.race_addr();
+        let mut state = lock_scheduler(&RACE_STATE);
+        let stale = state
+            .shadows
+            .range((addr, "")..)
+            .take_while(|&(&(shadow_addr, _), _)| shadow_addr == addr)
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+        for key in stale {
+            state.shadows.remove(&key);
+        }
+    }
+    value
+}
+
+/// Wrap the function a `go` statement at the caller starts, so that the
+/// new goroutine is ordered after what the calling goroutine did so far.
+#[track_caller]
+pub fn race_go<F: FnOnce() + Send + 'static>(
+    function: &'static str,
+    f: F,
+) -> impl FnOnce() + Send + 'static {
+    let site = RaceSite {
+        function,
+        location: std::panic::Location::caller(),
+    };
+    let parent = goroutine_id().get();
+    let mut state = lock_scheduler(&RACE_STATE);
+    let parent_clock = state
+        .clocks
+        .entry(parent)
+        .or_insert_with(|| VectorClock::started(parent));
+    let clock = parent_clock.clone();
+    parent_clock.tick(parent);
+    drop(state);
+    move || {
+        let id = goroutine_id().get();
+        let mut state = lock_scheduler(&RACE_STATE);
+        let mut clock = clock;
+        clock.tick(id);
+        state.clocks.insert(id, clock);
+        state.goroutines.insert(
+            id,
+            RaceGoroutine {
+                created: site,
+                finished: false,
+            },
+        );
+        drop(state);
+        let _finished = RaceGoroutineExit(id);
+        f();
+    }
+}
+
+/// Marks a goroutine finished in race reports once it returns or exits.
+struct RaceGoroutineExit(u64);
+
+impl Drop for RaceGoroutineExit {
+    fn drop(&mut self) {
+        if let Some(goroutine) = lock_scheduler(&RACE_STATE).goroutines.get_mut(&self.0) {
+            goroutine.finished = true;
+        }
+    }
+}
+
+/// A vector clock: for each goroutine, the number of its synchronization
+/// operations known to happen before.
+#[derive(Clone, Default)]
+struct VectorClock(std::collections::BTreeMap<u64, u64>);
+
+impl VectorClock {
+    /// The clock a goroutine starts with, at its own first tick.
+    fn started(goroutine: u64) -> Self {
+        let mut clock = Self::default();
+        clock.tick(goroutine);
+        clock
+    }
+
+    fn get(&self, goroutine: u64) -> u64 {
+        self.0.get(&goroutine).copied().unwrap_or(0)
+    }
+
+    fn tick(&mut self, goroutine: u64) {
+        *self.0.entry(goroutine).or_insert(0) += 1;
+    }
+
+    fn join(&mut self, other: &Self) {
+        for (&goroutine, &time) in &other.0 {
+            let entry = self.0.entry(goroutine).or_insert(0);
+            *entry = (*entry).max(time);
+        }
+    }
+}
+
+/// Where in the Go sources a memory access or a `go` statement is.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct RaceSite {
+    function: &'static str,
+    location: &'static std::panic::Location<'static>,
+}
+
+/// A memory access, at the time of the clock of its goroutine.
+#[derive(Clone, Copy)]
+struct RaceEvent {
+    goroutine: u64,
+    time: u64,
+    write: bool,
+    site: RaceSite,
+}
+
+/// The accesses to some memory not known to happen before all the later
+/// ones: the last write, and the reads since.
+#[derive(Default)]
+struct RaceShadow {
+    write: Option<RaceEvent>,
+    reads: Vec<RaceEvent>,
+}
+
+/// A goroutine started by a `go` statement.
+struct RaceGoroutine {
+    created: RaceSite,
+    finished: bool,
+}
+
+/// The state of the race detector.
+struct RaceState {
+    clocks: std::collections::BTreeMap<u64, VectorClock>,
+    /// The clocks of the synchronization objects, by address.
+    syncs: std::collections::BTreeMap<usize, VectorClock>,
+    /// The accesses to memory, by address and field.
+    shadows: std::collections::BTreeMap<(usize, &'static str), RaceShadow>,
+    goroutines: std::collections::BTreeMap<u64, RaceGoroutine>,
+    /// The pairs of racing accesses already reported.
+    reported: std::collections::BTreeSet<(RaceSite, RaceSite)>,
+}
+
+static RACE_STATE: Mutex<RaceState> = Mutex::new(RaceState {
+    clocks: std::collections::BTreeMap::new(),
+    syncs: std::collections::BTreeMap::new(),
+    shadows: std::collections::BTreeMap::new(),
+    goroutines: std::collections::BTreeMap::new(),
+    reported: std::collections::BTreeSet::new(),
+});
+
+/// Installed as `RACE_SYNC_HOOK`: both acquire and release the
+/// synchronization object at `addr`.
+#[allow(clippy::significant_drop_tightening)]
+fn race_acquire_release(addr: usize) {
+    let goroutine = goroutine_id().get();
+    let mut state = lock_scheduler(&RACE_STATE);
+    let RaceState { clocks, syncs, .. } = &mut *state;
+    let clock = clocks
+        .entry(goroutine)
+        .or_insert_with(|| VectorClock::started(goroutine));
+    let sync = syncs.entry(addr).or_default();
+    clock.join(sync);
+    sync.clone_from(clock);
+    clock.tick(goroutine);
+}
+
+/// Check an access to the memory at `addr` against the earlier ones, and
+/// report the first which races with it.
+#[track_caller]
+fn race_access(addr: usize, field: &'static str, function: &'static str, write: bool) {
+    if RACE_SYNC_HOOK.get().is_none() || addr == 0 {
+        return;
+    }
+    let goroutine = goroutine_id().get();
+    let site = RaceSite {
+        function,
+        location: std::panic::Location::caller(),
+    };
+    let mut state = lock_scheduler(&RACE_STATE);
+    let RaceState {
+        clocks, shadows, ..
+    } = &mut *state;
+    let clock = clocks
+        .entry(goroutine)
+        .or_insert_with(|| VectorClock::started(goroutine));
+    let concurrent = |earlier: &RaceEvent| {
+        earlier.goroutine != goroutine && earlier.time > clock.get(earlier.goroutine)
+    };
+    let event = RaceEvent {
+        goroutine,
+        time: clock.get(goroutine),
+        write,
+        site,
+    };
+    let shadow = shadows.entry((addr, field)).or_default();
+    let earlier = shadow.write.filter(concurrent).or_else(|| {
+        write
+            .then(|| shadow.reads.iter().copied().find(concurrent))
+            .flatten()
+    });
+    if write {
+        shadow.write = Some(event);
+        shadow.reads.clear();
+    } else {
+        shadow.reads.retain(concurrent);
+        shadow.reads.push(event);
+    }
+    let Some(earlier) = earlier else {
+        return;
+    };
+    if !state.reported.insert((earlier.site, site)) {
+        return;
+    }
+    let report = race_report(&state, addr, event, earlier);
+    drop(state);
+    RACES_REPORTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    ::std::eprint!("{report}");
+}
+
+/// Format a data race report as Go does.
+fn race_report(
+    state: &RaceState,
+    addr: usize,
+    access: RaceEvent,
+    earlier: RaceEvent,
+) -> std::string::String {
+    let by = |goroutine: u64| {
+        if goroutine == 1 {
+            "main goroutine".to_string()
+        } else {
+            format!("goroutine {goroutine}")
+        }
+    };
+    let kind = |event: RaceEvent| if event.write { "write" } else { "read" };
+    let mut report = std::string::String::from("==================\nWARNING: DATA RACE\n");
+    let current_kind = if access.write { "Write" } else { "Read" };
+    report.push_str(&format!(
+        "{current_kind} at {addr:#014x} by {}:\n{}",
+        by(access.goroutine),
+        race_frame(access.site)
+    ));
+    report.push_str(&format!(
+        "\nPrevious {} at {addr:#014x} by {}:\n{}",
+        kind(earlier),
+        by(earlier.goroutine),
+        race_frame(earlier.site)
+    ));
+    let mut goroutines = vec![access.goroutine];
+    if earlier.goroutine != access.goroutine {
+        goroutines.push(earlier.goroutine);
+    }
+    for id in goroutines {
+        if let Some(goroutine) = state.goroutines.get(&id) {
+            let status = if goroutine.finished {
+                "finished"
+            } else {
+                "running"
+            };
+            let created = race_frame(goroutine.created);
+            report.push_str(&format!(
+                "\nGoroutine {id} ({status}) created at:\n{created}"
+            ));
+        }
+    }
+    report.push_str("==================\n");
+    report
+}
+
+/// Format the Go function and source line of a race report entry. The
+/// line is looked up at the end of the generated line, past the accessed
+/// variable the instrumentation call starts before.
+fn race_frame(site: RaceSite) -> std::string::String {
+    let location = site.location;
+    let (file, line) = go_position(location.file(), location.line(), u32::MAX)
+        .unwrap_or_else(|| (location.file().to_string(), location.line()));
+    format!("  {}()\n      {file}:{line}\n", site.function)
+}
+
+/// Format the stack of a goroutine as Go does, the innermost call first.
+/// The goroutine started by a `go` statement ends with the function which
+/// ran it.
+///
+/// Without a `stack` captured by the goroutine itself, only the frame of
+/// the operation it stopped running at is known.
+fn write_goroutine(
+    out: &mut std::string::String,
+    id: u64,
+    record: GoroutineRecord,
+    stack: Option<&std::backtrace::Backtrace>,
+) {
+    let GoroutineRecord {
+        status,
+        creator,
+        go_statement,
+        stopped_at,
+    } = record;
+    out.push_str(&format!("goroutine {id} [{}]:\n", status.as_str()));
+    let Some(stack) = stack else {
+        if status == GoroutineStatus::Running && id != goroutine_id().get() {
+            out.push_str("\tgoroutine running on other thread; stack unavailable\n");
+        }
+        let frames = stopped_at.and_then(GoFrame::at);
+        write_go_frames(&mut *out, frames.as_slice());
+        if let Some(GoFrame {
+            function: GoFunction { name, .. },
+            file,
+            line,
+        }) = go_statement.and_then(GoFrame::at)
+        {
+            out.push_str(&format!(
+                "created by {name} in goroutine {creator}\n\t{file}:{line}\n"
+            ));
+        }
+        return;
+    };
+    let frames = go_frames(&stack.to_string(), module_path!());
+    write_go_frames(&mut *out, &frames);
+    if id != 1
+        && let Some(GoFrame {
+            function:
+                GoFunction {
+                    parent: Some(parent),
+                    ..
+                },
+            file,
+            line,
+        }) = frames.last()
+    {
+        out.push_str(&format!(
+            "created by {parent} in goroutine {creator}\n\t{file}:{line}\n"
+        ));
+    }
+}
+
+fn write_go_frames(out: &mut std::string::String, frames: &[GoFrame]) {
+    for GoFrame {
+        function: GoFunction { name, parent },
+        file,
+        line,
+    } in frames
+    {
+        let args = if parent.is_some() || name == "main.main" {
+            "()"
+        } else {
+            "(...)"
+        };
+        out.push_str(&format!("{name}{args}\n\t{file}:{line}\n"));
+    }
+}
+
+/// A frame of the Go code on a goroutine stack.
+struct GoFrame {
+    function: GoFunction,
+    /// The Go file, or the generated Rust file when the source position is
+    /// unknown, or `?` when the frame has no location.
+    file: std::string::String,
+    line: u32,
+}
+
+impl GoFrame {
+    /// The frame of a location in the generated code, in the innermost Go
+    /// function declared around it. Locations in the runtime have none.
+    fn at(location: &std::panic::Location<'_>) -> Option<Self> {
+        let (file, line, column) = (location.file(), location.line(), location.column());
+        if file == file!() {
+            return None;
+        }
+        let name = go_function_at(file, line, column)
+            .unwrap_or("?")
+            .to_string();
+        let (file, line) =
+            go_position(file, line, column).unwrap_or_else(|| (file.to_string(), line));
+        Some(Self {
+            function: GoFunction { name, parent: None },
+            file,
+            line,
+        })
+    }
+
+    fn new(function: GoFunction, location: Option<&str>) -> Self {
+        let position = location.and_then(rust_position);
+        let function = match position.and_then(|(file, _, _)| line_table(file)) {
+            Some(table) => function.in_package(table.package),
+            None => function,
+        };
+        let (file, line) = position
+            .map(|(file, line, column)| {
+                go_position(file, line, column).unwrap_or_else(|| (file.to_string(), line))
+            })
+            .unwrap_or_else(|| ("?".to_string(), 0));
+        Self {
+            function,
+            file,
+            line,
+        }
+    }
+}
+
+/// The frames of the Go code in a Rust backtrace, as printed by its
+/// `Display` implementation, given the module path of the runtime. The
+/// frames of the runtime and of the Rust standard library are left out.
+fn go_frames(backtrace: &str, runtime: &str) -> Vec<GoFrame> {
+    let mut frames = Vec::new();
+    let mut pending: Option<GoFunction> = None;
+    for line in backtrace.lines().map(str::trim_start) {
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(function) = pending.take() {
+                frames.push(GoFrame::new(function, Some(location)));
+            }
+            continue;
+        }
+        if let Some(function) = pending.take() {
+            frames.push(GoFrame::new(function, None));
+        }
+        let symbol = line
+            .split_once(": ")
+            .filter(|(index, _)| index.bytes().all(|b| b.is_ascii_digit()))
+            .map_or(line, |(_, symbol)| symbol);
+        pending = go_function(symbol, runtime);
+    }
+    if let Some(function) = pending {
+        frames.push(GoFrame::new(function, None));
+    }
+    frames
+}
+
+/// A Go function, as named in goroutine stacks.
+#[derive(Debug, PartialEq, Eq)]
+struct GoFunction {
+    name: std::string::String,
+    /// The function a function literal is declared in.
+    parent: Option<std::string::String>,
+}
+
+impl GoFunction {
+    /// The function named after the import path of its package rather than
+    /// the module the package compiles to.
+    fn in_package(self, package: &str) -> Self {
+        let qualify = |name: std::string::String| match name.split_once('.') {
+            Some((_, path)) => format!("{package}.{path}"),
+            None => name,
+        };
+        Self {
+            name: qualify(self.name),
+            parent: self.parent.map(qualify),
+        }
+    }
+}
+
+/// The Go function the code of a Rust symbol comes from, given the module
+/// path of the runtime. Symbols of the runtime, of the `runtime` package
+/// and of the Rust standard library have none, as Go hides the frames of
+/// its runtime.
+fn go_function(symbol: &str, runtime: &str) -> Option<GoFunction> {
+    let symbol = rust_symbol_path(symbol.trim());
+    if symbol
+        .strip_prefix(runtime)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    {
+        return None;
+    }
+    // Packages are modules of the library the main package includes, next
+    // to the runtime.
+    let library = runtime.rsplit_once("::").map(|(library, _)| library);
+    let program = library.map_or(runtime, |library| {
+        library.split("::").next().unwrap_or(library)
+    });
+    let (package, path) = if let Some(path) = library
+        .and_then(|library| symbol.strip_prefix(library))
+        .and_then(|path| path.strip_prefix("::"))
+    {
+        let (module, path) = path.split_once("::")?;
+        (module.replace("__", "/"), path)
+    } else {
+        let path = symbol.strip_prefix(program)?.strip_prefix("::")?;
+        ("main".to_string(), path)
+    };
+    if package == "runtime" {
+        return None;
+    }
+    let mut names = Vec::new();
+    let mut closures = 0usize;
+    for segment in path.split("::") {
+        if segment.starts_with('{') {
+            closures += 1;
+        } else {
+            names.push(segment);
+        }
+    }
+    // The body of `main` is a closure run by `run_main`.
+    if package == "main" && names == ["main"] {
+        closures = closures.checked_sub(1)?;
+    }
+    let function = format!("{package}.{}", names.join("."));
+    let literal = |depth: usize| {
+        let mut name = function.clone();
+        for level in 0..depth {
+            name.push_str(if level == 0 { ".func1" } else { ".1" });
+        }
+        name
+    };
+    Some(GoFunction {
+        name: literal(closures),
+        parent: closures.checked_sub(1).map(literal),
+    })
+}
+
+/// The path of the function a demangled Rust symbol names, without its
+/// hash, generic arguments, and the trait of a `<T as Trait>::method`
+/// symbol.
+fn rust_symbol_path(symbol: &str) -> std::string::String {
+    let symbol = match symbol.rsplit_once("::h") {
+        Some((path, hash)) if hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            path
+        }
+        _ => symbol,
+    };
+    let mut path = std::string::String::new();
+    let mut depth = 0usize;
+    let mut qualified = symbol.starts_with('<');
+    let mut in_trait = false;
+    for (i, ch) in symbol.char_indices() {
+        match ch {
+            '<' => depth += 1,
+            '>' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    qualified = false;
+                    in_trait = false;
+                }
+            }
+            ' ' if qualified
+                && depth == 1
+                && symbol.get(i..).is_some_and(|rest| rest.starts_with(" as ")) =>
+            {
+                in_trait = true;
+            }
+            _ if in_trait || depth > usize::from(qualified) => {}
+            ch => path.push(ch),
+        }
+    }
+    path
+}
+
+/// The file, line and column of the location of a frame in the generated
+/// Rust code, `file:line:column`.
+fn rust_position(location: &str) -> Option<(&str, u32, u32)> {
+    let mut parts = location.rsplitn(3, ':');
+    let (Some(column), Some(line), Some(file)) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    Some((file, line.parse().ok()?, column.parse().ok()?))
+}
+
+/// The Go file and line a 1-based position in a generated file comes
+/// from, according to the line tables the program registered.
+fn go_position(rust_file: &str, line: u32, column: u32) -> Option<(std::string::String, u32)> {
+    line_table(rust_file)?.position(line, column)
+}
+
+/// The name of the innermost Go function declared around a 1-based
+/// position in a generated file.
+fn go_function_at(rust_file: &str, line: u32, column: u32) -> Option<&'static str> {
+    line_table(rust_file)?.function(line, column)
+}
+
+/// The line table of a generated file, known by its path as the Rust
+/// compiler recorded it.
+fn line_table(rust_file: &str) -> Option<&'static LineTable> {
+    let name = std::path::Path::new(rust_file).file_name()?;
+    LINE_TABLES
+        .get()?
+        .iter()
+        .find(|table| std::ffi::OsStr::new(table.file) == name)
+        .copied()
+}
+
+static LINE_TABLES: std::sync::OnceLock<&'static [&'static LineTable]> = std::sync::OnceLock::new();
+
+/// The positions in the Go sources the code of a generated file comes
+/// from, which gors appends to the file when it builds source maps.
+pub struct LineTable {
+    /// The name of the generated file.
+    pub file: &'static str,
+    /// The import path of the package the file compiles.
+    pub package: &'static str,
+    pub sources: &'static [&'static str],
+    /// The 1-based generated line and column, the source index and the
+    /// 1-based source line, sorted by generated position. Only the
+    /// positions the source line changes at are listed.
+    pub lines: &'static [(u32, u32, u32, u32)],
+    /// The source index, the 1-based lines of the `func` keyword and of the
+    /// closing brace, and the name of the Go functions declared in the
+    /// sources, sorted by source and line.
+    pub functions: &'static [(u32, u32, u32, &'static str)],
+}
+
+impl LineTable {
+    /// The Go file and line of the last entry at or before a generated
+    /// position.
+    fn position(&self, line: u32, column: u32) -> Option<(std::string::String, u32)> {
+        let (source, source_line) = self.source_line(line, column)?;
+        let file = self.sources.get(usize::try_from(source).ok()?)?;
+        Some(((*file).to_string(), source_line))
+    }
+
+    /// The name of the innermost Go function declared around a generated
+    /// position.
+    fn function(&self, line: u32, column: u32) -> Option<&'static str> {
+        let (source, source_line) = self.source_line(line, column)?;
+        self.functions
+            .iter()
+            .rfind(|&&(s, first, last, _)| s == source && (first..=last).contains(&source_line))
+            .map(|&(_, _, _, name)| name)
+    }
+
+    /// The source index and line of the last entry at or before a generated
+    /// position. A position before the first entry of its line, such as the
+    /// start of a call the runtime tracks the caller of, takes that entry.
+    fn source_line(&self, line: u32, column: u32) -> Option<(u32, u32)> {
+        let after = self
+            .lines
+            .partition_point(|&(l, c, _, _)| (l, c) <= (line, column));
+        let before = after.checked_sub(1).and_then(|index| self.lines.get(index));
+        let &(_, _, source, source_line) = match self.lines.get(after) {
+            Some(next) if next.0 == line && before.is_none_or(|before| before.0 != line) => next,
+            _ => before?,
+        };
+        Some((source, source_line))
+    }
+}
+
+/// Symbolize the stacks of the program with the line tables of the
+/// generated files.
+pub fn set_line_tables(tables: &'static [&'static LineTable]) {
+    let _ = LINE_TABLES.set(tables);
+}
+
+/// The frames handed out as program counters by `runtime.Callers` and
+/// `runtime.Caller`: the program counter of a frame is its index plus one,
+/// as 0 is no program counter. A frame is a position in the code of the
+/// program, so there are no more of them than the program has lines.
+struct CallerFrames {
+    frames: Vec<(std::string::String, std::string::String, u32)>,
+    pcs: std::collections::BTreeMap<(std::string::String, std::string::String, u32), usize>,
+}
+
+static CALLER_FRAMES: Mutex<CallerFrames> = Mutex::new(CallerFrames {
+    frames: Vec::new(),
+    pcs: std::collections::BTreeMap::new(),
+});
+
+fn caller_pc(frame: GoFrame) -> usize {
+    let GoFrame {
+        function: GoFunction { name, .. },
+        file,
+        line,
+    } = frame;
+    let key = (name, file, line);
+    let mut callers = lock_scheduler(&CALLER_FRAMES);
+    if let Some(&pc) = callers.pcs.get(&key) {
+        return pc;
+    }
+    callers.frames.push(key.clone());
+    let pc = callers.frames.len();
+    callers.pcs.insert(key, pc);
+    drop(callers);
+    pc
+}
+
+/// The Go frames of the calling goroutine, the innermost first.
+fn go_callers() -> Vec<GoFrame> {
+    let stack = std::backtrace::Backtrace::force_capture();
+    go_frames(&stack.to_string(), module_path!())
+}
+
+/// `runtime.Callers`: fill `pc` with the program counters of the calling
+/// goroutine's stack.
+///
+/// The first `skip` frames are left out, `runtime.Callers` itself first.
+/// Return the number of entries written.
+pub fn callers(skip: isize, pc: &mut [usize]) -> isize {
+    let itself = GoFrame::new(
+        GoFunction {
+            name: "runtime.Callers".to_string(),
+            parent: None,
+        },
+        None,
+    );
+    let frames = std::iter::once(itself)
+        .chain(go_callers())
+        .skip(usize::try_from(skip).unwrap_or(0));
+    let mut written = 0isize;
+    for (slot, frame) in pc.iter_mut().zip(frames) {
+        *slot = caller_pc(frame);
+        written += 1;
+    }
+    written
+}
+
+/// `runtime.Caller`: the program counter, Go file and line of the function
+/// `skip` frames up the stack of the calling goroutine, 0 being the caller
+/// of `runtime.Caller`.
+///
+/// `runtime.Caller` tracks its caller, whose frame is known without
+/// capturing the stack.
+#[track_caller]
+pub fn caller(skip: isize) -> (usize, std::string::String, isize, bool) {
+    let frame = match usize::try_from(skip) {
+        Ok(0) => GoFrame::at(std::panic::Location::caller()),
+        Ok(skip) => go_callers().into_iter().nth(skip),
+        Err(_) => None,
+    };
+    let Some(frame) = frame else {
+        return (0, std::string::String::new(), 0, false);
+    };
+    let (file, line) = (frame.file.clone(), frame.line);
+    let line = isize::try_from(line).unwrap_or(isize::MAX);
+    (caller_pc(frame), file, line, true)
+}
+
+/// The Go function, file and line of a program counter `callers` or
+/// `caller` returned.
+pub fn caller_frame(pc: usize) -> Option<(std::string::String, std::string::String, isize)> {
+    let callers = lock_scheduler(&CALLER_FRAMES);
+    let (function, file, line) = callers.frames.get(pc.checked_sub(1)?)?.clone();
+    drop(callers);
+    Some((function, file, isize::try_from(line).unwrap_or(isize::MAX)))
+}
+
+/// The stack of the last panic of the calling goroutine, captured as it
+/// started unwinding, for `fatal_panic` to print.
+fn with_panic_stack<R>(f: impl FnOnce(&mut Option<std::backtrace::Backtrace>) -> R) -> R {
+    thread_local! {
+        static PANIC_STACK: std::cell::RefCell<Option<std::backtrace::Backtrace>> =
+            const { std::cell::RefCell::new(None) };
+    }
+    PANIC_STACK.with(|stack| f(&mut stack.borrow_mut()))
+}
+
+#[inline]
+pub fn set_recover_payload<T: Any + Send + 'static>(value: T) {
+    set_pending_panic(Box::new(value));
+}
+
+#[inline]
+pub fn set_recover_payload_any(value: GorsAny) {
+    set_pending_panic(value.into_send());
+}
+
+/// Like [`panic_nil`], for the functions [`set_recover_payload`] is for.
+pub fn set_recover_payload_nil() {
+    if godebug("panicnil").as_deref() == Some("1") {
+        set_recover_payload(());
+    } else {
+        set_recover_payload(Box::new(RuntimeError::panic_nil()) as Box<dyn error>);
+    }
+}
+
+/// Record the panic caught unwinding out of the body of a function with
+/// deferred calls.
+#[inline]
+pub fn set_recover_payload_box(value: Box<dyn Any + Send>) {
+    set_pending_panic(go_panic_payload(value));
+}
+
+fn set_pending_panic(payload: PanicPayload) {
+    with_goroutine_panics(|panics| panics.pending = Some(payload));
+}
+
+/// The Go panic value of a caught panic: the panics Rust raises where Go
+/// fails a run-time check become `runtime.Error` values.
+fn go_panic_payload(payload: PanicPayload) -> PanicPayload {
+    let message = payload
+        .downcast_ref::<std::string::String>()
+        .map(std::string::String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied());
+    match message.and_then(rust_panic_runtime_error) {
+        Some(error) => Box::new(Box::new(error) as Box<dyn error>),
+        None => payload,
+    }
+}
+
+/// The `runtime.Error` Go raises where Rust panics with `message`.
+fn rust_panic_runtime_error(message: &str) -> Option<RuntimeError> {
+    fn numbers(message: &str, separator: &str) -> Option<(usize, usize)> {
+        let (first, second) = message.split_once(separator)?;
+        Some((first.parse().ok()?, second.parse().ok()?))
+    }
+
+    if message == "attempt to divide by zero"
+        || message == "attempt to calculate the remainder with a divisor of zero"
+    {
+        return Some(RuntimeError::divide());
+    }
+    if message.ends_with("` value: GorsNilPointer") {
+        return Some(RuntimeError::nil_dereference());
+    }
+    if let Some(rest) = message.strip_prefix("index out of bounds: the len is ") {
+        let (len, index) = numbers(rest, " but the index is ")?;
+        return Some(RuntimeError::index(index, len));
+    }
+    if let Some(rest) = message.strip_prefix("range end index ") {
+        let (high, cap) = numbers(rest, " out of range for slice of length ")?;
+        return Some(RuntimeError::slice_high(high, cap));
+    }
+    if let Some(rest) = message.strip_prefix("range start index ") {
+        let (low, len) = numbers(rest, " out of range for slice of length ")?;
+        return Some(RuntimeError::slice_low(low, len));
+    }
+    if let Some(rest) = message.strip_prefix("slice index starts at ") {
+        let (low, high) = numbers(rest, " but ends at ")?;
+        return Some(RuntimeError::slice_low(low, high));
+    }
+    if let Some(rest) = message.strip_prefix("begin > end (") {
+        let (low, high) = numbers(rest.split_once(')')?.0, " > ")?;
+        return Some(RuntimeError::slice_low(low, high));
+    }
+    if let Some(rest) = message.strip_prefix("byte range starts at ") {
+        let (low, high) = numbers(rest, " but ends at ")?;
+        return Some(RuntimeError::slice_low(low, high));
+    }
+    let (start, rest) = if let Some(rest) = message.strip_prefix("start byte index ") {
+        (true, rest)
+    } else {
+        (false, message.strip_prefix("end byte index ")?)
+    };
+    let (index, len) = match rest.split_once(" is out of bounds of `") {
+        Some((index, text)) => (index.parse().ok()?, text.strip_suffix('`')?.len()),
+        None => numbers(rest, " is out of bounds for string of length ")?,
+    };
+    Some(if start {
+        RuntimeError::slice_low(index, len)
+    } else {
+        RuntimeError::string_high(index, len)
+    })
+}
+
+/// Take the panic of the function whose deferred calls are running, if it
+/// was not recovered yet.
+#[inline]
+pub fn recover() -> Box<dyn Any + Send> {
+    with_goroutine_panics(|panics| {
+        let Some(frame) = panics.deferring.last_mut() else {
+            return panics.pending.take();
+        };
+        if frame
+            .panic
+            .as_ref()
+            .is_some_and(|payload| payload.is::<Goexit>())
+        {
+            return None;
+        }
+        let payload = frame.panic.take()?;
+        frame.recovered = Some(EarlierPanic::new(payload.as_ref(), true));
+        Some(payload)
+    })
+    .unwrap_or_else(|| Box::new(()))
+}
+
+/// Run the deferred calls of a function, last deferred first, then resume
+/// its panic unless one of them recovered it.
+///
+/// A panic raised by a deferred call replaces the one being handled, and the
+/// remaining calls still run.
+pub fn run_deferred(deferred: &mut Vec<Box<dyn FnOnce()>>) {
+    with_goroutine_panics(|panics| {
+        let frame = DeferringFrame {
+            panic: panics.pending.take(),
+            recovered: None,
+            earlier: panics.earlier.len(),
+        };
+        panics.deferring.push(frame);
+    });
+    while let Some(call) = deferred.pop() {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(call));
+        with_goroutine_panics(|panics| {
+            let Some(frame) = panics.deferring.last_mut() else {
+                return;
+            };
+            let recovered = frame.recovered.take();
+            if let Err(payload) = result {
+                let replaced = frame
+                    .panic
+                    .take()
+                    .filter(|payload| !payload.is::<Goexit>())
+                    .map(|payload| EarlierPanic::new(payload.as_ref(), false));
+                panics.earlier.extend(recovered.or(replaced));
+                frame.panic = Some(go_panic_payload(payload));
+            } else if recovered.is_some() {
+                // The recovery completed: the panics it ended are gone.
+                panics.earlier.truncate(frame.earlier);
+            }
+        });
+    }
+    let panic = with_goroutine_panics(|panics| {
+        let frame = panics.deferring.pop()?;
+        if frame.panic.is_none() {
+            panics.earlier.truncate(frame.earlier);
+        }
+        frame.panic
+    });
+    if let Some(payload) = panic {
         std::panic::resume_unwind(payload);
     }
 }
@@ -2324,6 +6977,8 @@ pub fn recover_func<F: FnOnce() + std::panic::UnwindSafe>(f: F) -> Option<std::s
                 Some(s.clone())
             } else if let Some(s) = e.downcast_ref::<&str>() {
                 Some(s.to_string())
+            } else if let Some(e) = e.downcast_ref::<Box<dyn error>>() {
+                Some(e.Error())
             } else {
                 Some("unknown panic".to_string())
             }
@@ -2331,9 +6986,132 @@ pub fn recover_func<F: FnOnce() + std::panic::UnwindSafe>(f: F) -> Option<std::s
     }
 }
 
-#[inline]
-pub fn interface_is_nil(value: &dyn Any) -> bool {
-    value.type_id() == TypeId::of::<()>()
+#[inline]
+pub fn interface_is_nil(value: &dyn Any) -> bool {
+    interface_payload(value).type_id() == TypeId::of::<()>()
+}
+
+/// Files embedded by `//go:embed` into an `embed.FS`.
+///
+/// Paths are slash-separated and relative to the package directory.
+/// Directories are implied by the file paths, since the go command never
+/// embeds empty directories.
+#[derive(Clone, Copy, Default)]
+pub struct GorsEmbedFs {
+    files: &'static [(&'static str, &'static [u8])],
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct GorsEmbedEntry {
+    path: &'static str,
+    data: Option<&'static [u8]>,
+}
+
+impl GorsEmbedFs {
+    pub const fn new(files: &'static [(&'static str, &'static [u8])]) -> Self {
+        Self { files }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<GorsEmbedEntry> {
+        if !valid_fs_path(name) {
+            return None;
+        }
+        if name == "." {
+            return Some(GorsEmbedEntry {
+                path: ".",
+                data: None,
+            });
+        }
+        self.files.iter().find_map(|&(path, data)| {
+            if path == name {
+                return Some(GorsEmbedEntry {
+                    path,
+                    data: Some(data),
+                });
+            }
+            path.strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('/'))
+                .then(|| GorsEmbedEntry {
+                    path: path.get(..name.len()).unwrap_or(path),
+                    data: None,
+                })
+        })
+    }
+
+    pub fn read_file(&self, name: &str) -> Result<&'static [u8], std::string::String> {
+        match self.lookup(name) {
+            None => Err(format!("open {name}: file does not exist")),
+            Some(GorsEmbedEntry {
+                data: Some(data), ..
+            }) => Ok(data),
+            Some(_) => Err(format!("read {name}: is a directory")),
+        }
+    }
+
+    pub fn read_dir(&self, name: &str) -> Result<Vec<GorsEmbedEntry>, std::string::String> {
+        let dir = self
+            .lookup(name)
+            .ok_or_else(|| format!("open {name}: file does not exist"))?;
+        if !dir.is_dir() {
+            return Err(format!("read {name}: not a directory"));
+        }
+        let mut entries = std::collections::BTreeMap::new();
+        for &(path, data) in self.files {
+            let rest = if name == "." {
+                Some(path)
+            } else {
+                path.strip_prefix(name)
+                    .and_then(|rest| rest.strip_prefix('/'))
+            };
+            let Some(rest) = rest else {
+                continue;
+            };
+            let child = rest.split('/').next().unwrap_or(rest);
+            let child_end = path.len() - rest.len() + child.len();
+            entries.entry(child).or_insert_with(|| GorsEmbedEntry {
+                path: path.get(..child_end).unwrap_or(path),
+                data: (child.len() == rest.len()).then_some(data),
+            });
+        }
+        Ok(entries.into_values().collect())
+    }
+}
+
+impl GorsEmbedEntry {
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.path.rsplit('/').next().unwrap_or(self.path)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.data.is_none()
+    }
+
+    pub fn data(&self) -> &'static [u8] {
+        self.data.unwrap_or_default()
+    }
+
+    /// Copy the bytes after `offset` into `buf`, advancing `offset`. Returns
+    /// 0 at end of file.
+    pub fn read_at(&self, offset: &mut usize, buf: &mut [u8]) -> usize {
+        let rest = self.data().get(*offset..).unwrap_or_default();
+        let mut n = 0;
+        for (dst, src) in buf.iter_mut().zip(rest) {
+            *dst = *src;
+            n += 1;
+        }
+        *offset += n;
+        n
+    }
+}
+
+/// `io/fs.ValidPath`: unrooted, slash-separated, without empty, `.` or `..`
+/// elements (the name `.` itself is the root).
+pub fn valid_fs_path(name: &str) -> bool {
+    name == "." || name.split('/').all(|elem| !matches!(elem, "" | "." | ".."))
 }
 
 #[inline]
@@ -2433,6 +7211,50 @@ mod tests {
         assert!(!PartialEq::eq(&err_b, &err_c));
     }
 
+    #[test]
+    fn embed_fs_reads_files_and_implied_directories() {
+        static FILES: &[(&str, &[u8])] = &[
+            ("hello.txt", b"hello"),
+            ("static/css/site.css", b"body{}"),
+            ("static/index.html", b"<html>"),
+        ];
+        let fsys = GorsEmbedFs::new(FILES);
+
+        assert_eq!(fsys.read_file("hello.txt"), Ok(&b"hello"[..]));
+        assert_eq!(
+            fsys.read_file("missing.txt"),
+            Err("open missing.txt: file does not exist".to_string())
+        );
+        assert_eq!(
+            fsys.read_file("static"),
+            Err("read static: is a directory".to_string())
+        );
+        assert!(fsys.lookup("./hello.txt").is_none());
+        assert!(fsys.lookup("static/").is_none());
+        assert!(fsys.lookup("stat").is_none());
+
+        let root = fsys.read_dir(".").unwrap();
+        let names: Vec<_> = root
+            .iter()
+            .map(|entry| (entry.name(), entry.is_dir()))
+            .collect();
+        assert_eq!(names, [("hello.txt", false), ("static", true)]);
+        let nested = fsys.read_dir("static").unwrap();
+        let names: Vec<_> = nested.iter().map(GorsEmbedEntry::name).collect();
+        assert_eq!(names, ["css", "index.html"]);
+        assert_eq!(
+            fsys.read_dir("hello.txt").map(|entries| entries.len()),
+            Err("read hello.txt: not a directory".to_string())
+        );
+
+        let file = fsys.lookup("static/index.html").unwrap();
+        let (mut offset, mut buf) = (0, [0u8; 4]);
+        assert_eq!(file.read_at(&mut offset, &mut buf), 4);
+        assert_eq!(file.read_at(&mut offset, &mut buf), 2);
+        assert_eq!(&buf[..2], b"l>");
+        assert_eq!(file.read_at(&mut offset, &mut buf), 0);
+    }
+
     #[test]
     fn projected_field_pointers_alias_owner_fields() {
         #[derive(Default)]
@@ -2595,7 +7417,7 @@ mod tests {
         let values = vec![1, 2, 3];
         let array = [1, 2, 3, 4];
         let text = "hello".to_string();
-        let mut map = HashMap::new();
+        let mut map = GorsMap::new();
         map.insert("a", 1);
         let ch: Chan<i32> = make_chan(2);
         ch.send(1);
@@ -2610,6 +7432,125 @@ mod tests {
         assert_eq!(cap(&ch), 2);
     }
 
+    #[test]
+    fn nil_maps_read_like_empty_maps_and_panic_on_assignment() {
+        let mut map = GorsMap::<&str, i32>::default();
+        assert!(map.is_nil());
+        assert_eq!(len(&map), 0);
+        assert_eq!(map.get("a"), None);
+        assert_eq!((&map).into_iter().count(), 0);
+        delete(&mut map, &"a");
+        clear(&mut map);
+        assert!(map.is_nil());
+        assert_ne!(map, GorsMap::new());
+
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let inserted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.insert("a", 1);
+        }));
+        let incremented = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            *map.entry("a").or_default() += 1;
+        }));
+        std::panic::set_hook(previous_hook);
+
+        for payload in [inserted.unwrap_err(), incremented.unwrap_err()] {
+            let error = payload.downcast_ref::<Box<dyn error>>().unwrap();
+            assert_eq!(error.Error(), "assignment to entry in nil map");
+        }
+        let made = GorsMap::from([("a", 1)]);
+        assert!(!made.is_nil());
+        assert_eq!(made.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn finalizers_run_after_gc_once_their_object_is_unreachable() {
+        let finalized = make_chan::<isize>(2);
+        let object = GorsPtr::new(7isize);
+        let sender = finalized.clone();
+        set_finalizer(&object, move |object: GorsPtr<isize>| {
+            send(&sender, *object.lock().unwrap());
+        });
+        let cleared = GorsPtr::new(8isize);
+        let sender = finalized.clone();
+        set_finalizer(&cleared, move |object: GorsPtr<isize>| {
+            send(&sender, *object.lock().unwrap());
+        });
+        let nil_func: GorsFinalizerValue<isize> = Arc::new(Mutex::new(None));
+        set_finalizer(&cleared, nil_func);
+        set_finalizer(&cleared, nil);
+
+        gc();
+        keep_alive(object.clone());
+        assert!(matches!(finalized.try_recv(), Err(TryRecvError::Empty)));
+        drop(object);
+        drop(cleared);
+        gc();
+        assert_eq!(recv(&finalized), 7);
+        gc();
+        assert!(matches!(finalized.try_recv(), Err(TryRecvError::Empty)));
+    }
+
+    #[test]
+    fn mem_stats_count_allocations_and_frees() {
+        let before = read_mem_stats();
+        record_alloc(64);
+        record_alloc(32);
+        record_free(64);
+        let after = read_mem_stats();
+        assert!(after.mallocs >= before.mallocs + 2);
+        assert!(after.frees > before.frees);
+        assert!(after.total_alloc >= before.total_alloc + 96);
+        record_realloc(32, 48);
+        let resized = read_mem_stats();
+        assert!(resized.total_alloc >= after.total_alloc + 16);
+        assert!(num_goroutine() >= 1);
+    }
+
+    #[test]
+    fn gotraceback_and_godebug_settings_parse_like_go() {
+        let traceback = |level, all, crash| Traceback { level, all, crash };
+        assert_eq!(Traceback::parse(""), traceback(1, false, false));
+        assert_eq!(Traceback::parse("none"), traceback(0, false, false));
+        assert_eq!(Traceback::parse("single"), traceback(1, false, false));
+        assert_eq!(Traceback::parse("all"), traceback(1, true, false));
+        assert_eq!(Traceback::parse("system"), traceback(2, true, false));
+        assert_eq!(Traceback::parse("crash"), traceback(2, true, true));
+        assert_eq!(Traceback::parse("1"), traceback(1, true, false));
+        assert_eq!(Traceback::parse("bogus"), traceback(0, true, false));
+
+        let settings = "gctrace=1,panicnil=0, panicnil=1";
+        assert_eq!(godebug_setting(settings, "panicnil").as_deref(), Some("1"));
+        assert_eq!(godebug_setting(settings, "gctrace").as_deref(), Some("1"));
+        assert_eq!(godebug_setting(settings, "asyncpreemptoff"), None);
+    }
+
+    #[test]
+    fn map_iterations_visit_every_entry_once() {
+        let map: GorsMap<isize, isize> = (0..64).map(|key| (key, key * 2)).collect();
+        for _ in 0..8 {
+            let mut keys: Vec<isize> = map.keys().copied().collect();
+            keys.sort_unstable();
+            assert_eq!(keys, (0..64).collect::<Vec<_>>());
+            assert_eq!(map.values().sum::<isize>(), (0..64).sum::<isize>() * 2);
+        }
+        let mut entries: Vec<(isize, isize)> = map.into_iter().collect();
+        entries.sort_unstable();
+        assert_eq!(entries.first(), Some(&(0, 0)));
+        assert_eq!(entries.len(), 64);
+    }
+
+    #[test]
+    fn map_key_order_sorts_integers_and_strings() {
+        let mut integers = vec![3_isize, -1, 0, isize::MIN, 42, -7];
+        integers.sort_by_cached_key(MapKeyOrder::of);
+        assert_eq!(integers, vec![isize::MIN, -7, -1, 0, 3, 42]);
+
+        let mut strings = vec!["b", "ab", "", "a", "aa", "é"];
+        strings.sort_by_cached_key(MapKeyOrder::of);
+        assert_eq!(strings, vec!["", "a", "aa", "ab", "b", "é"]);
+    }
+
     #[test]
     fn append_copy_delete_and_clear_match_builtin_shape() {
         let values = append(vec![1, 2], 3);
@@ -2622,7 +7563,7 @@ mod tests {
         assert_eq!(copy(&mut dst, &src), 3);
         assert_eq!(dst, vec![7, 8, 9]);
 
-        let mut map = HashMap::new();
+        let mut map = GorsMap::new();
         map.insert("a", 1);
         map.insert("b", 2);
         delete(&mut map, &"a");
@@ -2695,6 +7636,119 @@ mod tests {
         assert_eq!(string_from_byte_seq(&mutable_slice), "gors");
     }
 
+    #[derive(Default)]
+    struct GcNode {
+        next: GorsPtr<GcNode>,
+        label: std::string::String,
+    }
+
+    impl GorsTrace for GcNode {
+        // The borrow picks `GcSkipField` for the fields not `GorsTrace`.
+        #[allow(clippy::needless_borrow)]
+        fn trace(&self, tracer: &mut GcTracer) {
+            #[allow(unused_imports)]
+            use super::{GcSkipField as _, GcTraceField as _};
+            (&GcField(&self.next)).trace_field(tracer);
+            (&GcField(&self.label)).trace_field(tracer);
+        }
+    }
+
+    #[test]
+    fn local_cells_and_thread_globals_share_state_without_locks() {
+        thread_local! {
+            static COUNT: &'static std::rc::Rc<LocalMutex<isize>> =
+                Box::leak(Box::new(std::rc::Rc::new(LocalMutex::new(1))));
+        }
+        *thread_global(&COUNT).lock().unwrap() += 1;
+        assert_eq!(*thread_global(&COUNT).lock().unwrap(), 2);
+
+        let square: std::rc::Rc<dyn Fn(isize) -> isize> = std::rc::Rc::new(|n| n * n);
+        let func = std::rc::Rc::new(LocalMutex::new(Some(square)));
+        let call = lock_local_func(&func).clone().unwrap();
+        assert_eq!(call(3), 9);
+    }
+
+    #[test]
+    fn cycle_collector_frees_unreachable_cycles_only() {
+        fn object(ptr: &GorsPtr<GcNode>) -> std::sync::Weak<Mutex<GcNode>> {
+            match &ptr.inner {
+                Some(GorsPtrInner::Direct(inner)) => Arc::downgrade(inner),
+                _ => std::sync::Weak::new(),
+            }
+        }
+
+        // A heap of its own, which collections by other tests leave alone.
+        let first = GorsPtr::new(GcNode::default());
+        let second = GorsPtr::new(GcNode {
+            next: first.clone(),
+            label: "second".to_string(),
+        });
+        first.lock().unwrap().next = second.clone();
+        let kept = GorsPtr::new(GcNode::default());
+        kept.lock().unwrap().next = kept.clone();
+        let (first_object, second_object) = (object(&first), object(&second));
+        let objects: [std::sync::Weak<dyn GcObject>; 3] =
+            [first_object.clone(), second_object.clone(), object(&kept)];
+        let mut heap = GcHeap {
+            objects: objects.into(),
+            next_collection: GC_MIN_OBJECTS,
+        };
+        drop((first, second));
+        assert!(first_object.upgrade().is_some());
+
+        let garbage = tracked_garbage(&mut heap);
+        assert_eq!(garbage.len(), 2);
+        for object in garbage {
+            object.clear_object();
+        }
+        assert!(first_object.upgrade().is_none());
+        assert!(second_object.upgrade().is_none());
+        assert_eq!(kept.lock().unwrap().next.ptr_id(), kept.ptr_id());
+        assert!(tracked_garbage(&mut heap).is_empty());
+        assert_eq!(heap.objects.len(), 1);
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn gc_probe_tracks_traceable_values_only() {
+        let traced = (&GcProbe::new(GcNode::default())).gors_ptr();
+        let untraced = (&GcProbe::new(1_isize)).gors_ptr();
+        let tracked = |address: *const ()| {
+            lock_scheduler(&GC_HEAP)
+                .objects
+                .iter()
+                .any(|object| object.as_ptr().cast::<()>() == address)
+        };
+        assert!(tracked(traced.ptr_id()));
+        assert!(!tracked(untraced.ptr_id()));
+    }
+
+    #[test]
+    fn collection_waits_for_goroutines_at_safepoints() {
+        // Take over from the collections other tests may run.
+        loop {
+            let mut stop = lock_scheduler(&GC_STOP);
+            if !stop.requested {
+                stop.requested = true;
+                break;
+            }
+            drop(stop);
+            std::thread::yield_now();
+        }
+        GC_STOP_REQUESTED.store(true, std::sync::atomic::Ordering::SeqCst);
+        let _ = GC_SAFEPOINT_HOOK.set(gc_safepoint);
+        let stopped = std::thread::spawn(yield_point);
+        while lock_scheduler(&GC_STOP).stopped == 0 {
+            std::thread::yield_now();
+        }
+        assert!(!stopped.is_finished());
+
+        GC_STOP_REQUESTED.store(false, std::sync::atomic::Ordering::SeqCst);
+        lock_scheduler(&GC_STOP).requested = false;
+        GC_RESUME.notify_all();
+        stopped.join().unwrap();
+    }
+
     #[test]
     fn lock_func_calls_shared_function_values() {
         let func: Arc<Mutex<dyn FnMut(isize) -> isize + Send>> =
@@ -2775,6 +7829,213 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn goroutines_share_worker_threads_and_communicate_over_channels() {
+        const GOROUTINES: usize = 100_000;
+        let results = make_chan::<usize>(0);
+        for i in 0..GOROUTINES {
+            let results = results.clone();
+            go(move || send(&results, i));
+        }
+        let sum = (0..GOROUTINES).map(|_| recv(&results)).sum::<usize>();
+        assert_eq!(sum, (0..GOROUTINES).sum::<usize>());
+    }
+
+    #[test]
+    fn sync_waits_block_until_released_or_notified() {
+        let done = make_chan::<bool>(0);
+        let sema = GorsPtr::new(0u32);
+        {
+            let (sema, done) = (sema.clone(), done.clone());
+            go(move || {
+                semacquire(&sema, SemaWait::Mutex);
+                send(&done, true);
+            });
+        }
+        semrelease(&sema);
+        assert!(recv(&done));
+
+        let list = GorsPtr::new(0u32);
+        for ticket in [notify_list_add(&list), notify_list_add(&list)] {
+            let (list, done) = (list.clone(), done.clone());
+            go(move || {
+                notify_list_wait(&list, ticket);
+                send(&done, true);
+            });
+        }
+        notify_list_notify_one(&list);
+        assert!(recv(&done));
+        assert_eq!(done.try_recv(), Err(TryRecvError::Empty));
+        notify_list_notify_all(&list);
+        assert!(recv(&done));
+        notify_list_wait(&list, notify_list_add(&list).wrapping_sub(1));
+    }
+
+    #[test]
+    fn spinning_goroutines_are_preempted_at_yield_points() {
+        let flag = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let done = make_chan::<bool>(0);
+        {
+            let flag = flag.clone();
+            let done = done.clone();
+            go(move || {
+                while !flag.load(std::sync::atomic::Ordering::Relaxed) {
+                    yield_point();
+                }
+                send(&done, true);
+            });
+        }
+        go(move || flag.store(true, std::sync::atomic::Ordering::Relaxed));
+        assert!(recv(&done));
+    }
+
+    #[test]
+    fn select_chooses_among_ready_cases_at_random() {
+        let (left, right) = (make_chan::<usize>(1000), make_chan::<usize>(1000));
+        for i in 0..1000 {
+            send(&left, i);
+            send(&right, i);
+        }
+        let mut chosen = [0; 2];
+        for _ in 0..1000 {
+            let mut left = SelectRecv::new(&left);
+            let mut right = SelectRecv::new(&right);
+            if let Some(count) = chosen.get_mut(select(&mut [&mut left, &mut right], false)) {
+                *count += 1;
+            }
+        }
+        assert!(chosen.iter().all(|&count| count > 350), "{chosen:?}");
+    }
+
+    #[test]
+    fn blocked_selects_rendezvous_on_unbuffered_channels() {
+        let (values, done) = (make_chan::<usize>(0), make_chan::<usize>(0));
+        {
+            let values = values.clone();
+            go(move || {
+                for i in 0..1000 {
+                    let mut value = SelectSend::new(&values, i);
+                    select(&mut [&mut value], false);
+                }
+            });
+        }
+        {
+            let done = done.clone();
+            go(move || {
+                let never = make_chan::<usize>(0);
+                let mut sum = 0;
+                for _ in 0..1000 {
+                    let mut value = SelectRecv::new(&values);
+                    let mut other = SelectRecv::new(&never);
+                    assert_eq!(select(&mut [&mut value, &mut other], false), 0);
+                    sum += value.received().0;
+                }
+                send(&done, sum);
+            });
+        }
+        assert_eq!(recv(&done), (0..1000).sum::<usize>());
+    }
+
+    #[test]
+    fn select_without_ready_case_chooses_default() {
+        let empty = make_chan::<usize>(0);
+        let mut recv_case = SelectRecv::new(&empty);
+        let mut send_case = SelectSend::new(&empty, 1);
+        assert_eq!(select(&mut [&mut recv_case, &mut send_case], true), 2);
+    }
+
+    /// The message of the runtime error `f` panics with, from the payload
+    /// `catch_unwind` returns.
+    fn panic_message(f: impl FnOnce()) -> Option<std::string::String> {
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).err()?;
+        payload
+            .downcast_ref::<Box<dyn error>>()
+            .map(|error| error.Error())
+    }
+
+    #[test]
+    fn closed_and_nil_channel_operations_panic() {
+        let unbuffered = make_chan::<usize>(0);
+        let started = make_chan::<()>(1);
+        let (sender, start) = (unbuffered.clone(), started.clone());
+        let blocked = std::thread::spawn(move || {
+            start.send(());
+            panic_message(|| sender.send(1))
+        });
+        started.recv();
+        // Close once the sender is blocked waiting for its value to be taken.
+        let state = unbuffered.inner.as_ref().unwrap();
+        while lock_chan(&state.0).senders.waiting == 0 {
+            std::thread::yield_now();
+        }
+        unbuffered.close();
+        assert_eq!(
+            blocked.join().unwrap().as_deref(),
+            Some("send on closed channel")
+        );
+        assert_eq!(unbuffered.recv(), None);
+        let closed = make_chan::<usize>(1);
+        closed.close();
+        assert_eq!(
+            panic_message(|| closed.send(1)).as_deref(),
+            Some("send on closed channel")
+        );
+        assert_eq!(
+            panic_message(|| {
+                let mut send_case = SelectSend::new(&closed, 1);
+                select(&mut [&mut send_case], true);
+            })
+            .as_deref(),
+            Some("send on closed channel")
+        );
+        assert_eq!(
+            panic_message(|| closed.close()).as_deref(),
+            Some("close of closed channel")
+        );
+        assert_eq!(
+            panic_message(|| Chan::<usize>::default().close()).as_deref(),
+            Some("close of nil channel")
+        );
+    }
+
+    #[test]
+    fn gomaxprocs_returns_the_previous_setting() {
+        let previous = gomaxprocs(0);
+        assert!(previous >= 1);
+        assert_eq!(gomaxprocs(0), previous);
+        assert_eq!(gomaxprocs(previous), previous);
+    }
+
+    #[test]
+    fn all_goroutines_asleep_compares_live_and_blocked_counts() {
+        assert!(!all_goroutines_asleep(LIVE_GOROUTINE));
+        assert!(all_goroutines_asleep(LIVE_GOROUTINE + BLOCKED_GOROUTINE));
+        assert!(!all_goroutines_asleep(
+            3 * LIVE_GOROUTINE + 2 * BLOCKED_GOROUTINE
+        ));
+        assert!(all_goroutines_asleep(
+            3 * LIVE_GOROUTINE + 3 * BLOCKED_GOROUTINE
+        ));
+    }
+
+    #[test]
+    fn deadlock_is_reported_once_no_goroutine_can_run() {
+        assert_eq!(deadlock(LIVE_GOROUTINE, false), None);
+        assert_eq!(
+            deadlock(2 * LIVE_GOROUTINE + 2 * BLOCKED_GOROUTINE, false),
+            Some("all goroutines are asleep - deadlock!")
+        );
+        assert_eq!(
+            deadlock(0, false),
+            Some("all goroutines are asleep - deadlock!")
+        );
+        assert_eq!(
+            deadlock(0, true),
+            Some("no goroutines (main called runtime.Goexit) - deadlock!")
+        );
+        assert_eq!(deadlock(LIVE_GOROUTINE, true), None);
+    }
+
     #[test]
     fn reflect_type_comparable_tracks_known_non_comparable_values() {
         assert!(reflect_type_comparable(
@@ -2845,7 +8106,7 @@ mod tests {
         let recovered = recover();
         assert!(!interface_is_nil(recovered.as_ref()));
         assert!(interface_is_nil(recover().as_ref()));
-        set_recover_payload_any(Box::new("stored".to_string()) as Box<dyn Any>);
+        set_recover_payload_any(GorsAny::new("stored".to_string()));
         let recovered_any = recover();
         assert_eq!(
             recovered_any.downcast_ref::<std::string::String>(),
@@ -2868,7 +8129,7 @@ mod tests {
         let previous_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(|_| {}));
         let panic_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            panic_any_payload(Box::new("payload".to_string()) as Box<dyn Any>);
+            panic_any_payload(GorsAny::new("payload".to_string()));
         }));
         let payload = panic_result.unwrap_err();
         assert_eq!(
@@ -2877,4 +8138,411 @@ mod tests {
         );
         std::panic::set_hook(previous_hook);
     }
+
+    #[test]
+    fn deferred_calls_handle_the_panics_of_their_goroutine() {
+        fn recovered_string() -> Option<std::string::String> {
+            recover().downcast_ref::<&str>().map(ToString::to_string)
+        }
+
+        let recovered = Arc::new(Mutex::new(Vec::new()));
+        let record = |recovered: &Arc<Mutex<Vec<Option<std::string::String>>>>| {
+            let recovered = Arc::clone(recovered);
+            Box::new(move || {
+                let value = recovered_string();
+                recovered.lock().unwrap().push(value);
+            }) as Box<dyn FnOnce()>
+        };
+
+        set_recover_payload("boom");
+        run_deferred(&mut vec![record(&recovered), record(&recovered)]);
+        assert_eq!(*recovered.lock().unwrap(), [Some("boom".to_string()), None]);
+
+        recovered.lock().unwrap().clear();
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        set_recover_payload("first");
+        run_deferred(&mut vec![
+            record(&recovered),
+            Box::new(|| panic_value("second")),
+        ]);
+        assert_eq!(*recovered.lock().unwrap(), [Some("second".to_string())]);
+
+        assert_eq!(
+            recover_func(|| {
+                set_recover_payload("unrecovered");
+                run_deferred(&mut vec![Box::new(|| {})]);
+            })
+            .as_deref(),
+            Some("unrecovered")
+        );
+        std::panic::set_hook(previous_hook);
+
+        recovered.lock().unwrap().clear();
+        let exited = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            set_recover_payload(Goexit);
+            run_deferred(&mut vec![record(&recovered)]);
+        }));
+        assert!(exited.is_err_and(|payload| payload.is::<Goexit>()));
+        assert_eq!(*recovered.lock().unwrap(), [None]);
+
+        let barrier = Arc::new(std::sync::Barrier::new(4));
+        let threads = ["a", "b", "c", "d"].map(|value| {
+            let barrier = Arc::clone(&barrier);
+            std::thread::spawn(move || {
+                set_recover_payload(value);
+                barrier.wait();
+                recovered_string()
+            })
+        });
+        for (thread, value) in threads.into_iter().zip(["a", "b", "c", "d"]) {
+            assert_eq!(thread.join().ok().flatten().as_deref(), Some(value));
+        }
+    }
+
+    #[test]
+    #[allow(clippy::indexing_slicing)]
+    fn rust_panics_become_go_runtime_errors() {
+        fn caught(f: impl FnOnce()) -> std::string::String {
+            let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_err();
+            let payload = go_panic_payload(payload);
+            match payload.downcast_ref::<Box<dyn error>>() {
+                Some(error) => error.Error(),
+                None => format!("not a runtime error: {}", describe_panic(payload.as_ref())),
+            }
+        }
+
+        let values = [1, 2, 3];
+        let text = "hello".to_string();
+        let [two, three, five, zero] = std::hint::black_box([2_usize, 3, 5, 0]);
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let messages = [
+            caught(|| _ = std::hint::black_box(values[five])),
+            caught(|| drop(values[..five].to_vec())),
+            caught(|| drop(values[five..].to_vec())),
+            caught(|| drop(values[three..two].to_vec())),
+            caught(|| drop(text[..five + 1].to_string())),
+            caught(|| drop(text[five + 1..].to_string())),
+            caught(|| drop(text[three..two].to_string())),
+            caught(|| _ = std::hint::black_box(five / zero)),
+            caught(|| _ = std::hint::black_box(five % zero)),
+            caught(|| drop(GorsPtr::<i32>::nil().lock().unwrap())),
+            caught(|| panic_value("index out of range")),
+        ];
+        std::panic::set_hook(previous_hook);
+
+        assert_eq!(
+            messages,
+            [
+                "runtime error: index out of range [5] with length 3",
+                "runtime error: slice bounds out of range [:5] with capacity 3",
+                "runtime error: slice bounds out of range [5:3]",
+                "runtime error: slice bounds out of range [3:2]",
+                "runtime error: slice bounds out of range [:6] with length 5",
+                "runtime error: slice bounds out of range [6:5]",
+                "runtime error: slice bounds out of range [3:2]",
+                "runtime error: integer divide by zero",
+                "runtime error: integer divide by zero",
+                "runtime error: invalid memory address or nil pointer dereference",
+                "not a runtime error: index out of range",
+            ]
+        );
+        assert_eq!(
+            RuntimeError::index(usize::MAX, 3).Error(),
+            "runtime error: index out of range [-1]"
+        );
+        assert_eq!(
+            RuntimeError::nil_map().Error(),
+            "assignment to entry in nil map"
+        );
+    }
+
+    #[test]
+    fn unrecovered_panics_are_reported_like_go() {
+        #[derive(Clone, PartialEq)]
+        struct Failure;
+
+        type Deferred = Vec<Box<dyn FnOnce()>>;
+
+        fn report(deferred: fn() -> Deferred) -> std::string::String {
+            std::thread::spawn(move || {
+                let mut deferred = deferred();
+                let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    set_recover_payload("first");
+                    run_deferred(&mut deferred);
+                }))
+                .unwrap_err();
+                with_goroutine_panics(|panics| panic_report(&panics.earlier, payload.as_ref()))
+            })
+            .join()
+            .unwrap()
+        }
+
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let reports = [
+            report(|| vec![Box::new(|| {})]),
+            report(|| vec![Box::new(|| panic_value(2.5))]),
+            report(|| {
+                vec![Box::new(|| {
+                    let _ = recover();
+                    panic_value("second");
+                })]
+            }),
+            report(|| {
+                vec![
+                    Box::new(|| std::panic::resume_unwind(recover())),
+                    Box::new(|| panic_value(with_panic_description(Failure, |_| "failed".into()))),
+                ]
+            }),
+            report(|| {
+                vec![
+                    Box::new(|| panic_value("third")),
+                    Box::new(|| {
+                        let _ = recover();
+                    }),
+                    Box::new(|| panic_value("second")),
+                ]
+            }),
+        ];
+        std::panic::set_hook(previous_hook);
+        assert_eq!(
+            reports,
+            [
+                "panic: first",
+                "panic: first\n\tpanic: +2.500000e+000",
+                "panic: first [recovered]\n\tpanic: second",
+                "panic: first\n\tpanic: failed [recovered, repanicked]",
+                "panic: third",
+            ]
+        );
+
+        assert_eq!(
+            describe_panic(panic_value_ref(&*box_any_comparable(7_i64))),
+            "7"
+        );
+        assert_eq!(describe_panic(&Failure), "failed");
+        assert_eq!(describe_panic(&()), "nil");
+        assert_eq!(describe_named_panic("main.Code", &7_isize), "main.Code(7)");
+        assert_eq!(
+            describe_named_panic("main.Name", &"n".to_string()),
+            "main.Name(\"n\")"
+        );
+        assert!(describe_named_panic("main.Pair", &Failure).starts_with("(main.Pair) 0x"));
+        assert_eq!(go_print_float(-0.001), "-1.000000e-003");
+        assert_eq!(go_print_float(1e100), "+1.000000e+100");
+        assert_eq!(go_print_float(f64::NEG_INFINITY), "-Inf");
+    }
+
+    #[test]
+    fn rust_symbols_name_the_go_functions_of_goroutine_stacks() {
+        let runtime = "main::__gors_lib::builtin";
+        let named = |symbol| go_function(symbol, runtime);
+        let function = |name: &str, parent: Option<&str>| {
+            Some(GoFunction {
+                name: name.to_string(),
+                parent: parent.map(ToString::to_string),
+            })
+        };
+
+        assert_eq!(named("main::worker"), function("main.worker", None));
+        assert_eq!(named("main::main"), None);
+        assert_eq!(
+            named("main::main::{{closure}}"),
+            function("main.main", None)
+        );
+        assert_eq!(
+            named("main::main::{{closure}}::{{closure}}"),
+            function("main.main.func1", Some("main.main"))
+        );
+        assert_eq!(
+            named("main::serve::{{closure}}::{closure#0}"),
+            function("main.serve.func1.1", Some("main.serve.func1"))
+        );
+        assert_eq!(
+            named("main::__gors_lib::fmt::Println::h0123456789abcdef"),
+            function("fmt.Println", None)
+        );
+        assert_eq!(
+            named("main::__gors_lib::encoding__json::Marshal"),
+            function("encoding/json.Marshal", None)
+        );
+        assert_eq!(
+            named("<main::Celsius as main::__gors_lib::fmt::Stringer>::String"),
+            function("main.Celsius.String", None)
+        );
+        assert_eq!(named("main::__gors_lib::builtin::Chan<T>::recv"), None);
+        assert_eq!(
+            named("<main::__gors_lib::builtin::Chan<isize>>::recv"),
+            None
+        );
+        assert_eq!(named("main::__gors_lib::runtime::Stack"), None);
+        assert_eq!(named("std::rt::lang_start_internal"), None);
+        assert_eq!(named("<unknown>"), None);
+    }
+
+    #[test]
+    fn goroutine_stacks_keep_the_go_frames_of_rust_backtraces() {
+        let backtrace = "   0: std::backtrace::Backtrace::force_capture
+             at /rustc/library/std/src/backtrace.rs:312:13
+   1: main::__gors_lib::builtin::Chan<T>::recv
+             at ./builtin.rs:2290:21
+   2: main::__gors_lib::fmt::Println
+             at ./fmt.rs:120:9
+   3: main::worker
+             at ./main.rs:30:5
+   4: main::main::{{closure}}::{{closure}}
+   5: std::rt::lang_start
+";
+        let frames = go_frames(backtrace, "main::__gors_lib::builtin");
+
+        let functions: Vec<_> = frames
+            .iter()
+            .map(|frame| frame.function.name.as_str())
+            .collect();
+        assert_eq!(functions, ["fmt.Println", "main.worker", "main.main.func1"]);
+        let locations: Vec<_> = frames
+            .iter()
+            .map(|frame| format!("{}:{}", frame.file, frame.line))
+            .collect();
+        assert_eq!(locations, ["./fmt.rs:120", "./main.rs:30", "?:0"]);
+        assert_eq!(
+            frames
+                .last()
+                .and_then(|frame| frame.function.parent.as_deref()),
+            Some("main.main")
+        );
+    }
+
+    #[test]
+    fn line_tables_map_generated_positions_back_to_go_lines() {
+        let table = LineTable {
+            file: "main.rs",
+            package: "main",
+            sources: &["main.go", "util.go"],
+            lines: &[(1, 1, 0, 1), (3, 4, 0, 3), (3, 5, 0, 4), (4, 3, 1, 7)],
+            functions: &[(0, 3, 5, "main.main"), (1, 6, 9, "main.(*T).Run")],
+        };
+
+        assert_eq!(table.position(1, 1), Some(("main.go".to_string(), 1)));
+        assert_eq!(table.position(2, 8), Some(("main.go".to_string(), 1)));
+        assert_eq!(table.position(3, 4), Some(("main.go".to_string(), 3)));
+        assert_eq!(table.position(3, 9), Some(("main.go".to_string(), 4)));
+        assert_eq!(table.position(9, 1), Some(("util.go".to_string(), 7)));
+        assert_eq!(table.position(0, 5), None);
+        assert_eq!(table.position(4, 1), Some(("util.go".to_string(), 7)));
+        assert_eq!(table.function(1, 1), None);
+        assert_eq!(table.function(3, 9), Some("main.main"));
+        assert_eq!(table.function(9, 1), Some("main.(*T).Run"));
+    }
+
+    #[test]
+    fn callers_hand_out_program_counters_of_go_frames() {
+        // The frames of the runtime crate itself are left out.
+        let mut pcs = [0usize; 4];
+        assert_eq!(callers(0, &mut pcs), 1);
+        let [pc, ..] = pcs;
+        assert_eq!(
+            caller_frame(pc),
+            Some(("runtime.Callers".to_string(), "?".to_string(), 0))
+        );
+        assert_eq!(callers(1, &mut pcs), 0);
+        assert_eq!(caller_frame(0), None);
+        assert_eq!(caller(0), (0, std::string::String::new(), 0, false));
+        assert!(!caller(-1).3);
+    }
+
+    #[test]
+    #[allow(clippy::coerce_container_to_any)] // generated code passes interface values as `&dyn Any`
+    fn interface_values_hold_basic_kinds_inline() {
+        assert!(matches!(GorsAny::new(7isize), GorsAny::Int(7)));
+        assert!(matches!(GorsAny::new("s".to_string()), GorsAny::String(_)));
+        assert!(GorsAny::new(()).is_nil());
+        assert!(matches!(
+            GorsAny::new(Box::new(1u8) as Box<dyn Any>),
+            GorsAny::Uint8(1)
+        ));
+        assert!(matches!(box_any_comparable(1.5f64), GorsAny::Float64(_)));
+        assert!(matches!(box_any_comparable([1u8, 2]), GorsAny::Boxed(_)));
+        let comparable: Box<dyn Any> =
+            Box::new(Box::new(GorsComparableAny("s".to_string(), |_, _| {}))
+                as Box<dyn GorsAnyComparable>);
+        assert!(matches!(GorsAny::from_box(comparable), GorsAny::String(_)));
+
+        let hash = |value: &GorsAny| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            value.hash(&mut hasher);
+            std::hash::Hasher::finish(&hasher)
+        };
+        assert!(GorsAny::new(0.0f64) == GorsAny::new(-0.0f64));
+        assert_eq!(hash(&GorsAny::new(0.0f64)), hash(&GorsAny::new(-0.0f64)));
+        assert!(GorsAny::new(1isize) != GorsAny::new(1i64));
+        assert!(box_any_comparable([1u8, 2]) == box_any_comparable([1u8, 2]));
+        assert_eq!(
+            hash(&box_any_comparable([1u8, 2])),
+            hash(&box_any_comparable([1u8, 2]))
+        );
+        assert!(any_eq(&GorsAny::new(3i32), &3i32));
+        assert!(matches!(clone_any(&GorsAny::new(2u16)), GorsAny::Uint16(2)));
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn comparable_probe_hashes_gors_hash_values_by_value() {
+        let hash = |value: &GorsAny| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            value.hash(&mut hasher);
+            std::hash::Hasher::finish(&hasher)
+        };
+        let hashed = |value: [f64; 2]| (&ComparableProbe::new(value)).box_any_comparable();
+        assert_eq!(hash(&hashed([0.0, 1.0])), hash(&hashed([-0.0, 1.0])));
+        assert_ne!(hash(&hashed([1.0, 2.0])), hash(&hashed([2.0, 1.0])));
+        #[derive(Clone, PartialEq)]
+        struct Opaque(u8);
+        let unhashed = |value| (&ComparableProbe::new(Opaque(value))).box_any_comparable();
+        assert!(unhashed(1) != unhashed(2));
+        assert_eq!(hash(&unhashed(1)), hash(&unhashed(2)));
+    }
+
+    #[test]
+    fn goroutine_stack_sizes_parse_with_binary_suffixes() {
+        assert_eq!(parse_stack_size("4096"), Some(4096));
+        assert_eq!(parse_stack_size(" 64M "), Some(64 << 20));
+        assert_eq!(parse_stack_size("2g"), Some(2 << 30));
+        assert_eq!(parse_stack_size("8k"), Some(8 << 10));
+        assert_eq!(parse_stack_size("0"), None);
+        assert_eq!(parse_stack_size("M"), None);
+        assert_eq!(parse_stack_size("lots"), None);
+        assert!(stack_overflow_message().ends_with("fatal error: stack overflow\n"));
+        assert!(worker_stack_size() <= goroutine_stack_size());
+    }
+
+    #[test]
+    fn stack_limits_leave_headroom_below_the_calling_frame() {
+        std::thread::spawn(|| {
+            check_stack();
+            set_stack_limit(1 << 20);
+            let limit = stack_limit().get();
+            assert!(limit < stack_position());
+            assert!(stack_position() - limit <= (1 << 20) - (STACK_HEADROOM / 2));
+            check_stack();
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn runtime_stack_formats_the_calling_goroutine_to_fit_the_buffer() {
+        let mut buf = [0u8; 4096];
+        let n = goroutine_stack(&mut buf, true);
+        let dump = std::str::from_utf8(buf.get(..n as usize).unwrap()).unwrap();
+        assert!(dump.starts_with("goroutine 1 [running]:\n"), "{dump}");
+
+        let mut short = [0u8; 9];
+        assert_eq!(goroutine_stack(&mut short, false), 9);
+        assert_eq!(&short, b"goroutine");
+        assert_eq!(GoroutineStatus::ChanReceive.as_str(), "chan receive");
+        assert_eq!(GoroutineStatus::SelectNoCases.as_str(), "select (no cases)");
+    }
 }
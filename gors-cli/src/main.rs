@@ -13,17 +13,54 @@ const RUST_EDITION: &str = "2024";
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     pretty_env_logger::init();
 
-    let opts: Opts = Opts::parse();
+    let opts: Opts = Opts::parse_from(normalize_go_style_flags(std::env::args()));
     match opts.subcmd {
         SubCommand::Ast(cmd) => ast(cmd),
         SubCommand::Build(cmd) => build(cmd),
+        SubCommand::Generate(cmd) => generate(cmd),
         SubCommand::Help(cmd) => help(cmd),
+        SubCommand::List(cmd) => list(cmd),
         SubCommand::Run(cmd) => run(cmd),
         SubCommand::Tokens(cmd) => tokens(cmd),
         SubCommand::Version => version(),
+        SubCommand::Vet(cmd) => vet(cmd),
     }
 }
 
+/// Single-dash long flags accepted for compatibility with the `go` command.
+const GO_STYLE_FLAGS: &[&str] = &["-deps", "-json", "-race", "-run"];
+
+/// Subcommands whose arguments accept [`GO_STYLE_FLAGS`].
+const GO_STYLE_FLAG_COMMANDS: &[&str] = &["generate", "list"];
+
+/// Subcommands which accept [`GO_STYLE_FLAGS`] before their first
+/// positional argument only.
+const GO_STYLE_LEADING_FLAG_COMMANDS: &[&str] = &["build", "run"];
+
+/// Rewrite Go-style `-flag` arguments into the `--flag` form clap expects.
+///
+/// Only arguments of [`GO_STYLE_FLAG_COMMANDS`], and the leading flags of
+/// [`GO_STYLE_LEADING_FLAG_COMMANDS`], are rewritten, so program arguments
+/// passed through `gors run` are left untouched.
+fn normalize_go_style_flags(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut args = args.into_iter().collect::<Vec<_>>();
+    let command = args.get(1).map_or("", String::as_str);
+    let accepts_go_flags = GO_STYLE_FLAG_COMMANDS.contains(&command);
+    let leading_only = GO_STYLE_LEADING_FLAG_COMMANDS.contains(&command);
+    if accepts_go_flags || leading_only {
+        for arg in args.iter_mut().skip(2) {
+            if leading_only && !arg.starts_with('-') {
+                break;
+            }
+            let flag = arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag);
+            if GO_STYLE_FLAGS.contains(&flag) {
+                arg.insert(0, '-');
+            }
+        }
+    }
+    args
+}
+
 /// Print a formatted error with source context
 fn print_error(diagnostic: &Diagnostic) {
     // Check if stdout supports colors
@@ -96,9 +133,15 @@ enum SubCommand {
     /// Transpile Go source to Rust files, writing to cache or --output
     #[command(display_order = 0)]
     Build(Build),
+    /// Run `//go:generate` directives in package sources
+    #[command(display_order = 0)]
+    Generate(Generate),
     /// Print this message or the help of the given command(s)
     #[command(display_order = 0)]
     Help(Help),
+    /// List packages and their metadata, like `go list`
+    #[command(display_order = 0)]
+    List(List),
     /// Transpile, compile, and run Go source path(s)
     #[command(display_order = 0)]
     Run(Run),
@@ -108,6 +151,9 @@ enum SubCommand {
     /// Print gors version
     #[command(display_order = 0)]
     Version,
+    /// Report compile errors in packages without writing output
+    #[command(display_order = 0)]
+    Vet(Vet),
 }
 
 #[derive(Parser)]
@@ -119,8 +165,9 @@ struct Ast {
 
 #[derive(Parser)]
 struct Build {
-    /// The Go source file or directory to build
-    path: String,
+    /// Packages to build: directories, `.go` files, or patterns like `./...`
+    #[arg(value_name = "PACKAGES")]
+    packages: Vec<String>,
     /// Build in release mode, with optimizations
     #[arg(long)]
     release: bool,
@@ -130,6 +177,48 @@ struct Build {
     /// Output file path
     #[arg(short, long)]
     output: Option<String>,
+    /// Print which variables are moved to the heap, like `-gcflags=-m`
+    #[arg(long)]
+    explain_escapes: bool,
+    /// Enable data race detection
+    #[arg(long)]
+    race: bool,
+    /// Keep the thread-safe runtime types in programs that start no
+    /// goroutines
+    #[arg(long)]
+    thread_safe: bool,
+}
+
+#[derive(Parser)]
+struct Generate {
+    /// Print the commands that would be run, without running them
+    #[arg(short = 'n')]
+    dry_run: bool,
+    /// Print the commands as they are run
+    #[arg(short = 'x')]
+    trace: bool,
+    /// Only run directives whose full text matches this regular expression
+    #[arg(long, value_name = "REGEXP")]
+    run: Option<String>,
+    /// Packages to process: directories, `.go` files, or patterns like `./...`
+    #[arg(value_name = "PACKAGES")]
+    packages: Vec<String>,
+}
+
+#[derive(Parser)]
+struct List {
+    /// Print package metadata as JSON objects
+    #[arg(long)]
+    json: bool,
+    /// Also list the dependencies of the named packages
+    #[arg(long)]
+    deps: bool,
+    /// Report packages that fail to load in their `Error` field instead of
+    /// failing
+    #[arg(short = 'e')]
+    errors: bool,
+    /// Packages to list: directories, `.go` files, `std`, or patterns like `./...`
+    patterns: Vec<String>,
 }
 
 #[derive(Parser)]
@@ -137,11 +226,28 @@ struct Run {
     /// Build in release mode, with optimizations
     #[arg(long)]
     release: bool,
+    /// Print which variables are moved to the heap, like `-gcflags=-m`
+    #[arg(long)]
+    explain_escapes: bool,
+    /// Enable data race detection
+    #[arg(long)]
+    race: bool,
+    /// Keep the thread-safe runtime types in programs that start no
+    /// goroutines
+    #[arg(long)]
+    thread_safe: bool,
     /// Go source file(s), directory, or package path, followed by optional program arguments
     #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
     args: Vec<String>,
 }
 
+#[derive(Parser)]
+struct Vet {
+    /// Packages to check: directories, `.go` files, or patterns like `./...`
+    #[arg(value_name = "PACKAGES")]
+    packages: Vec<String>,
+}
+
 #[derive(Parser)]
 struct Help {
     /// Print help for the command(s)
@@ -196,22 +302,240 @@ fn ast_output(file: &str) -> Result<Vec<u8>, String> {
 }
 
 fn build(cmd: Build) -> Result<(), Box<dyn std::error::Error>> {
+    let packages = expand_package_args(&cmd.packages);
+    if let [sources] = packages.as_slice() {
+        let Some(compiled) = compile_package(sources, false, cmd.race, cmd.thread_safe) else {
+            std::process::exit(1);
+        };
+        if cmd.explain_escapes {
+            explain_escapes(&compiled);
+        }
+        let output_dir = cmd
+            .output
+            .as_deref()
+            .map(PathBuf::from)
+            .map_or_else(|| build_cache_dir(&sources.join(" ")), Ok)?;
+        return write_build_output(compiled, &output_dir);
+    }
+
+    let mut failed = false;
+    for sources in &packages {
+        let Some(compiled) = compile_package(sources, true, cmd.race, cmd.thread_safe) else {
+            failed = true;
+            continue;
+        };
+        if cmd.explain_escapes {
+            explain_escapes(&compiled);
+        }
+        if !compiled.has_main {
+            continue;
+        }
+        let path = sources.join(" ");
+        let output_dir = match &cmd.output {
+            Some(output) => PathBuf::from(output).join(package_output_name(&path)),
+            None => build_cache_dir(&path)?,
+        };
+        write_build_output(compiled, &output_dir)?;
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn vet(cmd: Vet) -> Result<(), Box<dyn std::error::Error>> {
+    let packages = expand_package_args(&cmd.packages);
+    let multiple = packages.len() > 1;
+    let failed = packages
+        .iter()
+        .filter(|sources| compile_package(sources, multiple, false, false).is_none())
+        .count();
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn generate(cmd: Generate) -> Result<(), Box<dyn std::error::Error>> {
+    let run_filter = cmd.run.as_deref().map(regex::Regex::new).transpose()?;
+    for sources in expand_package_args(&cmd.packages) {
+        let (dir, files) = match generate_files(&sources) {
+            Ok(package) => package,
+            Err(err) => {
+                eprintln!("gors: {err}");
+                std::process::exit(1);
+            }
+        };
+        for path in files {
+            if !generate_file(&cmd, run_filter.as_ref(), &dir, &path)? {
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The directory and sources `gors generate` processes for one unit.
+///
+/// Like `go generate`, test files are processed too, after the package
+/// files.
+fn generate_files(sources: &[String]) -> Result<(PathBuf, Vec<String>), String> {
+    if sources.iter().all(|source| source.ends_with(".go")) {
+        let dir = sources
+            .first()
+            .and_then(|source| Path::new(source).parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        return Ok((dir, sources.to_vec()));
+    }
+    let dir = sources.first().map_or(".", String::as_str);
+    let files = gors::parser::scan_package_dir(dir).map_err(|err| err.to_string())?;
+    let mut paths = files.go_files;
+    paths.extend(files.test_go_files);
+    paths.extend(files.xtest_go_files);
+    Ok((PathBuf::from(dir), paths))
+}
+
+/// Run the directives of one file, reporting the first failure.
+///
+/// Returns `false` when a directive failed.
+fn generate_file(
+    cmd: &Generate,
+    run_filter: Option<&regex::Regex>,
+    dir: &Path,
+    path: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let buffer = std::fs::read_to_string(path)?;
+    let file = match gors::parser::parse_file(path, &buffer) {
+        Ok(file) => file,
+        Err(err) => {
+            print_error(&Diagnostic::from_parser_error(&err, path, &buffer));
+            return Ok(false);
+        }
+    };
+    let base_env = vec![
+        ("GOARCH".to_string(), go_target_arch().to_string()),
+        ("GOOS".to_string(), go_target_os().to_string()),
+    ];
+    let mut generator = gors::generate::Generator::new(path, file.name.name, base_env);
+    for directive in gors::generate::file_directives(&file) {
+        if run_filter.is_some_and(|filter| !filter.is_match(directive.text.trim())) {
+            continue;
+        }
+        let fail = |message: String| {
+            eprintln!("{path}:{}: {message}", directive.line);
+            Ok(false)
+        };
+        let words = match generator.command(&directive) {
+            Ok(Some(words)) => words,
+            Ok(None) => continue,
+            Err(message) => return fail(message),
+        };
+        let Some((program, args)) = words.split_first() else {
+            continue;
+        };
+        if cmd.dry_run || cmd.trace {
+            eprintln!("{}", words.join(" "));
+        }
+        if cmd.dry_run {
+            continue;
+        }
+        if program == "stringer" {
+            if let Err(message) = gors::generate::stringer::run(args, dir) {
+                return fail(format!("running {program:?}: {message}"));
+            }
+            continue;
+        }
+        let status = Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .envs(generator.env(&directive))
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => return fail(format!("running {program:?}: {status}")),
+            Err(err) => return fail(format!("running {program:?}: {err}")),
+        }
+    }
+    Ok(true)
+}
+
+/// Expand package pattern arguments into compilation units.
+///
+/// `.go` file arguments form a single unit; every other argument is expanded
+/// with [`gors::packages::expand_patterns`] into one unit per package.
+fn expand_package_args(args: &[String]) -> Vec<Vec<String>> {
+    if args.is_empty() {
+        return vec![vec![".".to_string()]];
+    }
+    if args.iter().all(|arg| arg.ends_with(".go")) {
+        return vec![args.to_vec()];
+    }
+    let mut packages = Vec::new();
+    for arg in args {
+        let expanded = gors::packages::expand_patterns(std::slice::from_ref(arg));
+        if expanded.is_empty() {
+            eprintln!("gors: warning: \"{arg}\" matched no packages");
+        }
+        for path in expanded {
+            let unit = vec![path];
+            if !packages.contains(&unit) {
+                packages.push(unit);
+            }
+        }
+    }
+    packages
+}
+
+/// Name of the output directory for a package built alongside others.
+fn package_output_name(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| path.trim_end_matches(".go").replace(['/', '\\'], "_"))
+}
+
+/// Parse and compile one package, printing its diagnostics on failure.
+///
+/// With `header`, failures are introduced by a `# path` line like the `go`
+/// command does when it reports on several packages. With `race`, the
+/// program is instrumented for the race detector. With `thread_safe`, a
+/// program that starts no goroutines keeps the thread-safe runtime types.
+fn compile_package(
+    sources: &[String],
+    header: bool,
+    race: bool,
+    thread_safe: bool,
+) -> Option<gors::compiler::CompiledProgram> {
+    let path = sources.join(" ");
+    let report_header = || {
+        if header {
+            eprintln!("# {path}");
+        }
+    };
+
     let parse_timer = ProfileTimer::start("cli.parse");
-    let program = match gors::parser::parse_program(&cmd.path) {
+    let program = match gors::parser::parse_program_files(sources) {
         Ok(result) => result,
         Err(gors::parser::PathParseError::ParserError(err)) => {
-            let (file, buffer) = if let Some((f, b)) = get_file_for_error(&cmd.path) {
+            let source_path = sources.first().cloned().unwrap_or_default();
+            let (file, buffer) = if let Some((f, b)) = get_file_for_error(&source_path) {
                 (f, b)
             } else {
-                (cmd.path.clone(), String::new())
+                (source_path, String::new())
             };
             let diagnostic = Diagnostic::from_parser_error(&err, &file, &buffer);
+            report_header();
             print_error(&diagnostic);
-            std::process::exit(1);
+            return None;
         }
         Err(err) => {
+            report_header();
             eprintln!("error: {}", err);
-            std::process::exit(1);
+            return None;
         }
     };
     drop(parse_timer);
@@ -221,10 +545,17 @@ fn build(cmd: Build) -> Result<(), Box<dyn std::error::Error>> {
         .files
         .first()
         .map(|(f, _)| f.clone())
-        .unwrap_or_else(|| cmd.path.clone());
+        .unwrap_or_else(|| path.clone());
 
-    let compiled = match gors::compiler::compile_program_multi(program) {
-        Ok(compiled) => compiled,
+    let compiled = if race {
+        gors::compiler::compile_program_multi_with_race_detection(program)
+    } else if thread_safe {
+        gors::compiler::compile_program_multi_thread_safe(program)
+    } else {
+        gors::compiler::compile_program_multi_with_source_maps(program)
+    };
+    match compiled {
+        Ok(compiled) => Some(compiled),
         Err(err) => {
             let diagnostic = Diagnostic::new(
                 &primary_file,
@@ -233,18 +564,25 @@ fn build(cmd: Build) -> Result<(), Box<dyn std::error::Error>> {
                 err.to_string(),
                 DiagnosticKind::Compiler,
             );
+            report_header();
             print_error(&diagnostic);
-            std::process::exit(1);
+            None
         }
-    };
+    }
+}
+
+fn explain_escapes(compiled: &gors::compiler::CompiledProgram) {
+    for decision in &compiled.escapes {
+        eprintln!("{decision}");
+    }
+}
 
+fn write_build_output(
+    compiled: gors::compiler::CompiledProgram,
+    output_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
     let output = gors::printer::generate_multi(compiled)?;
-    let output_dir = cmd
-        .output
-        .as_deref()
-        .map(PathBuf::from)
-        .map_or_else(|| build_cache_dir(&cmd.path), Ok)?;
-    let stats = write_generated_output(&output, &output_dir)?;
+    let stats = write_generated_output(&output, output_dir)?;
     let output_dir = output_dir.display();
     if stats.removed == 0 {
         println!(
@@ -257,7 +595,6 @@ fn build(cmd: Build) -> Result<(), Box<dyn std::error::Error>> {
             stats.written, stats.skipped, stats.removed
         );
     }
-
     Ok(())
 }
 
@@ -389,6 +726,51 @@ fn gors_cache_base() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(std::env::temp_dir().join("gors-cache"))
 }
 
+fn list(cmd: List) -> Result<(), Box<dyn std::error::Error>> {
+    let patterns = if cmd.patterns.is_empty() {
+        vec![".".to_string()]
+    } else {
+        cmd.patterns
+    };
+    let packages = gors::packages::list(&patterns, cmd.deps);
+
+    let stdout = std::io::stdout();
+    let mut w = std::io::BufWriter::new(stdout.lock());
+    let mut failed = false;
+    for package in &packages {
+        if let Some(error) = package.error.as_ref().filter(|_| !cmd.errors) {
+            eprintln!("error: {}", error.err);
+            failed = true;
+            continue;
+        }
+        if cmd.json {
+            write_package_json(&mut w, package)?;
+        } else {
+            writeln!(w, "{}", package.import_path)?;
+        }
+    }
+    w.flush()?;
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Write one package the way `go list -json` does: tab-indented, one
+/// object per package.
+fn write_package_json(
+    w: &mut impl Write,
+    package: &gors::packages::Package,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use serde::Serialize;
+
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut *w, formatter);
+    package.serialize(&mut serializer)?;
+    w.write_all(b"\n")?;
+    Ok(())
+}
+
 /// Split CLI arguments into source paths and program arguments.
 ///
 /// If the first argument ends with `.go`, all leading `.go` arguments are source
@@ -446,7 +828,14 @@ fn run(cmd: Run) -> Result<(), Box<dyn std::error::Error>> {
         .map(|(f, _)| f.clone())
         .unwrap_or_else(|| source_paths.first().cloned().unwrap_or_default());
 
-    let compiled = match gors::compiler::compile_program_multi(program) {
+    let compiled = if cmd.race {
+        gors::compiler::compile_program_multi_with_race_detection(program)
+    } else if cmd.thread_safe {
+        gors::compiler::compile_program_multi_thread_safe(program)
+    } else {
+        gors::compiler::compile_program_multi_with_source_maps(program)
+    };
+    let compiled = match compiled {
         Ok(compiled) => compiled,
         Err(err) => {
             let diagnostic = Diagnostic::new(
@@ -461,6 +850,9 @@ fn run(cmd: Run) -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    if cmd.explain_escapes {
+        explain_escapes(&compiled);
+    }
     let output = gors::printer::generate_multi(compiled)?;
 
     let cache_dir = run_cache_dir(&source_paths, cmd.release)?;
@@ -659,6 +1051,11 @@ impl<'a> From<RustcArgs<'a>> for Vec<String> {
             "unused_macros".to_string(),
             "-C".to_string(),
             "overflow-checks=off".to_string(),
+            // Goroutine stack dumps and `runtime.Caller` map the lines of the
+            // generated code back to the Go sources. Line tables alone would
+            // leave the inlined frames without their module path.
+            "-C".to_string(),
+            "debuginfo=limited".to_string(),
         ];
 
         if let Some(emit) = args.emit {
@@ -733,6 +1130,95 @@ mod tests {
         assert_eq!(program_args, args(&["arg"]));
     }
 
+    #[test]
+    fn expand_package_args_groups_go_files_and_expands_patterns() {
+        assert_eq!(expand_package_args(&[]), vec![args(&["."])]);
+        assert_eq!(
+            expand_package_args(&args(&["main.go", "util.go"])),
+            vec![args(&["main.go", "util.go"])]
+        );
+
+        let tmp = tempfile::tempdir().unwrap();
+        for dir in ["cmd/a", "cmd/b"] {
+            std::fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            std::fs::write(tmp.path().join(dir).join("main.go"), "package main\n").unwrap();
+        }
+        let root = tmp.path().to_string_lossy().into_owned();
+        let units = expand_package_args(&[format!("{root}/..."), format!("{root}/cmd/a")]);
+        assert_eq!(
+            units,
+            vec![vec![format!("{root}/cmd/a")], vec![format!("{root}/cmd/b")],]
+        );
+        assert_eq!(package_output_name(&format!("{root}/cmd/b")), "b");
+    }
+
+    #[test]
+    fn normalize_go_style_flags_only_rewrites_known_flags() {
+        let normalized =
+            normalize_go_style_flags(args(&["gors", "list", "-json", "-deps", "-x", "./pkg"]));
+        assert_eq!(
+            normalized,
+            args(&["gors", "list", "--json", "--deps", "-x", "./pkg"])
+        );
+
+        let generate_args = args(&["gors", "generate", "-x", "-run=stringer", "./..."]);
+        assert_eq!(
+            normalize_go_style_flags(generate_args),
+            args(&["gors", "generate", "-x", "--run=stringer", "./..."])
+        );
+
+        let run_args = args(&["gors", "run", "main.go", "-json"]);
+        assert_eq!(normalize_go_style_flags(run_args.clone()), run_args);
+
+        let race_args = args(&["gors", "run", "--release", "-race", "main.go", "-race"]);
+        assert_eq!(
+            normalize_go_style_flags(race_args),
+            args(&["gors", "run", "--release", "--race", "main.go", "-race"])
+        );
+    }
+
+    #[test]
+    fn generate_files_processes_test_files_after_package_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for (name, source) in [
+            ("b.go", "package p\n"),
+            ("a_test.go", "package p\n"),
+            ("c_test.go", "package p_test\n"),
+            ("a.go", "package p\n"),
+        ] {
+            std::fs::write(dir.path().join(name), source).expect("write source");
+        }
+        let root = dir.path().to_string_lossy().into_owned();
+
+        let (generate_dir, files) = generate_files(std::slice::from_ref(&root)).expect("files");
+        assert_eq!(generate_dir, dir.path());
+        assert_eq!(
+            files,
+            ["a.go", "b.go", "a_test.go", "c_test.go"]
+                .map(|name| dir.path().join(name).to_string_lossy().into_owned())
+                .to_vec()
+        );
+
+        let (file_dir, _) = generate_files(&args(&["main.go"])).expect("files");
+        assert_eq!(file_dir, PathBuf::from("."));
+    }
+
+    #[test]
+    fn write_package_json_uses_go_list_layout() {
+        let package = gors::packages::Package {
+            import_path: "example.com/app".to_string(),
+            name: "main".to_string(),
+            go_files: vec!["main.go".to_string()],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_package_json(&mut out, &package).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\n\t\"ImportPath\": \"example.com/app\",\n\t\"Name\": \"main\",\n\t\"GoFiles\": [\n\t\t\"main.go\"\n\t]\n}\n"
+        );
+    }
+
     #[test]
     fn write_generated_output_removes_files_missing_from_new_manifest() {
         let tmp = tempfile::tempdir().unwrap();
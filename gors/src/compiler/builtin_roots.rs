@@ -15,6 +15,7 @@ pub(super) fn expand(
             "Chan::try_recv",
             "Chan::try_recv_with_ok",
             "Chan::is_nil",
+            "Chan::race_synchronize",
             "new",
             "send",
             "recv",
@@ -22,6 +23,41 @@ pub(super) fn expand(
             "try_send",
             "try_recv",
             "try_recv_with_ok",
+            "race_synchronize",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
+    if needs_go_frames(roots) {
+        for root in [
+            "GoroutineStatus::as_str",
+            "GoFrame::at",
+            "GoFrame::new",
+            "GoFunction::in_package",
+            "LineTable::position",
+            "LineTable::function",
+            "LineTable::source_line",
+            "as_str",
+            "at",
+            "new",
+            "in_package",
+            "position",
+            "function",
+            "source_line",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
+    if needs_select_cases(roots) {
+        for root in [
+            "SelectCase",
+            "SelectRecv",
+            "SelectSend",
+            "SelectRecv::new",
+            "SelectRecv::received",
+            "SelectSend::new",
+            "impl SelectCase for SelectRecv",
+            "impl SelectCase for SelectSend",
         ] {
             expanded.insert(root.to_string());
         }
@@ -56,6 +92,138 @@ pub(super) fn expand(
             expanded.insert(root.to_string());
         }
     }
+    if needs_cycle_collector(roots) {
+        for root in [
+            "GorsTrace",
+            "GcTracer",
+            "GcField",
+            "GcTraceField",
+            "GcSkipField",
+            "GcProbe",
+            "GcProbe::new",
+            "GcTracedPtr",
+            "GcUntracedPtr",
+            "GcObject",
+            "GcHeap",
+            "GC_HEAP",
+            "GC_MIN_OBJECTS",
+            "GorsPtr::new_traced",
+            "collect_cycles",
+            "GC_STOP_REQUESTED",
+            "GC_SAFEPOINT_HOOK",
+            "GcStop",
+            "GC_STOP",
+            "GC_RESUME",
+            "GC_STOP_ATTEMPTS",
+            "GC_STOP_BACKOFF",
+            "stop_the_world",
+            "gc_safepoint",
+            "tracked_garbage",
+            "cycle_garbage",
+            "impl GorsTrace for GorsPtr",
+            "impl GorsTrace for Vec",
+            "impl GorsTrace for Option",
+            "impl GorsTrace for Box",
+            "impl GorsTrace for GorsMap",
+            "impl GcTraceField for GcField",
+            "impl GcSkipField for GcField",
+            "impl GcTracedPtr for GcProbe",
+            "impl GcUntracedPtr for GcProbe",
+            "impl GcObject for Mutex",
+            "trace",
+            "trace_field",
+            "gors_ptr",
+            "trace_object",
+            "clear_object",
+            "new_traced",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
+    if needs_comparable_hashing(roots) {
+        for root in [
+            "GorsHash",
+            "HashField",
+            "HashWriteField",
+            "HashSkipField",
+            "ComparableProbe",
+            "ComparableProbe::new",
+            "ComparableHashed",
+            "ComparableUnhashed",
+            "box_comparable",
+            "box_any_comparable",
+            "box_any_comparable_send_sync",
+            "impl GorsHash for f32",
+            "impl GorsHash for f64",
+            "impl GorsHash for GorsPtr",
+            "impl GorsHash for Chan",
+            "impl HashWriteField for HashField",
+            "impl HashSkipField for HashField",
+            "impl ComparableHashed for ComparableProbe",
+            "impl ComparableUnhashed for ComparableProbe",
+            "gors_hash",
+            "hash_field",
+            "hash_any",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
+    if needs_finalizers(roots) {
+        for root in [
+            "Finalizer",
+            "FinalizerFunc",
+            "GorsFinalizerValue",
+            "PendingFinalizer",
+            "FINALIZERS",
+            "FINALIZER_HOOK",
+            "impl Finalizer for PendingFinalizer",
+            "impl FinalizerFunc for F",
+            "impl FinalizerFunc for GorsFinalizerValue",
+            "impl FinalizerFunc for Option",
+            "run_finalizers",
+            "into_finalizer",
+            "object",
+            "unreachable",
+            "run",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
+    if needs_interface_values(roots) {
+        for root in [
+            "GorsAny",
+            "GorsAny::new",
+            "GorsAny::as_ref",
+            "GorsAny::payload",
+            "as_ref",
+            "payload",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
+    if needs_race_detector(roots) {
+        for root in [
+            "RaceAccess",
+            "impl RaceAccess for Mutex",
+            "impl RaceAccess for Arc",
+            "impl RaceAccess for LazyLock",
+            "impl RaceAccess for GorsPtr",
+            "impl RaceAccess for &",
+            "impl RaceAccess for &mut",
+            "VectorClock::started",
+            "VectorClock::get",
+            "VectorClock::tick",
+            "VectorClock::join",
+            "race_addr",
+            "race_sync",
+            "started",
+            "get",
+            "tick",
+            "join",
+        ] {
+            expanded.insert(root.to_string());
+        }
+    }
     if needs_len_trait(roots) {
         expanded.insert("Len".to_string());
     }
@@ -109,22 +277,95 @@ fn needs_channel_methods(roots: &std::collections::HashSet<String>) -> bool {
     })
 }
 
+/// The runtime prints goroutine stacks on crashes, deadlocks and
+/// `runtime.Stack`.
+fn needs_go_frames(roots: &std::collections::HashSet<String>) -> bool {
+    roots.iter().any(|root| {
+        matches!(
+            root.as_str(),
+            "run_main" | "go" | "goroutine_stack" | "callers" | "caller" | "set_line_tables"
+        )
+    })
+}
+
+fn needs_select_cases(roots: &std::collections::HashSet<String>) -> bool {
+    roots.iter().any(|root| {
+        matches!(root.as_str(), "select" | "SelectRecv" | "SelectSend")
+            || root.starts_with("SelectRecv::")
+            || root.starts_with("SelectSend::")
+    })
+}
+
 fn needs_gors_ptr_methods(roots: &std::collections::HashSet<String>) -> bool {
+    needs_race_detector(roots)
+        || roots.iter().any(|root| {
+            matches!(
+                root.as_str(),
+                "len"
+                    | "Len"
+                    | "cap"
+                    | "Cap"
+                    | "panic_value"
+                    | "panic_runtime_error"
+                    | "string_from_byte_seq"
+            ) || root == "GorsPtr"
+                || root == "GorsNilPointer"
+                || root.starts_with("GorsPtr::")
+                || root.starts_with("GorsNilPointer::")
+        })
+}
+
+/// Allocations of local structs go through `GcProbe`, and
+/// `runtime.GC` collects cycles explicitly.
+fn needs_cycle_collector(roots: &std::collections::HashSet<String>) -> bool {
     roots.iter().any(|root| {
         matches!(
             root.as_str(),
-            "len" | "Len" | "cap" | "Cap" | "panic_value" | "string_from_byte_seq"
-        ) || root == "GorsPtr"
-            || root == "GorsNilPointer"
-            || root.starts_with("GorsPtr::")
-            || root.starts_with("GorsNilPointer::")
+            "GorsTrace" | "GcProbe" | "GcProbe::new" | "collect_cycles" | "gc"
+        )
     })
 }
 
+/// Interface values of comparable types are boxed through a probe picking
+/// how they hash.
+fn needs_comparable_hashing(roots: &std::collections::HashSet<String>) -> bool {
+    roots.iter().any(|root| {
+        matches!(
+            root.as_str(),
+            "GorsHash" | "ComparableProbe" | "ComparableProbe::new"
+        )
+    })
+}
+
+/// `runtime.SetFinalizer` keeps its finalizers behind a trait object, run
+/// by `runtime.GC` through a hook.
+fn needs_finalizers(roots: &std::collections::HashSet<String>) -> bool {
+    roots.contains("set_finalizer")
+}
+
+/// Generated code calls `as_ref` on interface values, which the reachability
+/// pass cannot resolve to `GorsAny` on its own.
+fn needs_interface_values(roots: &std::collections::HashSet<String>) -> bool {
+    roots.iter().any(|root| {
+        matches!(
+            root.as_str(),
+            "GorsAny" | "box_any_comparable" | "clone_any" | "clone_any_ref" | "reflect_slice_any"
+        ) || root.starts_with("GorsAny::")
+    })
+}
+
+/// Programs built with `-race` check the accesses to cells of several types
+/// through `RaceAccess`, and their `sync` packages call `race_sync` once the
+/// runtime is already pruned.
+fn needs_race_detector(roots: &std::collections::HashSet<String>) -> bool {
+    roots.contains("enable_race_detection")
+}
+
 fn needs_len_trait(roots: &std::collections::HashSet<String>) -> bool {
-    roots
-        .iter()
-        .any(|root| matches!(root.as_str(), "len" | "Len" | "string_from_byte_seq"))
+    needs_cycle_collector(roots)
+        || roots
+            .iter()
+            .any(|root| matches!(root.as_str(), "len" | "Len" | "string_from_byte_seq"))
 }
 
 fn needs_byte_seq_trait(roots: &std::collections::HashSet<String>) -> bool {
@@ -8,9 +8,9 @@ use super::{
     receiver_type_scopes,
     syn_inspect::{
         call_target_key, clone_call_receiver_expr, expr_path_ident, expr_path_ident_or_clone,
-        is_box_dyn_any_type, is_box_leak_expr, is_lock_guard_wrapper_method, is_path_call_expr,
-        is_slice_range_index_expr, named_self_type, pat_ident_name, slice_type_inner,
-        strip_paren_or_group, vec_type_inner, zero_arg_method_call_receiver_expr,
+        is_box_dyn_any_type, is_box_leak_expr, is_gors_any_type, is_lock_guard_wrapper_method,
+        is_path_call_expr, is_slice_range_index_expr, named_self_type, pat_ident_name,
+        slice_type_inner, strip_paren_or_group, vec_type_inner, zero_arg_method_call_receiver_expr,
     },
     synthetic_names,
 };
@@ -991,7 +991,7 @@ fn cloneable_value_param_kind(
         return None;
     }
     if let Some(inner) = vec_type_inner(ty) {
-        return Some(if is_box_dyn_any_type(&inner) {
+        return Some(if is_box_dyn_any_type(&inner) || is_gors_any_type(&inner) {
             CloneValueParamKind::Take
         } else {
             CloneValueParamKind::Vec
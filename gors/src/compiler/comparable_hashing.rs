@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use super::{CompiledModule, syn_inspect::is_path_call_expr};
+
+/// Lets interface values holding comparable values hash by value, the way
+/// they compare.
+///
+/// Every local struct gets a `crate::builtin::GorsHash` impl hashing its
+/// fields, and `box_any_comparable` calls go through a `ComparableProbe`,
+/// which stores the value with its hash when its type can be hashed.
+pub(super) fn add_comparable_hashing(modules: &mut BTreeMap<String, CompiledModule>) {
+    let has_runtime_support = modules.values().any(|module| {
+        module.mod_name == "builtin"
+            && module.file.items.iter().any(
+                |item| matches!(item, syn::Item::Trait(item_trait) if item_trait.ident == "GorsHash"),
+            )
+    });
+    if !has_runtime_support {
+        return;
+    }
+    for module in modules.values_mut() {
+        if module.mod_name != "builtin" {
+            add_comparable_hashing_for_file(&mut module.file);
+        }
+    }
+}
+
+fn add_comparable_hashing_for_file(file: &mut syn::File) {
+    use syn::visit_mut::VisitMut;
+
+    let hash_impls = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Struct(item_struct) if !item_struct.fields.is_empty() => {
+                Some(hash_impl(item_struct))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    file.items.extend(hash_impls);
+
+    struct BoxingRewriter;
+
+    impl VisitMut for BoxingRewriter {
+        fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+            syn::visit_mut::visit_expr_mut(self, expr);
+            let syn::Expr::Call(call) = expr else {
+                return;
+            };
+            let method: syn::Ident =
+                if is_path_call_expr(&call.func, &["crate", "builtin", "box_any_comparable"]) {
+                    syn::parse_quote!(box_any_comparable)
+                } else if is_path_call_expr(
+                    &call.func,
+                    &["crate", "builtin", "box_any_comparable_send_sync"],
+                ) {
+                    syn::parse_quote!(box_any_comparable_send_sync)
+                } else {
+                    return;
+                };
+            let (Some(value), 1) = (call.args.first(), call.args.len()) else {
+                return;
+            };
+            *expr = syn::parse_quote! {
+                {
+                    #[allow(unused_imports)]
+                    use crate::builtin::{ComparableHashed as _, ComparableUnhashed as _};
+                    (&crate::builtin::ComparableProbe::new(#value)).#method()
+                }
+            };
+        }
+    }
+
+    BoxingRewriter.visit_file_mut(file);
+}
+
+fn hash_impl(item_struct: &syn::ItemStruct) -> syn::Item {
+    let ident = &item_struct.ident;
+    let (impl_generics, type_generics, where_clause) = item_struct.generics.split_for_impl();
+    let fields = item_struct
+        .fields
+        .members()
+        .map(|member| {
+            quote::quote! {
+                (&crate::builtin::HashField(&self.#member)).hash_field(state);
+            }
+        })
+        .collect::<Vec<_>>();
+    syn::parse_quote! {
+        impl #impl_generics crate::builtin::GorsHash for #ident #type_generics #where_clause {
+            fn gors_hash(&self, state: &mut dyn std::hash::Hasher) {
+                #[allow(unused_imports)]
+                use crate::builtin::{HashSkipField as _, HashWriteField as _};
+                #(#fields)*
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote as rust;
+
+    #[test]
+    fn hashes_struct_fields_and_probes_comparable_values() {
+        let mut file: syn::File = rust! {
+            #[derive(Clone, PartialEq)]
+            pub struct Point {
+                pub x: isize,
+                pub label: String,
+            }
+
+            pub fn boxed(p: Point) -> crate::builtin::GorsAny {
+                crate::builtin::box_any_comparable(p)
+            }
+        };
+
+        super::add_comparable_hashing_for_file(&mut file);
+        let tokens = quote!(#file).to_string();
+
+        assert!(tokens.contains("impl crate :: builtin :: GorsHash for Point"));
+        assert!(
+            tokens
+                .contains("(& crate :: builtin :: HashField (& self . x)) . hash_field (state) ;")
+        );
+        assert!(
+            tokens.contains(
+                "(& crate :: builtin :: HashField (& self . label)) . hash_field (state) ;"
+            )
+        );
+        assert!(tokens.contains(
+            "(& crate :: builtin :: ComparableProbe :: new (p)) . box_any_comparable ()"
+        ));
+        assert!(!tokens.contains("crate :: builtin :: box_any_comparable ("));
+    }
+}
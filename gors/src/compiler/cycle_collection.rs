@@ -0,0 +1,214 @@
+use std::collections::{BTreeMap, HashSet};
+
+use super::{
+    CompiledModule,
+    syn_inspect::{is_path_call_expr, path_is},
+};
+
+/// Lets the runtime collect cycles of `GorsPtr` objects.
+///
+/// Every local struct gets a `crate::builtin::GorsTrace` impl listing its
+/// fields, and `GorsPtr::new` calls allocating a local struct go through a
+/// `GcProbe`, which registers the object with the cycle collector when its
+/// type can be traced.
+pub(super) fn add_cycle_collection(modules: &mut BTreeMap<String, CompiledModule>) {
+    let has_runtime_support = modules.values().any(|module| {
+        module.mod_name == "builtin"
+            && module.file.items.iter().any(
+                |item| matches!(item, syn::Item::Trait(item_trait) if item_trait.ident == "GorsTrace"),
+            )
+    });
+    if !has_runtime_support {
+        return;
+    }
+    for module in modules.values_mut() {
+        if module.mod_name != "builtin" {
+            add_cycle_collection_for_file(&mut module.file);
+        }
+    }
+}
+
+fn add_cycle_collection_for_file(file: &mut syn::File) {
+    use syn::visit_mut::VisitMut;
+
+    let structs = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Struct(item_struct) if !item_struct.fields.is_empty() => Some(item_struct),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let allocatable = structs
+        .iter()
+        .filter(|item_struct| item_struct.generics.params.is_empty())
+        .map(|item_struct| item_struct.ident.to_string())
+        .collect::<HashSet<_>>();
+    let trace_impls = structs
+        .iter()
+        .map(|item_struct| trace_impl(item_struct))
+        .collect::<Vec<_>>();
+    if trace_impls.is_empty() {
+        return;
+    }
+    file.items.extend(trace_impls);
+
+    struct AllocationRewriter<'a> {
+        structs: &'a HashSet<String>,
+    }
+
+    impl VisitMut for AllocationRewriter<'_> {
+        fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+            syn::visit_mut::visit_expr_mut(self, expr);
+            let syn::Expr::Call(call) = expr else {
+                return;
+            };
+            if !is_path_call_expr(&call.func, &["crate", "builtin", "GorsPtr", "new"]) {
+                return;
+            }
+            let Some(value) = call.args.first() else {
+                return;
+            };
+            if call.args.len() != 1
+                || !allocated_struct_name(value).is_some_and(|name| self.structs.contains(&name))
+            {
+                return;
+            }
+            *expr = syn::parse_quote! {
+                {
+                    #[allow(unused_imports)]
+                    use crate::builtin::{GcTracedPtr as _, GcUntracedPtr as _};
+                    (&crate::builtin::GcProbe::new(#value)).gors_ptr()
+                }
+            };
+        }
+    }
+
+    AllocationRewriter {
+        structs: &allocatable,
+    }
+    .visit_file_mut(file);
+}
+
+fn trace_impl(item_struct: &syn::ItemStruct) -> syn::Item {
+    let ident = &item_struct.ident;
+    let (impl_generics, type_generics, where_clause) = item_struct.generics.split_for_impl();
+    let fields = item_struct
+        .fields
+        .members()
+        .map(|member| {
+            quote::quote! {
+                (&crate::builtin::GcField(&self.#member)).trace_field(tracer);
+            }
+        })
+        .collect::<Vec<_>>();
+    syn::parse_quote! {
+        impl #impl_generics crate::builtin::GorsTrace for #ident #type_generics #where_clause {
+            fn trace(&self, tracer: &mut crate::builtin::GcTracer) {
+                #[allow(unused_imports)]
+                use crate::builtin::{GcSkipField as _, GcTraceField as _};
+                #(#fields)*
+            }
+        }
+    }
+}
+
+/// Names the struct allocated by `S { .. }`, `S::default()` or
+/// `<S>::default()`.
+fn allocated_struct_name(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Struct(expr_struct) if expr_struct.qself.is_none() => {
+            expr_struct.path.get_ident().map(ToString::to_string)
+        }
+        syn::Expr::Call(call) if call.args.is_empty() => {
+            let syn::Expr::Path(path) = call.func.as_ref() else {
+                return None;
+            };
+            match &path.qself {
+                Some(qself) if path_is(&path.path, &["default"]) => {
+                    let syn::Type::Path(self_ty) = qself.ty.as_ref() else {
+                        return None;
+                    };
+                    self_ty
+                        .qself
+                        .is_none()
+                        .then(|| self_ty.path.get_ident().map(ToString::to_string))
+                        .flatten()
+                }
+                Some(_) => None,
+                None => {
+                    let [name, default] = path.path.segments.iter().collect::<Vec<_>>()[..] else {
+                        return None;
+                    };
+                    (default.ident == "default" && name.arguments.is_none())
+                        .then(|| name.ident.to_string())
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote as rust;
+
+    #[test]
+    fn traces_struct_fields_and_probes_struct_allocations() {
+        let mut file: syn::File = rust! {
+            pub struct Node {
+                pub next: crate::builtin::GorsPtr<Node>,
+                pub value: isize,
+            }
+
+            pub fn link() -> crate::builtin::GorsPtr<Node> {
+                let first = crate::builtin::GorsPtr::new(<Node>::default());
+                crate::builtin::GorsPtr::new(Node { next: first, value: 1 })
+            }
+        };
+
+        super::add_cycle_collection_for_file(&mut file);
+        let tokens = quote!(#file).to_string();
+
+        assert!(tokens.contains("impl crate :: builtin :: GorsTrace for Node"));
+        assert!(
+            tokens.contains(
+                "(& crate :: builtin :: GcField (& self . next)) . trace_field (tracer) ;"
+            )
+        );
+        assert!(
+            tokens.contains(
+                "(& crate :: builtin :: GcField (& self . value)) . trace_field (tracer) ;"
+            )
+        );
+        assert!(tokens.contains("crate :: builtin :: GcProbe :: new (< Node > :: default ())"));
+        assert!(
+            tokens
+                .contains("crate :: builtin :: GcProbe :: new (Node { next : first , value : 1 })")
+        );
+        assert!(!tokens.contains("GorsPtr :: new ("));
+    }
+
+    #[test]
+    fn leaves_allocations_of_other_values_untracked() {
+        let mut file: syn::File = rust! {
+            pub struct Pair<T>(T, T);
+
+            pub fn boxed() -> crate::builtin::GorsPtr<isize> {
+                let _pair = crate::builtin::GorsPtr::new(Pair::<isize>(1, 2));
+                crate::builtin::GorsPtr::new(0)
+            }
+        };
+
+        super::add_cycle_collection_for_file(&mut file);
+        let tokens = quote!(#file).to_string();
+
+        assert!(tokens.contains("impl < T > crate :: builtin :: GorsTrace for Pair < T >"));
+        assert!(
+            tokens
+                .contains("(& crate :: builtin :: GcField (& self . 1)) . trace_field (tracer) ;")
+        );
+        assert!(!tokens.contains("GcProbe"));
+    }
+}
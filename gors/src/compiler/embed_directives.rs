@@ -0,0 +1,789 @@
+//! `//go:embed` directives on package-level variables.
+//!
+//! Applies the checks gc and the go command make: a directive sits directly
+//! above a single `string`, `[]byte` or `embed.FS` variable without an
+//! initializer, in a file that imports "embed", and its patterns match files
+//! in the package directory. Matched files are lowered to `include_str!` and
+//! `include_bytes!`, so rustc tracks them as inputs of the generated crate.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::{CompilerError, ir, typeinfer::GoType};
+use crate::ast;
+use crate::token::{Position, Token};
+
+thread_local! {
+    static EMBEDDED_VARS: RefCell<HashMap<String, EmbeddedVar>> = RefCell::new(HashMap::new());
+}
+
+/// The files a `//go:embed` variable is initialized from, as absolute paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum EmbeddedVar {
+    String(PathBuf),
+    Bytes(PathBuf),
+    /// `embed.FS` files keyed by their slash-separated package-relative name.
+    Fs(BTreeMap<String, PathBuf>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EmbedKind {
+    String,
+    Bytes,
+    Fs,
+}
+
+struct Directive<'a> {
+    pos: &'a Position<'a>,
+    args: &'a str,
+}
+
+enum Anchor<'a, 'b> {
+    Var(&'b ast::ValueSpec<'a>),
+    VarGroup(&'b [ast::Spec<'a>]),
+    Other,
+}
+
+/// Validate the `//go:embed` directives of a package and resolve their
+/// patterns against the package directory.
+pub(super) fn embedded_vars_in_file(
+    file: &ast::File<'_>,
+) -> Result<HashMap<String, EmbeddedVar>, CompilerError> {
+    let directives = directives(file);
+    if directives.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let anchors = anchors(file);
+    let func_bodies = func_bodies(file);
+
+    let mut patterns_by_spec: Vec<(&ast::ValueSpec<'_>, &Position<'_>, Vec<String>)> = Vec::new();
+    for directive in &directives {
+        if func_bodies.iter().any(|(lbrace, rbrace)| {
+            same_file(lbrace, directive.pos)
+                && lbrace.offset < directive.pos.offset
+                && directive.pos.offset < rbrace.offset
+        }) {
+            return Err(embed_error("go:embed cannot apply to var inside func"));
+        }
+        let anchor = anchors
+            .iter()
+            .filter(|(pos, _)| same_file(pos, directive.pos) && pos.offset > directive.pos.offset)
+            .min_by_key(|(pos, _)| pos.offset)
+            .map(|(_, anchor)| anchor);
+        let spec = match anchor {
+            Some(Anchor::Var(spec)) => *spec,
+            Some(Anchor::VarGroup([ast::Spec::ValueSpec(spec)])) => spec,
+            Some(Anchor::VarGroup(_)) => {
+                return Err(embed_error("go:embed cannot apply to multiple vars"));
+            }
+            Some(Anchor::Other) | None => return Err(embed_error("misplaced compiler directive")),
+        };
+        let patterns = parse_patterns(directive.args)?;
+        match patterns_by_spec
+            .iter_mut()
+            .find(|(existing, _, _)| std::ptr::eq(*existing, spec))
+        {
+            Some((_, _, existing)) => existing.extend(patterns),
+            None => patterns_by_spec.push((spec, directive.pos, patterns)),
+        }
+    }
+
+    let mut embedded = HashMap::new();
+    for (spec, pos, patterns) in patterns_by_spec {
+        let Some(embed_name) = embed_import_name(file, pos) else {
+            return Err(embed_error(
+                "go:embed only allowed in Go files that import \"embed\"",
+            ));
+        };
+        let [name] = spec.names.as_slice() else {
+            return Err(embed_error("go:embed cannot apply to multiple vars"));
+        };
+        if spec.values.is_some() {
+            return Err(embed_error("go:embed cannot apply to var with initializer"));
+        }
+        let type_expr = spec.type_.as_ref();
+        let Some(kind) = type_expr.and_then(|ty| embed_kind(ty, embed_name)) else {
+            let type_name = type_expr
+                .map(|ty| ir::go_type_display_name(&GoType::from_expr(ty)))
+                .unwrap_or_default();
+            return Err(embed_error(format!(
+                "go:embed cannot apply to var of type {type_name}"
+            )));
+        };
+        if patterns.is_empty() {
+            return Err(embed_error("usage: //go:embed pattern..."));
+        }
+
+        let pkg_dir = Path::new(if pos.directory.is_empty() {
+            "."
+        } else {
+            pos.directory
+        });
+        let mut files = Vec::new();
+        for pattern in &patterns {
+            files.extend(resolve_pattern(pkg_dir, pattern).map_err(embed_error)?);
+        }
+        files.sort();
+        files.dedup();
+        let pkg_dir = std::fs::canonicalize(pkg_dir).unwrap_or_else(|_| pkg_dir.to_path_buf());
+        let var = match (kind, files.as_slice()) {
+            (EmbedKind::Fs, _) => EmbeddedVar::Fs(
+                files
+                    .into_iter()
+                    .map(|rel| {
+                        let path = pkg_dir.join(&rel);
+                        (rel, path)
+                    })
+                    .collect(),
+            ),
+            (EmbedKind::String, [rel]) => {
+                let path = pkg_dir.join(rel);
+                if std::fs::read(&path).is_ok_and(|data| std::str::from_utf8(&data).is_err()) {
+                    return Err(CompilerError::UnsupportedConstruct(format!(
+                        "go:embed of non-UTF-8 file {rel} into a string"
+                    )));
+                }
+                EmbeddedVar::String(path)
+            }
+            (EmbedKind::Bytes, [rel]) => EmbeddedVar::Bytes(pkg_dir.join(rel)),
+            (EmbedKind::String, _) => {
+                return Err(embed_error(
+                    "invalid go:embed: multiple files for type string",
+                ));
+            }
+            (EmbedKind::Bytes, _) => {
+                return Err(embed_error(
+                    "invalid go:embed: multiple files for type []byte",
+                ));
+            }
+        };
+        embedded.insert(name.name.to_string(), var);
+    }
+    Ok(embedded)
+}
+
+pub(super) struct EmbeddedVarsGuard {
+    previous: HashMap<String, EmbeddedVar>,
+}
+
+impl EmbeddedVarsGuard {
+    pub(super) fn set(current: HashMap<String, EmbeddedVar>) -> Self {
+        let previous = EMBEDDED_VARS.with(|vars| vars.replace(current));
+        Self { previous }
+    }
+}
+
+impl Drop for EmbeddedVarsGuard {
+    fn drop(&mut self) {
+        EMBEDDED_VARS.with(|vars| {
+            *vars.borrow_mut() = std::mem::take(&mut self.previous);
+        });
+    }
+}
+
+/// The initializer of the embedded package-level variable `name` whose Rust
+/// type is `ty`, if it has a `//go:embed` directive.
+pub(super) fn initializer(name: &str, ty: &syn::Type) -> Option<syn::Expr> {
+    let var = EMBEDDED_VARS.with(|vars| vars.borrow().get(name).cloned())?;
+    Some(match var {
+        EmbeddedVar::String(path) => {
+            let path = path_lit(&path);
+            syn::parse_quote! { include_str!(#path).to_string() }
+        }
+        EmbeddedVar::Bytes(path) => {
+            let path = path_lit(&path);
+            syn::parse_quote! { include_bytes!(#path).to_vec() }
+        }
+        EmbeddedVar::Fs(files) => {
+            let names = files.keys();
+            let paths = files.values().map(|path| path_lit(path));
+            let constructor: syn::ExprPath = match ty {
+                syn::Type::Path(type_path) if type_path.qself.is_none() => {
+                    let path = &type_path.path;
+                    syn::parse_quote! { #path::__gors_embedded }
+                }
+                _ => syn::parse_quote! { <#ty>::__gors_embedded },
+            };
+            syn::parse_quote! {
+                {
+                    const FILES: &[(&str, &[u8])] = &[#((#names, include_bytes!(#paths))),*];
+                    #constructor(FILES)
+                }
+            }
+        }
+    })
+}
+
+fn path_lit(path: &Path) -> syn::LitStr {
+    syn::LitStr::new(&path.to_string_lossy(), proc_macro2::Span::mixed_site())
+}
+
+fn embed_error(message: impl Into<String>) -> CompilerError {
+    CompilerError::UnsupportedConstruct(message.into())
+}
+
+fn same_file(a: &Position<'_>, b: &Position<'_>) -> bool {
+    a.directory == b.directory && a.file == b.file
+}
+
+fn directives<'a>(file: &'a ast::File<'a>) -> Vec<Directive<'a>> {
+    file.comments
+        .iter()
+        .flat_map(|group| &group.list)
+        .filter_map(|comment| {
+            let args = comment.text.strip_prefix("//go:embed")?;
+            (args.is_empty() || args.starts_with([' ', '\t'])).then_some(Directive {
+                pos: &comment.slash,
+                args,
+            })
+        })
+        .collect()
+}
+
+/// Positions where a directive stops applying: every top-level declaration,
+/// and each spec of a parenthesized `var` group.
+fn anchors<'a, 'b>(file: &'b ast::File<'a>) -> Vec<(&'b Position<'a>, Anchor<'a, 'b>)> {
+    let mut anchors = Vec::new();
+    for decl in &file.decls {
+        match decl {
+            ast::Decl::FuncDecl(func_decl) => {
+                let pos = func_decl
+                    .type_
+                    .func
+                    .as_ref()
+                    .unwrap_or(&func_decl.name.name_pos);
+                anchors.push((pos, Anchor::Other));
+            }
+            ast::Decl::GenDecl(gen_decl) if gen_decl.tok == Token::VAR => {
+                match (gen_decl.lparen.as_ref(), gen_decl.specs.as_slice()) {
+                    (None, [ast::Spec::ValueSpec(spec)]) => {
+                        anchors.push((&gen_decl.tok_pos, Anchor::Var(spec)));
+                    }
+                    _ => anchors.push((&gen_decl.tok_pos, Anchor::VarGroup(&gen_decl.specs))),
+                }
+                if gen_decl.lparen.is_some() {
+                    for spec in &gen_decl.specs {
+                        if let ast::Spec::ValueSpec(spec) = spec
+                            && let Some(name) = spec.names.first()
+                        {
+                            anchors.push((&name.name_pos, Anchor::Var(spec)));
+                        }
+                    }
+                }
+            }
+            ast::Decl::GenDecl(gen_decl) => anchors.push((&gen_decl.tok_pos, Anchor::Other)),
+        }
+    }
+    anchors
+}
+
+fn func_bodies<'a, 'b>(file: &'b ast::File<'a>) -> Vec<(&'b Position<'a>, &'b Position<'a>)> {
+    file.decls
+        .iter()
+        .filter_map(|decl| match decl {
+            ast::Decl::FuncDecl(ast::FuncDecl {
+                body: Some(body), ..
+            }) => Some((&body.lbrace, &body.rbrace)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The name the file of `pos` imports "embed" under, if it does.
+fn embed_import_name<'a>(file: &'a ast::File<'a>, pos: &Position<'_>) -> Option<&'a str> {
+    file.decls.iter().find_map(|decl| {
+        let ast::Decl::GenDecl(gen_decl) = decl else {
+            return None;
+        };
+        if gen_decl.tok != Token::IMPORT || !same_file(&gen_decl.tok_pos, pos) {
+            return None;
+        }
+        gen_decl.specs.iter().find_map(|spec| match spec {
+            ast::Spec::ImportSpec(import)
+                if import.path.value.trim_matches(['"', '`']) == "embed" =>
+            {
+                Some(import.name.as_ref().map_or("embed", |name| name.name))
+            }
+            _ => None,
+        })
+    })
+}
+
+fn embed_kind(type_expr: &ast::Expr<'_>, embed_name: &str) -> Option<EmbedKind> {
+    match type_expr {
+        ast::Expr::ParenExpr(paren) => embed_kind(&paren.x, embed_name),
+        ast::Expr::Ident(ident) if ident.name == "string" => Some(EmbedKind::String),
+        ast::Expr::Ident(ident) if embed_name == "." && ident.name == "FS" => Some(EmbedKind::Fs),
+        ast::Expr::ArrayType(array)
+            if array.len.is_none()
+                && matches!(&*array.elt, ast::Expr::Ident(elt) if matches!(elt.name, "byte" | "uint8")) =>
+        {
+            Some(EmbedKind::Bytes)
+        }
+        ast::Expr::SelectorExpr(selector)
+            if selector.sel.name == "FS"
+                && matches!(&*selector.x, ast::Expr::Ident(pkg) if pkg.name == embed_name) =>
+        {
+            Some(EmbedKind::Fs)
+        }
+        _ => None,
+    }
+}
+
+/// Split directive arguments into patterns; patterns may be Go string
+/// literals so they can contain spaces.
+fn parse_patterns(args: &str) -> Result<Vec<String>, CompilerError> {
+    let mut patterns = Vec::new();
+    let mut rest = args.trim_start();
+    while !rest.is_empty() {
+        let (pattern, tail) = match rest.chars().next() {
+            Some(quote @ ('"' | '`')) => {
+                let body = rest.get(1..).unwrap_or_default();
+                let end = if quote == '`' {
+                    body.find('`')
+                } else {
+                    interpreted_string_end(body)
+                };
+                let Some(end) = end else {
+                    return Err(invalid_quoted(rest));
+                };
+                let (literal, tail) = body.split_at(end);
+                let tail = tail.get(1..).unwrap_or_default();
+                if !tail.is_empty() && !tail.starts_with([' ', '\t']) {
+                    return Err(invalid_quoted(rest));
+                }
+                let pattern = if quote == '`' {
+                    literal.to_string()
+                } else {
+                    super::interpret_go_string_escapes(literal)
+                };
+                (pattern, tail)
+            }
+            _ => {
+                let end = rest.find([' ', '\t']).unwrap_or(rest.len());
+                let (pattern, tail) = rest.split_at(end);
+                (pattern.to_string(), tail)
+            }
+        };
+        patterns.push(pattern);
+        rest = tail.trim_start();
+    }
+    Ok(patterns)
+}
+
+fn interpreted_string_end(body: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, ch) in body.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn invalid_quoted(rest: &str) -> CompilerError {
+    embed_error(format!("invalid quoted string in //go:embed: {rest}"))
+}
+
+/// Resolve one pattern to the package-relative files it embeds, following
+/// the go command: a matched directory embeds its tree, minus files whose
+/// names begin with `.` or `_` (unless the pattern has an `all:` prefix),
+/// VCS metadata directories and nested modules.
+fn resolve_pattern(pkg_dir: &Path, pattern: &str) -> Result<Vec<String>, String> {
+    let (glob, all) = match pattern.strip_prefix("all:") {
+        Some(glob) => (glob, true),
+        None => (pattern, false),
+    };
+    let fail = |reason: String| format!("pattern {pattern}: {reason}");
+    if glob == "." || !valid_fs_path(glob) {
+        return Err(fail("invalid pattern syntax".to_string()));
+    }
+    let matches =
+        glob_relative(pkg_dir, glob).ok_or_else(|| fail("syntax error in pattern".to_string()))?;
+
+    let mut files = Vec::new();
+    for rel in matches {
+        let path = pkg_dir.join(&rel);
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        let what = if metadata.is_dir() {
+            "directory"
+        } else {
+            "file"
+        };
+        if rel_parents(&rel).any(|dir| pkg_dir.join(dir).join("go.mod").is_file()) {
+            return Err(fail(format!(
+                "cannot embed {what} {rel}: in different module"
+            )));
+        }
+        if let Some(bad) = rel.split('/').find(|elem| is_bad_embed_name(elem)) {
+            return Err(fail(format!(
+                "cannot embed {what} {rel}: invalid name {bad}"
+            )));
+        }
+        if metadata.is_file() {
+            files.push(rel);
+        } else if metadata.is_dir() {
+            let count = files.len();
+            walk_embed_dir(pkg_dir, &rel, all, &mut files).map_err(fail)?;
+            if files.len() == count {
+                return Err(fail(format!(
+                    "cannot embed directory {rel}: contains no embeddable files"
+                )));
+            }
+        } else {
+            return Err(fail(format!("cannot embed irregular file {rel}")));
+        }
+    }
+    if files.is_empty() {
+        return Err(fail("no matching files found".to_string()));
+    }
+    Ok(files)
+}
+
+/// `rel` and each of its parent directories below the package directory.
+fn rel_parents(rel: &str) -> impl Iterator<Item = &str> {
+    rel.match_indices('/')
+        .map(|(index, _)| rel.get(..index).unwrap_or(rel))
+        .chain(std::iter::once(rel))
+}
+
+fn walk_embed_dir(
+    pkg_dir: &Path,
+    rel: &str,
+    all: bool,
+    files: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut entries = std::fs::read_dir(pkg_dir.join(rel))
+        .map(|entries| entries.flatten().collect::<Vec<_>>())
+        .unwrap_or_default();
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let child = format!("{rel}/{name}");
+        if is_bad_embed_name(&name) || (!all && (name.starts_with('.') || name.starts_with('_'))) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !entry.path().join("go.mod").is_file() {
+                walk_embed_dir(pkg_dir, &child, all, files)?;
+            }
+        } else if file_type.is_file() {
+            files.push(child);
+        } else {
+            return Err(format!("cannot embed irregular file {child}"));
+        }
+    }
+    Ok(())
+}
+
+fn is_bad_embed_name(name: &str) -> bool {
+    matches!(name, "" | ".bzr" | ".hg" | ".git" | ".svn")
+}
+
+fn valid_fs_path(name: &str) -> bool {
+    name.split('/').all(|elem| !matches!(elem, "" | "." | ".."))
+}
+
+/// `filepath.Glob` relative to `dir`: the package-relative paths matching
+/// `pattern`, sorted. `None` for a malformed pattern.
+fn glob_relative(dir: &Path, pattern: &str) -> Option<Vec<String>> {
+    let mut matches = vec![String::new()];
+    for elem in pattern.split('/') {
+        let mut next = Vec::new();
+        for prefix in &matches {
+            let parent = dir.join(prefix);
+            let join = |name: &str| {
+                if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{prefix}/{name}")
+                }
+            };
+            if !has_glob_meta(elem) {
+                match_elem(elem, "")?;
+                let literal = unescape_glob(elem);
+                if std::fs::symlink_metadata(parent.join(&literal)).is_ok() {
+                    next.push(join(&literal));
+                }
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(&parent) else {
+                match_elem(elem, "")?;
+                continue;
+            };
+            let mut names = entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                if match_elem(elem, &name)? {
+                    next.push(join(&name));
+                }
+            }
+        }
+        matches = next;
+    }
+    Some(matches)
+}
+
+fn has_glob_meta(elem: &str) -> bool {
+    elem.contains(['*', '?', '[', '\\'])
+}
+
+fn unescape_glob(elem: &str) -> String {
+    let mut out = String::new();
+    let mut chars = elem.chars();
+    while let Some(ch) = chars.next() {
+        out.push(if ch == '\\' {
+            chars.next().unwrap_or(ch)
+        } else {
+            ch
+        });
+    }
+    out
+}
+
+/// `path.Match` for a single path element: `*`, `?`, `[...]` classes with
+/// ranges and `^` negation, and `\` escapes. `None` for a malformed pattern.
+fn match_elem(pattern: &str, name: &str) -> Option<bool> {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // Validate the whole pattern up front, like `path.Match` does when it
+    // reports `ErrBadPattern` regardless of where matching stops.
+    let mut index = 0;
+    while let Some(&ch) = pattern.get(index) {
+        index = match ch {
+            '[' => class_end(&pattern, index)?,
+            '\\' => {
+                pattern.get(index + 1)?;
+                index + 2
+            }
+            _ => index + 1,
+        };
+    }
+    Some(match_from(&pattern, &name))
+}
+
+fn match_from(pattern: &[char], name: &[char]) -> bool {
+    let Some((&first, rest)) = pattern.split_first() else {
+        return name.is_empty();
+    };
+    match first {
+        '*' => {
+            (0..=name.len()).any(|skip| name.get(skip..).is_some_and(|tail| match_from(rest, tail)))
+        }
+        '?' => name
+            .split_first()
+            .is_some_and(|(_, tail)| match_from(rest, tail)),
+        '[' => {
+            let Some(end) = class_end(pattern, 0) else {
+                return false;
+            };
+            let Some((&ch, tail)) = name.split_first() else {
+                return false;
+            };
+            class_matches(pattern.get(1..end - 1).unwrap_or_default(), ch)
+                && match_from(pattern.get(end..).unwrap_or_default(), tail)
+        }
+        '\\' => {
+            let Some((&literal, rest)) = rest.split_first() else {
+                return false;
+            };
+            name.split_first()
+                .is_some_and(|(&ch, tail)| ch == literal && match_from(rest, tail))
+        }
+        literal => name
+            .split_first()
+            .is_some_and(|(&ch, tail)| ch == literal && match_from(rest, tail)),
+    }
+}
+
+/// Index just past the `]` closing the class that opens at `start`.
+fn class_end(pattern: &[char], start: usize) -> Option<usize> {
+    let mut index = start + 1;
+    if pattern.get(index) == Some(&'^') {
+        index += 1;
+    }
+    let mut first = true;
+    loop {
+        match *pattern.get(index)? {
+            ']' if first => return None,
+            ']' => return Some(index + 1),
+            '\\' => {
+                pattern.get(index + 1)?;
+                index += 2;
+            }
+            '-' if first => return None,
+            _ => index += 1,
+        }
+        first = false;
+    }
+}
+
+fn class_matches(class: &[char], ch: char) -> bool {
+    let (negated, mut class) = match class.split_first() {
+        Some(('^', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut matched = false;
+    while let Some((lo, rest)) = class_char(class) {
+        let (hi, rest) = match rest.split_first() {
+            Some(('-', range_rest)) => class_char(range_rest).unwrap_or((lo, range_rest)),
+            _ => (lo, rest),
+        };
+        matched |= lo <= ch && ch <= hi;
+        class = rest;
+    }
+    matched != negated
+}
+
+fn class_char(class: &[char]) -> Option<(char, &[char])> {
+    match class.split_first()? {
+        ('\\', rest) => rest.split_first().map(|(&ch, rest)| (ch, rest)),
+        (&ch, rest) => Some((ch, rest)),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic, clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, rel: &str, contents: &str) {
+        let path = dir.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn matches_path_elements_like_path_match() {
+        assert_eq!(match_elem("*.txt", "hello.txt"), Some(true));
+        assert_eq!(match_elem("*.txt", "hello.go"), Some(false));
+        assert_eq!(match_elem("h?llo", "hello"), Some(true));
+        assert_eq!(match_elem("[a-c]x", "bx"), Some(true));
+        assert_eq!(match_elem("[^a-c]x", "bx"), Some(false));
+        assert_eq!(match_elem("\\*", "*"), Some(true));
+        assert_eq!(match_elem("[", "["), None);
+        assert_eq!(match_elem("a[", "b"), None);
+    }
+
+    #[test]
+    fn parses_quoted_and_bare_patterns() {
+        assert_eq!(
+            parse_patterns(" a.txt \"with space.txt\"\t`raw\\name`").unwrap(),
+            ["a.txt", "with space.txt", "raw\\name"]
+        );
+        assert!(parse_patterns(" \"unterminated").is_err());
+    }
+
+    #[test]
+    fn resolves_patterns_like_the_go_command() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        write(dir, "hello.txt", "hello");
+        write(dir, "static/index.html", "<html>");
+        write(dir, "static/.hidden", "");
+        write(dir, "static/_draft.html", "");
+        write(dir, "static/css/site.css", "body{}");
+        write(dir, "nested/go.mod", "module nested\n");
+        write(dir, "nested/data.txt", "");
+        write(dir, "empty/.keep", "");
+
+        assert_eq!(resolve_pattern(dir, "hello.txt").unwrap(), ["hello.txt"]);
+        assert_eq!(
+            resolve_pattern(dir, "static").unwrap(),
+            ["static/css/site.css", "static/index.html"]
+        );
+        assert_eq!(
+            resolve_pattern(dir, "all:static").unwrap(),
+            [
+                "static/.hidden",
+                "static/_draft.html",
+                "static/css/site.css",
+                "static/index.html"
+            ]
+        );
+        assert_eq!(
+            resolve_pattern(dir, "static/*.html").unwrap(),
+            ["static/_draft.html", "static/index.html"]
+        );
+        assert_eq!(
+            resolve_pattern(dir, "missing.txt").unwrap_err(),
+            "pattern missing.txt: no matching files found"
+        );
+        assert_eq!(
+            resolve_pattern(dir, "../hello.txt").unwrap_err(),
+            "pattern ../hello.txt: invalid pattern syntax"
+        );
+        assert_eq!(
+            resolve_pattern(dir, "nested/data.txt").unwrap_err(),
+            "pattern nested/data.txt: cannot embed file nested/data.txt: in different module"
+        );
+        assert_eq!(
+            resolve_pattern(dir, "empty").unwrap_err(),
+            "pattern empty: cannot embed directory empty: contains no embeddable files"
+        );
+    }
+
+    #[test]
+    fn rejects_non_utf8_files_embedded_into_strings() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("data.bin"), [0x61, 0xff]).unwrap();
+        let filename = tmp.path().join("main.go").to_string_lossy().into_owned();
+        let source = "package main\n\nimport _ \"embed\"\n\n//go:embed data.bin\nvar data string\n";
+        let file = crate::parser::parse_file(&filename, source).unwrap();
+
+        assert_eq!(
+            embedded_vars_in_file(&file).unwrap_err().to_string(),
+            CompilerError::UnsupportedConstruct(
+                "go:embed of non-UTF-8 file data.bin into a string".to_string()
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn lowers_embedded_vars_to_include_macros() {
+        let path = PathBuf::from("/pkg/hello.txt");
+        let _guard = EmbeddedVarsGuard::set(HashMap::from([
+            ("greeting".to_string(), EmbeddedVar::String(path.clone())),
+            ("raw".to_string(), EmbeddedVar::Bytes(path.clone())),
+            (
+                "content".to_string(),
+                EmbeddedVar::Fs(BTreeMap::from([("hello.txt".to_string(), path)])),
+            ),
+        ]));
+        let string_ty: syn::Type = syn::parse_quote! { String };
+        let fs_ty: syn::Type = syn::parse_quote! { embed::FS };
+        let render = |expr: Option<syn::Expr>| quote::quote!(#expr).to_string();
+
+        assert_eq!(
+            render(initializer("greeting", &string_ty)),
+            "include_str ! (\"/pkg/hello.txt\") . to_string ()"
+        );
+        assert_eq!(
+            render(initializer("raw", &string_ty)),
+            "include_bytes ! (\"/pkg/hello.txt\") . to_vec ()"
+        );
+        let fs = render(initializer("content", &fs_ty));
+        assert!(
+            fs.contains("(\"hello.txt\" , include_bytes ! (\"/pkg/hello.txt\"))"),
+            "{fs}"
+        );
+        assert!(
+            fs.contains("embed :: FS :: __gors_embedded (FILES)"),
+            "{fs}"
+        );
+        assert!(initializer("other", &string_ty).is_none());
+    }
+}
@@ -0,0 +1,1668 @@
+//! Escape analysis for address-taken locals.
+//!
+//! Taking the address of a local makes the compiler keep it in a shared
+//! `Arc<Mutex<_>>` cell, so that the `GorsPtr` can outlive the frame and be
+//! used from other goroutines. Most addresses never leave the function
+//! though: `p := &x` followed by `*p` and `p.f` uses, `*&x` or `(&x).f`.
+//! Those are rewritten in the Go AST to use `x` directly before lowering,
+//! which keeps `x` a plain stack value. Names are resolved through scopes,
+//! so a `p := &x` is only rewritten where `x` still names the same variable.
+//!
+//! Addresses passed to a function of the file that does not retain them,
+//! either as an argument or as a pointer receiver, are taken of a copy of
+//! the local instead, made before the call and stored back after it. Only
+//! the copy is boxed, and the local stays on the stack. The other addresses
+//! escape, through a return, a store, a closure or an unknown callee, and
+//! are boxed.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::{ast, ir, token, typeinfer};
+
+/// Where a local variable lives, reported by `--explain-escapes` in the
+/// format of the Go compiler's `-gcflags=-m`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EscapeDecision {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub name: String,
+    /// Why the variable is moved to the heap, or `None` for an address that
+    /// does not escape.
+    pub heap_reason: Option<&'static str>,
+}
+
+impl EscapeDecision {
+    fn new(position: &token::Position<'_>, name: &str, heap_reason: Option<&'static str>) -> Self {
+        let file = if position.directory.is_empty()
+            || position.file.is_empty()
+            || position.file.starts_with('/')
+        {
+            position.file.to_string()
+        } else {
+            format!("{}/{}", position.directory, position.file)
+        };
+        Self {
+            file,
+            line: position.line,
+            column: position.column,
+            name: name.to_string(),
+            heap_reason,
+        }
+    }
+}
+
+impl fmt::Display for EscapeDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            file,
+            line,
+            column,
+            name,
+            heap_reason,
+        } = self;
+        match heap_reason {
+            Some(reason) => write!(
+                f,
+                "{file}:{line}:{column}: moved to heap: {name} ({reason})"
+            ),
+            None => write!(f, "{file}:{line}:{column}: &{name} does not escape"),
+        }
+    }
+}
+
+/// Rewrites the addresses that do not escape their function to direct uses
+/// of the variable, and reports them.
+pub(super) fn lower_non_escaping_addresses(file: &mut ast::File<'_>) -> Vec<EscapeDecision> {
+    let mut decisions = Vec::new();
+    for func_decl in func_decls_mut(file) {
+        let facts = FunctionFacts::of(func_decl);
+        let mut rewriter = Rewriter::default();
+        for (alias, pointer) in facts.aliases() {
+            decisions.push(EscapeDecision::new(&alias.address_pos, alias.target, None));
+            rewriter.removed.insert(alias.pointer_offset);
+            for occurrence in &pointer.occurrences {
+                rewriter.renames.insert(occurrence.offset, alias.target);
+            }
+        }
+        if let Some(body) = &mut func_decl.body {
+            rewriter.block(body);
+        }
+        decisions.extend(rewriter.decisions);
+    }
+
+    let facts = func_decls_mut(file)
+        .map(|func_decl| FunctionFacts::of(func_decl))
+        .collect::<Vec<_>>();
+    let func_decls = file.decls.iter().filter_map(|decl| match decl {
+        ast::Decl::FuncDecl(func_decl) => Some(func_decl),
+        ast::Decl::GenDecl(_) => None,
+    });
+    let callees = Callees::of(func_decls.zip(&facts));
+    for (func_decl, facts) in func_decls_mut(file).zip(&facts) {
+        let mut rewriter = Rewriter::default();
+        for (offset, copy) in facts.copies(&callees, &mut decisions) {
+            rewriter.renames.insert(offset, copy.temp);
+            rewriter.copies.insert(offset, copy);
+        }
+        if let Some(body) = &mut func_decl.body {
+            rewriter.block(body);
+        }
+    }
+    decisions
+}
+
+fn func_decls_mut<'f, 'a>(
+    file: &'f mut ast::File<'a>,
+) -> impl Iterator<Item = &'f mut ast::FuncDecl<'a>> {
+    file.decls.iter_mut().filter_map(|decl| match decl {
+        ast::Decl::FuncDecl(func_decl) => Some(func_decl),
+        ast::Decl::GenDecl(_) => None,
+    })
+}
+
+/// Reports the locals and parameters that still live in shared cells once
+/// [`lower_non_escaping_addresses`] ran.
+pub(super) fn heap_escapes_in_file(
+    file: &ast::File<'_>,
+    env: &typeinfer::TypeEnv,
+) -> Vec<EscapeDecision> {
+    let mut decisions = Vec::new();
+    for decl in &file.decls {
+        let ast::Decl::FuncDecl(func_decl) = decl else {
+            continue;
+        };
+        let Some(body) = &func_decl.body else {
+            continue;
+        };
+        let facts = FunctionFacts::of(func_decl);
+        let mut env = env.clone();
+        for field in func_decl_fields(func_decl) {
+            let ty = field
+                .type_
+                .as_ref()
+                .map_or(typeinfer::GoType::Unknown, typeinfer::GoType::from_expr);
+            for name in field.names.iter().flatten() {
+                env.set_var(name.name, ty.clone());
+            }
+        }
+        let param_names = facts
+            .declarations
+            .keys()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let mut names =
+            ir::address_taken_names_in_block_with_declared_bindings(body, &env, param_names);
+        let captured = ir::mutable_func_lit_capture_names_in_block(body, &env);
+        names.extend(captured.iter().cloned());
+        for name in names {
+            // The copies made around calls are boxed in place of the
+            // variable, and reported as its non-escaping address.
+            if name.starts_with(UNBOXED_PREFIX) {
+                continue;
+            }
+            let Some(position) = facts
+                .declarations
+                .get(name.as_str())
+                .and_then(|positions| positions.first())
+            else {
+                continue;
+            };
+            let reason = facts
+                .addresses
+                .iter()
+                .find(|(target, _)| *target == name)
+                .map(|(_, role)| match role {
+                    Role::Returned => "address returned",
+                    Role::CallArg => "address passed to a call",
+                    Role::Stored => "address stored",
+                    Role::Value | Role::Callee => "address taken",
+                })
+                .unwrap_or_else(|| {
+                    if captured.contains(&name) {
+                        "captured by a closure"
+                    } else {
+                        "pointer method receiver"
+                    }
+                });
+            decisions.push(EscapeDecision::new(position, &name, Some(reason)));
+        }
+    }
+    decisions
+}
+
+const UNBOXED_PREFIX: &str = "__gors_unboxed_";
+
+/// The names of the copies a function makes, in order. Identifiers borrow
+/// their names from the sources, so the names come from a fixed set; the
+/// locals a function has no names left for stay boxed.
+const COPY_TEMPS: [&str; 16] = [
+    "__gors_unboxed_0",
+    "__gors_unboxed_1",
+    "__gors_unboxed_2",
+    "__gors_unboxed_3",
+    "__gors_unboxed_4",
+    "__gors_unboxed_5",
+    "__gors_unboxed_6",
+    "__gors_unboxed_7",
+    "__gors_unboxed_8",
+    "__gors_unboxed_9",
+    "__gors_unboxed_10",
+    "__gors_unboxed_11",
+    "__gors_unboxed_12",
+    "__gors_unboxed_13",
+    "__gors_unboxed_14",
+    "__gors_unboxed_15",
+];
+
+/// The syntactic role of an expression within its parent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Value,
+    Callee,
+    CallArg,
+    Returned,
+    Stored,
+}
+
+/// The scopes opened while walking a function.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Block,
+    /// The body of a `for` or `range` loop, run any number of times.
+    Loop,
+    /// A function literal, or the call of a `go` or `defer` statement, which
+    /// may run after the statement.
+    Func,
+}
+
+trait Visit<'a> {
+    fn stmt(&mut self, _stmt: &ast::Stmt<'a>) {}
+    fn expr(&mut self, _expr: &ast::Expr<'a>, _role: Role) {}
+    /// `value` is set when the declaration gives the name a type, or a
+    /// composite literal, other than a pointer.
+    fn declared(&mut self, _ident: &ast::Ident<'a>, _value: bool) {}
+    fn open_scope(&mut self, _scope: Scope) {}
+    fn close_scope(&mut self) {}
+    /// Called with each statement of a statement list before walking it,
+    /// and with `None` after.
+    fn item(&mut self, _stmt: Option<&ast::Stmt<'a>>) {}
+}
+
+/// What a use of a local does with it.
+#[derive(Clone, Copy)]
+enum Use<'a> {
+    Value,
+    /// `*x` or `x[i]`.
+    Deref,
+    /// `x.f`, outside of a call.
+    Field(&'a str),
+    /// `x == y` or `x != y`.
+    Compared,
+    /// `&x`, as argument `index` of a call to the package function `callee`
+    /// if any.
+    Address {
+        callee: Option<(&'a str, usize)>,
+        position: token::Position<'a>,
+    },
+    /// `&x.f`, `&x[i]` or `x[i:j]`.
+    PartAddress,
+    /// `x.m()`.
+    MethodCall(&'a str),
+    /// `x.f.m()`.
+    PartMethodCall,
+    /// `x` as argument `index` of a call to the package function `callee`.
+    Arg(&'a str, usize),
+    /// The key of a composite literal element, which may name a field.
+    Key,
+    /// `x, err := …` in the scope that declares `x`.
+    Redeclared,
+}
+
+struct Occurrence<'a> {
+    offset: usize,
+    kind: Use<'a>,
+    funcs: usize,
+    loops: usize,
+    /// The statement list item holding the use, when copies of the local can
+    /// be made around it.
+    item: Option<usize>,
+}
+
+/// A variable declared in the function, resolved through scopes.
+struct Local<'a> {
+    name: &'a str,
+    declared_at: usize,
+    funcs: usize,
+    loops: usize,
+    value: bool,
+    /// The `p := &x` declaration of this local.
+    alias: Option<usize>,
+    occurrences: Vec<Occurrence<'a>>,
+}
+
+impl Local<'_> {
+    fn captured(&self) -> bool {
+        self.occurrences
+            .iter()
+            .any(|occurrence| occurrence.funcs > self.funcs)
+    }
+}
+
+/// A `p := &x` declaration.
+struct Alias<'a> {
+    pointer_offset: usize,
+    pointer: Option<usize>,
+    target: &'a str,
+    /// The local `x`, or `None` for a package variable.
+    resolved: Option<usize>,
+    address_pos: token::Position<'a>,
+    /// Whether `x` names another variable where `p` is used.
+    shadowed: bool,
+}
+
+/// The copy of a local made around a statement, whose address is taken in
+/// place of the local's.
+struct LocalCopy<'a> {
+    local: &'a str,
+    temp: &'a str,
+}
+
+/// What a function body does with its locals.
+#[derive(Default)]
+struct FunctionFacts<'a> {
+    /// Every declaration of each name, in any scope.
+    declarations: BTreeMap<&'a str, Vec<token::Position<'a>>>,
+    addresses: Vec<(&'a str, Role)>,
+    locals: Vec<Local<'a>>,
+    aliases: Vec<Alias<'a>>,
+    /// Whether the function defers calls or jumps with `goto`, which the
+    /// copies made around statements could not follow.
+    defers_or_jumps: bool,
+    scopes: Vec<(Scope, BTreeMap<&'a str, usize>)>,
+    funcs: usize,
+    loops: usize,
+    /// The statement list items being walked, with the names they declare
+    /// when copies can be made around them.
+    items: Vec<Option<(usize, Vec<&'a str>)>>,
+    next_item: usize,
+    /// The uses of identifiers, claimed by the enclosing expression.
+    claims: BTreeMap<usize, Use<'a>>,
+}
+
+impl<'a> FunctionFacts<'a> {
+    fn of(func_decl: &ast::FuncDecl<'a>) -> Self {
+        let mut facts = Self::default();
+        facts.open_scope(Scope::Func);
+        for field in func_decl_fields(func_decl) {
+            for name in field.names.iter().flatten() {
+                facts.declared(name, value_field(field));
+            }
+        }
+        if let Some(body) = &func_decl.body {
+            walk_stmts(&body.list, &mut facts);
+        }
+        facts.close_scope();
+        facts
+    }
+
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|(_, names)| names.get(name).copied())
+    }
+
+    fn claim(&mut self, expr: &ast::Expr<'a>, kind: Use<'a>) {
+        if let Some(ident) = bare_ident(expr) {
+            self.claims.entry(ident.name_pos.offset).or_insert(kind);
+        }
+    }
+
+    fn occur(&mut self, ident: &ast::Ident<'a>, kind: Use<'a>) {
+        let Some(index) = self.resolve(ident.name) else {
+            return;
+        };
+        let item = self.items.last().and_then(|item| {
+            item.as_ref()
+                .filter(|(_, declared)| !declared.contains(&ident.name))
+                .map(|(item, _)| *item)
+        });
+        let Some(local) = self.locals.get(index) else {
+            return;
+        };
+        if let Some(alias) = local.alias.and_then(|alias| self.aliases.get(alias)) {
+            let shadowed = self.resolve(alias.target) != alias.resolved;
+            if let Some(alias) = local.alias.and_then(|alias| self.aliases.get_mut(alias)) {
+                alias.shadowed |= shadowed;
+            }
+        }
+        let occurrence = Occurrence {
+            offset: ident.name_pos.offset,
+            kind,
+            funcs: self.funcs,
+            loops: self.loops,
+            item,
+        };
+        if let Some(local) = self.locals.get_mut(index) {
+            local.occurrences.push(occurrence);
+        }
+    }
+
+    /// `p := &x` declarations whose pointer is only ever dereferenced, in a
+    /// scope where `x` still names the same variable, so that `*p` and
+    /// `p.f` can use `x` directly.
+    fn aliases(&self) -> impl Iterator<Item = (&Alias<'a>, &Local<'a>)> {
+        self.aliases.iter().filter_map(|alias| {
+            let pointer = self.locals.get(alias.pointer?)?;
+            let dereferenced = pointer.occurrences.iter().all(|occurrence| {
+                matches!(
+                    occurrence.kind,
+                    Use::Deref | Use::Field(_) | Use::PartAddress | Use::PartMethodCall
+                )
+            });
+            (!alias.shadowed && !pointer.captured() && pointer.name != alias.target && dereferenced)
+                .then_some((alias, pointer))
+        })
+    }
+
+    /// The uses of locals to replace by a copy, so that the locals whose
+    /// address is only taken for calls which do not retain it stay on the
+    /// stack. The copy is made before the statement and stored back after
+    /// it.
+    ///
+    /// Copies are only made for calls run at most once per declaration of
+    /// the local: a copy in a loop would allocate at every iteration, where
+    /// boxing the local allocates once.
+    fn copies(
+        &self,
+        callees: &Callees<'a>,
+        decisions: &mut Vec<EscapeDecision>,
+    ) -> BTreeMap<usize, LocalCopy<'a>> {
+        let mut copies = BTreeMap::new();
+        if self.defers_or_jumps {
+            return copies;
+        }
+        let mut temps = 0;
+        for local in &self.locals {
+            // The copies themselves, when the file is lowered again.
+            if local.captured() || local.name.starts_with(UNBOXED_PREFIX) {
+                continue;
+            }
+            let site = |occurrence: &Occurrence<'a>| {
+                occurrence.item.is_some()
+                    && occurrence.loops == local.loops
+                    && match occurrence.kind {
+                        Use::Address {
+                            callee: Some((callee, index)),
+                            ..
+                        } => callees.kept.contains(&Callee::Param(callee, index)),
+                        Use::MethodCall(name) => {
+                            local.value
+                                && callees.pointer_receivers(name)
+                                && callees.receivers_kept(name)
+                        }
+                        _ => false,
+                    }
+            };
+            let blocked = local.occurrences.iter().any(|occurrence| {
+                !site(occurrence)
+                    && match occurrence.kind {
+                        Use::Value | Use::Deref | Use::Compared | Use::Arg(..) => false,
+                        Use::Field(name) => callees.pointer_receivers(name),
+                        Use::MethodCall(name) => callees
+                            .methods
+                            .get(name)
+                            .is_none_or(|receivers| receivers.iter().any(Option::is_some)),
+                        _ => true,
+                    }
+            });
+            if blocked {
+                continue;
+            }
+            let items = local
+                .occurrences
+                .iter()
+                .filter(|occurrence| site(occurrence))
+                .filter_map(|occurrence| occurrence.item)
+                .collect::<BTreeSet<_>>();
+            let Some(names) = COPY_TEMPS.get(temps..temps + items.len()) else {
+                continue;
+            };
+            temps += items.len();
+            for (item, &temp) in items.into_iter().zip(names) {
+                for occurrence in &local.occurrences {
+                    if occurrence.item != Some(item) {
+                        continue;
+                    }
+                    if let Use::Address { position, .. } = occurrence.kind {
+                        decisions.push(EscapeDecision::new(&position, local.name, None));
+                    }
+                    copies.insert(
+                        occurrence.offset,
+                        LocalCopy {
+                            local: local.name,
+                            temp,
+                        },
+                    );
+                }
+            }
+        }
+        copies
+    }
+}
+
+impl<'a> Visit<'a> for FunctionFacts<'a> {
+    fn stmt(&mut self, stmt: &ast::Stmt<'a>) {
+        match stmt {
+            ast::Stmt::DeferStmt(_) => self.defers_or_jumps = true,
+            ast::Stmt::BranchStmt(branch) if branch.tok == token::Token::GOTO => {
+                self.defers_or_jumps = true;
+            }
+            _ => {}
+        }
+        if let Some((pointer, target, address_pos)) = address_alias_decl(stmt) {
+            self.aliases.push(Alias {
+                pointer_offset: pointer.name_pos.offset,
+                pointer: None,
+                target: target.name,
+                resolved: self.resolve(target.name),
+                address_pos,
+                shadowed: false,
+            });
+        }
+    }
+
+    fn expr(&mut self, expr: &ast::Expr<'a>, role: Role) {
+        match expr {
+            ast::Expr::Ident(ident) => {
+                let kind = self
+                    .claims
+                    .remove(&ident.name_pos.offset)
+                    .unwrap_or(Use::Value);
+                self.occur(ident, kind);
+            }
+            ast::Expr::StarExpr(star) => self.claim(&star.x, Use::Deref),
+            ast::Expr::IndexExpr(index) => self.claim(&index.x, Use::Deref),
+            ast::Expr::SelectorExpr(selector) if role != Role::Callee => {
+                self.claim(&selector.x, Use::Field(selector.sel.name));
+            }
+            ast::Expr::CallExpr(call) => {
+                if let ast::Expr::SelectorExpr(selector) = call.fun.as_ref() {
+                    self.claim(&selector.x, Use::MethodCall(selector.sel.name));
+                    if let Some(root) = root_ident(&selector.x) {
+                        self.claims
+                            .entry(root.name_pos.offset)
+                            .or_insert(Use::PartMethodCall);
+                    }
+                }
+                let callee = bare_ident(&call.fun)
+                    .filter(|callee| self.resolve(callee.name).is_none())
+                    .map(|callee| callee.name);
+                for (index, arg) in call.args.iter().flatten().enumerate() {
+                    let Some(callee) = callee else {
+                        break;
+                    };
+                    match arg {
+                        ast::Expr::UnaryExpr(unary) if unary.op == token::Token::AND => {
+                            let kind = Use::Address {
+                                callee: Some((callee, index)),
+                                position: unary.op_pos,
+                            };
+                            self.claim(&unary.x, kind);
+                        }
+                        _ => self.claim(arg, Use::Arg(callee, index)),
+                    }
+                }
+            }
+            ast::Expr::UnaryExpr(unary) if unary.op == token::Token::AND => {
+                if let ast::Expr::Ident(target) = unary.x.as_ref() {
+                    self.addresses.push((target.name, role));
+                }
+                let kind = Use::Address {
+                    callee: None,
+                    position: unary.op_pos,
+                };
+                self.claim(&unary.x, kind);
+                if let Some(root) = root_ident(&unary.x) {
+                    self.claims
+                        .entry(root.name_pos.offset)
+                        .or_insert(Use::PartAddress);
+                }
+            }
+            ast::Expr::SliceExpr(slice) => {
+                if let Some(root) = root_ident(&slice.x) {
+                    self.claims
+                        .entry(root.name_pos.offset)
+                        .or_insert(Use::PartAddress);
+                }
+            }
+            ast::Expr::BinaryExpr(binary)
+                if matches!(binary.op, token::Token::EQL | token::Token::NEQ) =>
+            {
+                self.claim(&binary.x, Use::Compared);
+                self.claim(&binary.y, Use::Compared);
+            }
+            ast::Expr::KeyValueExpr(kv) => self.claim(&kv.key, Use::Key),
+            _ => {}
+        }
+    }
+
+    fn declared(&mut self, ident: &ast::Ident<'a>, value: bool) {
+        self.declarations
+            .entry(ident.name)
+            .or_default()
+            .push(ident.name_pos);
+        if ident.name == "_" {
+            return;
+        }
+        let redeclared = self
+            .scopes
+            .last()
+            .and_then(|(_, names)| names.get(ident.name))
+            .is_some();
+        if redeclared {
+            self.occur(ident, Use::Redeclared);
+            return;
+        }
+        let index = self.locals.len();
+        let alias = self
+            .aliases
+            .iter()
+            .rposition(|alias| alias.pointer_offset == ident.name_pos.offset);
+        if let Some(alias) = alias.and_then(|alias| self.aliases.get_mut(alias)) {
+            alias.pointer = Some(index);
+        }
+        self.locals.push(Local {
+            name: ident.name,
+            declared_at: ident.name_pos.offset,
+            funcs: self.funcs,
+            loops: self.loops,
+            value,
+            alias,
+            occurrences: Vec::new(),
+        });
+        if let Some((_, names)) = self.scopes.last_mut() {
+            names.insert(ident.name, index);
+        }
+    }
+
+    fn open_scope(&mut self, scope: Scope) {
+        match scope {
+            Scope::Block => {}
+            Scope::Loop => self.loops += 1,
+            Scope::Func => self.funcs += 1,
+        }
+        self.scopes.push((scope, BTreeMap::new()));
+    }
+
+    fn close_scope(&mut self) {
+        match self.scopes.pop() {
+            Some((Scope::Loop, _)) => self.loops -= 1,
+            Some((Scope::Func, _)) => self.funcs -= 1,
+            Some((Scope::Block, _)) | None => {}
+        }
+    }
+
+    fn item(&mut self, stmt: Option<&ast::Stmt<'a>>) {
+        let Some(stmt) = stmt else {
+            self.items.pop();
+            return;
+        };
+        let declared = match stmt {
+            ast::Stmt::ExprStmt(stmt) if matches!(stmt.x, ast::Expr::CallExpr(_)) => {
+                Some(Vec::new())
+            }
+            ast::Stmt::AssignStmt(assign) => Some(if assign.tok == token::Token::DEFINE {
+                assign
+                    .lhs
+                    .iter()
+                    .filter_map(bare_ident)
+                    .map(|ident| ident.name)
+                    .collect()
+            } else {
+                Vec::new()
+            }),
+            ast::Stmt::ReturnStmt(_) => Some(Vec::new()),
+            _ => None,
+        };
+        self.items
+            .push(declared.map(|declared| (self.next_item, declared)));
+        self.next_item += 1;
+    }
+}
+
+/// A pointer parameter or receiver of a package function.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Callee<'a> {
+    /// Parameter `index` of the function `name`.
+    Param(&'a str, usize),
+    /// The receiver of the method at this index among the file's functions.
+    Receiver(usize),
+}
+
+/// The pointer parameters and receivers of the file's functions which never
+/// outlive a call: the function only dereferences and compares them, or
+/// hands them to other such parameters and receivers.
+struct Callees<'a> {
+    /// The functions declaring each method name, `None` for the methods
+    /// with a value receiver.
+    methods: BTreeMap<&'a str, Vec<Option<usize>>>,
+    kept: BTreeSet<Callee<'a>>,
+}
+
+impl<'a> Callees<'a> {
+    fn of<'f>(
+        functions: impl Iterator<Item = (&'f ast::FuncDecl<'a>, &'f FunctionFacts<'a>)>,
+    ) -> Self
+    where
+        'a: 'f,
+    {
+        let mut methods = BTreeMap::<_, Vec<_>>::new();
+        let mut params = BTreeMap::new();
+        for (index, (func_decl, facts)) in functions.enumerate() {
+            let local = |ident: &ast::Ident<'a>| {
+                facts
+                    .locals
+                    .iter()
+                    .find(|local| local.declared_at == ident.name_pos.offset)
+            };
+            let defined = func_decl.body.is_some();
+            if let Some(recv) = &func_decl.recv {
+                let field = recv.list.first();
+                let pointer = field
+                    .and_then(|field| field.type_.as_ref())
+                    .is_some_and(|ty| matches!(ty, ast::Expr::StarExpr(_)));
+                methods
+                    .entry(func_decl.name.name)
+                    .or_default()
+                    .push(pointer.then_some(index));
+                if pointer && defined {
+                    let receiver = field
+                        .and_then(|field| field.names.as_ref())
+                        .and_then(|names| names.first())
+                        .and_then(local);
+                    params.insert(Callee::Receiver(index), receiver);
+                }
+                continue;
+            }
+            let mut position = 0;
+            for field in &func_decl.type_.params.list {
+                let names = field.names.as_deref().unwrap_or_default();
+                let count = names.len().max(1);
+                let pointer = matches!(field.type_, Some(ast::Expr::StarExpr(_)));
+                for offset in 0..count {
+                    if pointer && defined {
+                        let param = names.get(offset).and_then(local);
+                        params.insert(Callee::Param(func_decl.name.name, position + offset), param);
+                    }
+                }
+                position += count;
+            }
+        }
+
+        let mut callees = Self {
+            methods,
+            kept: params
+                .iter()
+                .filter(|(_, param)| param.is_none_or(|param| !param.captured()))
+                .map(|(callee, _)| *callee)
+                .collect(),
+        };
+        loop {
+            let retained = callees
+                .kept
+                .iter()
+                .filter(|callee| {
+                    params.get(callee).copied().flatten().is_some_and(|param| {
+                        !param
+                            .occurrences
+                            .iter()
+                            .all(|occurrence| callees.keeps(occurrence.kind))
+                    })
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            if retained.is_empty() {
+                return callees;
+            }
+            for callee in retained {
+                callees.kept.remove(&callee);
+            }
+        }
+    }
+
+    /// Whether a pointer parameter or receiver used this way still does not
+    /// outlive the call.
+    fn keeps(&self, kind: Use<'a>) -> bool {
+        match kind {
+            Use::Deref | Use::Compared => true,
+            Use::Field(name) => !self.methods.contains_key(name),
+            Use::MethodCall(name) => self.receivers_kept(name),
+            Use::Arg(callee, index) => self.kept.contains(&Callee::Param(callee, index)),
+            _ => false,
+        }
+    }
+
+    fn pointer_receivers(&self, name: &str) -> bool {
+        self.methods
+            .get(name)
+            .is_some_and(|receivers| receivers.iter().any(Option::is_some))
+    }
+
+    fn receivers_kept(&self, name: &str) -> bool {
+        self.methods.get(name).is_some_and(|receivers| {
+            receivers
+                .iter()
+                .flatten()
+                .all(|index| self.kept.contains(&Callee::Receiver(*index)))
+        })
+    }
+}
+
+/// The receiver, parameters and results of a function.
+fn func_decl_fields<'f, 'a>(
+    func_decl: &'f ast::FuncDecl<'a>,
+) -> impl Iterator<Item = &'f ast::Field<'a>> {
+    func_decl
+        .recv
+        .iter()
+        .chain(std::iter::once(&func_decl.type_.params))
+        .chain(func_decl.type_.results.iter())
+        .flat_map(|list| &list.list)
+}
+
+fn value_field(field: &ast::Field<'_>) -> bool {
+    field
+        .type_
+        .as_ref()
+        .is_some_and(|ty| !matches!(ty, ast::Expr::StarExpr(_) | ast::Expr::Ellipsis(_)))
+}
+
+/// `x`, possibly parenthesized.
+fn bare_ident<'e, 'a>(expr: &'e ast::Expr<'a>) -> Option<&'e ast::Ident<'a>> {
+    match expr {
+        ast::Expr::Ident(ident) => Some(ident),
+        ast::Expr::ParenExpr(paren) => bare_ident(&paren.x),
+        _ => None,
+    }
+}
+
+/// `x` in `x.f[i]` and the like.
+fn root_ident<'e, 'a>(expr: &'e ast::Expr<'a>) -> Option<&'e ast::Ident<'a>> {
+    match expr {
+        ast::Expr::Ident(ident) => Some(ident),
+        ast::Expr::ParenExpr(paren) => root_ident(&paren.x),
+        ast::Expr::SelectorExpr(selector) => root_ident(&selector.x),
+        ast::Expr::IndexExpr(index) => root_ident(&index.x),
+        ast::Expr::StarExpr(star) => root_ident(&star.x),
+        _ => None,
+    }
+}
+
+/// Matches `p := &x`.
+fn address_alias_decl<'s, 'a>(
+    stmt: &'s ast::Stmt<'a>,
+) -> Option<(&'s ast::Ident<'a>, &'s ast::Ident<'a>, token::Position<'a>)> {
+    let ast::Stmt::AssignStmt(assign) = stmt else {
+        return None;
+    };
+    let ([ast::Expr::Ident(pointer)], [ast::Expr::UnaryExpr(unary)]) =
+        (assign.lhs.as_slice(), assign.rhs.as_slice())
+    else {
+        return None;
+    };
+    let ast::Expr::Ident(target) = unary.x.as_ref() else {
+        return None;
+    };
+    (assign.tok == token::Token::DEFINE && unary.op == token::Token::AND && pointer.name != "_")
+        .then_some((pointer, target, unary.op_pos))
+}
+
+fn walk_stmts<'a>(stmts: &[ast::Stmt<'a>], v: &mut impl Visit<'a>) {
+    for stmt in stmts {
+        v.item(Some(stmt));
+        walk_stmt(stmt, v);
+        v.item(None);
+    }
+}
+
+fn walk_block<'a>(block: &ast::BlockStmt<'a>, v: &mut impl Visit<'a>) {
+    v.open_scope(Scope::Block);
+    walk_stmts(&block.list, v);
+    v.close_scope();
+}
+
+fn walk_stmt<'a>(stmt: &ast::Stmt<'a>, v: &mut impl Visit<'a>) {
+    v.stmt(stmt);
+    let expr = |expr: &ast::Expr<'a>, role: Role, v: &mut _| walk_expr(expr, role, v);
+    match stmt {
+        ast::Stmt::AssignStmt(assign) => {
+            for rhs in &assign.rhs {
+                expr(rhs, Role::Stored, v);
+            }
+            for (index, lhs) in assign.lhs.iter().enumerate() {
+                match lhs {
+                    ast::Expr::Ident(ident) if assign.tok == token::Token::DEFINE => {
+                        let value = assign.lhs.len() == assign.rhs.len()
+                            && matches!(assign.rhs.get(index), Some(ast::Expr::CompositeLit(_)));
+                        v.declared(ident, value);
+                    }
+                    _ => expr(lhs, Role::Value, v),
+                }
+            }
+        }
+        ast::Stmt::BlockStmt(block) => walk_block(block, v),
+        ast::Stmt::BranchStmt(_) | ast::Stmt::EmptyStmt(_) => {}
+        ast::Stmt::CaseClause(clause) => {
+            for value in clause.list.iter().flatten() {
+                expr(value, Role::Value, v);
+            }
+            v.open_scope(Scope::Block);
+            walk_stmts(&clause.body, v);
+            v.close_scope();
+        }
+        ast::Stmt::CommClause(clause) => {
+            v.open_scope(Scope::Block);
+            if let Some(comm) = &clause.comm {
+                walk_stmt(comm, v);
+            }
+            walk_stmts(&clause.body, v);
+            v.close_scope();
+        }
+        ast::Stmt::DeclStmt(decl) => {
+            for spec in &decl.decl.specs {
+                let ast::Spec::ValueSpec(value_spec) = spec else {
+                    continue;
+                };
+                for value in value_spec.values.iter().flatten() {
+                    expr(value, Role::Stored, v);
+                }
+                for (index, name) in value_spec.names.iter().enumerate() {
+                    let value = match &value_spec.type_ {
+                        Some(ty) => !matches!(ty, ast::Expr::StarExpr(_)),
+                        None => matches!(
+                            value_spec
+                                .values
+                                .as_ref()
+                                .and_then(|values| values.get(index)),
+                            Some(ast::Expr::CompositeLit(_))
+                        ),
+                    };
+                    v.declared(name, value);
+                }
+            }
+        }
+        ast::Stmt::DeferStmt(defer) => {
+            v.open_scope(Scope::Func);
+            walk_call(&defer.call, v);
+            v.close_scope();
+        }
+        ast::Stmt::ExprStmt(stmt) => expr(&stmt.x, Role::Value, v),
+        ast::Stmt::ForStmt(for_stmt) => {
+            v.open_scope(Scope::Block);
+            if let Some(init) = &for_stmt.init {
+                walk_stmt(init, v);
+            }
+            v.open_scope(Scope::Loop);
+            if let Some(cond) = &for_stmt.cond {
+                expr(cond, Role::Value, v);
+            }
+            if let Some(post) = &for_stmt.post {
+                walk_stmt(post, v);
+            }
+            walk_block(&for_stmt.body, v);
+            v.close_scope();
+            v.close_scope();
+        }
+        ast::Stmt::GoStmt(go) => {
+            v.open_scope(Scope::Func);
+            walk_call(&go.call, v);
+            v.close_scope();
+        }
+        ast::Stmt::IfStmt(if_stmt) => {
+            v.open_scope(Scope::Block);
+            if let Some(init) = if_stmt.init.as_ref() {
+                walk_stmt(init, v);
+            }
+            expr(&if_stmt.cond, Role::Value, v);
+            walk_block(&if_stmt.body, v);
+            if let Some(else_) = if_stmt.else_.as_ref() {
+                walk_stmt(else_, v);
+            }
+            v.close_scope();
+        }
+        ast::Stmt::IncDecStmt(inc_dec) => expr(&inc_dec.x, Role::Value, v),
+        ast::Stmt::LabeledStmt(labeled) => walk_stmt(&labeled.stmt, v),
+        ast::Stmt::RangeStmt(range) => {
+            expr(&range.x, Role::Value, v);
+            v.open_scope(Scope::Loop);
+            for binding in range.key.iter().chain(range.value.iter()) {
+                match binding {
+                    ast::Expr::Ident(ident) if range.tok == Some(token::Token::DEFINE) => {
+                        v.declared(ident, false);
+                    }
+                    _ => expr(binding, Role::Value, v),
+                }
+            }
+            walk_block(&range.body, v);
+            v.close_scope();
+        }
+        ast::Stmt::ReturnStmt(ret) => {
+            for result in &ret.results {
+                expr(result, Role::Returned, v);
+            }
+        }
+        ast::Stmt::SelectStmt(select) => walk_block(&select.body, v),
+        ast::Stmt::SendStmt(send) => {
+            expr(&send.chan, Role::Value, v);
+            expr(&send.value, Role::Stored, v);
+        }
+        ast::Stmt::SwitchStmt(switch) => {
+            v.open_scope(Scope::Block);
+            if let Some(init) = &switch.init {
+                walk_stmt(init, v);
+            }
+            if let Some(tag) = &switch.tag {
+                expr(tag, Role::Value, v);
+            }
+            walk_block(&switch.body, v);
+            v.close_scope();
+        }
+        ast::Stmt::TypeSwitchStmt(switch) => {
+            v.open_scope(Scope::Block);
+            if let Some(init) = &switch.init {
+                walk_stmt(init, v);
+            }
+            walk_stmt(&switch.assign, v);
+            walk_block(&switch.body, v);
+            v.close_scope();
+        }
+    }
+}
+
+fn walk_call<'a>(call: &ast::CallExpr<'a>, v: &mut impl Visit<'a>) {
+    walk_expr(&call.fun, Role::Callee, v);
+    for arg in call.args.iter().flatten() {
+        walk_expr(arg, Role::CallArg, v);
+    }
+}
+
+fn walk_expr<'a>(expr: &ast::Expr<'a>, role: Role, v: &mut impl Visit<'a>) {
+    v.expr(expr, role);
+    let value = |expr: &ast::Expr<'a>, v: &mut _| walk_expr(expr, Role::Value, v);
+    match expr {
+        ast::Expr::ArrayType(array) => {
+            if let Some(len) = &array.len {
+                value(len, v);
+            }
+            value(&array.elt, v);
+        }
+        ast::Expr::BasicLit(_)
+        | ast::Expr::FuncType(_)
+        | ast::Expr::Ident(_)
+        | ast::Expr::InterfaceType(_)
+        | ast::Expr::StructType(_) => {}
+        ast::Expr::BinaryExpr(binary) => {
+            value(&binary.x, v);
+            value(&binary.y, v);
+        }
+        ast::Expr::CallExpr(call) => walk_call(call, v),
+        ast::Expr::ChanType(chan) => value(&chan.value, v),
+        ast::Expr::CompositeLit(composite) => {
+            if let Some(ty) = &composite.type_ {
+                value(ty, v);
+            }
+            for elt in composite.elts.iter().flatten() {
+                walk_expr(elt, Role::Stored, v);
+            }
+        }
+        ast::Expr::Ellipsis(ellipsis) => {
+            if let Some(elt) = &ellipsis.elt {
+                value(elt, v);
+            }
+        }
+        ast::Expr::FuncLit(func_lit) => {
+            v.open_scope(Scope::Func);
+            for field in std::iter::once(&func_lit.type_.params)
+                .chain(func_lit.type_.results.iter())
+                .flat_map(|list| &list.list)
+            {
+                for name in field.names.iter().flatten() {
+                    v.declared(name, value_field(field));
+                }
+            }
+            walk_stmts(&func_lit.body.list, v);
+            v.close_scope();
+        }
+        ast::Expr::IndexExpr(index) => {
+            value(&index.x, v);
+            value(&index.index, v);
+        }
+        ast::Expr::IndexListExpr(index) => {
+            value(&index.x, v);
+            for index in &index.indices {
+                value(index, v);
+            }
+        }
+        ast::Expr::KeyValueExpr(kv) => {
+            value(&kv.key, v);
+            walk_expr(&kv.value, Role::Stored, v);
+        }
+        ast::Expr::MapType(map) => {
+            value(&map.key, v);
+            value(&map.value, v);
+        }
+        ast::Expr::ParenExpr(paren) => walk_expr(&paren.x, role, v),
+        ast::Expr::SelectorExpr(selector) => value(&selector.x, v),
+        ast::Expr::SliceExpr(slice) => {
+            value(&slice.x, v);
+            for bound in [&slice.low, &slice.high, &slice.max].into_iter().flatten() {
+                value(bound, v);
+            }
+        }
+        ast::Expr::StarExpr(star) => value(&star.x, v),
+        ast::Expr::TypeAssertExpr(assert) => {
+            value(&assert.x, v);
+            if let Some(ty) = &assert.type_ {
+                value(ty, v);
+            }
+        }
+        ast::Expr::UnaryExpr(unary) => value(&unary.x, v),
+    }
+}
+
+/// The locals that a statement copies, in the order of their uses.
+struct CopiedLocals<'r, 'a> {
+    copies: &'r BTreeMap<usize, LocalCopy<'a>>,
+    scopes: Vec<Scope>,
+    found: Vec<(&'a str, &'a str, token::Position<'a>)>,
+}
+
+impl<'a> Visit<'a> for CopiedLocals<'_, 'a> {
+    fn expr(&mut self, expr: &ast::Expr<'a>, _role: Role) {
+        let ast::Expr::Ident(ident) = expr else {
+            return;
+        };
+        // Copies are never made for uses in function literals.
+        if self.scopes.contains(&Scope::Func) {
+            return;
+        }
+        if let Some(copy) = self.copies.get(&ident.name_pos.offset)
+            && !self.found.iter().any(|(_, temp, _)| *temp == copy.temp)
+        {
+            self.found.push((copy.local, copy.temp, ident.name_pos));
+        }
+    }
+
+    fn open_scope(&mut self, scope: Scope) {
+        self.scopes.push(scope);
+    }
+
+    fn close_scope(&mut self) {
+        self.scopes.pop();
+    }
+}
+
+/// Replaces the non-escaping addresses by the variables they point to.
+#[derive(Default)]
+struct Rewriter<'a> {
+    /// The new names of identifiers, by offset.
+    renames: BTreeMap<usize, &'a str>,
+    /// The `p := &x` declarations to remove, by the offset of `p`.
+    removed: BTreeSet<usize>,
+    /// The uses of locals to replace by a copy, by offset.
+    copies: BTreeMap<usize, LocalCopy<'a>>,
+    decisions: Vec<EscapeDecision>,
+}
+
+impl<'a> Rewriter<'a> {
+    fn block(&mut self, block: &mut ast::BlockStmt<'a>) {
+        self.stmts(&mut block.list);
+    }
+
+    fn stmts(&mut self, stmts: &mut Vec<ast::Stmt<'a>>) {
+        for mut stmt in std::mem::take(stmts) {
+            if address_alias_decl(&stmt)
+                .is_some_and(|(pointer, _, _)| self.removed.contains(&pointer.name_pos.offset))
+            {
+                continue;
+            }
+            let mut copied = CopiedLocals {
+                copies: &self.copies,
+                scopes: Vec::new(),
+                found: Vec::new(),
+            };
+            if matches!(
+                stmt,
+                ast::Stmt::AssignStmt(_) | ast::Stmt::ExprStmt(_) | ast::Stmt::ReturnStmt(_)
+            ) {
+                walk_stmt(&stmt, &mut copied);
+            }
+            let copied = copied.found;
+            for (local, temp, position) in &copied {
+                stmts.push(assign_stmt(temp, token::Token::DEFINE, local, *position));
+            }
+            let returns = matches!(stmt, ast::Stmt::ReturnStmt(_));
+            self.stmt(&mut stmt);
+            stmts.push(stmt);
+            if !returns {
+                for (local, temp, position) in &copied {
+                    stmts.push(assign_stmt(local, token::Token::ASSIGN, temp, *position));
+                }
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut ast::Stmt<'a>) {
+        match stmt {
+            ast::Stmt::AssignStmt(assign) => {
+                for expr in assign.lhs.iter_mut().chain(assign.rhs.iter_mut()) {
+                    self.expr(expr);
+                }
+            }
+            ast::Stmt::BlockStmt(block) => self.block(block),
+            ast::Stmt::BranchStmt(_) | ast::Stmt::EmptyStmt(_) => {}
+            ast::Stmt::CaseClause(clause) => {
+                for expr in clause.list.iter_mut().flatten() {
+                    self.expr(expr);
+                }
+                self.stmts(&mut clause.body);
+            }
+            ast::Stmt::CommClause(clause) => {
+                if let Some(comm) = &mut clause.comm {
+                    self.stmt(comm);
+                }
+                self.stmts(&mut clause.body);
+            }
+            ast::Stmt::DeclStmt(decl) => {
+                for spec in &mut decl.decl.specs {
+                    if let ast::Spec::ValueSpec(value_spec) = spec {
+                        for value in value_spec.values.iter_mut().flatten() {
+                            self.expr(value);
+                        }
+                    }
+                }
+            }
+            ast::Stmt::DeferStmt(defer) => self.call(&mut defer.call),
+            ast::Stmt::ExprStmt(stmt) => self.expr(&mut stmt.x),
+            ast::Stmt::ForStmt(for_stmt) => {
+                if let Some(init) = &mut for_stmt.init {
+                    self.stmt(init);
+                }
+                if let Some(cond) = &mut for_stmt.cond {
+                    self.expr(cond);
+                }
+                if let Some(post) = &mut for_stmt.post {
+                    self.stmt(post);
+                }
+                self.block(&mut for_stmt.body);
+            }
+            ast::Stmt::GoStmt(go) => self.call(&mut go.call),
+            ast::Stmt::IfStmt(if_stmt) => {
+                if let Some(init) = if_stmt.init.as_mut() {
+                    self.stmt(init);
+                }
+                self.expr(&mut if_stmt.cond);
+                self.block(&mut if_stmt.body);
+                if let Some(else_) = if_stmt.else_.as_mut() {
+                    self.stmt(else_);
+                }
+            }
+            ast::Stmt::IncDecStmt(inc_dec) => self.expr(&mut inc_dec.x),
+            ast::Stmt::LabeledStmt(labeled) => self.stmt(&mut labeled.stmt),
+            ast::Stmt::RangeStmt(range) => {
+                for expr in range.key.iter_mut().chain(range.value.iter_mut()) {
+                    self.expr(expr);
+                }
+                self.expr(&mut range.x);
+                self.block(&mut range.body);
+            }
+            ast::Stmt::ReturnStmt(ret) => {
+                for result in &mut ret.results {
+                    self.expr(result);
+                }
+            }
+            ast::Stmt::SelectStmt(select) => self.block(&mut select.body),
+            ast::Stmt::SendStmt(send) => {
+                self.expr(&mut send.chan);
+                self.expr(&mut send.value);
+            }
+            ast::Stmt::SwitchStmt(switch) => {
+                if let Some(init) = &mut switch.init {
+                    self.stmt(init);
+                }
+                if let Some(tag) = &mut switch.tag {
+                    self.expr(tag);
+                }
+                self.block(&mut switch.body);
+            }
+            ast::Stmt::TypeSwitchStmt(switch) => {
+                if let Some(init) = &mut switch.init {
+                    self.stmt(init);
+                }
+                self.stmt(&mut switch.assign);
+                self.block(&mut switch.body);
+            }
+        }
+    }
+
+    fn call(&mut self, call: &mut ast::CallExpr<'a>) {
+        self.expr(&mut call.fun);
+        for arg in call.args.iter_mut().flatten() {
+            self.expr(arg);
+        }
+    }
+
+    fn expr(&mut self, expr: &mut ast::Expr<'a>) {
+        self.lower(expr);
+        match expr {
+            ast::Expr::ArrayType(array) => {
+                if let Some(len) = &mut array.len {
+                    self.expr(len);
+                }
+                self.expr(&mut array.elt);
+            }
+            ast::Expr::BasicLit(_)
+            | ast::Expr::FuncType(_)
+            | ast::Expr::InterfaceType(_)
+            | ast::Expr::StructType(_) => {}
+            ast::Expr::Ident(ident) => {
+                if let Some(target) = self.renames.get(&ident.name_pos.offset) {
+                    ident.name = target;
+                    ident.obj = None;
+                }
+            }
+            ast::Expr::BinaryExpr(binary) => {
+                self.expr(&mut binary.x);
+                self.expr(&mut binary.y);
+            }
+            ast::Expr::CallExpr(call) => self.call(call),
+            ast::Expr::ChanType(chan) => self.expr(&mut chan.value),
+            ast::Expr::CompositeLit(composite) => {
+                if let Some(ty) = &mut composite.type_ {
+                    self.expr(ty);
+                }
+                for elt in composite.elts.iter_mut().flatten() {
+                    self.expr(elt);
+                }
+            }
+            ast::Expr::Ellipsis(ellipsis) => {
+                if let Some(elt) = &mut ellipsis.elt {
+                    self.expr(elt);
+                }
+            }
+            ast::Expr::FuncLit(func_lit) => self.block(&mut func_lit.body),
+            ast::Expr::IndexExpr(index) => {
+                self.expr(&mut index.x);
+                self.expr(&mut index.index);
+            }
+            ast::Expr::IndexListExpr(index) => {
+                self.expr(&mut index.x);
+                for index in &mut index.indices {
+                    self.expr(index);
+                }
+            }
+            ast::Expr::KeyValueExpr(kv) => {
+                self.expr(&mut kv.key);
+                self.expr(&mut kv.value);
+            }
+            ast::Expr::MapType(map) => {
+                self.expr(&mut map.key);
+                self.expr(&mut map.value);
+            }
+            ast::Expr::ParenExpr(paren) => self.expr(&mut paren.x),
+            ast::Expr::SelectorExpr(selector) => self.expr(&mut selector.x),
+            ast::Expr::SliceExpr(slice) => {
+                self.expr(&mut slice.x);
+                for bound in [&mut slice.low, &mut slice.high, &mut slice.max]
+                    .into_iter()
+                    .flatten()
+                {
+                    self.expr(bound);
+                }
+            }
+            ast::Expr::StarExpr(star) => self.expr(&mut star.x),
+            ast::Expr::TypeAssertExpr(assert) => {
+                self.expr(&mut assert.x);
+                if let Some(ty) = &mut assert.type_ {
+                    self.expr(ty);
+                }
+            }
+            ast::Expr::UnaryExpr(unary) => self.expr(&mut unary.x),
+        }
+    }
+
+    /// Rewrites `*p` to `x` for an alias `p` of `&x`, `*&x` to `x` and
+    /// `(&x).f` to `x.f`.
+    fn lower(&mut self, expr: &mut ast::Expr<'a>) {
+        match expr {
+            ast::Expr::StarExpr(star) => {
+                if let ast::Expr::Ident(pointer) = star.x.as_ref()
+                    && let Some(target) = self.renames.get(&pointer.name_pos.offset)
+                {
+                    *expr = ast::Expr::Ident(ast::Ident {
+                        name_pos: pointer.name_pos,
+                        name: target,
+                        obj: None,
+                    });
+                } else if let Some(target) = self.take_address_operand(&mut star.x) {
+                    *expr = target;
+                }
+            }
+            ast::Expr::SelectorExpr(selector) => {
+                if let ast::Expr::ParenExpr(paren) = selector.x.as_mut()
+                    && let Some(target) = self.take_address_operand(&mut paren.x)
+                {
+                    *selector.x = target;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Takes `x` out of `&x`.
+    fn take_address_operand(&mut self, expr: &mut ast::Expr<'a>) -> Option<ast::Expr<'a>> {
+        let ast::Expr::UnaryExpr(unary) = expr else {
+            return None;
+        };
+        let ast::Expr::Ident(target) = unary.x.as_ref() else {
+            return None;
+        };
+        if unary.op != token::Token::AND {
+            return None;
+        }
+        self.decisions
+            .push(EscapeDecision::new(&unary.op_pos, target.name, None));
+        let placeholder = ast::Expr::Ident(ast::Ident {
+            name_pos: unary.op_pos,
+            name: "_",
+            obj: None,
+        });
+        Some(std::mem::replace(&mut unary.x, placeholder))
+    }
+}
+
+/// `lhs := rhs` or `lhs = rhs`.
+fn assign_stmt<'a>(
+    lhs: &'a str,
+    tok: token::Token,
+    rhs: &'a str,
+    position: token::Position<'a>,
+) -> ast::Stmt<'a> {
+    let ident = |name| {
+        ast::Expr::Ident(ast::Ident {
+            name_pos: position,
+            name,
+            obj: None,
+        })
+    };
+    ast::Stmt::AssignStmt(ast::AssignStmt {
+        lhs: vec![ident(lhs)],
+        tok_pos: position,
+        tok,
+        rhs: vec![ident(rhs)],
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::parser::parse_file;
+
+    fn lowered(source: &str) -> (String, Vec<String>) {
+        let mut file = parse_file("main.go", source).unwrap();
+        let mut decisions = super::lower_non_escaping_addresses(&mut file);
+        let mut env = crate::compiler::typeinfer::TypeEnv::new();
+        env.scan_file(&file);
+        decisions.extend(super::heap_escapes_in_file(&file, &env));
+        let rust = crate::compiler::compile(file).unwrap();
+        (
+            quote::quote!(#rust).to_string(),
+            decisions.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[test]
+    fn keeps_locals_with_dereferenced_addresses_on_the_stack() {
+        let (rust, decisions) = lowered(
+            "package main
+
+type Point struct{ X, Y int }
+
+func main() {
+	v := 3
+	p := &v
+	*p += 4
+	pt := Point{1, 2}
+	q := &pt
+	q.X = 5
+	println(v, (&pt).Y, *&v)
+}
+",
+        );
+
+        assert!(!rust.contains("Mutex"), "{rust}");
+        assert_eq!(
+            decisions,
+            [
+                "main.go:7:7: &v does not escape",
+                "main.go:10:7: &pt does not escape",
+                "main.go:12:14: &pt does not escape",
+                "main.go:12:23: &v does not escape",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_aliases_of_shadowed_package_variables() {
+        let (rust, decisions) = lowered(
+            "package main
+
+var g int
+
+func main() {
+	p := &g
+	g := 2
+	*p = 1
+	println(g)
+}
+",
+        );
+
+        assert!(rust.contains("let mut p"), "{rust}");
+        assert_eq!(
+            decisions,
+            ["main.go:7:2: moved to heap: g (address stored)"]
+        );
+    }
+
+    #[test]
+    fn resolves_aliases_through_scopes() {
+        let (rust, decisions) = lowered(
+            "package main
+
+func main() {
+	x := 1
+	if x > 0 {
+		p := &x
+		*p = 2
+	}
+	{
+		p := 3
+		println(p)
+	}
+	println(x)
+}
+",
+        );
+
+        assert!(!rust.contains("Mutex"), "{rust}");
+        assert_eq!(decisions, ["main.go:6:8: &x does not escape"]);
+    }
+
+    #[test]
+    fn copies_locals_around_calls_that_do_not_retain_their_address() {
+        let (rust, decisions) = lowered(
+            "package main
+
+type Counter struct{ n int }
+
+func (c *Counter) Inc() { c.n++ }
+
+func inc(p *int) { *p += 1 }
+
+func bump(p *int) { inc(p) }
+
+func main() {
+	n := 1
+	inc(&n)
+	bump(&n)
+	var c Counter
+	c.Inc()
+	println(n, c.n)
+}
+",
+        );
+
+        assert!(rust.contains("__gors_unboxed_0"), "{rust}");
+        assert!(rust.contains("__gors_unboxed_2"), "{rust}");
+        assert_eq!(
+            decisions,
+            [
+                "main.go:13:6: &n does not escape",
+                "main.go:14:7: &n does not escape",
+            ]
+        );
+    }
+
+    #[test]
+    fn boxes_locals_whose_address_is_retained_or_taken_in_a_loop() {
+        let (rust, decisions) = lowered(
+            "package main
+
+var saved *int
+
+func keep(p *int) { saved = p }
+
+func inc(p *int) { *p += 1 }
+
+func main() {
+	m := 1
+	keep(&m)
+	n := 1
+	for i := 0; i < 3; i++ {
+		inc(&n)
+	}
+	println(m, n)
+}
+",
+        );
+
+        assert!(!rust.contains("__gors_unboxed"), "{rust}");
+        assert_eq!(
+            decisions,
+            [
+                "main.go:10:2: moved to heap: m (address passed to a call)",
+                "main.go:12:2: moved to heap: n (address passed to a call)",
+            ]
+        );
+    }
+
+    #[test]
+    fn moves_escaping_addresses_to_the_heap() {
+        let (rust, decisions) = lowered(
+            "package main
+
+func keep(p *int) *int { return p }
+
+func leak() *int {
+	v := 3
+	return &v
+}
+
+func main() {
+	n := 1
+	keep(&n)
+	total := 0
+	add := func(x int) { total += x }
+	add(n)
+	p := &total
+	go func() { *p = 1 }()
+	println(n, *leak(), total)
+}
+",
+        );
+
+        assert!(rust.contains("Mutex"), "{rust}");
+        assert_eq!(
+            decisions,
+            [
+                "main.go:6:2: moved to heap: v (address returned)",
+                "main.go:11:2: moved to heap: n (address passed to a call)",
+                "main.go:13:2: moved to heap: total (address stored)",
+            ]
+        );
+    }
+}
@@ -881,6 +881,16 @@ fn set_interface_slice_param_types(sig: &mut syn::Signature, trait_name: &str, m
         for (idx, input) in sig.inputs.iter_mut().skip(1).enumerate() {
             if !method_param_needs_borrowed_slice_for_candidates(&env, trait_name, method_name, idx)
             {
+                // The trait takes the slice by value even when this method
+                // borrows it; the forwarding call lends it the Vec.
+                if let syn::FnArg::Typed(pat_type) = input
+                    && let syn::Type::Reference(reference) = pat_type.ty.as_ref()
+                    && reference.mutability.is_some()
+                    && let syn::Type::Slice(slice) = reference.elem.as_ref()
+                {
+                    let elem = &slice.elem;
+                    *pat_type.ty = syn::parse_quote! { Vec<#elem> };
+                }
                 continue;
             }
             let Some(typeinfer::GoType::Slice(elem)) =
@@ -971,6 +981,8 @@ fn interface_forward_arg_exprs(
                     );
                 if trait_borrows && !target_borrows {
                     syn::parse_quote! { (#ident).to_vec() }
+                } else if target_borrows && !trait_borrows {
+                    syn::parse_quote! { &mut #ident }
                 } else {
                     syn::parse_quote! { #ident }
                 }
@@ -46,7 +46,9 @@ pub(super) fn pointer_type_satisfies(
 ) -> bool {
     super::TYPE_ENV.with(|env| {
         let env = env.borrow();
-        if env.is_interface(trait_name) {
+        if !error_method_matches(&env, struct_name, trait_name) {
+            false
+        } else if env.is_interface(trait_name) {
             let methods = satisfaction_methods(&env, trait_name, required_methods);
             env.named_type_implements_methods(struct_name, &methods, true)
         } else {
@@ -64,7 +66,9 @@ pub(super) fn value_type_satisfies(
 ) -> bool {
     super::TYPE_ENV.with(|env| {
         let env = env.borrow();
-        if env.is_interface(trait_name) {
+        if !error_method_matches(&env, struct_name, trait_name) {
+            false
+        } else if env.is_interface(trait_name) {
             let methods = satisfaction_methods(&env, trait_name, required_methods);
             env.named_type_implements_methods(struct_name, &methods, false)
         } else {
@@ -73,6 +77,23 @@ pub(super) fn value_type_satisfies(
     })
 }
 
+/// Whether a type's `Error` method, if `trait_name` is `error`, is
+/// `Error() string`: an `Error` method with another signature, like
+/// `Error(args ...any)`, does not make a type an error.
+fn error_method_matches(env: &typeinfer::TypeEnv, struct_name: &str, trait_name: &str) -> bool {
+    if trait_name != "error" {
+        return true;
+    }
+    env.named_type_method_func_key(struct_name, "Error")
+        .is_none_or(|key| {
+            env.get_func_params(&key).is_empty()
+                && matches!(
+                    env.get_func_returns(&key).as_slice(),
+                    [typeinfer::GoType::String]
+                )
+        })
+}
+
 fn satisfaction_methods(
     env: &typeinfer::TypeEnv,
     trait_name: &str,
@@ -4,6 +4,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{ast, token};
 
+use super::language_version::{LanguageFeature, LanguageVersion};
 use super::typeinfer::{GoChannelDirection, GoType, TypeEnv, TypeKind};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1619,6 +1620,11 @@ pub enum InvalidRangeReason {
         expected: String,
         actual: String,
     },
+    LanguageVersion {
+        type_name: String,
+        feature: LanguageFeature,
+        lang: LanguageVersion,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1651,6 +1657,10 @@ pub enum InvalidSignature {
         results: usize,
     },
     InvalidTypeParameterDecl,
+    LanguageVersion {
+        feature: LanguageFeature,
+        lang: LanguageVersion,
+    },
     MainFunction {
         type_params: usize,
         params: usize,
@@ -3385,6 +3395,13 @@ fn invalid_type_parameter_list(
     names: &mut BTreeSet<String>,
 ) -> Option<InvalidSignature> {
     let type_params = type_params?;
+    let lang = super::language_version::active();
+    if !type_params.list.is_empty() && !lang.supports(LanguageFeature::TypeParameters) {
+        return Some(InvalidSignature::LanguageVersion {
+            feature: LanguageFeature::TypeParameters,
+            lang,
+        });
+    }
     for field in &type_params.list {
         let Some(field_names) = &field.names else {
             return Some(InvalidSignature::InvalidTypeParameterDecl);
@@ -8960,6 +8977,19 @@ fn invalid_range_clause(range: &ast::RangeStmt<'_>, env: &TypeEnv) -> Option<Inv
             type_name: go_type_display_name(&ty),
         });
     };
+    let lang = super::language_version::active();
+    let feature = match kind {
+        RangeKind::Integer => Some(LanguageFeature::RangeOverInt),
+        RangeKind::Function => Some(LanguageFeature::RangeOverFunc),
+        _ => None,
+    };
+    if let Some(feature) = feature.filter(|feature| !lang.supports(*feature)) {
+        return Some(InvalidRangeReason::LanguageVersion {
+            type_name: go_type_display_name(&ty),
+            feature,
+            lang,
+        });
+    }
     if got > max {
         return Some(InvalidRangeReason::BindingCount { kind, max, got });
     }
@@ -9443,7 +9473,7 @@ fn max_range_binding_count_for_type(ty: &GoType) -> Option<(RangeKind, usize)> {
     }
 }
 
-fn go_type_display_name(ty: &GoType) -> String {
+pub(super) fn go_type_display_name(ty: &GoType) -> String {
     match ty {
         GoType::Bool => "bool".to_string(),
         GoType::Float32 => "float32".to_string(),
@@ -12566,6 +12596,7 @@ fn selector_receiver_method_key(selector: &ast::SelectorExpr<'_>, env: &TypeEnv)
     let receiver_name = selector_base_value_type(selector, env)
         .and_then(|ty| super::method_expressions::method_receiver_name(&ty, env))?;
     env.get_method_func_key(&receiver_name, selector.sel.name)
+        .or_else(|| env.named_type_method_func_key(&receiver_name, selector.sel.name))
 }
 
 fn selector_base_value_type(selector: &ast::SelectorExpr<'_>, env: &TypeEnv) -> Option<GoType> {
@@ -16086,10 +16117,22 @@ pub fn for_clause_per_iteration_capture_names_in_block(
     names
 }
 
+/// Names declared by a `for` clause init that need a fresh binding per iteration.
+///
+/// Before go1.22 every iteration shares one variable, so nothing is rebound;
+/// the names are still shared captures (see
+/// [`for_clause_per_iteration_capture_names_in_block`]).
 pub fn for_clause_per_iteration_capture_names(
     for_stmt: &ast::ForStmt<'_>,
     env: &TypeEnv,
 ) -> BTreeSet<String> {
+    if !super::language_version::active().supports(LanguageFeature::PerIterationLoopVars) {
+        return BTreeSet::new();
+    }
+    for_clause_observed_init_names(for_stmt, env)
+}
+
+fn for_clause_observed_init_names(for_stmt: &ast::ForStmt<'_>, env: &TypeEnv) -> BTreeSet<String> {
     let init_names = for_clause_init_define_names(for_stmt.init.as_deref());
     if init_names.is_empty() {
         return BTreeSet::new();
@@ -16418,7 +16461,7 @@ fn collect_for_clause_per_iteration_capture_names_in_stmt(
 ) {
     match stmt {
         ast::Stmt::ForStmt(for_stmt) => {
-            names.extend(for_clause_per_iteration_capture_names(for_stmt, env));
+            names.extend(for_clause_observed_init_names(for_stmt, env));
             collect_for_clause_per_iteration_capture_names_in_block(&for_stmt.body, env, names);
         }
         ast::Stmt::BlockStmt(block) => {
@@ -21282,6 +21325,13 @@ mod tests {
 
         let names = super::for_clause_per_iteration_capture_names(for_stmt, &env);
         assert!(names.contains("i"));
+
+        let _language_version = crate::compiler::language_version::LanguageVersionGuard::set(
+            crate::compiler::language_version::LanguageVersion::new(1, 21),
+        );
+        assert!(super::for_clause_per_iteration_capture_names(for_stmt, &env).is_empty());
+        let body = func.body.as_ref().expect("body");
+        assert!(super::for_clause_per_iteration_capture_names_in_block(body, &env).contains("i"));
     }
 
     #[test]
@@ -337,6 +337,6 @@ fn is_ambient_trait_name(name: &str) -> bool {
 fn is_runtime_support_trait_name(name: &str) -> bool {
     matches!(
         name,
-        "error" | "GorsReflectOps" | "ProjectedCell" | "ProjectedGuard"
+        "error" | "GorsReflectOps" | "ProjectedCell" | "ProjectedGuard" | "Hasher"
     )
 }
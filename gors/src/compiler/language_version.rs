@@ -0,0 +1,178 @@
+//! Go language version (`-lang`) selected by the `go` directive in go.mod.
+//!
+//! The version gates syntax that gc rejects in older modules and picks the
+//! `for` loop variable semantics (shared before go1.22, per-iteration after).
+
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    static ACTIVE_LANGUAGE_VERSION: RefCell<Option<LanguageVersion>> = const { RefCell::new(None) };
+}
+
+/// A `goMAJOR.MINOR` language version. Patch releases and pre-release
+/// suffixes do not change language semantics and are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LanguageVersion {
+    major: u32,
+    minor: u32,
+}
+
+/// Language features whose availability depends on the language version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageFeature {
+    TypeParameters,
+    PerIterationLoopVars,
+    RangeOverInt,
+    RangeOverFunc,
+}
+
+impl LanguageVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parse `go1.22`, `1.22`, `1.22.3` or `go1.23rc1`.
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim();
+        let version = version.strip_prefix("go").unwrap_or(version);
+        let mut parts = version.splitn(3, '.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts
+            .next()?
+            .split(|ch: char| !ch.is_ascii_digit())
+            .next()?;
+        Some(Self::new(major, minor.parse().ok()?))
+    }
+
+    /// The language version of the pinned Go toolchain, used when no go.mod
+    /// (or no `go` directive) selects one.
+    pub fn toolchain() -> Self {
+        Self::parse(crate::GO_VERSION).unwrap_or(LanguageFeature::RangeOverFunc.since())
+    }
+
+    /// Resolve the version of a package: a file-level `//go:build goX.Y`
+    /// constraint wins over the module's `go` directive.
+    pub fn for_package(file_go_version: &str, module_go_version: Option<&str>) -> Self {
+        Self::parse(file_go_version)
+            .or_else(|| module_go_version.and_then(Self::parse))
+            .unwrap_or_else(Self::toolchain)
+    }
+
+    pub fn supports(self, feature: LanguageFeature) -> bool {
+        self >= feature.since()
+    }
+}
+
+impl fmt::Display for LanguageVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "go{}.{}", self.major, self.minor)
+    }
+}
+
+impl LanguageFeature {
+    pub const fn since(self) -> LanguageVersion {
+        match self {
+            Self::TypeParameters => LanguageVersion::new(1, 18),
+            Self::PerIterationLoopVars | Self::RangeOverInt => LanguageVersion::new(1, 22),
+            Self::RangeOverFunc => LanguageVersion::new(1, 23),
+        }
+    }
+}
+
+/// Format the gc-style tail of a version error, e.g.
+/// `requires go1.18 or later (-lang was set to go1.17; check go.mod)`.
+pub(super) fn requires_message(feature: LanguageFeature, lang: LanguageVersion) -> String {
+    format!(
+        "requires {} or later (-lang was set to {lang}; check go.mod)",
+        feature.since()
+    )
+}
+
+pub(super) struct LanguageVersionGuard {
+    previous: Option<LanguageVersion>,
+}
+
+impl LanguageVersionGuard {
+    pub(super) fn set(current: LanguageVersion) -> Self {
+        let previous = ACTIVE_LANGUAGE_VERSION.with(|version| version.replace(Some(current)));
+        Self { previous }
+    }
+}
+
+impl Drop for LanguageVersionGuard {
+    fn drop(&mut self) {
+        ACTIVE_LANGUAGE_VERSION.with(|version| {
+            *version.borrow_mut() = self.previous;
+        });
+    }
+}
+
+pub(super) fn active() -> LanguageVersion {
+    ACTIVE_LANGUAGE_VERSION
+        .with(|version| *version.borrow())
+        .unwrap_or_else(LanguageVersion::toolchain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_directive_and_build_constraint_forms() {
+        assert_eq!(
+            LanguageVersion::parse("go1.21"),
+            Some(LanguageVersion::new(1, 21))
+        );
+        assert_eq!(
+            LanguageVersion::parse("1.24.3"),
+            Some(LanguageVersion::new(1, 24))
+        );
+        assert_eq!(
+            LanguageVersion::parse("go1.23rc1"),
+            Some(LanguageVersion::new(1, 23))
+        );
+        assert_eq!(LanguageVersion::parse(""), None);
+        assert_eq!(LanguageVersion::parse("go1"), None);
+    }
+
+    #[test]
+    fn file_constraint_overrides_module_directive() {
+        assert_eq!(
+            LanguageVersion::for_package("go1.21", Some("1.22")),
+            LanguageVersion::new(1, 21)
+        );
+        assert_eq!(
+            LanguageVersion::for_package("", Some("1.20")),
+            LanguageVersion::new(1, 20)
+        );
+        assert_eq!(
+            LanguageVersion::for_package("", None),
+            LanguageVersion::toolchain()
+        );
+    }
+
+    #[test]
+    fn guard_restores_previous_version() {
+        assert_eq!(active(), LanguageVersion::toolchain());
+        {
+            let _outer = LanguageVersionGuard::set(LanguageVersion::new(1, 21));
+            assert!(!active().supports(LanguageFeature::PerIterationLoopVars));
+            assert!(active().supports(LanguageFeature::TypeParameters));
+            {
+                let _inner = LanguageVersionGuard::set(LanguageVersion::new(1, 17));
+                assert!(!active().supports(LanguageFeature::TypeParameters));
+            }
+            assert_eq!(active(), LanguageVersion::new(1, 21));
+        }
+        assert_eq!(active(), LanguageVersion::toolchain());
+    }
+
+    #[test]
+    fn requires_message_matches_gc() {
+        assert_eq!(
+            requires_message(LanguageFeature::RangeOverInt, LanguageVersion::new(1, 21)),
+            "requires go1.22 or later (-lang was set to go1.21; check go.mod)"
+        );
+    }
+}
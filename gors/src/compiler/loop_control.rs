@@ -36,6 +36,14 @@ pub(super) fn range_for_loop(
     )]
 }
 
+/// Start each iteration with a scheduler preemption point, so a goroutine
+/// spinning in a loop lets the others run.
+pub(super) fn with_yield_point(mut body: syn::Block) -> syn::Block {
+    body.stmts
+        .insert(0, syn::parse_quote! { crate::builtin::yield_point(); });
+    body
+}
+
 pub(super) fn with_iteration_tail(
     mut body: syn::Block,
     loop_label_name: Option<&str>,
@@ -20,14 +20,18 @@ mod borrowed_views;
 mod builtin_pruning;
 mod builtin_roots;
 mod call_arg_rewrites;
+mod comparable_hashing;
 mod const_context;
 mod current_receiver;
+mod cycle_collection;
 mod dce_iteration;
 mod dce_pruning;
 mod dce_reachability;
 mod defer_context;
 mod display_impls;
+mod embed_directives;
 mod embedded_interfaces;
+pub mod escape_analysis;
 mod external_interface_implementors;
 mod external_roots;
 mod generated_attrs;
@@ -41,6 +45,7 @@ mod interface_method_sets;
 mod interface_type_env;
 pub mod ir;
 mod item_reachability;
+pub mod language_version;
 mod loop_control;
 pub mod manifest;
 mod method_expressions;
@@ -50,6 +55,7 @@ mod package_context;
 pub(crate) mod passes;
 mod phantom_type_params;
 mod predeclared;
+mod race_detection;
 mod reachability_cache;
 mod reachability_context;
 mod reachability_names;
@@ -68,7 +74,9 @@ mod selector_semantics;
 mod semantic;
 mod semantic_reachability;
 mod shared_captures;
+mod single_threaded;
 mod source_map_context;
+mod stack_checks;
 mod stdlib_modules;
 mod struct_derives;
 mod syn_inspect;
@@ -104,6 +112,7 @@ use current_receiver::{CurrentReceiver, CurrentReceiverGuard};
 use dce_iteration::DceIterationContext;
 use dce_reachability::reachable_stdlib_items;
 use defer_context::PanicReturnsThroughDeferGuard;
+use embed_directives::EmbeddedVarsGuard;
 use external_interface_implementors::ExternalInterfaceImplementorsGuard;
 use external_roots::ExternalRootCollector;
 use go_strings::{
@@ -119,6 +128,7 @@ use import_context::{
 };
 #[cfg(test)]
 use item_reachability::reachable_item_for_names;
+use language_version::{LanguageVersion, LanguageVersionGuard};
 use package_context::{CurrentGoPackageNameGuard, MainPackageVarModeGuard};
 use phantom_type_params::add_fields_for_unused_type_params;
 use proc_macro2::Span;
@@ -147,9 +157,10 @@ use std::fmt;
 use syn::Token;
 use syn_inspect::{
     arc_mutex_new_inner_expr, box_dyn_any_cast_source_expr, call_expr_path_last_ident,
-    dedupe_syn_types, expr_path_ident, fn_arg_ident, impl_trait_targets_match, is_box_dyn_any_expr,
-    is_box_leak_expr, is_box_new_call, is_box_type_with_any_bound, is_clone_call_expr,
-    is_path_call_expr, is_self_or_ref_self_expr, pat_ident_name, receiver_expr_needs_scoped_temp,
+    dedupe_syn_types, expr_path_ident, fn_arg_ident, gors_any_new_arg, impl_trait_targets_match,
+    is_box_dyn_any_expr, is_box_leak_expr, is_box_new_call, is_box_type_with_any_bound,
+    is_clone_call_expr, is_gors_any_nil_expr, is_gors_any_type, is_path_call_expr,
+    is_self_or_ref_self_expr, pat_ident_name, receiver_expr_needs_scoped_temp,
     syn_expr_matches_target, type_param_bound_matches, vec_type_inner,
 };
 use type_decl_facts::EmbeddedInterfaceField;
@@ -654,7 +665,7 @@ fn rust_safe_ident_name(name: &str) -> String {
     }
 }
 
-fn is_rust_keyword(name: &str) -> bool {
+pub(crate) fn is_rust_keyword(name: &str) -> bool {
     matches!(
         name,
         "as" | "break"
@@ -1121,7 +1132,7 @@ fn generic_slice_param_go_type(
 fn generic_map_param_type(expr: &ast::Expr, info: &TypeParamInfo) -> Option<syn::Type> {
     match expr {
         ast::Expr::Ident(ident) => info.map_aliases.get(ident.name).map(|(key, value)| {
-            syn::parse_quote! { std::collections::HashMap<#key, #value> }
+            syn::parse_quote! { crate::builtin::GorsMap<#key, #value> }
         }),
         _ => None,
     }
@@ -4224,160 +4235,111 @@ fn compile_select_stmt(select_stmt: ast::SelectStmt) -> Result<Vec<syn::Stmt>, C
             return Ok(vec![select_labeled_block(select_label, stmts)]);
         }
         let stmt: syn::Stmt = syn::parse_quote! {
-            loop {
-                std::thread::park();
-            }
+            crate::builtin::block_forever();
         };
         return Ok(vec![select_labeled_block(select_label, vec![stmt])]);
     }
 
-    // Single case with optional default
-    if cases.len() == 1 {
+    // Single blocking case: just the channel operation
+    if cases.len() == 1 && default_body.is_none() {
         let case = cases.remove(0);
         let Some(comm) = case.comm.map(|c| *c) else {
             return Err(CompilerError::UnsupportedConstruct(
                 "select case without comm".into(),
             ));
         };
-
-        let case_body_stmts = compile_select_case_body(case.body, &select_label)?;
-
-        if let Some(default) = default_body.take() {
-            // Non-blocking: try_recv/try_send with fallback
-            let default_stmts = compile_select_case_body(default, &select_label)?;
-
-            match comm {
-                ast::Stmt::ExprStmt(expr_stmt) => {
-                    // <-ch in expression position
-                    if let Some(ch) = extract_channel_recv(expr_stmt.x) {
-                        let stmt: syn::Stmt = syn::parse_quote! {
-                            {
-                                if let Some((_, _)) = (#ch).try_recv_with_ok() {
-                                    #(#case_body_stmts)*
-                                } else {
-                                    #(#default_stmts)*
-                                }
-                            }
-                        };
-                        return Ok(vec![select_labeled_block(select_label, vec![stmt])]);
-                    }
-                }
-                ast::Stmt::AssignStmt(assign) => {
-                    let (value_ident, ok_ident) = synthetic_names::next_select_recv_idents();
-                    if let Some((ch, binding_stmts)) =
-                        select_receive_assignment_parts(assign, &value_ident, &ok_ident)?
-                    {
-                        let stmt: syn::Stmt = syn::parse_quote! {
-                            {
-                                if let Some((#value_ident, #ok_ident)) = (#ch).try_recv_with_ok() {
-                                    #(#binding_stmts)*
-                                    #(#case_body_stmts)*
-                                } else {
-                                    #(#default_stmts)*
-                                }
-                            }
-                        };
-                        return Ok(vec![select_labeled_block(select_label, vec![stmt])]);
-                    }
-                }
-                ast::Stmt::SendStmt(send) => {
-                    let ch: syn::Expr = send.chan.into();
-                    let val: syn::Expr = send.value.into();
-                    let stmt: syn::Stmt = syn::parse_quote! {
-                        {
-                            if #ch.try_send(#val).is_ok() {
-                                #(#case_body_stmts)*
-                            } else {
-                                #(#default_stmts)*
-                            }
-                        }
-                    };
-                    return Ok(vec![select_labeled_block(select_label, vec![stmt])]);
-                }
-                _ => {}
-            }
-        } else {
-            // Blocking single case
-            let comm_stmts: Vec<syn::Stmt> = Vec::<syn::Stmt>::try_from(comm)?;
-            let mut all_stmts = comm_stmts;
-            all_stmts.extend(case_body_stmts);
-            return Ok(vec![select_labeled_block(select_label, all_stmts)]);
-        }
+        let mut all_stmts: Vec<syn::Stmt> = Vec::<syn::Stmt>::try_from(comm)?;
+        all_stmts.extend(compile_select_case_body(case.body, &select_label)?);
+        return Ok(vec![select_labeled_block(select_label, all_stmts)]);
     }
 
-    // Multiple cases: generate loop with try_recv checks
+    // Otherwise: the runtime picks a ready case, or blocks until one is
+    let chosen_ident = synthetic_names::select_chosen_ident();
+    let mut case_decls: Vec<syn::Stmt> = Vec::new();
+    let mut case_idents: Vec<syn::Ident> = Vec::new();
     let mut arms: Vec<proc_macro2::TokenStream> = Vec::new();
-    for case in cases {
+    for (index, case) in cases.into_iter().enumerate() {
         let Some(comm) = case.comm.map(|c| *c) else {
-            continue;
+            return Err(CompilerError::UnsupportedConstruct(
+                "select case without comm".into(),
+            ));
         };
+        let case_ident = synthetic_names::select_case_ident(index);
+        let (case_decl, binding_stmts) = select_case_parts(comm, &case_ident)?;
         let body_stmts = non_tail_stmt_list(compile_select_case_body(case.body, &select_label)?);
-
-        match comm {
-            ast::Stmt::ExprStmt(expr_stmt) => {
-                if let Some(ch) = extract_channel_recv(expr_stmt.x) {
-                    arms.push(quote::quote! {
-                        if let Some((_, _)) = (#ch).try_recv_with_ok() {
-                            #(#body_stmts)*
-                            break;
-                        }
-                    });
-                    continue;
-                }
-            }
-            ast::Stmt::AssignStmt(assign) => {
-                let (value_ident, ok_ident) = synthetic_names::next_select_recv_idents();
-                if let Some((ch, binding_stmts)) =
-                    select_receive_assignment_parts(assign, &value_ident, &ok_ident)?
-                {
-                    arms.push(quote::quote! {
-                        if let Some((#value_ident, #ok_ident)) = (#ch).try_recv_with_ok() {
-                            #(#binding_stmts)*
-                            #(#body_stmts)*
-                            break;
-                        }
-                    });
-                    continue;
-                }
-            }
-            ast::Stmt::SendStmt(send) => {
-                let ch: syn::Expr = send.chan.into();
-                let val: syn::Expr = send.value.into();
-                arms.push(quote::quote! {
-                    if #ch.try_send(#val).is_ok() {
-                        #(#body_stmts)*
-                        break;
-                    }
-                });
-                continue;
-            }
-            _ => {}
-        }
+        case_decls.push(case_decl);
+        case_idents.push(case_ident);
+        arms.push(quote::quote! {
+            if #chosen_ident == #index {
+                #(#binding_stmts)*
+                #(#body_stmts)*
+            } else
+        });
     }
 
-    let default_arm = if let Some(body) = default_body {
-        let stmts = non_tail_stmt_list(compile_select_case_body(body, &select_label)?);
-        quote::quote! {
-            #(#stmts)*
-            break;
-        }
-    } else {
-        quote::quote! {
-            std::thread::yield_now();
-        }
+    let has_default = default_body.is_some();
+    let default_stmts = match default_body {
+        Some(body) => non_tail_stmt_list(compile_select_case_body(body, &select_label)?),
+        None => Vec::new(),
     };
 
     let stmt = parse_select_stmt(quote::quote! {
         {
-            loop {
-                #(#arms)*
-                #default_arm
+            #(#case_decls)*
+            let #chosen_ident = crate::builtin::select(&mut [#(&mut #case_idents),*], #has_default);
+            #(#arms)* {
+                #(#default_stmts)*
             }
         }
     })?;
     Ok(vec![select_labeled_block(select_label, vec![stmt])])
 }
 
+/// The runtime case of a select communication clause, and the statements
+/// binding its received value, if any.
+fn select_case_parts(
+    comm: ast::Stmt,
+    case_ident: &syn::Ident,
+) -> Result<(syn::Stmt, Vec<syn::Stmt>), CompilerError> {
+    match comm {
+        ast::Stmt::ExprStmt(expr_stmt) => {
+            if let Some(ch) = extract_channel_recv(expr_stmt.x) {
+                let case_decl = syn::parse_quote! {
+                    let mut #case_ident = crate::builtin::SelectRecv::new(&(#ch));
+                };
+                return Ok((case_decl, Vec::new()));
+            }
+        }
+        ast::Stmt::AssignStmt(assign) => {
+            let (value_ident, ok_ident) = synthetic_names::next_select_recv_idents();
+            if let Some((ch, binding_stmts)) =
+                select_receive_assignment_parts(assign, &value_ident, &ok_ident)?
+            {
+                let case_decl = syn::parse_quote! {
+                    let mut #case_ident = crate::builtin::SelectRecv::new(&(#ch));
+                };
+                let mut stmts: Vec<syn::Stmt> = vec![syn::parse_quote! {
+                    let (#value_ident, #ok_ident) = #case_ident.received();
+                }];
+                stmts.extend(binding_stmts);
+                return Ok((case_decl, stmts));
+            }
+        }
+        ast::Stmt::SendStmt(send) => {
+            let ch: syn::Expr = send.chan.into();
+            let val: syn::Expr = send.value.into();
+            let case_decl = syn::parse_quote! {
+                let mut #case_ident = crate::builtin::SelectSend::new(&(#ch), #val);
+            };
+            return Ok((case_decl, Vec::new()));
+        }
+        _ => {}
+    }
+    Err(CompilerError::UnsupportedConstruct(
+        "select case must be a send or receive operation".into(),
+    ))
+}
+
 fn extract_channel_recv(expr: ast::Expr) -> Option<syn::Expr> {
     if let ast::Expr::UnaryExpr(unary) = expr
         && unary.op == token::Token::ARROW
@@ -4760,14 +4722,16 @@ fn reset_lowering_thread_state() {
     borrowed_views::clear_view_methods();
 }
 
-pub fn compile(file: ast::File) -> Result<syn::File, CompilerError> {
+pub fn compile(mut file: ast::File) -> Result<syn::File, CompilerError> {
     reset_lowering_thread_state();
+    escape_analysis::lower_non_escaping_addresses(&mut file);
     // Pre-scan the AST to build a type environment
     let mut type_env = typeinfer::TypeEnv::new();
     type_env.scan_file(&file);
     let import_package_names = file_import_package_names(&file);
     semantic::validate_file(&file, &type_env, &import_package_names)?;
     semantic::validate_unused_imports(&file, &import_package_names)?;
+    let _embedded_vars = EmbeddedVarsGuard::set(embed_directives::embedded_vars_in_file(&file)?);
     let _semantic_file = semantic::FileFacts::lower(&file, &type_env);
     set_import_package_names(import_package_names);
     set_dot_import_renames(BTreeMap::new());
@@ -4898,6 +4862,12 @@ pub struct CompiledModule {
 pub struct CompiledProgram {
     pub modules: BTreeMap<String, CompiledModule>,
     pub has_main: bool,
+    /// Escape analysis decisions for the local packages, in source order.
+    pub escapes: Vec<escape_analysis::EscapeDecision>,
+    /// The source mappings of the local packages, by import path, when
+    /// source maps are tracked. Those of the main package stay with the
+    /// tracker of the compiling thread.
+    pub package_source_maps: BTreeMap<String, crate::mapping::SourceMapTracker>,
 }
 
 #[derive(Clone)]
@@ -4976,12 +4946,45 @@ fn refresh_local_top_level_var_types(
 
 struct CompileSession {
     source_map_config: Option<Vec<(String, String)>>,
+    track_source_maps: bool,
+    package_source_maps: BTreeMap<String, crate::mapping::SourceMapTracker>,
 }
 
 impl CompileSession {
     fn new(source_map_config: Option<Vec<(String, String)>>) -> Self {
         reset_lowering_thread_state();
-        Self { source_map_config }
+        if source_map_config.is_none() {
+            source_map_context::clear();
+        }
+        Self {
+            track_source_maps: source_map_config.is_some(),
+            source_map_config,
+            package_source_maps: BTreeMap::new(),
+        }
+    }
+
+    fn start_package_source_map_tracking(
+        &self,
+        pkg: &crate::parser::ParsedPackage,
+        rust_file: &str,
+    ) {
+        if !self.track_source_maps {
+            return;
+        }
+        source_map_context::start_many(
+            pkg.files
+                .iter()
+                .map(|(file, source)| (file.clone(), Some(source.clone())))
+                .collect(),
+            rust_file,
+        );
+    }
+
+    fn finish_package_source_map_tracking(&mut self, import_path: &str) {
+        if self.track_source_maps {
+            self.package_source_maps
+                .insert(import_path.to_string(), source_map_context::take());
+        }
     }
 
     fn start_main_source_map_tracking(&mut self) {
@@ -5335,7 +5338,7 @@ pub fn compute_content_hash(files: &[(String, String)]) -> String {
 pub fn compile_program_multi(
     program: crate::parser::ParsedProgram,
 ) -> Result<CompiledProgram, CompilerError> {
-    compile_program_impl(program, None)
+    compile_program_impl(program, None, false, true)
 }
 
 /// Like [`compile_program_multi`] but also starts source map tracking for the
@@ -5349,6 +5352,8 @@ pub fn compile_program_multi_with_source_map(
     compile_program_impl(
         program,
         Some(vec![(go_file.to_string(), go_source.to_string())]),
+        false,
+        true,
     )
 }
 
@@ -5358,13 +5363,38 @@ pub fn compile_program_multi_with_source_maps(
     program: crate::parser::ParsedProgram,
 ) -> Result<CompiledProgram, CompilerError> {
     let sources = program.main_package.files.clone();
-    compile_program_impl(program, Some(sources))
+    compile_program_impl(program, Some(sources), false, true)
 }
 
-fn compile_program_impl(
+/// Like [`compile_program_multi_with_source_maps`] but keeps the thread-safe
+/// runtime types in programs that start no goroutines, to compare the
+/// single-threaded lowering with it.
+pub fn compile_program_multi_thread_safe(
+    program: crate::parser::ParsedProgram,
+) -> Result<CompiledProgram, CompilerError> {
+    let sources = program.main_package.files.clone();
+    compile_program_impl(program, Some(sources), false, false)
+}
+
+/// Like [`compile_program_multi_with_source_maps`] but also instruments the
+/// program for the race detector, like `go build -race`.
+pub fn compile_program_multi_with_race_detection(
     program: crate::parser::ParsedProgram,
+) -> Result<CompiledProgram, CompilerError> {
+    let sources = program.main_package.files.clone();
+    compile_program_impl(program, Some(sources), true, false)
+}
+
+fn compile_program_impl(
+    mut program: crate::parser::ParsedProgram,
     source_map_config: Option<Vec<(String, String)>>,
+    race_detection: bool,
+    single_threaded: bool,
 ) -> Result<CompiledProgram, CompilerError> {
+    let mut escapes = std::iter::once(&mut program.main_package)
+        .chain(&mut program.imports)
+        .flat_map(|pkg| escape_analysis::lower_non_escaping_addresses(&mut pkg.ast))
+        .collect::<Vec<_>>();
     let mut session = CompileSession::new(source_map_config);
     let graph = PackageGraph::from_program(&program);
     let mut modules = BTreeMap::new();
@@ -5379,6 +5409,10 @@ fn compile_program_impl(
 
     let local_compile_timer = ProfileTimer::start("compiler.local_compile");
     for pkg in program.imports {
+        let _language_version = LanguageVersionGuard::set(LanguageVersion::for_package(
+            pkg.ast.go_version,
+            pkg.go_version.as_deref(),
+        ));
         let mut type_env = graph
             .local_type_envs
             .get(&pkg.import_path)
@@ -5400,6 +5434,9 @@ fn compile_program_impl(
         );
         semantic::validate_file(&pkg.ast, &type_env, &graph.import_package_names)?;
         semantic::validate_unused_imports(&pkg.ast, &graph.import_package_names)?;
+        escapes.extend(escape_analysis::heap_escapes_in_file(&pkg.ast, &type_env));
+        let _embedded_vars =
+            EmbeddedVarsGuard::set(embed_directives::embedded_vars_in_file(&pkg.ast)?);
         let lowering = LoweringContext {
             semantic_file: semantic::FileFacts::lower(&pkg.ast, &type_env),
             type_env,
@@ -5422,7 +5459,9 @@ fn compile_program_impl(
             .unwrap_or_else(|| pkg.name.clone());
         let plan = ModulePlan::local(&pkg, mod_name);
         let module_key = pkg.import_path.clone();
+        session.start_package_source_map_tracking(&pkg, &plan.filename);
         let mut pkg_file = TryInto::<syn::File>::try_into(pkg.ast)?;
+        session.finish_package_source_map_tracking(&module_key);
         rewrite_import_module_paths(&mut pkg_file, &lowering.import_rewrites);
         passes::pass_for_imported_package(&mut pkg_file);
         rewrite_import_module_paths(&mut pkg_file, &lowering.import_rewrites);
@@ -5444,6 +5483,10 @@ fn compile_program_impl(
         return Err(invalid_signature_error(invalid));
     }
 
+    let main_language_version = LanguageVersionGuard::set(LanguageVersion::for_package(
+        program.main_package.ast.go_version,
+        program.main_package.go_version.as_deref(),
+    ));
     let mut main_type_env = typeinfer::TypeEnv::new();
     main_type_env.scan_file(&program.main_package.ast);
     merge_import_type_envs(
@@ -5469,6 +5512,13 @@ fn compile_program_impl(
         &graph.import_package_names,
     )?;
     semantic::validate_unused_imports(&program.main_package.ast, &graph.import_package_names)?;
+    escapes.extend(escape_analysis::heap_escapes_in_file(
+        &program.main_package.ast,
+        &main_type_env,
+    ));
+    let main_embedded_vars = EmbeddedVarsGuard::set(embed_directives::embedded_vars_in_file(
+        &program.main_package.ast,
+    )?);
     let main_lowering = LoweringContext {
         semantic_file: semantic::FileFacts::lower(&program.main_package.ast, &main_type_env),
         type_env: main_type_env,
@@ -5500,6 +5550,11 @@ fn compile_program_impl(
     rewrite_import_module_paths(&mut main_file, &main_lowering.import_rewrites);
 
     modules.insert("__main__".to_string(), main_plan.into_module(main_file));
+    // The standard library compiles with mappings of its own sources, which
+    // must not end up in those of the main package.
+    let main_source_map = source_map_context::take();
+    drop(main_embedded_vars);
+    drop(main_language_version);
     drop(local_compile_timer);
 
     let stdlib_timer = ProfileTimer::start("compiler.stdlib_resolution");
@@ -5512,10 +5567,26 @@ fn compile_program_impl(
     drop(stdlib_timer);
 
     prepend_stdlib_package_init_calls(&mut modules, &graph.stdlib_imports);
+    stack_checks::add_stack_checks(&mut modules);
+    let line_tables = main_source_map.is_active();
+    source_map_context::restore(main_source_map);
+    if let Some(main_module) = modules.get_mut("__main__") {
+        run_main_as_goroutine(&mut main_module.file, line_tables);
+    }
+
+    if race_detection {
+        race_detection::instrument_local_packages(&mut modules);
+    }
+    cycle_collection::add_cycle_collection(&mut modules);
+    comparable_hashing::add_comparable_hashing(&mut modules);
+    let local_runtime_items = single_threaded::local_runtime_items(&modules);
 
     let dce_timer = ProfileTimer::start("compiler.dce");
     prune_generated_dead_code(&mut modules, has_main_fn);
     inject_post_prune_stdlib_helpers(&mut modules, &graph.stdlib_imports);
+    if race_detection {
+        race_detection::instrument_sync_packages(&mut modules);
+    }
     prune_generated_dead_code(&mut modules, has_main_fn);
     borrow_mutated_vec_params(&mut modules);
     restore_vec_newtype_method_receivers(&mut modules);
@@ -5524,12 +5595,20 @@ fn compile_program_impl(
     clone_vec_value_call_args(&mut modules);
     add_fields_for_unused_type_params(&mut modules);
     drop(dce_timer);
+    // Programs built with `-race` keep their shared cells, whose accesses
+    // are instrumented.
+    if single_threaded {
+        single_threaded::lower_single_threaded_program(&mut modules, local_runtime_items);
+    }
 
     prefix_final_module_paths(&mut modules);
+    escapes.sort();
 
     Ok(CompiledProgram {
         modules,
         has_main: has_main_fn,
+        escapes,
+        package_source_maps: session.package_source_maps,
     })
 }
 
@@ -5592,6 +5671,34 @@ fn prepend_stdlib_package_init_calls(
     prepend_init_calls_to_file(&mut main_module.file, &module_names);
 }
 
+/// Run the body of `main`, package initialization included, under the
+/// runtime, which crashes the program like Go on an unrecovered panic or a
+/// stack overflow. With `line_tables`, the runtime symbolizes stacks with
+/// the line tables the printer appends to the generated files.
+fn run_main_as_goroutine(file: &mut syn::File, line_tables: bool) {
+    for item in &mut file.items {
+        let syn::Item::Fn(func) = item else {
+            continue;
+        };
+        if func.sig.ident != "main" {
+            continue;
+        }
+        let stmts = std::mem::take(&mut func.block.stmts);
+        if line_tables {
+            let line_tables = crate::generated_names::line_tables_ident();
+            func.block.stmts.push(syn::parse_quote! {
+                crate::builtin::set_line_tables(&#line_tables);
+            });
+        }
+        func.block.stmts.push(syn::parse_quote! {
+            crate::builtin::run_main(|| {
+                #(#stmts)*
+            });
+        });
+        break;
+    }
+}
+
 fn module_has_nonempty_init(module: &CompiledModule) -> bool {
     module.file.items.iter().any(|item| {
         matches!(
@@ -6090,11 +6197,12 @@ pub(crate) fn add_post_merge_interface_helpers(file: &mut syn::File) {
 /// let rust_ast = compiler::compile_with_source_map(go_ast, "example.go", go_source).unwrap();
 /// ```
 pub fn compile_with_source_map(
-    file: ast::File,
+    mut file: ast::File,
     go_file: &str,
     go_source: &str,
 ) -> Result<syn::File, CompilerError> {
     source_map_context::start(go_file, "output.rs", Some(go_source));
+    escape_analysis::lower_non_escaping_addresses(&mut file);
 
     synthetic_names::reset_lowering_counters();
     goto_context::clear_state_contexts();
@@ -6104,6 +6212,7 @@ pub fn compile_with_source_map(
     let import_package_names = file_import_package_names(&file);
     semantic::validate_file(&file, &type_env, &import_package_names)?;
     semantic::validate_unused_imports(&file, &import_package_names)?;
+    let _embedded_vars = EmbeddedVarsGuard::set(embed_directives::embedded_vars_in_file(&file)?);
     let _semantic_file = semantic::FileFacts::lower(&file, &type_env);
     set_import_package_names(import_package_names);
     set_dot_import_renames(BTreeMap::new());
@@ -6506,7 +6615,7 @@ fn anonymous_struct_type(struct_type: ast::StructType) -> syn::Type {
     {
         syn::parse_quote! { () }
     } else {
-        syn::parse_quote! { Box<dyn std::any::Any> }
+        syn::parse_quote! { crate::builtin::GorsAny }
     }
 }
 
@@ -6563,7 +6672,7 @@ fn type_from_expr_ref(expr: &ast::Expr) -> syn::Type {
         ast::Expr::ParenExpr(paren) => type_from_expr_ref(&paren.x),
         ast::Expr::FuncType(func_type) => shared_func_type_from_ast(func_type),
         ast::Expr::Ident(ident) if ident.name == "any" => {
-            syn::parse_quote! { Box<dyn std::any::Any> }
+            syn::parse_quote! { crate::builtin::GorsAny }
         }
         ast::Expr::Ident(ident) if ident.name == "complex64" => {
             syn::parse_quote! { crate::builtin::Complex64 }
@@ -6623,7 +6732,7 @@ fn type_from_expr_ref(expr: &ast::Expr) -> syn::Type {
                 let inner = container_value_type_from_expr(elt);
                 syn::parse_quote! { Vec<#inner> }
             } else {
-                syn::parse_quote! { Vec<Box<dyn std::any::Any>> }
+                syn::parse_quote! { Vec<crate::builtin::GorsAny> }
             }
         }
         ast::Expr::SelectorExpr(selector_expr) => {
@@ -6648,14 +6757,14 @@ fn type_from_expr_ref(expr: &ast::Expr) -> syn::Type {
             type_with_generic_args(base, args)
         }
         ast::Expr::InterfaceType(_) => {
-            syn::parse_quote! { Box<dyn std::any::Any> }
+            syn::parse_quote! { crate::builtin::GorsAny }
         }
         ast::Expr::MapType(map_type) => {
             let key = rust_map_key_type_from_type_expr(&map_type.key);
             let key_go_type = typeinfer::GoType::from_expr(&map_type.key);
             let value =
                 rust_map_storage_value_type(&key_go_type, type_from_expr_ref(&map_type.value));
-            syn::parse_quote! { std::collections::HashMap<#key, #value> }
+            syn::parse_quote! { crate::builtin::GorsMap<#key, #value> }
         }
         ast::Expr::StructType(struct_type) => {
             if struct_type
@@ -6665,10 +6774,10 @@ fn type_from_expr_ref(expr: &ast::Expr) -> syn::Type {
             {
                 syn::parse_quote! { () }
             } else {
-                syn::parse_quote! { Box<dyn std::any::Any> }
+                syn::parse_quote! { crate::builtin::GorsAny }
             }
         }
-        _ => syn::parse_quote! { Box<dyn std::any::Any> },
+        _ => syn::parse_quote! { crate::builtin::GorsAny },
     }
 }
 
@@ -7434,11 +7543,14 @@ fn compile_type_spec(ts: ast::TypeSpec) -> Result<Vec<syn::Item>, CompilerError>
                         if let Some(name) = embedded_name {
                             let field_ident =
                                 syn::Ident::new(&rust_safe_ident_name(&name), Span::mixed_site());
+                            // Other packages reach the exported methods
+                            // promoted from an unexported embedded type
+                            // through its field.
                             let field_vis: syn::Visibility =
                                 if name.chars().next().is_some_and(|c| c.is_uppercase()) {
                                     syn::parse_quote! { pub }
                                 } else {
-                                    syn::Visibility::Inherited
+                                    syn::parse_quote! { pub(crate) }
                                 };
                             if let Some(trait_path) = &borrowed_interface_trait_path {
                                 embedded_interface_fields.push(EmbeddedInterfaceField {
@@ -7847,6 +7959,7 @@ fn compile_type_spec(ts: ast::TypeSpec) -> Result<Vec<syn::Item>, CompilerError>
             let slice_elem_type = slice_elem_type_from_expr(&other);
             let underlying_go_type = typeinfer::GoType::from_expr(&other);
             let is_slice_alias = matches!(underlying_go_type, typeinfer::GoType::Slice(_));
+            let is_map_alias = matches!(underlying_go_type, typeinfer::GoType::Map(_, _));
             let is_func_alias = matches!(underlying_go_type, typeinfer::GoType::Func { .. });
             let is_copy_alias = !is_func_alias && go_type_is_copy(&underlying_go_type);
             let is_numeric_alias = resolved_go_type(&underlying_go_type).is_numeric();
@@ -8022,6 +8135,13 @@ fn compile_type_spec(ts: ast::TypeSpec) -> Result<Vec<syn::Item>, CompilerError>
                     }
                 });
             }
+            if is_map_alias {
+                items.push(syn::parse_quote! {
+                    impl #impl_generics crate::builtin::Len for #ident #ty_generics #where_clause {
+                        fn len_value(&self) -> usize { self.0.len() }
+                    }
+                });
+            }
             if is_byte_slice {
                 items.push(syn::parse_quote! {
                     impl crate::builtin::Len for #ident {
@@ -8415,7 +8535,7 @@ fn top_level_var_expr_and_type_from_ref(
 
 fn interface_alias_target_type_from_expr(expr: &ast::Expr) -> Option<syn::Type> {
     match typeinfer::GoType::from_expr(expr) {
-        typeinfer::GoType::Any => Some(syn::parse_quote! { Box<dyn std::any::Any> }),
+        typeinfer::GoType::Any => Some(syn::parse_quote! { crate::builtin::GorsAny }),
         typeinfer::GoType::Error => Some(syn::parse_quote! { Box<dyn crate::builtin::error> }),
         _ => boxed_interface_value_type_from_expr(expr),
     }
@@ -9888,7 +10008,7 @@ fn compile_general_type_conversion(call_expr: ast::CallExpr) -> syn::Expr {
 
     if matches!(&target_fun, ast::Expr::Ident(id) if id.name == "any") {
         let arg: syn::Expr = raw_arg.into();
-        return syn::parse_quote! { Box::new(#arg) as Box<dyn std::any::Any> };
+        return syn::parse_quote! { crate::builtin::GorsAny::new(#arg) };
     }
 
     let target_ty = type_from_expr_ref(&target_fun);
@@ -10079,7 +10199,7 @@ fn compile_type_conversion(call_expr: ast::CallExpr, kind: &str) -> syn::Expr {
         }
         "complex64" => syn::parse_quote! { crate::builtin::to_complex64(#arg) },
         "complex128" => syn::parse_quote! { crate::builtin::to_complex128(#arg) },
-        "any" => syn::parse_quote! { Box::new(#arg) as Box<dyn std::any::Any> },
+        "any" => syn::parse_quote! { crate::builtin::GorsAny::new(#arg) },
         "[]byte" => syn::parse_quote! { (#arg).as_bytes().to_vec() },
         "[]rune" => syn::parse_quote! { (#arg).chars().map(|ch| ch as i32).collect::<Vec<i32>>() },
         _ => compile_error_expr(format!("unsupported type conversion: {kind}")),
@@ -10632,7 +10752,7 @@ fn rust_type_from_inferred_go_type(go_type: &typeinfer::GoType) -> syn::Type {
             let key = rust_map_key_type_from_go_type(&key_go_type);
             let value =
                 rust_map_storage_value_type(&key_go_type, rust_type_from_inferred_go_type(&value));
-            syn::parse_quote! { std::collections::HashMap<#key, #value> }
+            syn::parse_quote! { crate::builtin::GorsMap<#key, #value> }
         }
         typeinfer::GoType::Pointer(inner) => {
             let inner = rust_type_from_inferred_go_type(&inner);
@@ -10652,11 +10772,11 @@ fn rust_type_from_inferred_go_type(go_type: &typeinfer::GoType) -> syn::Type {
             type_with_generic_args(base, args)
         }
         typeinfer::GoType::Any | typeinfer::GoType::Interface(_) => {
-            syn::parse_quote! { Box<dyn std::any::Any> }
+            syn::parse_quote! { crate::builtin::GorsAny }
         }
         typeinfer::GoType::Error => syn::parse_quote! { Box<dyn crate::builtin::error> },
-        typeinfer::GoType::Unknown => syn::parse_quote! { Box<dyn std::any::Any> },
-        _ => syn::parse_quote! { Box<dyn std::any::Any> },
+        typeinfer::GoType::Unknown => syn::parse_quote! { crate::builtin::GorsAny },
+        _ => syn::parse_quote! { crate::builtin::GorsAny },
     }
 }
 
@@ -10686,7 +10806,7 @@ fn rust_type_preserving_named_go_type(go_type: &typeinfer::GoType) -> syn::Type
             let key = rust_map_key_type_from_go_type(key_go_type);
             let value =
                 rust_map_storage_value_type(key_go_type, rust_type_preserving_named_go_type(value));
-            syn::parse_quote! { std::collections::HashMap<#key, #value> }
+            syn::parse_quote! { crate::builtin::GorsMap<#key, #value> }
         }
         _ => rust_type_from_inferred_go_type(go_type),
     }
@@ -10815,19 +10935,24 @@ fn call_signature_param_types(call_expr: &ast::CallExpr) -> Vec<typeinfer::GoTyp
     }
 }
 
-enum VariadicCallTarget {
+enum VariadicCallTarget<'a> {
     Function(syn::Expr),
     Method {
-        receiver: syn::Expr,
+        selector: ast::SelectorExpr<'a>,
         method: syn::Ident,
+        should_clone_receiver: bool,
     },
 }
 
-impl VariadicCallTarget {
+impl VariadicCallTarget<'_> {
     fn call(self, args: syn::punctuated::Punctuated<syn::Expr, Token![,]>) -> syn::Expr {
         match self {
             Self::Function(fun) => syn::parse_quote! { #fun(#args) },
-            Self::Method { receiver, method } => method_call_expr(receiver, method, args),
+            Self::Method {
+                selector,
+                method,
+                should_clone_receiver,
+            } => receiver_method_call_expr(selector, method, args, should_clone_receiver),
         }
     }
 }
@@ -10838,10 +10963,20 @@ fn compile_variadic_call_target(fun: ast::Expr) -> VariadicCallTarget {
         ast::Expr::SelectorExpr(selector) if selector_base_is_import(&selector) => {
             VariadicCallTarget::Function(syn::Expr::Path(selector.into()))
         }
-        ast::Expr::SelectorExpr(selector) => VariadicCallTarget::Method {
-            receiver: method_receiver_expr_from_ref(*selector.x),
-            method: selector.sel.into(),
-        },
+        ast::Expr::SelectorExpr(selector) => {
+            record_mapping(&selector.sel.name_pos, Some(selector.sel.name));
+            VariadicCallTarget::Method {
+                should_clone_receiver: value_method_call_receiver_should_clone(
+                    &selector.x,
+                    selector.sel.name,
+                ),
+                method: syn::Ident::new(
+                    &rust_safe_ident_name(selector.sel.name),
+                    Span::mixed_site(),
+                ),
+                selector,
+            }
+        }
         _ => VariadicCallTarget::Function(compile_error_expr("unsupported variadic call target")),
     }
 }
@@ -10903,7 +11038,7 @@ fn compile_variadic_call(call_expr: ast::CallExpr, variadic_start: usize) -> syn
     let fixed_args: Vec<&syn::Expr> = final_args.iter().take(variadic_start).collect();
 
     let vec_expr: syn::Expr = if variadic_args.is_empty() && variadic_is_any {
-        syn::parse_quote! { Vec::<Box<dyn std::any::Any>>::new() }
+        syn::parse_quote! { Vec::<crate::builtin::GorsAny>::new() }
     } else if variadic_args.is_empty() {
         syn::parse_quote! { Vec::new() }
     } else {
@@ -11007,9 +11142,9 @@ fn compile_variadic_any_box_arg(
     if matches!(resolved_go_type(&actual), typeinfer::GoType::Any) {
         compiled
     } else if should_clone_boxed_value && !is_clone_call_expr(&compiled) {
-        syn::parse_quote! { Box::new((#compiled).clone()) as Box<dyn std::any::Any> }
+        syn::parse_quote! { crate::builtin::GorsAny::new((#compiled).clone()) }
     } else {
-        syn::parse_quote! { Box::new(#compiled) as Box<dyn std::any::Any> }
+        syn::parse_quote! { crate::builtin::GorsAny::new(#compiled) }
     }
 }
 
@@ -11091,11 +11226,11 @@ fn compile_builtin(call_expr: ast::CallExpr) -> syn::Expr {
                         rust_map_storage_value_type(&key_go_type, type_from_expr_ref(&map.value));
                     match remaining.as_slice() {
                         [] => {
-                            syn::parse_quote! { std::collections::HashMap::<#key_type, #val_type>::new() }
+                            syn::parse_quote! { crate::builtin::GorsMap::<#key_type, #val_type>::new() }
                         }
                         [cap_arg, ..] => {
                             let cap_arg = compile_usize_size_arg(cap_arg);
-                            syn::parse_quote! { std::collections::HashMap::<#key_type, #val_type>::with_capacity(#cap_arg) }
+                            syn::parse_quote! { crate::builtin::GorsMap::<#key_type, #val_type>::with_capacity(#cap_arg) }
                         }
                     }
                 }
@@ -11279,11 +11414,11 @@ fn compile_named_make_call(type_arg: &ast::Expr, args: &[MakeSizeArg]) -> Option
                 rust_map_storage_value_type(&key, rust_type_from_inferred_go_type(&value));
             let inner: syn::Expr = match args {
                 [] => {
-                    syn::parse_quote! { std::collections::HashMap::<#key_type, #value_type>::new() }
+                    syn::parse_quote! { crate::builtin::GorsMap::<#key_type, #value_type>::new() }
                 }
                 [cap_arg, ..] => {
                     let cap_arg = compile_usize_size_arg(cap_arg);
-                    syn::parse_quote! { std::collections::HashMap::<#key_type, #value_type>::with_capacity(#cap_arg) }
+                    syn::parse_quote! { crate::builtin::GorsMap::<#key_type, #value_type>::with_capacity(#cap_arg) }
                 }
             };
             Some(syn::parse_quote! { #named_type(#inner) })
@@ -11491,9 +11626,38 @@ fn compile_panic_builtin(raw_args: Vec<ast::Expr>) -> syn::Expr {
     let Some(arg) = raw_args.into_iter().next() else {
         return syn::parse_quote! { crate::builtin::panic_value(()) };
     };
+    if is_nil_expr(ast_unparen_expr_ref(&arg)) {
+        if defer_context::panic_returns_through_defer() {
+            return syn::parse_quote! {{
+                crate::builtin::set_recover_payload_nil();
+                return;
+            }};
+        }
+        return syn::parse_quote! { crate::builtin::panic_nil() };
+    }
     let arg_type = TYPE_ENV.with(|env| typeinfer::GoType::infer_expr(&arg, &env.borrow()));
     let arg_is_any = matches!(resolved_go_type(&arg_type), typeinfer::GoType::Any);
-    let arg = compile_variadic_any_arg(arg, Some(&typeinfer::GoType::Any));
+    let arg_is_concrete_error = go_type_implements_error(&arg_type)
+        && !matches!(resolved_go_type(&arg_type), typeinfer::GoType::Error);
+    let description = if arg_is_concrete_error || arg_is_any {
+        None
+    } else {
+        panic_value_description(&arg_type)
+    };
+    let mut arg = if description.is_some() {
+        // The value keeps its named type, for `recover` and to be described.
+        compile_expr_with_expected(arg, None)
+    } else {
+        compile_variadic_any_arg(arg, Some(&typeinfer::GoType::Any))
+    };
+    if arg_is_concrete_error {
+        // Go prints the panics of errors with their `Error` method.
+        arg = syn::parse_quote! {
+            crate::builtin::with_panic_description(#arg, |value| crate::builtin::error::Error(value))
+        };
+    } else if let Some(description) = description {
+        arg = syn::parse_quote! { crate::builtin::with_panic_description(#arg, #description) };
+    }
     if defer_context::panic_returns_through_defer() {
         if arg_is_any {
             let arg = clone_any_expr(arg);
@@ -11514,6 +11678,80 @@ fn compile_panic_builtin(raw_args: Vec<ast::Expr>) -> syn::Expr {
     syn::parse_quote! { crate::builtin::panic_value(#arg) }
 }
 
+/// How a crash prints the panic values of `arg_type`, as the function
+/// `with_panic_description` takes: `fmt.Stringer`s through their `String`
+/// method, and the values of the other named types with their Go type name,
+/// like `main.T(1)` or `(main.T) 0xc000012345`.
+fn panic_value_description(arg_type: &typeinfer::GoType) -> Option<syn::Expr> {
+    let (name, pointer) = match arg_type {
+        typeinfer::GoType::Named(name) => (name, false),
+        typeinfer::GoType::Pointer(inner) => match inner.as_ref() {
+            typeinfer::GoType::Named(name) => (name, true),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (is_alias, basic_underlying) = TYPE_ENV.with(|env| {
+        let env = env.borrow();
+        let underlying = env.resolve_alias(&typeinfer::GoType::Named(name.clone()));
+        let basic = matches!(env.get_type_kind(name), Some(typeinfer::TypeKind::Alias(_)))
+            && (underlying.is_numeric()
+                || matches!(
+                    underlying,
+                    typeinfer::GoType::String | typeinfer::GoType::Bool
+                ));
+        (env.is_type_alias(name), basic)
+    });
+    if is_alias {
+        return None;
+    }
+    let string_method = format!("{name}.String");
+    let (has_value_string, has_string) = TYPE_ENV.with(|env| {
+        let env = env.borrow();
+        let is_stringer = env.get_func_params(&string_method).is_empty()
+            && matches!(
+                env.get_func_returns(&string_method).as_slice(),
+                [typeinfer::GoType::String]
+            );
+        (
+            is_stringer && env.has_value_method(&string_method),
+            is_stringer && env.has_func(&string_method),
+        )
+    });
+    if has_string {
+        // A path call, which keeps the method reachable without knowing the
+        // type of the closure parameter.
+        let rust_ty = named_go_type_path(name);
+        if has_value_string && !pointer {
+            return Some(syn::parse_quote! { |value| <#rust_ty>::String(value) });
+        }
+        if has_value_string {
+            return Some(syn::parse_quote! {
+                |value| <#rust_ty>::String(&*value.lock().unwrap())
+            });
+        }
+        if pointer {
+            return Some(syn::parse_quote! { |value| <#rust_ty>::String(value.clone()) });
+        }
+    }
+    let type_name =
+        package_context::current_package_qualified_name(name).unwrap_or_else(|| name.clone());
+    if pointer {
+        let type_name = format!("*{type_name}");
+        return Some(syn::parse_quote! {
+            |value| crate::builtin::describe_named_panic(#type_name, value)
+        });
+    }
+    if basic_underlying {
+        return Some(syn::parse_quote! {
+            |value| crate::builtin::describe_named_panic(#type_name, &value.0)
+        });
+    }
+    Some(syn::parse_quote! {
+        |value| crate::builtin::describe_named_panic(#type_name, value)
+    })
+}
+
 fn wrap_void_defer_body_in_unwind_catch(block: &mut syn::Block) {
     if block.stmts.is_empty() {
         return;
@@ -12450,19 +12688,26 @@ fn compile_array_literal(array_type: &ast::ArrayType, raw_elts: Vec<ast::Expr>)
 fn compile_map_literal(map_type: &ast::MapType, raw_elts: Vec<ast::Expr>) -> syn::Expr {
     let key_go_type = typeinfer::GoType::from_expr(&map_type.key);
     let value_go_type = typeinfer::GoType::from_expr(&map_type.value);
+    compile_map_literal_with_types(&key_go_type, &value_go_type, raw_elts)
+}
+
+fn compile_map_literal_with_types(
+    key_go_type: &typeinfer::GoType,
+    value_go_type: &typeinfer::GoType,
+    raw_elts: Vec<ast::Expr>,
+) -> syn::Expr {
     let elts = raw_elts
         .into_iter()
         .filter_map(|elt| {
             let ast::Expr::KeyValueExpr(kv) = elt else {
                 return None;
             };
-            let value = compile_expr_with_expected(*kv.value, Some(&value_go_type));
-            if interface_map_key_trait_path(&key_go_type).is_some() {
-                let key_value = compile_map_key_value_expr(*kv.key, &key_go_type);
+            let value = compile_expr_with_expected(*kv.value, Some(value_go_type));
+            if interface_map_key_trait_path(key_go_type).is_some() {
+                let key_value = compile_map_key_value_expr(*kv.key, key_go_type);
                 let key_value_ref: syn::Expr = syn::parse_quote! { __gors_map_key_value };
-                let key = compile_map_key_expr_from_value(&key_value_ref, &key_go_type);
-                let stored_key =
-                    compile_interface_map_stored_key_expr(&key_value_ref, &key_go_type);
+                let key = compile_map_key_expr_from_value(&key_value_ref, key_go_type);
+                let stored_key = compile_interface_map_stored_key_expr(&key_value_ref, key_go_type);
                 return Some(syn::parse_quote! {{
                     let __gors_map_key_value = #key_value;
                     (
@@ -12471,12 +12716,12 @@ fn compile_map_literal(map_type: &ast::MapType, raw_elts: Vec<ast::Expr>) -> syn
                     )
                 }});
             }
-            let key = compile_map_key_expr(*kv.key, &key_go_type);
+            let key = compile_map_key_expr(*kv.key, key_go_type);
             Some(syn::parse_quote! { (#key, #value) })
         })
         .collect::<Vec<syn::Expr>>();
     syn::parse_quote! {
-        std::collections::HashMap::from([#(#elts),*])
+        crate::builtin::GorsMap::from([#(#elts),*])
     }
 }
 
@@ -12519,6 +12764,13 @@ fn compile_composite_lit(comp_lit: ast::CompositeLit) -> syn::Expr {
                         }
                         return syn::parse_quote! { #type_ident(Vec::from([#(#elts),*])) };
                     }
+                    if let typeinfer::GoType::Map(key_type, value_type) = alias_type {
+                        let map = compile_map_literal_with_types(&key_type, &value_type, raw_elts);
+                        if is_declared_alias {
+                            return map;
+                        }
+                        return syn::parse_quote! { #type_ident(#map) };
+                    }
                     let elts = compile_raw_elts(raw_elts);
                     if elts.is_empty() || elts.iter().any(|elt| matches!(elt, syn::Expr::Tuple(_)))
                     {
@@ -12837,7 +13089,8 @@ fn compile_slice_expr(slice_expr: ast::SliceExpr) -> syn::Expr {
                 let __gors_start = #start;
                 let __gors_end = #end;
                 let __gors_max = #max;
-                let mut __gors_slice = (#source_ref[__gors_start..__gors_end]).to_vec();
+                let mut __gors_slice =
+                    (#source_ref[crate::builtin::bounds(__gors_start..__gors_end)]).to_vec();
                 let __gors_cap = __gors_max.saturating_sub(__gors_start);
                 if __gors_slice.capacity() < __gors_cap {
                     __gors_slice.reserve_exact(__gors_cap - __gors_slice.capacity());
@@ -12853,7 +13106,8 @@ fn compile_slice_expr(slice_expr: ast::SliceExpr) -> syn::Expr {
             let __gors_start = #start;
             let __gors_end = #end;
             let __gors_max = #max;
-            let mut __gors_slice = (__gors_source[__gors_start..__gors_end]).to_vec();
+            let mut __gors_slice =
+                (__gors_source[crate::builtin::bounds(__gors_start..__gors_end)]).to_vec();
             let __gors_cap = __gors_max.saturating_sub(__gors_start);
             if __gors_slice.capacity() < __gors_cap {
                 __gors_slice.reserve_exact(__gors_cap - __gors_slice.capacity());
@@ -12872,13 +13126,13 @@ fn compile_slice_expr(slice_expr: ast::SliceExpr) -> syn::Expr {
             syn::parse_quote! { (#slice_source)[..] }
         }
         (Some(lo), None) => {
-            syn::parse_quote! { (#slice_source)[#lo..] }
+            syn::parse_quote! { (#slice_source)[crate::builtin::bounds(#lo..)] }
         }
         (None, Some(hi)) => {
-            syn::parse_quote! { (#slice_source)[..#hi] }
+            syn::parse_quote! { (#slice_source)[crate::builtin::bounds(..#hi)] }
         }
         (Some(lo), Some(hi)) => {
-            syn::parse_quote! { (#slice_source)[#lo..#hi] }
+            syn::parse_quote! { (#slice_source)[crate::builtin::bounds(#lo..#hi)] }
         }
     };
 
@@ -12964,9 +13218,15 @@ fn compile_self_slice_assignment_rhs<'a>(
     {
         let slice: syn::Expr = match (has_low, has_high) {
             (false, false) => syn::parse_quote! { (#target)[..] },
-            (true, false) => syn::parse_quote! { (#target)[__gors_slice_low..] },
-            (false, true) => syn::parse_quote! { (#target)[..__gors_slice_high] },
-            (true, true) => syn::parse_quote! { (#target)[__gors_slice_low..__gors_slice_high] },
+            (true, false) => {
+                syn::parse_quote! { (#target)[crate::builtin::bounds(__gors_slice_low..)] }
+            }
+            (false, true) => {
+                syn::parse_quote! { (#target)[crate::builtin::bounds(..__gors_slice_high)] }
+            }
+            (true, true) => syn::parse_quote! {
+                (#target)[crate::builtin::bounds(__gors_slice_low..__gors_slice_high)]
+            },
         };
         return SelfSliceAssignmentRhs::Compiled(syn::parse_quote! {{
             #low_stmt
@@ -12998,7 +13258,8 @@ fn compile_self_slice_assignment_rhs<'a>(
             #high_stmt
             #max_stmt
             let __gors_slice_source = std::mem::take(&mut #target);
-            let mut __gors_slice = (__gors_slice_source[#start..#end]).to_vec();
+            let mut __gors_slice =
+                (__gors_slice_source[crate::builtin::bounds(#start..#end)]).to_vec();
             let __gors_cap = __gors_slice_max.saturating_sub(#start);
             if __gors_slice.capacity() < __gors_cap {
                 __gors_slice.reserve_exact(__gors_cap - __gors_slice.capacity());
@@ -13009,11 +13270,15 @@ fn compile_self_slice_assignment_rhs<'a>(
 
     let slice: syn::Expr = match (has_low, has_high) {
         (false, false) => syn::parse_quote! { (__gors_slice_source)[..] },
-        (true, false) => syn::parse_quote! { (__gors_slice_source)[__gors_slice_low..] },
-        (false, true) => syn::parse_quote! { (__gors_slice_source)[..__gors_slice_high] },
-        (true, true) => {
-            syn::parse_quote! { (__gors_slice_source)[__gors_slice_low..__gors_slice_high] }
+        (true, false) => {
+            syn::parse_quote! { (__gors_slice_source)[crate::builtin::bounds(__gors_slice_low..)] }
         }
+        (false, true) => syn::parse_quote! {
+            (__gors_slice_source)[crate::builtin::bounds(..__gors_slice_high)]
+        },
+        (true, true) => syn::parse_quote! {
+            (__gors_slice_source)[crate::builtin::bounds(__gors_slice_low..__gors_slice_high)]
+        },
     };
 
     if is_string_slice {
@@ -13215,6 +13480,13 @@ fn compile_call_arg_with_expected_and_ownership(
     {
         return arg.into();
     }
+    // Parameters are only left untyped for primitives that keep the value,
+    // like the finalizers of `runtime.SetFinalizer`.
+    if matches!(expected, Some(typeinfer::GoType::Unknown))
+        && let ast::Expr::FuncLit(func_lit) = arg
+    {
+        return compile_func_lit_with_capture_mode(func_lit, true);
+    }
     let should_clone_any = any_call_arg_needs_clone(&arg, expected, actual);
     let should_clone_value =
         expected.is_some_and(|expected| expr_should_clone_for_value_param(&arg, expected, actual));
@@ -13339,7 +13611,7 @@ fn compile_forwarded_multi_return_arg_value(
         if go_type_supports_runtime_any_comparable(&actual) {
             return syn::parse_quote! { crate::builtin::box_any_comparable(#temp) };
         }
-        return syn::parse_quote! { Box::new(#temp) as Box<dyn std::any::Any> };
+        return syn::parse_quote! { crate::builtin::GorsAny::new(#temp) };
     }
 
     if matches!(resolved_go_type(expected), typeinfer::GoType::Error) {
@@ -13453,11 +13725,15 @@ fn slice_lvalue_expr_from_base(
     let low = low.and_then(lvalue_index_component_expr);
     let high = high.and_then(lvalue_index_component_expr);
     match (low, high) {
-        (Some(low), Some(high)) => {
-            Some(syn::parse_quote! { (#base)[(#low) as usize..(#high) as usize] })
+        (Some(low), Some(high)) => Some(syn::parse_quote! {
+            (#base)[crate::builtin::bounds((#low) as usize..(#high) as usize)]
+        }),
+        (Some(low), None) => {
+            Some(syn::parse_quote! { (#base)[crate::builtin::bounds((#low) as usize..)] })
+        }
+        (None, Some(high)) => {
+            Some(syn::parse_quote! { (#base)[crate::builtin::bounds(..(#high) as usize)] })
         }
-        (Some(low), None) => Some(syn::parse_quote! { (#base)[(#low) as usize..] }),
-        (None, Some(high)) => Some(syn::parse_quote! { (#base)[..(#high) as usize] }),
         (None, None) => Some(syn::parse_quote! { (#base)[..] }),
     }
 }
@@ -13503,6 +13779,7 @@ fn lvalue_expr_from_ref(expr: &ast::Expr) -> Option<syn::Expr> {
     match expr {
         ast::Expr::Ident(ident) => {
             if let Some(expr) = shared_capture_lvalue_expr(ident.name) {
+                record_mapping(&ident.name_pos, Some(ident.name));
                 return Some(expr);
             }
             if ident_top_level_var_type(ident.name).is_some()
@@ -13634,7 +13911,7 @@ fn lvalue_index_component_expr(expr: &ast::Expr) -> Option<syn::Expr> {
                     let __gors_index_base = (#base).clone();
                     let __gors_index = (#idx) as usize;
                     if __gors_index >= __gors_index_base.len() {
-                        crate::builtin::panic_value("index out of range");
+                        crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                     }
                     (__gors_index_base.as_bytes()[__gors_index]).clone()
                 }})
@@ -13713,7 +13990,7 @@ fn compile_mutable_slice_view_index_read(
                         <#receiver_ty>::#method(&mut *__gors_index_owner_guard, #args);
                     let __gors_index = (#idx) as usize;
                     if __gors_index >= __gors_index_base.len() {
-                        crate::builtin::panic_value("index out of range");
+                        crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                     }
                     #read_expr
                 }};
@@ -13726,7 +14003,7 @@ fn compile_mutable_slice_view_index_read(
                         <#receiver_ty>::#method(__gors_index_owner, #args);
                     let __gors_index = (#idx) as usize;
                     if __gors_index >= __gors_index_base.len() {
-                        crate::builtin::panic_value("index out of range");
+                        crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                     }
                     #read_expr
                 }};
@@ -13739,7 +14016,7 @@ fn compile_mutable_slice_view_index_read(
         let __gors_index_base = __gors_index_owner.#method(#args);
         let __gors_index = (#idx) as usize;
         if __gors_index >= __gors_index_base.len() {
-            crate::builtin::panic_value("index out of range");
+            crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
         }
         #read_expr
     }}
@@ -13914,6 +14191,50 @@ fn pointer_cell_copy_out_method_call_expr(
     }})
 }
 
+/// A call of `method` on the receiver `sel.x`, through its pointer receiver,
+/// a promoted pointer method or a pointer field when it has one.
+fn receiver_method_call_expr(
+    sel: ast::SelectorExpr,
+    method: syn::Ident,
+    args: syn::punctuated::Punctuated<syn::Expr, Token![,]>,
+    should_clone_receiver: bool,
+) -> syn::Expr {
+    if method_has_pointer_receiver_for_expr(&sel.x, sel.sel.name) {
+        return pointer_receiver_method_call_expr(sel, method, args);
+    }
+    if let Some(target) = promoted_pointer_method_target(&sel) {
+        let receiver_ty = target.receiver_ty;
+        let receiver = target.receiver;
+        let receiver = if method_uses_borrowed_pointer_receiver(&target.receiver_name, sel.sel.name)
+        {
+            syn::parse_quote! { &mut *#receiver.lock().unwrap() }
+        } else {
+            receiver
+        };
+        return syn::parse_quote! { <#receiver_ty>::#method(#receiver, #args) };
+    }
+    if let Some(target) = pointer_field_method_target(&sel) {
+        let receiver_ty = target.receiver_ty;
+        let receiver = target.receiver;
+        return syn::parse_quote! { <#receiver_ty>::#method(&mut *#receiver, #args) };
+    }
+    if let Some(expr) = pointer_cell_copy_out_method_call_expr(&sel, method.clone(), args.clone()) {
+        return expr;
+    }
+    if let Some(expr) =
+        pointer_cell_interface_field_method_call_expr(&sel, method.clone(), args.clone())
+    {
+        return expr;
+    }
+    let receiver = method_receiver_expr_from_ref(*sel.x);
+    let receiver = if should_clone_receiver {
+        syn::parse_quote! { (#receiver).clone() }
+    } else {
+        receiver
+    };
+    method_call_expr(receiver, method, args)
+}
+
 fn method_call_expr(
     receiver: syn::Expr,
     method: syn::Ident,
@@ -14942,6 +15263,7 @@ fn pointer_selector_owner_expr(expr: &ast::Expr) -> Option<syn::Expr> {
     match ast_unparen_expr_ref(expr) {
         ast::Expr::Ident(ident) => {
             if let Some(expr) = shared_capture_read_expr(ident.name) {
+                record_mapping(&ident.name_pos, Some(ident.name));
                 return Some(expr);
             }
             let ident = value_ident(ident.name);
@@ -15241,7 +15563,7 @@ fn compile_expr_with_expected(
                 }
             }
             Some(typeinfer::GoType::Any | typeinfer::GoType::Interface(_)) => {
-                syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any> }
+                syn::parse_quote! { crate::builtin::GorsAny::Nil }
             }
             Some(typeinfer::GoType::Error) => {
                 syn::parse_quote! {
@@ -15363,7 +15685,7 @@ fn compile_expr_with_expected(
         }
         if go_type_interface_name(&actual).is_some_and(|name| name != "error") {
             let owned = compile_owned_interface_expr(expr, &actual);
-            return syn::parse_quote! { Box::new(#owned) as Box<dyn std::any::Any> };
+            return syn::parse_quote! { crate::builtin::GorsAny::new(#owned) };
         }
         if matches!(resolved_go_type(&actual), typeinfer::GoType::Error) {
             let should_clone =
@@ -15374,7 +15696,7 @@ fn compile_expr_with_expected(
             } else {
                 expr
             };
-            return syn::parse_quote! { Box::new(#value) as Box<dyn std::any::Any> };
+            return syn::parse_quote! { crate::builtin::GorsAny::new(#value) };
         }
         if go_type_points_to_nonclone_named_struct(&actual)
             && let ast::Expr::UnaryExpr(unary) = &expr
@@ -15402,7 +15724,7 @@ fn compile_expr_with_expected(
         if go_type_supports_runtime_any_comparable(&actual) {
             return syn::parse_quote! { crate::builtin::box_any_comparable(#expr) };
         }
-        return syn::parse_quote! { Box::new(#expr) as Box<dyn std::any::Any> };
+        return syn::parse_quote! { crate::builtin::GorsAny::new(#expr) };
     }
 
     if matches!(
@@ -15873,7 +16195,7 @@ fn compile_function_field_call(call_expr: ast::CallExpr) -> Option<syn::Expr> {
                 let __gors_func = crate::builtin::lock_func(&__gors_func_target);
                 match __gors_func.as_ref() {
                     Some(__gors_func) => __gors_func.clone(),
-                    None => crate::builtin::panic_value("nil function"),
+                    None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::nil_dereference()),
                 }
             };
         (&*__gors_func)(#args)
@@ -15902,12 +16224,12 @@ fn compile_go_function_value_call_stmt(call_expr: ast::CallExpr) -> Option<syn::
     Some(syn::parse_quote! {{
         let #func_target_ident = (#func).clone();
         #(#arg_bindings)*
-        ::std::thread::spawn(move || {
+        crate::builtin::go(move || {
             let __gors_func = {
                 let __gors_func = crate::builtin::lock_func(&#func_target_ident);
                 match __gors_func.as_ref() {
                     Some(__gors_func) => __gors_func.clone(),
-                    None => crate::builtin::panic_value("nil function"),
+                    None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::nil_dereference()),
                 }
             };
             (&*__gors_func)(#(#arg_idents),*);
@@ -15980,7 +16302,7 @@ fn compile_function_value_call_args(
     let variadic_args: Vec<&syn::Expr> = compiled_args.iter().skip(variadic_start).collect();
     let fixed_args: Vec<&syn::Expr> = compiled_args.iter().take(variadic_start).collect();
     let vec_expr: syn::Expr = if variadic_args.is_empty() && variadic_is_any {
-        syn::parse_quote! { Vec::<Box<dyn std::any::Any>>::new() }
+        syn::parse_quote! { Vec::<crate::builtin::GorsAny>::new() }
     } else if variadic_args.is_empty() {
         syn::parse_quote! { Vec::new() }
     } else {
@@ -16403,8 +16725,18 @@ fn is_nil_type_case_expr(expr: &ast::Expr) -> bool {
     matches!(expr, ast::Expr::Ident(id) if id.name == "nil")
 }
 
+/// The types an `any` value may hold when it implements `interface_name`.
+/// Error values, like the run-time errors of the builtins, are boxed.
+fn type_switch_interface_implementors(interface_name: &str) -> Vec<syn::Type> {
+    let mut implementors = interface_assertion_implementors(interface_name, None);
+    if interface_name == "error" {
+        implementors.insert(0, syn::parse_quote! { Box<dyn crate::builtin::error> });
+    }
+    implementors
+}
+
 fn type_switch_interface_condition(interface_name: &str, any_ref: syn::Expr) -> syn::Expr {
-    interface_assertion_implementors(interface_name, None)
+    type_switch_interface_implementors(interface_name)
         .into_iter()
         .map(|implementor| syn::parse_quote! { crate::builtin::any_is::<#implementor>(#any_ref) })
         .reduce(|acc, expr| syn::parse_quote! { #acc || #expr })
@@ -16414,7 +16746,7 @@ fn type_switch_interface_condition(interface_name: &str, any_ref: syn::Expr) ->
 fn type_switch_interface_binding(interface_name: &str, any_ref: syn::Expr) -> syn::Expr {
     let trait_path = interface_trait_path_from_name(interface_name);
     let fallback = interface_assertion_fallback(&trait_path, interface_name, false);
-    interface_assertion_implementors(interface_name, None)
+    type_switch_interface_implementors(interface_name)
         .into_iter()
         .rev()
         .fold(fallback, |result, implementor| {
@@ -16612,7 +16944,7 @@ fn compile_range_stmt(range_stmt: ast::RangeStmt) -> Result<Vec<syn::Stmt>, Comp
     TYPE_ENV.with(|env| {
         *env.borrow_mut() = env_snapshot;
     });
-    let body: syn::Block = body?;
+    let body = loop_control::with_yield_point(body?);
     let is_function_item_range =
         range_function_yield_params.is_some() && is_function_item_expr(&range_stmt.x);
     let x: syn::Expr = range_stmt.x.into();
@@ -16952,7 +17284,7 @@ fn compile_range_function_call_stmt(
                 let __gors_func = crate::builtin::lock_func(&(#fun_expr));
                 match __gors_func.as_ref() {
                     Some(__gors_func) => __gors_func.clone(),
-                    None => crate::builtin::panic_value("nil function"),
+                    None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::nil_dereference()),
                 }
             };
             (&*__gors_func)(#yield_value);
@@ -17533,22 +17865,31 @@ fn compile_top_level_value_spec(
                 None => {
                     if anonymous_struct_type.is_some() {
                         syn::parse_quote! { Default::default() }
+                    } else if let Some(init) = inferred_type
+                        .as_ref()
+                        .and_then(|ty| embed_directives::initializer(go_name, ty))
+                    {
+                        init
                     } else {
                         zero_values::expr_for_optional_type(type_expr.as_ref())
                     }
                 }
             };
             let mut ty: syn::Type =
-                inferred_type.unwrap_or_else(|| syn::parse_quote! { Box<dyn std::any::Any> });
-            if is_box_type_with_any_bound(&ty) {
+                inferred_type.unwrap_or_else(|| syn::parse_quote! { crate::builtin::GorsAny });
+            if is_box_type_with_any_bound(&ty) || is_gors_any_type(&ty) {
                 ty = syn::parse_quote! { Box<dyn std::any::Any + Send + Sync> };
-                if is_box_dyn_any_expr(&value) {
+                if is_box_dyn_any_expr(&value) || is_gors_any_nil_expr(&value) {
                     value =
                         syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any + Send + Sync> };
                 } else if let Some(inner) = box_dyn_any_cast_source_expr(&value) {
                     value = syn::parse_quote! {
                         #inner as Box<dyn std::any::Any + Send + Sync>
                     };
+                } else if let Some(inner) = gors_any_new_arg(&value) {
+                    value = syn::parse_quote! {
+                        Box::new(#inner) as Box<dyn std::any::Any + Send + Sync>
+                    };
                 } else if let Some(arg) = box_any_comparable_call_arg(&value) {
                     value = syn::parse_quote! {
                         crate::builtin::box_any_comparable_send_sync(#arg)
@@ -18026,7 +18367,7 @@ fn compile_integer_division_or_remainder(binary: ast::BinaryExpr) -> syn::Expr {
         let __gors_div_left = #left;
         let __gors_div_right = #right;
         if __gors_div_right == 0 {
-            crate::builtin::panic_value("integer divide by zero");
+            crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::divide());
         }
         __gors_div_left #op __gors_div_right
     }}
@@ -18297,7 +18638,18 @@ fn go_type_is_unsafe_pointer_value(ty: &typeinfer::GoType) -> bool {
     )
 }
 
-fn compile_container_nil_check(other_expr: syn::Expr, is_eq: bool) -> syn::Expr {
+fn compile_container_nil_check(
+    other_expr: syn::Expr,
+    other_ty: &typeinfer::GoType,
+    is_eq: bool,
+) -> syn::Expr {
+    if matches!(resolved_go_type(other_ty), typeinfer::GoType::Map(_, _)) {
+        return if is_eq {
+            syn::parse_quote! { (#other_expr).is_nil() }
+        } else {
+            syn::parse_quote! { !(#other_expr).is_nil() }
+        };
+    }
     if is_eq {
         syn::parse_quote! { (#other_expr).is_empty() }
     } else {
@@ -18344,7 +18696,7 @@ fn compile_any_equality_comparison<'src>(
     let left = compile_expr_with_expected(*binary_expr.x, Some(&typeinfer::GoType::Any));
     let right = compile_expr_with_expected(*binary_expr.y, Some(&typeinfer::GoType::Any));
     let eq: syn::Expr = syn::parse_quote! {
-        crate::builtin::any_eq((#left).as_ref(), (#right).as_ref())
+        crate::builtin::any_eq(&(#left), &(#right))
     };
     Ok(if op == token::Token::EQL {
         eq
@@ -18497,7 +18849,7 @@ fn compile_binary_expr(binary_expr: ast::BinaryExpr) -> syn::Expr {
             } else {
                 syn::Expr::from(*binary_expr.x)
             };
-            return compile_container_nil_check(other_expr, is_eq);
+            return compile_container_nil_check(other_expr, &other_ty, is_eq);
         }
 
         if go_type_is_channel(&other_ty) {
@@ -18744,7 +19096,7 @@ fn compile_go_typed_shift_expr(
             let __gors_shift_left: #left_rust_ty = #left;
             let __gors_shift_count = #right;
             if __gors_shift_count < 0 {
-                crate::builtin::panic_value("negative shift count");
+                crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::new("negative shift amount"));
             }
             let __gors_shift_count = __gors_shift_count as u128;
             if __gors_shift_count >= (<#left_rust_ty>::BITS as u128) {
@@ -18766,7 +19118,7 @@ fn compile_go_typed_shift_expr(
             let __gors_shift_left: #left_rust_ty = #left;
             let __gors_shift_count = #right;
             if __gors_shift_count < 0 {
-                crate::builtin::panic_value("negative shift count");
+                crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::new("negative shift amount"));
             }
             let __gors_shift_count = __gors_shift_count as u128;
             if __gors_shift_count >= (<#left_rust_ty>::BITS as u128) {
@@ -18783,7 +19135,7 @@ fn compile_go_typed_shift_expr(
             let __gors_shift_left: #left_rust_ty = #left;
             let __gors_shift_count = #right;
             if __gors_shift_count < 0 {
-                crate::builtin::panic_value("negative shift count");
+                crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::new("negative shift amount"));
             }
             let __gors_shift_count = __gors_shift_count as u128;
             if __gors_shift_count >= (<#left_rust_ty>::BITS as u128) {
@@ -19174,7 +19526,7 @@ fn compile_type_method_expression_call(call_expr: ast::CallExpr) -> syn::Expr {
             args.push(arg.clone());
         }
         let vec_expr: syn::Expr = if variadic_args.is_empty() && variadic_is_any {
-            syn::parse_quote! { Vec::<Box<dyn std::any::Any>>::new() }
+            syn::parse_quote! { Vec::<crate::builtin::GorsAny>::new() }
         } else if variadic_args.is_empty() {
             syn::parse_quote! { Vec::new() }
         } else {
@@ -19298,10 +19650,7 @@ fn defer_stack_decl_stmt() -> syn::Stmt {
             struct __GorsDeferStack(Vec<Box<dyn FnOnce()>>);
             impl Drop for __GorsDeferStack {
                 fn drop(&mut self) {
-                    while let Some(__gors_defer) = self.0.pop() {
-                        __gors_defer();
-                    }
-                    crate::builtin::resume_unrecovered_panic();
+                    crate::builtin::run_deferred(&mut self.0);
                 }
             }
             __GorsDeferStack(Vec::new())
@@ -19417,7 +19766,7 @@ fn compile_defer_stmt(mut call: ast::CallExpr) -> Vec<syn::Stmt> {
                     let __gors_func = crate::builtin::lock_func(&(#fun_expr));
                     match __gors_func.as_ref() {
                         Some(__gors_func) => __gors_func.clone(),
-                        None => crate::builtin::panic_value("nil function"),
+                        None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::nil_dereference()),
                     }
                 };
             },
@@ -19566,6 +19915,14 @@ fn invalid_statement_error(invalid: ir::InvalidStatement) -> CompilerError {
             ir::InvalidRangeReason::TypeMismatch { expected, actual } => {
                 format!("invalid range assignment: cannot assign {actual} to {expected}")
             }
+            ir::InvalidRangeReason::LanguageVersion {
+                type_name,
+                feature,
+                lang,
+            } => format!(
+                "invalid range clause: cannot range over {type_name}: {}",
+                language_version::requires_message(feature, lang)
+            ),
         },
         ir::InvalidStatement::Receive { reason } => {
             format!(
@@ -19890,6 +20247,10 @@ fn invalid_signature_error(invalid: ir::InvalidSignature) -> CompilerError {
         ir::InvalidSignature::InvalidTypeParameterDecl => {
             "type parameter declaration must include names and a constraint".to_string()
         }
+        ir::InvalidSignature::LanguageVersion { feature, lang } => format!(
+            "type parameter {}",
+            language_version::requires_message(feature, lang)
+        ),
         ir::InvalidSignature::MainFunction {
             type_params,
             params,
@@ -20671,70 +21032,8 @@ impl From<ast::Expr<'_>> for syn::Expr {
                                 args.push(arg);
                             }
                         }
-                        if method_has_pointer_receiver_for_expr(&sel.x, sel.sel.name) {
-                            let expr = pointer_receiver_method_call_expr(sel, method, args);
-                            return wrap_call_expr_with_slice_alias_writebacks(
-                                expr,
-                                slice_alias_writebacks,
-                            );
-                        }
-                        if let Some(target) = promoted_pointer_method_target(&sel) {
-                            let receiver_ty = target.receiver_ty;
-                            let receiver = target.receiver;
-                            let receiver = if method_uses_borrowed_pointer_receiver(
-                                &target.receiver_name,
-                                sel.sel.name,
-                            ) {
-                                syn::parse_quote! { &mut *#receiver.lock().unwrap() }
-                            } else {
-                                receiver
-                            };
-                            let expr = syn::parse_quote! {
-                                <#receiver_ty>::#method(#receiver, #args)
-                            };
-                            return wrap_call_expr_with_slice_alias_writebacks(
-                                expr,
-                                slice_alias_writebacks,
-                            );
-                        }
-                        if let Some(target) = pointer_field_method_target(&sel) {
-                            let receiver_ty = target.receiver_ty;
-                            let receiver = target.receiver;
-                            let expr = syn::parse_quote! {
-                                <#receiver_ty>::#method(&mut *#receiver, #args)
-                            };
-                            return wrap_call_expr_with_slice_alias_writebacks(
-                                expr,
-                                slice_alias_writebacks,
-                            );
-                        }
-                        if let Some(expr) = pointer_cell_copy_out_method_call_expr(
-                            &sel,
-                            method.clone(),
-                            args.clone(),
-                        ) {
-                            return wrap_call_expr_with_slice_alias_writebacks(
-                                expr,
-                                slice_alias_writebacks,
-                            );
-                        }
-                        if let Some(expr) = pointer_cell_interface_field_method_call_expr(
-                            &sel,
-                            method.clone(),
-                            args.clone(),
-                        ) {
-                            return wrap_call_expr_with_slice_alias_writebacks(
-                                expr,
-                                slice_alias_writebacks,
-                            );
-                        }
-                        let receiver = method_receiver_expr_from_ref(*sel.x);
-                        let receiver = if should_clone_receiver {
-                            syn::parse_quote! { (#receiver).clone() }
-                        } else {
-                            receiver
-                        };
-                        let expr = method_call_expr(receiver, method, args);
+                        let expr =
+                            receiver_method_call_expr(sel, method, args, should_clone_receiver);
                         return wrap_call_expr_with_slice_alias_writebacks(
                             expr,
                             slice_alias_writebacks,
@@ -20754,6 +21053,7 @@ impl From<ast::Expr<'_>> for syn::Expr {
             ast::Expr::Ident(ident) => {
                 let ident_name = ident.name;
                 if let Some(expr) = shared_capture_read_expr(ident_name) {
+                    record_mapping(&ident.name_pos, Some(ident_name));
                     return expr;
                 }
                 if let Some(expr) = dot_import_path_expr(ident_name) {
@@ -21002,7 +21302,7 @@ impl From<ast::Expr<'_>> for syn::Expr {
                         let __gors_index_base = (#pointer_array_cell).lock().unwrap();
                         let __gors_index = (#idx) as usize;
                         if __gors_index >= __gors_index_base.len() {
-                            crate::builtin::panic_value("index out of range");
+                            crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                         }
                         (__gors_index_base[__gors_index]).clone()
                     }};
@@ -21030,7 +21330,7 @@ impl From<ast::Expr<'_>> for syn::Expr {
                         let __gors_index_base = (#base).clone();
                         let __gors_index = (#idx) as usize;
                         if __gors_index >= __gors_index_base.len() {
-                            crate::builtin::panic_value("index out of range");
+                            crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                         }
                         (__gors_index_base.as_bytes()[__gors_index]).clone()
                     }};
@@ -21041,7 +21341,7 @@ impl From<ast::Expr<'_>> for syn::Expr {
                         let __gors_index_base = &#base;
                         let __gors_index = (#idx) as usize;
                         if __gors_index >= __gors_index_base.len() {
-                            crate::builtin::panic_value("index out of range");
+                            crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                         }
                         crate::builtin::clone_any(&__gors_index_base[__gors_index])
                     }};
@@ -21051,7 +21351,7 @@ impl From<ast::Expr<'_>> for syn::Expr {
                     let __gors_index_base = &#base;
                     let __gors_index = (#idx) as usize;
                     if __gors_index >= __gors_index_base.len() {
-                        crate::builtin::panic_value("index out of range");
+                        crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                     }
                     (__gors_index_base[__gors_index]).clone()
                 }}
@@ -21096,8 +21396,15 @@ impl From<ast::Expr<'_>> for syn::Expr {
                             false,
                         )
                     } else {
+                        let asserted_go_type = typeinfer::GoType::from_expr(&type_expr);
                         let ty: syn::Type = (*type_expr).into();
-                        type_assert_concrete_expr(x, &source_type, source_is_borrowable, ty)
+                        type_assert_concrete_expr(
+                            x,
+                            &source_type,
+                            source_is_borrowable,
+                            ty,
+                            &asserted_go_type,
+                        )
                     }
                 } else {
                     // type switch x.(type) — handled at statement level
@@ -21122,14 +21429,14 @@ impl From<ast::Expr<'_>> for syn::Expr {
                 syn::parse_quote! { <#ty>::default() }
             }
             ast::Expr::FuncType(_) => {
-                syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any> }
+                syn::parse_quote! { crate::builtin::GorsAny::Nil }
             }
             ast::Expr::StructType(struct_type) => {
                 let ty = anonymous_struct_type(struct_type);
                 syn::parse_quote! { <#ty>::default() }
             }
             ast::Expr::InterfaceType(_) => {
-                syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any> }
+                syn::parse_quote! { crate::builtin::GorsAny::Nil }
             }
             ast::Expr::IndexListExpr(index_list_expr) => {
                 let base: syn::Expr = (*index_list_expr.x).into();
@@ -21928,6 +22235,7 @@ impl TryFrom<ast::File<'_>> for syn::File {
                                     let mut values_iter = vs.values.unwrap_or_default().into_iter();
 
                                     for name in names {
+                                        let go_name = name.name;
                                         let ident: syn::Ident = name.into();
                                         let init_ast = values_iter.next();
 
@@ -21949,7 +22257,10 @@ impl TryFrom<ast::File<'_>> for syn::File {
                                         } else if let (Some(type_expr), Some(ty)) =
                                             (type_expr.as_ref(), rust_type.as_ref())
                                         {
-                                            let zero = zero_values::expr_for_type(type_expr);
+                                            let zero = embed_directives::initializer(go_name, ty)
+                                                .unwrap_or_else(|| {
+                                                    zero_values::expr_for_type(type_expr)
+                                                });
                                             package_var_stmts.push(syn::parse_quote! {
                                                 let mut #ident: #ty = #zero;
                                             });
@@ -22533,7 +22844,7 @@ fn type_from_param_expr(expr: &ast::Expr) -> syn::Type {
                 let inner = type_from_expr_ref(elt);
                 syn::parse_quote! { Vec<#inner> }
             } else {
-                syn::parse_quote! { Vec<Box<dyn std::any::Any>> }
+                syn::parse_quote! { Vec<crate::builtin::GorsAny> }
             }
         }
         ast::Expr::FuncType(func_type) => shared_func_type_from_ast(func_type),
@@ -23078,12 +23389,104 @@ fn bool_to_uint8_intrinsic_param(func_decl: &ast::FuncDecl) -> Option<syn::Ident
 }
 
 fn compiler_intrinsic_func_block(func_decl: &ast::FuncDecl) -> Option<syn::Block> {
+    if let Some(block) = sync_runtime_hook_block(func_decl) {
+        return Some(block);
+    }
+    if let Some(duration) = time_sleep_param(func_decl) {
+        return Some(syn::parse_quote!({
+            crate::builtin::sleep(#duration.0);
+        }));
+    }
     let param = bool_to_uint8_intrinsic_param(func_decl)?;
     Some(syn::parse_quote!({
         if #param { 1u8 } else { 0u8 }
     }))
 }
 
+/// `sync` waits on semaphores and notify lists through bodyless functions
+/// linked to the Go runtime. They become calls to the runtime's own, which
+/// block the goroutine without holding its processor. Spins become
+/// scheduling points, so the goroutine the caller waits for gets to run.
+fn sync_runtime_hook_block(func_decl: &ast::FuncDecl) -> Option<syn::Block> {
+    if func_decl.body.is_some() {
+        return None;
+    }
+    let name = package_context::current_package_qualified_name(func_decl.name.name)?;
+    let mut params = func_decl
+        .type_
+        .params
+        .list
+        .iter()
+        .flat_map(|field| field.names.iter().flatten())
+        .map(|name| syn::Ident::new(&rust_safe_ident_name(name.name), Span::mixed_site()));
+    let reason = match name.as_str() {
+        "sync.runtime_Semacquire" => quote::quote! { Semacquire },
+        "sync.runtime_SemacquireWaitGroup" => quote::quote! { WaitGroup },
+        "sync.runtime_SemacquireMutex" => quote::quote! { Mutex },
+        "sync.runtime_SemacquireRWMutexR" => quote::quote! { RWMutexR },
+        "sync.runtime_SemacquireRWMutex" => quote::quote! { RWMutex },
+        "sync.runtime_Semrelease" => {
+            let sema = params.next()?;
+            return Some(syn::parse_quote!({
+                crate::builtin::semrelease(&#sema);
+            }));
+        }
+        "sync.runtime_notifyListAdd" => {
+            let list = params.next()?;
+            return Some(syn::parse_quote!({
+                crate::builtin::notify_list_add(&#list)
+            }));
+        }
+        "sync.runtime_notifyListWait" => {
+            let (list, ticket) = (params.next()?, params.next()?);
+            return Some(syn::parse_quote!({
+                crate::builtin::notify_list_wait(&#list, #ticket);
+            }));
+        }
+        "sync.runtime_notifyListNotifyOne" => {
+            let list = params.next()?;
+            return Some(syn::parse_quote!({
+                crate::builtin::notify_list_notify_one(&#list);
+            }));
+        }
+        "sync.runtime_notifyListNotifyAll" => {
+            let list = params.next()?;
+            return Some(syn::parse_quote!({
+                crate::builtin::notify_list_notify_all(&#list);
+            }));
+        }
+        "sync.runtime_doSpin" => {
+            return Some(syn::parse_quote!({
+                crate::builtin::gosched();
+            }));
+        }
+        _ => return None,
+    };
+    let sema = params.next()?;
+    Some(syn::parse_quote!({
+        crate::builtin::semacquire(&#sema, crate::builtin::SemaWait::#reason);
+    }))
+}
+
+/// `time.Sleep` is bodyless, linked to the Go runtime. It becomes a call to
+/// the runtime, which reports the goroutine as sleeping meanwhile.
+fn time_sleep_param(func_decl: &ast::FuncDecl) -> Option<syn::Ident> {
+    if func_decl.body.is_some()
+        || package_context::current_package_qualified_name(func_decl.name.name).as_deref()
+            != Some("time.Sleep")
+    {
+        return None;
+    }
+    let [param] = func_decl.type_.params.list.as_slice() else {
+        return None;
+    };
+    let param_name = param.names.as_ref()?.first()?;
+    Some(syn::Ident::new(
+        &rust_safe_ident_name(param_name.name),
+        Span::mixed_site(),
+    ))
+}
+
 fn generic_map_clone_func_block(
     func_decl: &ast::FuncDecl,
     type_param_info: &TypeParamInfo,
@@ -23106,7 +23509,7 @@ fn generic_map_clone_func_block(
     }
     let param_ident = syn::Ident::new(&rust_safe_ident_name(param_name.name), Span::mixed_site());
     Some(syn::parse_quote!({
-        let mut __gors_clone = std::collections::HashMap::new();
+        let mut __gors_clone = crate::builtin::GorsMap::new();
         for (__gors_k, __gors_v) in (#param_ident).iter() {
             __gors_clone.insert(__gors_k.clone(), __gors_v.clone());
         }
@@ -23568,20 +23971,28 @@ impl TryFrom<ast::Stmt<'_>> for Vec<syn::Stmt> {
             )]),
             ast::Stmt::ForStmt(s) => Ok(vec![syn::Stmt::Expr(s.try_into()?, None)]),
             ast::Stmt::GoStmt(go_stmt) => {
-                // go f(args...) => std::thread::spawn(move || { f(args...); })
-                // go func() { ... }() => std::thread::spawn(move || { ... })
+                // go f(args...) => crate::builtin::go(move || { f(args...); })
+                // go func() { ... }() => crate::builtin::go(move || { ... })
+                record_mapping(&go_stmt.go, Some("go"));
                 let call_expr = go_stmt.call;
 
                 if let ast::Expr::FuncLit(func_lit) = *call_expr.fun {
-                    // Inline the body directly into the spawn closure
+                    // Inline the body directly into the goroutine closure
                     let clones = goroutine_capture_clones(&func_lit);
-                    let block: syn::Block = func_lit.body.try_into()?;
+                    let body_has_defer = block_has_defer(&func_lit.body);
+                    let _panic_returns_through_defer =
+                        PanicReturnsThroughDeferGuard::set(body_has_defer);
+                    let mut block: syn::Block = func_lit.body.try_into()?;
+                    if body_has_defer {
+                        prepend_defer_stack(&mut block);
+                        wrap_void_defer_body_in_unwind_catch(&mut block);
+                    }
                     let stmts = &block.stmts;
                     Ok(vec![syn::Stmt::Expr(
                         syn::parse_quote! {
                             {
                                 #(#clones)*
-                                ::std::thread::spawn(move || { #(#stmts)* })
+                                crate::builtin::go(move || { #(#stmts)* })
                             }
                         },
                         Some(<Token![;]>::default()),
@@ -23601,14 +24012,14 @@ impl TryFrom<ast::Stmt<'_>> for Vec<syn::Stmt> {
                     let call: syn::Expr = ast::Expr::CallExpr(call_expr).into();
                     if clone_stmts.is_empty() {
                         Ok(vec![syn::parse_quote! {
-                            ::std::thread::spawn(move || { #call; });
+                            crate::builtin::go(move || { #call; });
                         }])
                     } else {
                         Ok(vec![syn::Stmt::Expr(
                             syn::parse_quote! {
                                 {
                                     #(#clone_stmts)*
-                                    ::std::thread::spawn(move || { #call; })
+                                    crate::builtin::go(move || { #call; })
                                 }
                             },
                             Some(<Token![;]>::default()),
@@ -23952,7 +24363,7 @@ fn compile_for_stmt(
     }
     let _init_active_local_names = scoped_active_local_names_for_stmt(init_active_local_names);
 
-    let mut body: syn::Block = for_stmt.body.try_into()?;
+    let mut body = loop_control::with_yield_point(for_stmt.body.try_into()?);
     let mut loop_label = label_ident.map(|ident| syn::Label {
         name: syn::Lifetime {
             apostrophe: Span::call_site(),
@@ -24892,16 +25303,66 @@ fn type_assert_concrete_expr(
     source_type: &typeinfer::GoType,
     source_is_borrowable: bool,
     asserted_type: syn::Type,
+    asserted_go_type: &typeinfer::GoType,
 ) -> syn::Expr {
+    let interface_name = go_runtime_type_name(source_type);
+    let asserted_name = go_runtime_type_name(asserted_go_type);
     let body = syn::parse_quote! {
         match __gors_any_option.and_then(|__gors_any| crate::builtin::any_downcast_ref::<#asserted_type>(__gors_any)) {
             Some(__v) => __v.clone(),
-            None => crate::builtin::panic_value("type assertion failed"),
+            None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::type_assertion(
+                #interface_name,
+                __gors_any_option,
+                #asserted_name,
+            )),
         }
     };
     type_assert_with_any_option(source, source_type, source_is_borrowable, body)
 }
 
+/// How Go's run-time errors name `ty`, like `int`, `*main.T` or
+/// `interface {}`.
+fn go_runtime_type_name(ty: &typeinfer::GoType) -> String {
+    match ty {
+        typeinfer::GoType::Bool => "bool".to_string(),
+        typeinfer::GoType::Int => "int".to_string(),
+        typeinfer::GoType::Int8 => "int8".to_string(),
+        typeinfer::GoType::Int16 => "int16".to_string(),
+        typeinfer::GoType::Int32 => "int32".to_string(),
+        typeinfer::GoType::Int64 => "int64".to_string(),
+        typeinfer::GoType::Uint => "uint".to_string(),
+        typeinfer::GoType::Uint8 => "uint8".to_string(),
+        typeinfer::GoType::Uint16 => "uint16".to_string(),
+        typeinfer::GoType::Uint32 => "uint32".to_string(),
+        typeinfer::GoType::Uint64 => "uint64".to_string(),
+        typeinfer::GoType::Uintptr => "uintptr".to_string(),
+        typeinfer::GoType::Float32 => "float32".to_string(),
+        typeinfer::GoType::Float64 => "float64".to_string(),
+        typeinfer::GoType::Complex64 => "complex64".to_string(),
+        typeinfer::GoType::Complex128 => "complex128".to_string(),
+        typeinfer::GoType::String => "string".to_string(),
+        typeinfer::GoType::Slice(elem) => format!("[]{}", go_runtime_type_name(elem)),
+        typeinfer::GoType::Map(key, value) => format!(
+            "map[{}]{}",
+            go_runtime_type_name(key),
+            go_runtime_type_name(value)
+        ),
+        typeinfer::GoType::Pointer(inner) => format!("*{}", go_runtime_type_name(inner)),
+        typeinfer::GoType::Chan { elem, .. } => format!("chan {}", go_runtime_type_name(elem)),
+        typeinfer::GoType::Named(name)
+        | typeinfer::GoType::Interface(name)
+        | typeinfer::GoType::Instantiated { name, .. } => {
+            package_context::qualify_interface_name(name)
+        }
+        typeinfer::GoType::Any => "interface {}".to_string(),
+        typeinfer::GoType::Error => "error".to_string(),
+        typeinfer::GoType::Array(_)
+        | typeinfer::GoType::Func { .. }
+        | typeinfer::GoType::Unit
+        | typeinfer::GoType::Unknown => ir::go_type_display_name(ty),
+    }
+}
+
 fn comma_ok_type_assert_concrete_expr(
     source: syn::Expr,
     source_type: &typeinfer::GoType,
@@ -27515,12 +27976,14 @@ func F() {}
                 import_path: String::new(),
                 ast: parse_file("main.go", main_source).unwrap(),
                 files: vec![("main.go".to_string(), main_source.to_string())],
+                go_version: None,
             },
             imports: vec![ParsedPackage {
                 name: "fmt".to_string(),
                 import_path: "example/fmt".to_string(),
                 ast: parse_file("fmt.go", local_source).unwrap(),
                 files: vec![("fmt.go".to_string(), local_source.to_string())],
+                go_version: None,
             }],
             stdlib_imports: Vec::new(),
         };
@@ -27554,6 +28017,7 @@ func F() {}
             import_path: "example/lib".to_string(),
             ast: parse_file("lib.go", local_source).unwrap(),
             files: vec![("lib.go".to_string(), local_source.to_string())],
+            go_version: None,
         };
 
         let local_plan = super::ModulePlan::local(&local_pkg, "example__lib".to_string());
@@ -27802,7 +28266,7 @@ func (o *Once) Do(f func()) {
     }
 
     #[test]
-    fn compile_program_multi_preserves_named_function_result_types() {
+    fn compile_program_multi_preserves_named_function_result_types_threaded() {
         let tmp = tempfile::tempdir().unwrap();
         write_fixture_file(
             tmp.path().join("main.go").as_path(),
@@ -27816,6 +28280,7 @@ func retry(f func([]byte) Errno) Errno {
 }
 
 func main() {
+	go func() {}()
 	_ = retry(func(buf []byte) Errno {
 		return Errno(1)
 	})
@@ -27838,6 +28303,40 @@ func main() {
         );
     }
 
+    #[test]
+    fn compile_program_multi_preserves_named_function_result_types_single_threaded() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+type Errno uintptr
+
+func retry(f func([]byte) Errno) Errno {
+	return f(nil)
+}
+
+func main() {
+	_ = retry(func(buf []byte) Errno {
+		return Errno(1)
+	})
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let main_rs = output.files.get("main.rs").unwrap();
+
+        assert!(
+            main_rs.contains("std::rc::Rc<dyn Fn(&mut [u8]) -> Errno>"),
+            "{main_rs}"
+        );
+        assert!(
+            !main_rs.contains("Send + Sync") && !main_rs.contains("dyn Fn(&mut [u8]) -> usize"),
+            "{main_rs}"
+        );
+    }
     #[test]
     fn compile_program_multi_preserves_inferred_named_static_types() {
         let tmp = tempfile::tempdir().unwrap();
@@ -27887,6 +28386,38 @@ func Run() {
         );
     }
 
+    #[test]
+    fn compile_program_multi_panics_with_nil_through_the_runtime() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+func recovered() {
+	defer func() {
+		println(recover() == nil)
+	}()
+	panic(nil)
+}
+
+func main() {
+	recovered()
+	panic((nil))
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let main_rs = output.files.get("main.rs").unwrap();
+
+        assert!(main_rs.contains("crate::builtin::panic_nil()"), "{main_rs}");
+        assert!(
+            !main_rs.contains("clone_any(&Default::default())"),
+            "{main_rs}"
+        );
+    }
+
     #[test]
     fn compile_program_multi_repanics_any_payloads_from_recover() {
         let tmp = tempfile::tempdir().unwrap();
@@ -28016,7 +28547,7 @@ func main() {
         assert_eq!(quote! { #byte_slice_ty }.to_string(), "Vec < u8 >");
         assert_eq!(
             quote! { #map_ty }.to_string(),
-            "std :: collections :: HashMap < String , u64 >"
+            "crate :: builtin :: GorsMap < String , u64 >"
         );
     }
 
@@ -28044,7 +28575,7 @@ func CopyMap[M ~map[K]V, K comparable, V any](m M) M {
 
         assert!(output.contains("pub fn CopyMap"), "{output}");
         assert!(
-            output.contains("let mut __gors_clone = std :: collections :: HashMap :: new ()")
+            output.contains("let mut __gors_clone = crate :: builtin :: GorsMap :: new ()")
                 && output.contains("for (__gors_k , __gors_v) in (m) . iter ()")
                 && output
                     .contains("__gors_clone . insert (__gors_k . clone () , __gors_v . clone ())"),
@@ -28236,7 +28767,7 @@ func main() {
 
         assert_eq!(pointer_eq, "(p) . is_nil ()");
         assert_eq!(slice_eq, "(s) . is_empty ()");
-        assert_eq!(map_ne, "! (m) . is_empty ()");
+        assert_eq!(map_ne, "! (m) . is_nil ()");
         assert_eq!(chan_ne, "! (ch) . is_nil ()");
         assert_eq!(unsafe_pointer_ne, "(u) != 0usize");
     }
@@ -31872,6 +32403,7 @@ func main() {
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec![],
@@ -31893,6 +32425,84 @@ func main() {
         assert!(!builtin_item_names.contains("append"));
     }
 
+    fn compile_program_with_go_version(
+        go_source: &'static str,
+        go_version: &str,
+    ) -> Result<super::CompiledProgram, super::CompilerError> {
+        let ast = parse_file("main.go", go_source).unwrap();
+        super::compile_program_multi(crate::parser::ParsedProgram {
+            main_package: crate::parser::ParsedPackage {
+                name: "main".to_string(),
+                import_path: String::new(),
+                ast,
+                files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: Some(go_version.to_string()),
+            },
+            imports: vec![],
+            stdlib_imports: vec![],
+        })
+    }
+
+    #[test]
+    fn compile_program_multi_rejects_syntax_newer_than_go_directive() {
+        let range_int =
+            "package main\n\nfunc main() {\n\tfor i := range 3 {\n\t\tprintln(i)\n\t}\n}\n";
+        let err = compile_program_with_go_version(range_int, "1.21")
+            .err()
+            .expect("range over int must require go1.22");
+        assert_eq!(
+            err.to_string(),
+            "unsupported construct: invalid range clause: cannot range over int: \
+             requires go1.22 or later (-lang was set to go1.21; check go.mod)"
+        );
+        assert!(compile_program_with_go_version(range_int, "1.22").is_ok());
+
+        let generic = "package main\n\nfunc id[T any](v T) T { return v }\n\nfunc main() {\n\tprintln(id(1))\n}\n";
+        let err = compile_program_with_go_version(generic, "1.17")
+            .err()
+            .expect("type parameters must require go1.18");
+        assert!(
+            err.to_string()
+                .contains("type parameter requires go1.18 or later (-lang was set to go1.17"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn compile_program_multi_shares_loop_vars_before_go1_22_threaded() {
+        let go_source = "package main\n\nfunc main() {\n\tgo func() {}()\n\tvar fs []func()\n\tfor i := 0; i < 3; i++ {\n\t\tfs = append(fs, func() { println(i) })\n\t}\n\tfor _, f := range fs {\n\t\tf()\n\t}\n}\n";
+        let render = |go_version| {
+            let compiled = compile_program_with_go_version(go_source, go_version).unwrap();
+            let main = compiled.modules.get("__main__").unwrap();
+            prettyplease::unparse(&main.file)
+        };
+        let per_iteration = render("1.22");
+        let shared = render("1.21");
+        let rebinds = |output: &str| output.matches("Arc::new").count();
+        assert!(
+            rebinds(&per_iteration) > rebinds(&shared),
+            "per-iteration:\n{per_iteration}\nshared:\n{shared}"
+        );
+        assert!(!per_iteration.contains("Rc::new"), "{per_iteration}");
+    }
+
+    #[test]
+    fn compile_program_multi_shares_loop_vars_before_go1_22_single_threaded() {
+        let go_source = "package main\n\nfunc main() {\n\tvar fs []func()\n\tfor i := 0; i < 3; i++ {\n\t\tfs = append(fs, func() { println(i) })\n\t}\n\tfor _, f := range fs {\n\t\tf()\n\t}\n}\n";
+        let render = |go_version| {
+            let compiled = compile_program_with_go_version(go_source, go_version).unwrap();
+            let main = compiled.modules.get("__main__").unwrap();
+            prettyplease::unparse(&main.file)
+        };
+        let per_iteration = render("1.22");
+        let shared = render("1.21");
+        let rebinds = |output: &str| output.matches("Rc::new").count();
+        assert!(
+            rebinds(&per_iteration) > rebinds(&shared),
+            "per-iteration:\n{per_iteration}\nshared:\n{shared}"
+        );
+        assert!(!per_iteration.contains("Arc::new"), "{per_iteration}");
+    }
     #[test]
     fn compile_program_multi_preserves_main_package_var_types() {
         let go_source = r#"package main
@@ -31915,6 +32525,7 @@ func main() {
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec![],
@@ -31948,6 +32559,7 @@ Done:
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec![],
@@ -31980,6 +32592,7 @@ func main() {
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec![],
@@ -32009,6 +32622,7 @@ func main() {
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec![],
@@ -33930,6 +34544,48 @@ func main() {
         assert!(context_rs.contains("close(&(*closedchan)"), "{context_rs}");
     }
 
+    #[test]
+    fn compile_program_multi_runs_main_under_the_runtime() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+type failure struct{}
+
+func (failure) Error() string { return "failed" }
+
+var ready = true
+
+func main() {
+	if ready {
+		panic(failure{})
+	}
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let main_rs = output.files.get("main.rs").unwrap();
+        let body = main_rs
+            .split_once("pub fn main() {")
+            .map(|(_, body)| body.trim_start())
+            .unwrap();
+        assert!(body.starts_with("crate::builtin::run_main(||"), "{main_rs}");
+        assert!(body.contains("crate::builtin::check_stack();"), "{main_rs}");
+        assert!(!main_rs.contains("sigaction"), "{main_rs}");
+        assert!(!main_rs.contains("sigwait"), "{main_rs}");
+        assert!(
+            main_rs.contains("crate::builtin::with_panic_description("),
+            "{main_rs}"
+        );
+        let builtin_rs = output.files.get("builtin.rs").unwrap();
+        assert!(builtin_rs.contains("fn fatal_panic"), "{builtin_rs}");
+        assert!(!builtin_rs.contains("struct Scheduler"), "{builtin_rs}");
+    }
+
     #[test]
     fn compile_program_multi_keeps_error_returns_owned_for_pointer_methods() {
         let tmp = tempfile::tempdir().unwrap();
@@ -34256,7 +34912,10 @@ func makeState() (state, bool) {
 	return state{value: 1}, true
 }
 
+var last *state
+
 func consume(p *state) int {
+	last = p
 	return p.value
 }
 
@@ -34308,8 +34967,11 @@ type state struct {
 	value int
 }
 
+var last *state
+
 func fill(p *state) {
 	p.value = 7
+	last = p
 }
 
 func makeState() (out state) {
@@ -34583,8 +35245,11 @@ type Header struct {
 
 type Writer struct{}
 
+var current *Writer
+
 func (w *Writer) Use(h *Header) {
 	h.Name = "used"
+	current = w
 }
 
 func main() {
@@ -34697,9 +35362,8 @@ func main() {
         let output = compile_temp_program(tmp.path());
         let main_rs = output.files.get("main.rs").unwrap();
         assert!(
-            main_rs.contains("Box::new((err).clone()) as Box<dyn std::any::Any>")
-                || main_rs
-                    .contains("Box :: new ((err) . clone ()) as Box < dyn std :: any :: Any >"),
+            main_rs.contains("crate::builtin::GorsAny::new((err).clone())")
+                || main_rs.contains("crate :: builtin :: GorsAny :: new ((err) . clone ())"),
             "{main_rs}"
         );
     }
@@ -34935,7 +35599,43 @@ func main() {
     }
 
     #[test]
-    fn compile_program_multi_boxes_interface_captures_for_stored_func_lits() {
+    fn compile_program_multi_moves_captures_into_finalizer_func_lits() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+import "runtime"
+
+type resource struct {
+	id int
+}
+
+func open(label string) {
+	r := &resource{id: 1}
+	runtime.SetFinalizer(r, func(r *resource) {
+		println(label, r.id)
+	})
+}
+
+func main() {
+	open("closed")
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let main_rs = output.files.get("main.rs").unwrap();
+        assert!(
+            main_rs.contains("move |mut r: crate::builtin::GorsPtr<resource>|"),
+            "{main_rs}"
+        );
+    }
+
+    #[test]
+    fn compile_program_multi_boxes_interface_captures_for_stored_func_lits_threaded() {
         let tmp = tempfile::tempdir().unwrap();
         write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
         write_fixture_file(
@@ -34969,6 +35669,7 @@ func save(parent Context, child canceler) {
 }
 
 func main() {
+	go func() {}()
 	save(parent{}, child{})
 }
 "#,
@@ -34990,6 +35691,62 @@ func main() {
         );
     }
 
+    #[test]
+    fn compile_program_multi_boxes_interface_captures_for_stored_func_lits_single_threaded() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+type Context interface {
+	Err() error
+}
+
+type canceler interface {
+	cancel(error, error)
+}
+
+type parent struct{}
+
+func (parent) Err() error { return nil }
+
+type child struct{}
+
+func (child) cancel(error, error) {}
+
+func keep(fn func()) {}
+
+func save(parent Context, child canceler) {
+	keep(func() {
+		child.cancel(parent.Err(), parent.Err())
+	})
+	_ = parent
+}
+
+func main() {
+	save(parent{}, child{})
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let main_rs = output.files.get("main.rs").unwrap();
+        assert!(
+            main_rs.contains("let parent__local = std::rc::Rc::new")
+                && (main_rs.contains("Context::__gors_clone_box(&*(parent__local))")
+                    || main_rs.contains("Context :: __gors_clone_box (& * (parent__local))")),
+            "{main_rs}"
+        );
+        assert!(
+            main_rs.contains("let child__local = std::rc::Rc::new")
+                && (main_rs.contains("canceler::__gors_clone_box(&*(child__local))")
+                    || main_rs.contains("canceler :: __gors_clone_box (& * (child__local))")),
+            "{main_rs}"
+        );
+    }
+
     #[test]
     fn compile_program_multi_clones_readonly_chan_captures_for_stored_func_lits() {
         let tmp = tempfile::tempdir().unwrap();
@@ -35143,8 +35900,8 @@ func main() {
         let output = compile_temp_program(tmp.path());
         let main_rs = output.files.get("main.rs").unwrap();
         assert!(
-            main_rs.contains("Box::new(Interface::__gors_clone_box(&*(arg)))")
-                || main_rs.contains("Box :: new (Interface :: __gors_clone_box (& * (arg)))"),
+            main_rs.contains("GorsAny::new(Interface::__gors_clone_box(&*(arg)))")
+                || main_rs.contains("GorsAny :: new (Interface :: __gors_clone_box (& * (arg)))"),
             "{main_rs}"
         );
         assert!(!main_rs.contains("Box::new(&mut *arg)"), "{main_rs}");
@@ -35779,7 +36536,11 @@ var Value any = "value"
             "{anyp_rs}"
         );
         assert!(
-            anyp_rs.contains("crate::builtin::box_any_comparable_send_sync(\"value\".to_string())"),
+            anyp_rs.contains("crate::builtin::ComparableProbe::new(\"value\".to_string())"),
+            "{anyp_rs}"
+        );
+        assert!(
+            anyp_rs.contains(".box_any_comparable_send_sync()"),
             "{anyp_rs}"
         );
         assert!(
@@ -35844,7 +36605,7 @@ func Read() int {
         assert!(
             state_rs.contains("pub static Env: std::sync::LazyLock")
                 && state_rs.contains(
-                    "std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, isize>>>"
+                    "std::sync::Arc<std::sync::Mutex<crate::builtin::GorsMap<String, isize>>>"
                 ),
             "{state_rs}"
         );
@@ -36025,7 +36786,7 @@ func Read() int {
     }
 
     #[test]
-    fn compile_program_multi_uses_stable_cells_for_package_var_addresses() {
+    fn compile_program_multi_uses_stable_cells_for_package_var_addresses_threaded() {
         let tmp = tempfile::tempdir().unwrap();
         write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
         write_fixture_file(
@@ -36036,6 +36797,7 @@ package main
 import "example/state"
 
 func main() {
+	go func() {}()
 	_ = state.Same()
 }
 "#,
@@ -36069,6 +36831,52 @@ func Same() bool {
         assert!(!state_rs.contains("GorsPtr::new(*Key)"), "{state_rs}");
     }
 
+    #[test]
+    fn compile_program_multi_uses_stable_cells_for_package_var_addresses_single_threaded() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+import "example/state"
+
+func main() {
+	_ = state.Same()
+}
+"#,
+        );
+        write_fixture_file(
+            tmp.path().join("state/state.go").as_path(),
+            r#"
+package state
+
+var Key int
+
+func Same() bool {
+	return &Key == &Key
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let state_rs = output.files.get("example__state.rs").unwrap();
+        assert!(
+            state_rs.contains(
+                "pub static Key : & 'static std::rc::Rc < crate\n    ::builtin::LocalMutex < isize > >"
+            ),
+            "{state_rs}"
+        );
+        assert!(
+            state_rs.contains(
+                "let __gors_address_ref = &*(crate::builtin::thread_global(&Key)).lock().unwrap();"
+            ) && state_rs.contains("__gors_address_ref as *const _ as usize"),
+            "{state_rs}"
+        );
+        assert!(!state_rs.contains("GorsPtr::new(*Key)"), "{state_rs}");
+    }
+
     #[test]
     fn compile_program_multi_passes_package_var_addresses_to_interfaces_as_cells() {
         let tmp = tempfile::tempdir().unwrap();
@@ -36224,7 +37032,7 @@ var StdEncoding = NewEncoding()
     }
 
     #[test]
-    fn compile_program_multi_locks_package_pointer_var_method_receivers() {
+    fn compile_program_multi_locks_package_pointer_var_method_receivers_threaded() {
         let tmp = tempfile::tempdir().unwrap();
         write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
         write_fixture_file(
@@ -36235,6 +37043,7 @@ package main
 import "example/debugpkg"
 
 func main() {
+	go func() {}()
 	_ = debugpkg.Debug.Value()
 	_ = debugpkg.Read()
 }
@@ -36293,6 +37102,72 @@ func Read() string {
         assert!(!debugpkg_rs.contains(".Value()"), "{debugpkg_rs}");
     }
 
+    #[test]
+    fn compile_program_multi_locks_package_pointer_var_method_receivers_single_threaded() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture_file(tmp.path().join("go.mod").as_path(), "module example\n");
+        write_fixture_file(
+            tmp.path().join("main.go").as_path(),
+            r#"
+package main
+
+import "example/debugpkg"
+
+func main() {
+	_ = debugpkg.Debug.Value()
+	_ = debugpkg.Read()
+}
+"#,
+        );
+        write_fixture_file(
+            tmp.path().join("debugpkg/debugpkg.go").as_path(),
+            r#"
+package debugpkg
+
+type Setting struct {
+	value string
+}
+
+var Debug = NewSetting("on")
+
+func NewSetting(value string) *Setting {
+	return &Setting{value: value}
+}
+
+func (s *Setting) Value() string {
+	return s.value
+}
+
+func Read() string {
+	return Debug.Value()
+}
+"#,
+        );
+
+        let output = compile_temp_program(tmp.path());
+        let main_rs = output.files.get("main.rs").unwrap();
+        let debugpkg_rs = output.files.get("example__debugpkg.rs").unwrap();
+        assert!(
+            debugpkg_rs.contains("crate::builtin::GorsPtr<Setting>"),
+            "{debugpkg_rs}"
+        );
+        let compact = |source: &str| source.split_whitespace().collect::<String>();
+        assert!(
+            compact(main_rs).contains(
+                "<debugpkg::Setting>::Value(((*crate::builtin::thread_global(&debugpkg::Debug)).clone()).clone()"
+            ),
+            "{main_rs}"
+        );
+        assert!(!main_rs.contains(".Value()"), "{main_rs}");
+        assert!(
+            compact(debugpkg_rs).contains(
+                "<Setting>::Value(((crate::builtin::thread_global(&Debug)).lock().unwrap().clone()).clone()"
+            ),
+            "{debugpkg_rs}"
+        );
+        assert!(!debugpkg_rs.contains(".Value()"), "{debugpkg_rs}");
+    }
+
     #[test]
     fn compile_program_multi_resolves_imported_constructor_pointer_var_method_receivers() {
         let tmp = tempfile::tempdir().unwrap();
@@ -36733,8 +37608,11 @@ func (v Value) AssignStep() int {
 	return x
 }
 
+var last *Value
+
 func (v *Value) step() int {
 	v.n++
+	last = v
 	return v.n
 }
 "#,
@@ -37851,6 +38729,27 @@ func main() {
         assert!(!output.contains("struct Dropped"), "{output}");
     }
 
+    #[test]
+    fn reachable_items_skip_library_path_segments_in_macro_tokens() {
+        let mut file: syn::File = syn::parse_quote! {
+            fn string() {}
+            struct Kept;
+
+            fn root() {
+                clone_if!(Vec<std::string::String>);
+                clone_if!(Kept);
+            }
+        };
+        let roots = std::collections::HashSet::from(["root".to_string()]);
+        let module_names = std::collections::HashSet::new();
+
+        super::prune_items_to_roots(&mut file.items, &roots, &module_names);
+        let output = quote::quote!(#file).to_string();
+
+        assert!(output.contains("struct Kept"), "{output}");
+        assert!(!output.contains("fn string"), "{output}");
+    }
+
     #[test]
     fn builtin_root_expansion_keeps_reflect_slice_swapper_methods() {
         let roots = std::collections::HashSet::from(["reflect_value_swapper".to_string()]);
@@ -37903,6 +38802,24 @@ func main() {
         }
     }
 
+    #[test]
+    fn builtin_root_expansion_keeps_finalizers_and_cycle_collector_for_runtime_gc() {
+        let roots =
+            std::collections::HashSet::from(["set_finalizer".to_string(), "gc".to_string()]);
+        let expanded = super::builtin_roots::expand(&roots);
+
+        for expected in [
+            "FinalizerFunc",
+            "impl Finalizer for PendingFinalizer",
+            "impl FinalizerFunc for GorsFinalizerValue",
+            "run_finalizers",
+            "collect_cycles",
+            "impl GcObject for Mutex",
+        ] {
+            assert!(expanded.contains(expected), "{expanded:?}");
+        }
+    }
+
     #[test]
     fn builtin_root_expansion_keeps_string_byte_sequence_bounds() {
         let roots = std::collections::HashSet::from(["string_from_byte_seq".to_string()]);
@@ -38043,7 +38960,10 @@ const Header = "hello, world"
         let main_rs = output.files.get("main.rs").unwrap();
         assert!(main_rs.contains("config::Header()"), "{main_rs}");
         assert!(main_rs.contains("&config::Header()"), "{main_rs}");
-        assert!(main_rs.contains("(config::Header())[.."), "{main_rs}");
+        assert!(
+            main_rs.contains("(config::Header())[crate::builtin::bounds(.."),
+            "{main_rs}"
+        );
         assert!(!main_rs.contains("config::Header[.."), "{main_rs}");
     }
 
@@ -38226,6 +39146,7 @@ func main() {
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec![
@@ -38269,6 +39190,7 @@ func main() {
                 import_path: String::new(),
                 ast,
                 files: vec![("main.go".to_string(), go_source.to_string())],
+                go_version: None,
             },
             imports: vec![],
             stdlib_imports: vec!["fmt".to_string(), "os".to_string()],
@@ -39587,13 +40509,50 @@ func main() {
             "#,
             rust! {
                 #[derive(Clone, Default)]
-                pub struct Dict(pub std::collections::HashMap<String, isize>);
+                pub struct Dict(pub crate::builtin::GorsMap<String, isize>);
                 impl std::ops::Deref for Dict {
-                    type Target = std::collections::HashMap<String, isize>;
-                    fn deref(&self) -> &std::collections::HashMap<String, isize> { &self.0 }
+                    type Target = crate::builtin::GorsMap<String, isize>;
+                    fn deref(&self) -> &crate::builtin::GorsMap<String, isize> { &self.0 }
                 }
                 impl std::ops::DerefMut for Dict {
-                    fn deref_mut(&mut self) -> &mut std::collections::HashMap<String, isize> { &mut self.0 }
+                    fn deref_mut(&mut self) -> &mut crate::builtin::GorsMap<String, isize> { &mut self.0 }
+                }
+                impl crate::builtin::Len for Dict {
+                    fn len_value(&self) -> usize { self.0.len() }
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn it_should_compile_named_map_literal_with_entries() {
+        test(
+            r#"
+                package main
+
+                type Dict map[string]int
+
+                func main() {
+                    d := Dict{"a": 1}
+                    d["b"] = 2
+                }
+            "#,
+            rust! {
+                #[derive(Clone, Default)]
+                pub struct Dict(pub crate::builtin::GorsMap<String, isize>);
+                impl std::ops::Deref for Dict {
+                    type Target = crate::builtin::GorsMap<String, isize>;
+                    fn deref(&self) -> &crate::builtin::GorsMap<String, isize> { &self.0 }
+                }
+                impl std::ops::DerefMut for Dict {
+                    fn deref_mut(&mut self) -> &mut crate::builtin::GorsMap<String, isize> { &mut self.0 }
+                }
+                impl crate::builtin::Len for Dict {
+                    fn len_value(&self) -> usize { self.0.len() }
+                }
+                pub fn main() {
+                    let mut d = Dict(crate::builtin::GorsMap::from([("a".to_string(), 1)]));
+                    (d).insert("b".to_string(), 2);
                 }
             },
         );
@@ -39612,7 +40571,7 @@ func main() {
             "#,
             rust! {
                 pub fn main() {
-                    let mut m = std::collections::HashMap::<String, isize>::new();
+                    let mut m = crate::builtin::GorsMap::<String, isize>::new();
                     (m).insert("a".to_string(), 1);
                 }
             },
@@ -39711,7 +40670,7 @@ func main() {
         assert!(
             rust_src.contains("let mut values = Dict(")
                 && rust_src.contains(
-                    "std::collections::HashMap::<String, isize>::with_capacity((4) as usize)"
+                    "crate::builtin::GorsMap::<String, isize>::with_capacity((4) as usize)"
                 ),
             "expected make of named map to allocate and wrap the map: {rust_src}"
         );
@@ -39731,7 +40690,7 @@ func main() {
             "#,
             rust! {
                 pub fn main() {
-                    let mut m = std::collections::HashMap::from([("a".to_string(), 1)]);
+                    let mut m = crate::builtin::GorsMap::from([("a".to_string(), 1)]);
                     *m.entry("a".to_string()).or_default() += 1;
                     *m.entry("b".to_string()).or_default() -= 1;
                 }
@@ -39752,7 +40711,7 @@ func main() {
             "#,
             rust! {
                 pub fn main() {
-                    let mut m = std::collections::HashMap::from([("a".to_string(), 1)]);
+                    let mut m = crate::builtin::GorsMap::from([("a".to_string(), 1)]);
                     let _ = {
                         let __gors_map_key = "missing".to_string();
                         (m).get(&__gors_map_key).cloned().unwrap_or_default()
@@ -39775,7 +40734,7 @@ func main() {
             "#,
             rust! {
                 pub fn main() {
-                    let mut m = std::collections::HashMap::from([("a".to_string(), 1)]);
+                    let mut m = crate::builtin::GorsMap::from([("a".to_string(), 1)]);
                     let _ = m;
                 }
             },
@@ -39797,7 +40756,7 @@ func main() {
             "#,
             rust! {
                 pub fn main() {
-                    let mut m = std::collections::HashMap::from([("alice".to_string(), 25)]);
+                    let mut m = crate::builtin::GorsMap::from([("alice".to_string(), 25)]);
                     let (mut val, mut ok) = {
                         let __gors_map_key = "alice".to_string();
                         match (m).get(&__gors_map_key) {
@@ -39912,8 +40871,11 @@ func main() {
                     Value int
                 }
 
+                var last *Counter
+
                 func (c *Counter) Increment() {
                     c.Value = c.Value + 1
+                    last = c
                 }
 
                 func main() {
@@ -39997,8 +40959,11 @@ func main() {
                     Value int
                 }
 
+                var last *Counter
+
                 func (c *Counter) Increment() {
                     c.Value = c.Value + 1
+                    last = c
                 }
 
                 func (c Counter) Next() int {
@@ -40044,8 +41009,11 @@ func main() {
                     Value int
                 }
 
+                var last *Counter
+
                 func (c *Counter) Increment() {
                     c.Value = c.Value + 1
+                    last = c
                 }
 
                 func Bump(c Counter) int {
@@ -40085,8 +41053,11 @@ func main() {
                     Value int
                 }
 
+                var last *Counter
+
                 func (c *Counter) Increment() {
                     c.Value = c.Value + 1
+                    last = c
                 }
 
                 func (c Counter) Add(other Counter) int {
@@ -40733,7 +41704,7 @@ func main() {
         );
         assert!(
             compact.contains("b={let__gors_slice_high=")
-                && compact.contains("&mut(b)[..__gors_slice_high]"),
+                && compact.contains("&mut(b)[crate::builtin::bounds(..__gors_slice_high)]"),
             "{main_rs}"
         );
         assert!(
@@ -40817,6 +41788,7 @@ func main() {
                 pub fn main() {
                     let mut s = Vec::from([1, 2, 3]);
                     for (mut i, mut v) in (s).iter().cloned().enumerate().map(|(i, v)| (i as isize, v)) {
+                        crate::builtin::yield_point();
                         let mut x = i + v;
                         let _ = x;
                     }
@@ -40954,11 +41926,12 @@ func main() {
             rust! {
                 pub fn Index<E: PartialEq + Clone + Default>(mut s: &mut [E], mut v: E) -> isize {
                     for mut i in 0..(crate::builtin::len(&s) as isize) {
+                        crate::builtin::yield_point();
                         if v == {
                             let __gors_index_base = &s;
                             let __gors_index = (i) as usize;
                             if __gors_index >= __gors_index_base.len() {
-                                crate::builtin::panic_value("index out of range");
+                                crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                             }
                             (__gors_index_base[__gors_index]).clone()
                         } {
@@ -41025,7 +41998,10 @@ func main() {
             rust! {
                 pub fn main() {
                     let mut s = Vec::from([1, 2, 3]);
-                    let mut t = ((s)[(1usize) as usize..(2usize) as usize]).to_vec();
+                    let mut t = ((s)[crate::builtin::bounds(
+                        (1usize) as usize..(2usize) as usize
+                    )])
+                    .to_vec();
                     let _ = t;
                 }
             },
@@ -41059,7 +42035,9 @@ func main() {
             "expected pointer-to-array full slice to bind the pointer guard: {output}"
         );
         assert!(
-            output.contains("(& * __gors_source) [__gors_start .. __gors_end]"),
+            output.contains(
+                "(& * __gors_source) [crate :: builtin :: bounds (__gors_start .. __gors_end)]"
+            ),
             "expected pointer-to-array full slice to index through the guarded array: {output}"
         );
         assert!(
@@ -41115,7 +42093,7 @@ func main() {
             rust! {
                 pub fn main() {
                     let mut s = Vec::from([1, 2, 3]);
-                    let mut t = ((s)[(1usize) as usize..]).to_vec();
+                    let mut t = ((s)[crate::builtin::bounds((1usize) as usize..)]).to_vec();
                     {
                         let __gors_slice_alias_value = 9;
                         (t)[(0usize) as usize] = __gors_slice_alias_value;
@@ -41453,7 +42431,8 @@ func main() {
                     s = {
                         let __gors_slice_high = ((crate::builtin::len(&s) as isize) - 1) as usize;
                         let __gors_slice_source = std::mem::take(&mut s);
-                        ((__gors_slice_source)[..__gors_slice_high]).to_string()
+                        ((__gors_slice_source)[crate::builtin::bounds(..__gors_slice_high)])
+                            .to_string()
                     };
                 }
             },
@@ -41541,7 +42520,9 @@ func main() {
                     data []byte
                 }
 
-                func use(*holder) {}
+                var last *holder
+
+                func use(h *holder) { last = h }
 
                 func main() {
                     var h holder
@@ -41632,9 +42613,11 @@ func main() {
         let main_rs = output.files.get("main.rs").unwrap();
 
         assert!(
-            main_rs.contains("fill(&mut (h.data)[..(n) as usize])")
-                || main_rs.contains("fill(&mut ((h.data)[..(n) as usize]))")
-                || main_rs.contains("fill(&mut ((h).data)[..(n) as usize])")
+            main_rs.contains("fill(&mut (h.data)[crate::builtin::bounds(..(n) as usize)])")
+                || main_rs
+                    .contains("fill(&mut ((h.data)[crate::builtin::bounds(..(n) as usize)]))")
+                || main_rs
+                    .contains("fill(&mut ((h).data)[crate::builtin::bounds(..(n) as usize)])")
                 || main_rs.contains("fill (& mut (h . data) [.. (n) as usize])"),
             "expected borrowed sliced field argument to use the selector lvalue: {main_rs}"
         );
@@ -41672,8 +42655,9 @@ func main() {
         let main_rs = output.files.get("main.rs").unwrap();
 
         assert!(
-            main_rs.contains(".lock().unwrap().data)[..(n) as usize]")
-                || main_rs.contains(".lock().unwrap()).data)[..(n) as usize]")
+            main_rs.contains(".lock().unwrap().data)[crate::builtin::bounds(..(n) as usize)]")
+                || main_rs
+                    .contains(".lock().unwrap()).data)[crate::builtin::bounds(..(n) as usize)]")
                 || main_rs.contains(". lock () . unwrap () . data) [.. (n) as usize]"),
             "expected borrowed sliced pointer field argument to use the locked selector lvalue: {main_rs}"
         );
@@ -43567,7 +44551,7 @@ func (sink) Write(p []byte) (int, error) {
             "#,
             rust! {
                 pub fn main() {
-                    let mut m = std::collections::HashMap::from([("a".to_string(), 1)]);
+                    let mut m = crate::builtin::GorsMap::from([("a".to_string(), 1)]);
                     {
                         let __gors_delete_key = "a".to_string();
                         crate::builtin::delete(&mut m, &__gors_delete_key)
@@ -45038,7 +46022,7 @@ func (sink) Write(p []byte) (int, error) {
             "#,
             rust! {
                 pub fn main() {
-                    let mut x: Box<dyn std::any::Any> = crate::builtin::box_any_comparable((42 as isize));
+                    let mut x: crate::builtin::GorsAny = crate::builtin::box_any_comparable((42 as isize));
                     let _ = x;
                 }
             },
@@ -45088,10 +46072,7 @@ func (sink) Write(p []byte) (int, error) {
                         struct __GorsDeferStack(Vec<Box<dyn FnOnce()>>);
                         impl Drop for __GorsDeferStack {
                             fn drop(&mut self) {
-                                while let Some(__gors_defer) = self.0.pop() {
-                                    __gors_defer();
-                                }
-                                crate::builtin::resume_unrecovered_panic();
+                                crate::builtin::run_deferred(&mut self.0);
                             }
                         }
                         __GorsDeferStack(Vec::new())
@@ -45866,7 +46847,7 @@ func main() {
             "#,
         );
         assert!(
-            rust_src.contains("std::thread::park();"),
+            rust_src.contains("crate::builtin::block_forever();"),
             "Expected empty select to park forever:\n{}",
             rust_src
         );
@@ -45897,12 +46878,47 @@ func main() {
             "#,
         );
         assert!(
-            rust_src.contains("try_recv"),
-            "Expected try_recv in output:\n{}",
+            rust_src.contains("crate::builtin::select("),
+            "Expected runtime select in output:\n{}",
             rust_src
         );
     }
 
+    #[test]
+    fn it_should_lower_select_cases_to_the_runtime_select() {
+        let rust_src = go_to_rust(
+            r#"
+            package main
+
+            func main() {
+                src := make(chan int)
+                dst := make(chan int)
+                select {
+                case v, ok := <-src:
+                    println(v, ok)
+                case dst <- 1:
+                    println("sent")
+                }
+            }
+            "#,
+        );
+        for expected in [
+            "let mut __gors_select_case_0 = crate::builtin::SelectRecv::new(&(src));",
+            "let mut __gors_select_case_1 = crate::builtin::SelectSend::new(&(dst), 1);",
+            "&mut [&mut __gors_select_case_0, &mut __gors_select_case_1],\n                false,",
+            ".received();",
+        ] {
+            assert!(
+                rust_src.contains(expected),
+                "Expected {expected:?} in output:\n{rust_src}"
+            );
+        }
+        assert!(
+            !rust_src.contains("gosched") && !rust_src.contains("try_recv"),
+            "Expected select not to poll:\n{rust_src}"
+        );
+    }
+
     #[test]
     fn it_should_compile_go_stmt_with_func_lit() {
         let rust_src = go_to_rust(
@@ -45917,8 +46933,8 @@ func main() {
             "#,
         );
         assert!(
-            rust_src.contains("spawn"),
-            "Expected spawn in output:\n{}",
+            rust_src.contains("crate::builtin::go"),
+            "Expected builtin::go in output:\n{}",
             rust_src
         );
         assert!(
@@ -45928,6 +46944,31 @@ func main() {
         );
     }
 
+    #[test]
+    fn it_should_declare_the_defer_stack_of_go_func_lits() {
+        let rust_src = go_to_rust(
+            r#"
+            package main
+
+            func done() {}
+
+            func main() {
+                go func() {
+                    defer done()
+                }()
+            }
+            "#,
+        );
+        let decl_pos = rust_src
+            .find("let mut __gors_defer_stack")
+            .expect("expected the goroutine to declare its defer stack");
+        let push_pos = rust_src
+            .rfind("__gors_defer_stack")
+            .expect("expected the deferred call to be pushed");
+        assert!(decl_pos < push_pos, "{rust_src}");
+        assert!(rust_src.contains("catch_unwind"), "{rust_src}");
+    }
+
     #[test]
     fn it_should_clone_interface_captures_for_go_func_lits() {
         let rust_src = go_to_rust(
@@ -45989,10 +47030,12 @@ func main() {
         let target_pos = rust_src
             .find("__gors_go_func_target")
             .expect("expected goroutine function target binding");
-        let spawn_pos = rust_src.find("spawn").expect("expected thread spawn");
+        let go_pos = rust_src
+            .find("crate::builtin::go")
+            .expect("expected goroutine start");
         assert!(
-            target_pos < spawn_pos,
-            "Expected function target evaluation before spawn:\n{}",
+            target_pos < go_pos,
+            "Expected function target evaluation before go:\n{}",
             rust_src
         );
         assert!(
@@ -46022,8 +47065,63 @@ func main() {
         assert!(
             rust_src.contains("let __gors_go_func_target")
                 && rust_src.contains("h.lock().unwrap().f")
-                && rust_src.contains("::std::thread::spawn"),
-            "Expected selector function target evaluation before spawn:\n{}",
+                && rust_src.contains("crate::builtin::go"),
+            "Expected selector function target evaluation before go:\n{}",
+            rust_src
+        );
+    }
+
+    #[test]
+    fn it_should_emit_yield_points_at_loop_back_edges() {
+        let rust_src = go_to_rust(
+            r#"
+            package main
+            func main() {
+                n := 0
+                for n < 10 {
+                    n++
+                }
+                for range 3 {
+                    n--
+                }
+            }
+            "#,
+        );
+        assert_eq!(
+            rust_src.matches("crate::builtin::yield_point();").count(),
+            2,
+            "Expected a yield point in each loop body:\n{}",
+            rust_src
+        );
+    }
+
+    #[test]
+    fn it_should_lower_sync_runtime_waits_to_runtime_semaphores() {
+        let rust_src = go_to_rust(
+            r#"
+            package sync
+            type notifyList struct{ wait uint32 }
+            func runtime_SemacquireMutex(s *uint32, lifo bool, skipframes int)
+            func runtime_Semrelease(s *uint32, handoff bool, skipframes int)
+            func runtime_notifyListWait(l *notifyList, t uint32)
+            func runtime_canSpin(i int) bool
+            func runtime_doSpin()
+            "#,
+        );
+        for expected in [
+            "crate::builtin::semacquire(&s, crate::builtin::SemaWait::Mutex);",
+            "crate::builtin::semrelease(&s);",
+            "crate::builtin::notify_list_wait(&l, t);",
+        ] {
+            assert!(
+                rust_src.contains(expected),
+                "Expected {expected}:\n{rust_src}"
+            );
+        }
+        assert_eq!(
+            rust_src.matches("gosched").count(),
+            1,
+            "Expected only spins to yield:\n{}",
             rust_src
         );
     }
@@ -46040,8 +47138,8 @@ func main() {
             "#,
         );
         assert!(
-            rust_src.contains("spawn"),
-            "Expected spawn in output:\n{}",
+            rust_src.contains("crate::builtin::go(move ||"),
+            "Expected builtin::go in output:\n{}",
             rust_src
         );
     }
@@ -46261,7 +47359,7 @@ func main() {
                             let __gors_func = crate::builtin::lock_func(&__gors_func_target);
                             match __gors_func.as_ref() {
                                 Some(__gors_func) => __gors_func.clone(),
-                                None => crate::builtin::panic_value("nil function"),
+                                None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::nil_dereference()),
                             }
                         };
                         (&*__gors_func)(
@@ -46269,7 +47367,7 @@ func main() {
                                 let __gors_index_base = &data;
                                 let __gors_index = (i) as usize;
                                 if __gors_index >= __gors_index_base.len() {
-                                    crate::builtin::panic_value("index out of range");
+                                    crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                                 }
                                 (__gors_index_base[__gors_index]).clone()
                             }).clone(),
@@ -46277,7 +47375,7 @@ func main() {
                                 let __gors_index_base = &data;
                                 let __gors_index = (i - 1) as usize;
                                 if __gors_index >= __gors_index_base.len() {
-                                    crate::builtin::panic_value("index out of range");
+                                    crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::index(__gors_index, __gors_index_base.len()));
                                 }
                                 (__gors_index_base[__gors_index]).clone()
                             }).clone()
@@ -46512,7 +47610,7 @@ func main() {}
         assert!(output.contains("pub struct Store<K, V>"), "{output}");
         assert!(!output.contains("pub struct Store<K:"), "{output}");
         assert!(
-            output.contains("m: Store<Box<dyn std::any::Any>, Box<dyn std::any::Any>>"),
+            output.contains("m: Store<crate::builtin::GorsAny, crate::builtin::GorsAny>"),
             "{output}"
         );
         assert!(
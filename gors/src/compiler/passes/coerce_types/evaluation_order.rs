@@ -3,7 +3,7 @@ use syn::visit_mut::{self, VisitMut};
 use super::super::super::{
     syn_inspect::{
         expr_contains_method_call, expr_contains_path_ident, mut_borrowed_path_name,
-        receiver_root_ident_name,
+        receiver_root_ident_name, slice_index_range_mut,
     },
     synthetic_names,
 };
@@ -112,7 +112,7 @@ fn hoist_slice_bounds_with_generated_locks(expr: &mut syn::Expr, hoisted: &mut V
             hoist_slice_bounds_with_generated_locks(&mut paren.expr, hoisted);
         }
         syn::Expr::Index(index) => {
-            if let syn::Expr::Range(range) = index.index.as_mut() {
+            if let Some(range) = slice_index_range_mut(&mut index.index) {
                 hoist_range_bound_with_generated_locks(&mut range.start, hoisted);
                 hoist_range_bound_with_generated_locks(&mut range.end, hoisted);
             }
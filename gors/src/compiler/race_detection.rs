@@ -0,0 +1,483 @@
+use std::collections::BTreeMap;
+
+use super::{
+    CompiledModule, gors_ptr_inner_type,
+    syn_inspect::{is_path_call_expr, path_starts_with, type_path_ident_name},
+};
+
+/// The standard library packages whose accesses to shared cells are
+/// synchronization operations: the atomics, and the locks built on them.
+const SYNC_MODULES: &[&str] = &["sync", "sync__atomic"];
+
+/// Methods of the values in shared cells which modify them.
+const MUTATING_METHODS: &[&str] = &[
+    "clear", "extend", "insert", "pop", "push", "push_str", "remove", "truncate",
+];
+
+/// Instruments the local packages for the race detector, like `-race`.
+///
+/// Their accesses to shared cells (variables captured by reference,
+/// pointed-to values and package variables) are checked by the runtime,
+/// which tracks the happens-before order `go` statements and channel
+/// operations establish between goroutines.
+pub(super) fn instrument_local_packages(modules: &mut BTreeMap<String, CompiledModule>) {
+    use syn::visit_mut::VisitMut;
+
+    for module in modules
+        .values_mut()
+        .filter(|module| module.mod_name != "builtin" && !module.is_stdlib)
+    {
+        let package = if module.is_main {
+            "main".to_string()
+        } else {
+            module.mod_name.replace("__", "/")
+        };
+        AccessInstrumenter {
+            package,
+            names: Vec::new(),
+            closures: 0,
+        }
+        .visit_file_mut(&mut module.file);
+        if module.is_main {
+            enable_in_main(&mut module.file);
+        }
+    }
+}
+
+/// Makes the accesses of `sync` and `sync/atomic` to shared cells
+/// synchronize goroutines, once their runtime replacements are injected.
+pub(super) fn instrument_sync_packages(modules: &mut BTreeMap<String, CompiledModule>) {
+    use syn::visit_mut::VisitMut;
+
+    struct SyncAccesses;
+
+    impl VisitMut for SyncAccesses {
+        fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+            syn::visit_mut::visit_expr_mut(self, expr);
+            if let Some(cell) = locked_cell_mut(expr) {
+                *cell = syn::parse_quote!(crate::builtin::race_sync(&#cell));
+            }
+        }
+    }
+
+    for module in modules
+        .values_mut()
+        .filter(|module| module.is_stdlib && SYNC_MODULES.contains(&module.mod_name.as_str()))
+    {
+        SyncAccesses.visit_file_mut(&mut module.file);
+    }
+}
+
+fn enable_in_main(file: &mut syn::File) {
+    for item in &mut file.items {
+        if let syn::Item::Fn(func) = item
+            && func.sig.ident == "main"
+        {
+            func.block.stmts.insert(
+                0,
+                syn::parse_quote! {
+                    crate::builtin::enable_race_detection();
+                },
+            );
+            return;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// Checks the accesses to shared cells, on behalf of the Go function the
+/// code comes from. Functions are named like the runtime names them in
+/// goroutine stacks: function literals after the function they are in.
+struct AccessInstrumenter {
+    package: String,
+    /// The functions and types the visited code is in.
+    names: Vec<String>,
+    /// The closures the visited code is in.
+    closures: usize,
+}
+
+impl AccessInstrumenter {
+    fn function(&self) -> syn::LitStr {
+        let mut name = format!("{}.{}", self.package, self.names.join("."));
+        // The body of `main` is a closure run by `run_main`.
+        let closures = if self.package == "main" && self.names == ["main"] {
+            self.closures.saturating_sub(1)
+        } else {
+            self.closures
+        };
+        for level in 0..closures {
+            name.push_str(if level == 0 { ".func1" } else { ".1" });
+        }
+        syn::LitStr::new(&name, proc_macro2::Span::mixed_site())
+    }
+
+    /// Instruments `expr` if it accesses a shared cell: `cell.lock().unwrap()`
+    /// followed by dereferences, field accesses and indexing.
+    fn instrument_place(&mut self, expr: &mut syn::Expr, access: Access) -> bool {
+        if !is_shared_place(expr) {
+            return false;
+        }
+        self.instrument_shared_place(expr, String::new(), access);
+        true
+    }
+
+    /// Instruments an access to `field` of a shared cell, or to the whole
+    /// value when `field` is empty.
+    fn instrument_shared_place(&mut self, expr: &mut syn::Expr, field: String, access: Access) {
+        use syn::visit_mut::VisitMut;
+
+        match expr {
+            syn::Expr::Paren(paren) => self.instrument_shared_place(&mut paren.expr, field, access),
+            syn::Expr::Group(group) => self.instrument_shared_place(&mut group.expr, field, access),
+            syn::Expr::Unary(unary) => self.instrument_shared_place(&mut unary.expr, field, access),
+            syn::Expr::Field(expr_field) => {
+                let field = match &expr_field.member {
+                    syn::Member::Named(ident) => ident.to_string(),
+                    syn::Member::Unnamed(index) => index.index.to_string(),
+                };
+                self.instrument_shared_place(&mut expr_field.base, field, access);
+            }
+            // Indexing reads the slice header of the cell; the elements are
+            // not tracked.
+            syn::Expr::Index(index) => {
+                self.visit_expr_mut(&mut index.index);
+                self.instrument_shared_place(&mut index.expr, String::new(), Access::Read);
+            }
+            _ => {
+                let Some(cell) = locked_cell_mut(expr) else {
+                    return;
+                };
+                if !self.instrument_place(cell, Access::Read) {
+                    self.visit_expr_mut(cell);
+                }
+                let field = syn::LitStr::new(&field, proc_macro2::Span::mixed_site());
+                let function = self.function();
+                *cell = match access {
+                    Access::Read => {
+                        syn::parse_quote!(crate::builtin::race_read(&#cell, #field, #function))
+                    }
+                    Access::Write => {
+                        syn::parse_quote!(crate::builtin::race_write(&#cell, #field, #function))
+                    }
+                };
+            }
+        }
+    }
+
+    fn visit_written_place(&mut self, expr: &mut syn::Expr) {
+        use syn::visit_mut::VisitMut;
+
+        if !self.instrument_place(expr, Access::Write) {
+            self.visit_expr_mut(expr);
+        }
+    }
+}
+
+impl syn::visit_mut::VisitMut for AccessInstrumenter {
+    fn visit_item_fn_mut(&mut self, item: &mut syn::ItemFn) {
+        self.names.push(item.sig.ident.to_string());
+        syn::visit_mut::visit_item_fn_mut(self, item);
+        self.names.pop();
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut syn::ItemImpl) {
+        let self_type = match gors_ptr_inner_type(&item.self_ty) {
+            Some(pointee) => format!("(*{})", type_path_ident_name(&pointee).unwrap_or_default()),
+            None => type_path_ident_name(&item.self_ty).unwrap_or_default(),
+        };
+        self.names.push(self_type);
+        syn::visit_mut::visit_item_impl_mut(self, item);
+        self.names.pop();
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, item: &mut syn::ImplItemFn) {
+        // Methods with pointer receivers take them as `GorsPtr<Self>`.
+        let pointer_receiver = matches!(
+            item.sig.inputs.first(),
+            Some(syn::FnArg::Typed(receiver))
+                if gors_ptr_inner_type(&receiver.ty)
+                    .is_some_and(|pointee| type_path_ident_name(&pointee).as_deref() == Some("Self"))
+        );
+        let self_type = self.names.last().cloned();
+        if pointer_receiver && let Some(name) = self.names.last_mut() {
+            *name = format!("(*{name})");
+        }
+        self.names.push(item.sig.ident.to_string());
+        syn::visit_mut::visit_impl_item_fn_mut(self, item);
+        self.names.pop();
+        if let (Some(name), Some(self_type)) = (self.names.last_mut(), self_type) {
+            *name = self_type;
+        }
+    }
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut syn::ExprClosure) {
+        self.closures += 1;
+        syn::visit_mut::visit_expr_closure_mut(self, closure);
+        self.closures -= 1;
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        match expr {
+            syn::Expr::Assign(assign) => {
+                self.visit_written_place(&mut assign.left);
+                self.visit_expr_mut(&mut assign.right);
+            }
+            syn::Expr::Binary(binary) if is_compound_assign(&binary.op) => {
+                self.visit_written_place(&mut binary.left);
+                self.visit_expr_mut(&mut binary.right);
+            }
+            syn::Expr::Reference(reference) if reference.mutability.is_some() => {
+                self.visit_written_place(&mut reference.expr);
+            }
+            syn::Expr::MethodCall(call)
+                if MUTATING_METHODS.contains(&call.method.to_string().as_str()) =>
+            {
+                self.visit_written_place(&mut call.receiver);
+                for arg in &mut call.args {
+                    self.visit_expr_mut(arg);
+                }
+            }
+            syn::Expr::Call(call) if is_path_call_expr(&call.func, &["crate", "builtin", "go"]) => {
+                let function = self.function();
+                syn::visit_mut::visit_expr_call_mut(self, call);
+                if let Some(body) = call.args.first_mut() {
+                    *body = syn::parse_quote!(crate::builtin::race_go(#function, #body));
+                }
+            }
+            syn::Expr::Call(call)
+                if is_path_call_expr(&call.func, &["std", "panic", "catch_unwind"]) =>
+            {
+                // Functions with `defer` run their body in this closure,
+                // which is not a function literal of the Go code.
+                for arg in &mut call.args {
+                    match unwind_safe_closure_body(arg) {
+                        Some(body) => self.visit_expr_mut(body),
+                        None => self.visit_expr_mut(arg),
+                    }
+                }
+            }
+            syn::Expr::Call(call) if is_cell_allocation(call) => {
+                syn::visit_mut::visit_expr_call_mut(self, call);
+                *expr = syn::parse_quote!(crate::builtin::race_new(#expr));
+            }
+            _ => {
+                if !self.instrument_place(expr, Access::Read) {
+                    syn::visit_mut::visit_expr_mut(self, expr);
+                }
+            }
+        }
+    }
+}
+
+/// The body of the closure in `std::panic::AssertUnwindSafe(|| body)`.
+fn unwind_safe_closure_body(expr: &mut syn::Expr) -> Option<&mut syn::Expr> {
+    let syn::Expr::Call(call) = expr else {
+        return None;
+    };
+    if !is_path_call_expr(&call.func, &["std", "panic", "AssertUnwindSafe"]) {
+        return None;
+    }
+    match call.args.first_mut()? {
+        syn::Expr::Closure(closure) => Some(&mut closure.body),
+        _ => None,
+    }
+}
+
+const fn is_compound_assign(op: &syn::BinOp) -> bool {
+    matches!(
+        op,
+        syn::BinOp::AddAssign(_)
+            | syn::BinOp::SubAssign(_)
+            | syn::BinOp::MulAssign(_)
+            | syn::BinOp::DivAssign(_)
+            | syn::BinOp::RemAssign(_)
+            | syn::BinOp::BitXorAssign(_)
+            | syn::BinOp::BitAndAssign(_)
+            | syn::BinOp::BitOrAssign(_)
+            | syn::BinOp::ShlAssign(_)
+            | syn::BinOp::ShrAssign(_)
+    )
+}
+
+/// Whether `expr` is `cell.lock().unwrap()`, followed by dereferences,
+/// field accesses and indexing.
+fn is_shared_place(mut expr: &syn::Expr) -> bool {
+    loop {
+        expr = match expr {
+            syn::Expr::Paren(paren) => &paren.expr,
+            syn::Expr::Group(group) => &group.expr,
+            syn::Expr::Unary(unary) if matches!(unary.op, syn::UnOp::Deref(_)) => &unary.expr,
+            syn::Expr::Field(field) => &field.base,
+            syn::Expr::Index(index) => &index.expr,
+            _ => return locked_cell(expr).is_some(),
+        };
+    }
+}
+
+/// The cell of `cell.lock().unwrap()`, when it is a variable, a field or
+/// a package variable, rather than a cell of the runtime.
+fn locked_cell(expr: &syn::Expr) -> Option<&syn::Expr> {
+    let syn::Expr::MethodCall(unwrap) = expr else {
+        return None;
+    };
+    let syn::Expr::MethodCall(lock) = unwrap.receiver.as_ref() else {
+        return None;
+    };
+    if unwrap.method != "unwrap" || lock.method != "lock" || !unwrap.args.is_empty() {
+        return None;
+    }
+    if !lock.args.is_empty() {
+        return None;
+    }
+    let mut cell = lock.receiver.as_ref();
+    while let syn::Expr::Paren(paren) = cell {
+        cell = &paren.expr;
+    }
+    match cell {
+        syn::Expr::Path(path) => (!path_starts_with(&path.path, &["crate", "builtin"])
+            && !path
+                .path
+                .segments
+                .iter()
+                .any(|segment| segment.ident.to_string().starts_with("__gors")))
+        .then_some(lock.receiver.as_ref()),
+        syn::Expr::Field(_) => Some(lock.receiver.as_ref()),
+        _ => None,
+    }
+}
+
+fn locked_cell_mut(expr: &mut syn::Expr) -> Option<&mut syn::Expr> {
+    locked_cell(expr)?;
+    let syn::Expr::MethodCall(unwrap) = expr else {
+        return None;
+    };
+    let syn::Expr::MethodCall(lock) = unwrap.receiver.as_mut() else {
+        return None;
+    };
+    Some(lock.receiver.as_mut())
+}
+
+/// Whether `call` allocates a shared cell: `GorsPtr::new(value)` or
+/// `Arc::new(Mutex::new(value))`.
+fn is_cell_allocation(call: &syn::ExprCall) -> bool {
+    if is_path_call_expr(&call.func, &["crate", "builtin", "GorsPtr", "new"]) {
+        return true;
+    }
+    is_path_call_expr(&call.func, &["std", "sync", "Arc", "new"])
+        && matches!(
+            call.args.first(),
+            Some(syn::Expr::Call(inner))
+                if is_path_call_expr(&inner.func, &["std", "sync", "Mutex", "new"])
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote as rust;
+    use syn::visit_mut::VisitMut;
+
+    fn instrument(mut file: syn::File) -> String {
+        super::AccessInstrumenter {
+            package: "main".to_string(),
+            names: Vec::new(),
+            closures: 0,
+        }
+        .visit_file_mut(&mut file);
+        quote!(#file).to_string()
+    }
+
+    #[test]
+    fn checks_reads_and_writes_of_shared_cells() {
+        let tokens = instrument(rust! {
+            fn bump(c: crate::builtin::GorsPtr<Counter>) {
+                c.lock().unwrap().n += 1;
+            }
+
+            fn main() {
+                crate::builtin::run_main(|| {
+                    let x = std::sync::Arc::new(std::sync::Mutex::new(0));
+                    crate::builtin::go(move || {
+                        *x.lock().unwrap() = 1;
+                    });
+                    crate::builtin::println_value(*x.lock().unwrap());
+                });
+            }
+        });
+
+        assert!(tokens.contains(
+            "crate :: builtin :: race_write (& c , \"n\" , \"main.bump\") . lock () . unwrap () . n += 1"
+        ));
+        assert!(tokens.contains(
+            "crate :: builtin :: race_new (std :: sync :: Arc :: new (std :: sync :: Mutex :: new (0)))"
+        ));
+        assert!(tokens.contains(
+            "crate :: builtin :: go (crate :: builtin :: race_go (\"main.main\" , move | |"
+        ));
+        assert!(tokens.contains(
+            "* crate :: builtin :: race_write (& x , \"\" , \"main.main.func1\") . lock () . unwrap () = 1"
+        ));
+        assert!(tokens.contains(
+            "* crate :: builtin :: race_read (& x , \"\" , \"main.main\") . lock () . unwrap ()"
+        ));
+    }
+
+    #[test]
+    fn names_function_literals_with_defer_like_go() {
+        let tokens = instrument(rust! {
+            fn main() {
+                crate::builtin::run_main(|| {
+                    let x = std::sync::Arc::new(std::sync::Mutex::new(0));
+                    crate::builtin::go(move || {
+                        let __gors_panic_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                            *x.lock().unwrap() = 1;
+                        }));
+                    });
+                });
+            }
+        });
+
+        assert!(tokens.contains("race_write (& x , \"\" , \"main.main.func1\")"));
+    }
+
+    #[test]
+    fn names_methods_with_pointer_receivers_like_go() {
+        let tokens = instrument(rust! {
+            impl Counter {
+                pub fn Get(&self) -> isize {
+                    self.n
+                }
+                pub fn Inc(mut c: crate::builtin::GorsPtr<Self>) {
+                    c.lock().unwrap().n += 1;
+                }
+            }
+
+            impl Getter for crate::builtin::GorsPtr<Counter> {
+                fn Get(&mut self) -> isize {
+                    Counter::Get(&*self.lock().unwrap())
+                }
+            }
+        });
+
+        assert!(tokens.contains("race_write (& c , \"n\" , \"main.(*Counter).Inc\")"));
+        assert!(tokens.contains("race_read (& self , \"\" , \"main.(*Counter).Get\")"));
+    }
+
+    #[test]
+    fn leaves_runtime_cells_unchecked() {
+        let tokens = instrument(rust! {
+            fn main() {
+                let value = *crate::builtin::STATE.lock().unwrap();
+                let other = *__gors_state.lock().unwrap();
+                let local = 1;
+            }
+        });
+
+        assert!(!tokens.contains("race_"));
+    }
+}
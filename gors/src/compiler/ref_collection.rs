@@ -37,6 +37,10 @@ pub(super) fn collect_refs_from_item(
     struct BoundCollector<'a> {
         names: std::collections::HashSet<String>,
         types: std::collections::HashMap<String, ReceiverTypeRef>,
+        /// How many times each name is bound, and how many of those
+        /// bindings have a known type. Types are tracked per name rather
+        /// than per scope, so a name also bound without one has none.
+        bindings: std::collections::HashMap<String, (usize, usize)>,
         module_names: &'a ReachabilityNameSet,
         item_names: &'a ReachabilityNameSet,
         top_level_field_types: &'a ReceiverFieldTypeMap,
@@ -47,6 +51,24 @@ pub(super) fn collect_refs_from_item(
     }
 
     impl BoundCollector<'_> {
+        fn bind_type(&mut self, name: String, ty: ReceiverTypeRef) {
+            self.bindings.entry(name.clone()).or_default().1 += 1;
+            self.types.insert(name, ty);
+        }
+
+        /// The types of the names whose every binding has a known type.
+        fn into_types(self) -> std::collections::HashMap<String, ReceiverTypeRef> {
+            let bindings = self.bindings;
+            self.types
+                .into_iter()
+                .filter(|(name, _)| {
+                    bindings
+                        .get(name)
+                        .is_some_and(|(bound, typed)| typed >= bound)
+                })
+                .collect()
+        }
+
         fn specialize_receiver_type(&self, receiver_type: ReceiverTypeRef) -> ReceiverTypeRef {
             specialize_self_receiver_type(receiver_type, self.current_self_type.as_ref())
         }
@@ -191,6 +213,7 @@ pub(super) fn collect_refs_from_item(
     impl VisitMut for BoundCollector<'_> {
         fn visit_pat_ident_mut(&mut self, pat: &mut syn::PatIdent) {
             self.names.insert(pat.ident.to_string());
+            self.bindings.entry(pat.ident.to_string()).or_default().0 += 1;
             syn::visit_mut::visit_pat_ident_mut(self, pat);
         }
 
@@ -200,7 +223,7 @@ pub(super) fn collect_refs_from_item(
                 && let Some(ty) = receiver_type_from_type(&pat_type.ty, self.module_names)
             {
                 let ty = self.specialize_receiver_type(ty);
-                self.types.insert(name, ty);
+                self.bind_type(name, ty);
             }
             syn::visit_mut::visit_fn_arg_mut(self, arg);
         }
@@ -212,7 +235,7 @@ pub(super) fn collect_refs_from_item(
                     && let Some(ty) = receiver_type_from_type(&pat_type.ty, self.module_names)
                 {
                     let ty = self.specialize_receiver_type(ty);
-                    self.types.insert(name, ty);
+                    self.bind_type(name, ty);
                 }
             }
             syn::visit_mut::visit_expr_closure_mut(self, closure);
@@ -227,7 +250,7 @@ pub(super) fn collect_refs_from_item(
                     if let Some(name) = pat_ident_name(pat)
                         && let Some(receiver_type) = receiver_type
                     {
-                        self.types.insert(name, receiver_type);
+                        self.bind_type(name, receiver_type);
                     }
                 }
             }
@@ -237,7 +260,7 @@ pub(super) fn collect_refs_from_item(
                 && let Some(ty) = receiver_type_from_type(&pat_type.ty, self.module_names)
             {
                 let ty = self.specialize_receiver_type(ty);
-                self.types.insert(name, ty);
+                self.bind_type(name, ty);
             } else if let Some(init) = &local.init
                 && let Some(name) = pat_ident_name(&local.pat)
                 && let Some(ty) = self.bound_receiver_type_from_expr(&init.expr).or_else(|| {
@@ -250,7 +273,7 @@ pub(super) fn collect_refs_from_item(
                 })
             {
                 let ty = self.specialize_receiver_type(ty);
-                self.types.insert(name, ty);
+                self.bind_type(name, ty);
             }
             syn::visit_mut::visit_local_mut(self, local);
         }
@@ -790,6 +813,7 @@ pub(super) fn collect_refs_from_item(
     let mut bound_collector = BoundCollector {
         names: std::collections::HashSet::new(),
         types: std::collections::HashMap::new(),
+        bindings: std::collections::HashMap::new(),
         module_names: context.module_names,
         item_names: context.item_names,
         top_level_field_types: context.top_level_field_types,
@@ -809,8 +833,8 @@ pub(super) fn collect_refs_from_item(
         top_level_field_types: context.top_level_field_types,
         top_level_element_types: context.top_level_element_types,
         top_level_return_types: context.top_level_return_types,
-        bound_names: bound_collector.names,
-        bound_types: bound_collector.types,
+        bound_names: std::mem::take(&mut bound_collector.names),
+        bound_types: bound_collector.into_types(),
         current_self_type: None,
         current_self_reachability_names: Vec::new(),
         local_names: std::collections::HashSet::new(),
@@ -1635,4 +1659,41 @@ mod tests {
         assert!(external_refs.is_empty(), "{external_refs:?}");
         assert!(names.contains("helper"), "{names:?}");
     }
+
+    #[test]
+    fn collect_refs_falls_back_to_method_names_for_rebound_receivers() {
+        let module_names = ReachabilityNameSet::new();
+        let item_names = ReachabilityNameSet::from(["bucket".to_string(), "lookup".to_string()]);
+        let top_level_names = item_names.clone();
+        let top_level_types = ReceiverTypeMap::new();
+        let top_level_field_types = ReceiverFieldTypeMap::new();
+        let top_level_element_types = ReceiverTypeMap::new();
+        let top_level_return_types = ReceiverTypeMap::new();
+        let top_level_tuple_return_types = ReceiverTupleReturnMap::new();
+        let context = RefCollectionContext {
+            module_names: &module_names,
+            item_names: &item_names,
+            top_level_names: &top_level_names,
+            top_level_types: &top_level_types,
+            top_level_field_types: &top_level_field_types,
+            top_level_element_types: &top_level_element_types,
+            top_level_return_types: &top_level_return_types,
+            top_level_tuple_return_types: &top_level_tuple_return_types,
+        };
+        let mut item: syn::Item = syn::parse_quote! {
+            fn update(value: bucket) {
+                if let Some(value) = lookup(value) {
+                    value.fill();
+                }
+            }
+        };
+
+        let (names, _) = collect_refs_from_item(&mut item, &context);
+
+        assert!(names.contains("fill"), "{names:?}");
+        assert!(
+            !names.contains(&impl_method_reachability_name("bucket", "fill")),
+            "{names:?}"
+        );
+    }
 }
@@ -96,7 +96,7 @@ fn map_iter_module_file() -> syn::File {
 fn value_function_items(has_deep_equal: bool, has_value_of: bool) -> Vec<syn::Item> {
     let value_of = has_value_of.then(|| {
         syn::parse_quote! {
-            pub fn ValueOf(value: Box<dyn std::any::Any>) -> Value {
+            pub fn ValueOf(value: crate::builtin::GorsAny) -> Value {
                 let _ = value;
                 Value::default()
             }
@@ -104,7 +104,7 @@ fn value_function_items(has_deep_equal: bool, has_value_of: bool) -> Vec<syn::It
     });
     let deep_equal = has_deep_equal.then(|| {
         syn::parse_quote! {
-            pub fn DeepEqual(x: Box<dyn std::any::Any>, y: Box<dyn std::any::Any>) -> bool {
+            pub fn DeepEqual(x: crate::builtin::GorsAny, y: crate::builtin::GorsAny) -> bool {
                 crate::builtin::any_eq((x).as_ref(), (y).as_ref())
             }
         }
@@ -160,7 +160,7 @@ fn value_method_items(module: &CompiledModule) -> Vec<syn::Item> {
                 0
             }
 
-            pub fn Interface(&self) -> Box<dyn std::any::Any> {
+            pub fn Interface(&self) -> crate::builtin::GorsAny {
                 Box::new(())
             }
 
@@ -52,7 +52,7 @@ fn replace_pool_module(module: &mut CompiledModule) -> bool {
         },
         syn::parse_quote! {
             impl Pool {
-                pub fn Get(mut p: crate::builtin::GorsPtr<Self>) -> Box<dyn std::any::Any> {
+                pub fn Get(mut p: crate::builtin::GorsPtr<Self>) -> crate::builtin::GorsAny {
                     let new_func = p
                         .lock()
                         .unwrap()
@@ -62,11 +62,11 @@ fn replace_pool_module(module: &mut CompiledModule) -> bool {
                         .clone();
                     match new_func {
                         Some(new_func) => new_func(),
-                        None => Box::new(()) as Box<dyn std::any::Any>,
+                        None => crate::builtin::GorsAny::Nil,
                     }
                 }
 
-                pub fn Put(mut p: crate::builtin::GorsPtr<Self>, _x: Box<dyn std::any::Any>) {
+                pub fn Put(mut p: crate::builtin::GorsPtr<Self>, _x: crate::builtin::GorsAny) {
                     let _ = p;
                 }
             }
@@ -138,14 +138,14 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
                         .position(|(entry_key, _)| crate::builtin::any_eq(entry_key.as_ref(), key))
                 }
 
-                fn __gors_nil_any() -> Box<dyn std::any::Any> {
-                    Box::new(()) as Box<dyn std::any::Any>
+                fn __gors_nil_any() -> crate::builtin::GorsAny {
+                    crate::builtin::GorsAny::Nil
                 }
 
                 pub fn Load(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                ) -> (Box<dyn std::any::Any>, bool) {
+                    mut key: crate::builtin::GorsAny,
+                ) -> (crate::builtin::GorsAny, bool) {
                     Self::__gors_assert_comparable_key(key.as_ref());
                     let entries = Self::__gors_entries(&m);
                     let entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
@@ -157,8 +157,8 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
 
                 pub fn Store(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                    mut value: Box<dyn std::any::Any>,
+                    mut key: crate::builtin::GorsAny,
+                    mut value: crate::builtin::GorsAny,
                 ) {
                     let _ = Self::Swap(m, key, value);
                 }
@@ -173,9 +173,9 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
 
                 pub fn LoadOrStore(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                    mut value: Box<dyn std::any::Any>,
-                ) -> (Box<dyn std::any::Any>, bool) {
+                    mut key: crate::builtin::GorsAny,
+                    mut value: crate::builtin::GorsAny,
+                ) -> (crate::builtin::GorsAny, bool) {
                     Self::__gors_assert_comparable_key(key.as_ref());
                     let entries = Self::__gors_entries(&m);
                     let mut entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
@@ -192,8 +192,8 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
 
                 pub fn LoadAndDelete(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                ) -> (Box<dyn std::any::Any>, bool) {
+                    mut key: crate::builtin::GorsAny,
+                ) -> (crate::builtin::GorsAny, bool) {
                     Self::__gors_assert_comparable_key(key.as_ref());
                     let entries = Self::__gors_entries(&m);
                     let mut entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
@@ -204,15 +204,15 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
                     (Self::__gors_nil_any(), false)
                 }
 
-                pub fn Delete(mut m: crate::builtin::GorsPtr<Self>, mut key: Box<dyn std::any::Any>) {
+                pub fn Delete(mut m: crate::builtin::GorsPtr<Self>, mut key: crate::builtin::GorsAny) {
                     let _ = Self::LoadAndDelete(m, key);
                 }
 
                 pub fn Swap(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                    mut value: Box<dyn std::any::Any>,
-                ) -> (Box<dyn std::any::Any>, bool) {
+                    mut key: crate::builtin::GorsAny,
+                    mut value: crate::builtin::GorsAny,
+                ) -> (crate::builtin::GorsAny, bool) {
                     Self::__gors_assert_comparable_key(key.as_ref());
                     let entries = Self::__gors_entries(&m);
                     let mut entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
@@ -230,9 +230,9 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
 
                 pub fn CompareAndSwap(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                    mut old: Box<dyn std::any::Any>,
-                    mut new: Box<dyn std::any::Any>,
+                    mut key: crate::builtin::GorsAny,
+                    mut old: crate::builtin::GorsAny,
+                    mut new: crate::builtin::GorsAny,
                 ) -> bool {
                     Self::__gors_assert_comparable_key(key.as_ref());
                     if !crate::builtin::interface_is_nil(old.as_ref())
@@ -253,8 +253,8 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
 
                 pub fn CompareAndDelete(
                     mut m: crate::builtin::GorsPtr<Self>,
-                    mut key: Box<dyn std::any::Any>,
-                    mut old: Box<dyn std::any::Any>,
+                    mut key: crate::builtin::GorsAny,
+                    mut old: crate::builtin::GorsAny,
                 ) -> bool {
                     Self::__gors_assert_comparable_key(key.as_ref());
                     if !crate::builtin::interface_is_nil(old.as_ref())
@@ -279,7 +279,7 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
                         std::sync::Mutex<
                             Option<
                                 std::sync::Arc<
-                                    dyn Fn(Box<dyn std::any::Any>, Box<dyn std::any::Any>) -> bool
+                                    dyn Fn(crate::builtin::GorsAny, crate::builtin::GorsAny) -> bool
                                         + Send
                                         + Sync,
                                 >,
@@ -287,7 +287,7 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
                         >,
                     >,
                 ) {
-                    let snapshot: Vec<(Box<dyn std::any::Any>, Box<dyn std::any::Any>)> = {
+                    let snapshot: Vec<(crate::builtin::GorsAny, crate::builtin::GorsAny)> = {
                         let entries = Self::__gors_entries(&m);
                         let entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
                         entries
@@ -304,7 +304,7 @@ fn replace_map_module(module: &mut CompiledModule) -> bool {
                         let __gors_func = crate::builtin::lock_func(&f);
                         match __gors_func.as_ref() {
                             Some(__gors_func) => __gors_func.clone(),
-                            None => crate::builtin::panic_value("nil function"),
+                            None => crate::builtin::panic_runtime_error(crate::builtin::RuntimeError::nil_dereference()),
                         }
                     };
                     for (key, value) in snapshot {
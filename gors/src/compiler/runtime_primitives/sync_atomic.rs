@@ -200,7 +200,7 @@ pub(super) fn replace_module(module: &mut CompiledModule) -> bool {
         },
         syn::parse_quote! {
             impl Value {
-                pub fn Load(mut v: crate::builtin::GorsPtr<Self>) -> Box<dyn std::any::Any> {
+                pub fn Load(mut v: crate::builtin::GorsPtr<Self>) -> crate::builtin::GorsAny {
                     let value = v.lock().unwrap();
                     let value = value
                         .v
@@ -211,7 +211,7 @@ pub(super) fn replace_module(module: &mut CompiledModule) -> bool {
 
                 pub fn Store(
                     mut v: crate::builtin::GorsPtr<Self>,
-                    val: Box<dyn std::any::Any>,
+                    val: crate::builtin::GorsAny,
                 ) {
                     if crate::builtin::interface_is_nil(val.as_ref()) {
                         crate::builtin::panic_value(
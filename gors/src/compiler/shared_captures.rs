@@ -1,7 +1,10 @@
 use std::cell::RefCell;
 use std::collections::{BTreeSet, HashSet};
 
-use super::{TYPE_ENV, ast, go_type_is_copy, ir, synthetic_names, typeinfer, value_ident};
+use super::{
+    TYPE_ENV, ast, go_type_is_copy, ir, source_map_context::record_mapping, synthetic_names,
+    typeinfer, value_ident,
+};
 
 thread_local! {
     static SHARED_CAPTURE_NAMES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
@@ -78,6 +81,7 @@ pub(super) fn shared_capture_ident(expr: &ast::Expr) -> Option<syn::Ident> {
     if !is_shared_capture_name(ident.name) {
         return None;
     }
+    record_mapping(&ident.name_pos, Some(ident.name));
     Some(value_ident(ident.name))
 }
 
@@ -0,0 +1,641 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use syn::{visit::Visit, visit_mut::VisitMut};
+
+use super::{
+    CompiledModule,
+    syn_inspect::{is_path_call_expr, path_is, path_starts_with},
+};
+
+/// Runtime items the lowering refers to. Nothing reaches them before the
+/// lowering runs, so they are set aside before dead code elimination.
+const LOCAL_RUNTIME_ITEMS: &[&str] = &["LocalMutex", "lock_local_func", "thread_global"];
+
+/// Runtime items that only exist once a program shares memory between
+/// threads.
+const CONCURRENT_RUNTIME_ITEMS: &[&str] = &["go", "select", "Chan"];
+
+/// `Mutex` methods `LocalMutex` does not provide.
+const UNSUPPORTED_MUTEX_METHODS: &[&str] = &["get_mut", "into_inner", "is_poisoned", "try_lock"];
+
+pub(super) fn local_runtime_items(modules: &BTreeMap<String, CompiledModule>) -> Vec<syn::Item> {
+    modules
+        .values()
+        .filter(|module| module.mod_name == "builtin")
+        .flat_map(|module| &module.file.items)
+        .filter(|item| {
+            item_name(item).is_some_and(|name| LOCAL_RUNTIME_ITEMS.contains(&name.as_str()))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Lowers programs whose reachable code never starts a goroutine to
+/// single-threaded runtime types: shared cells and function values become
+/// `Rc<LocalMutex<_>>` and `Rc<dyn Fn>`, package variables holding them
+/// become thread-locals, and loops stop polling for preemption.
+///
+/// This runs after dead code elimination, so only reachable code decides.
+/// The program is left untouched when any module uses a value in a way
+/// the lowering cannot follow, such as a function value stored in a struct
+/// field or a cell handed to the runtime.
+pub(super) fn lower_single_threaded_program(
+    modules: &mut BTreeMap<String, CompiledModule>,
+    runtime_items: Vec<syn::Item>,
+) {
+    let Some(builtin) = modules.values().find(|module| module.mod_name == "builtin") else {
+        return;
+    };
+    if runtime_items.is_empty() || starts_goroutines(&builtin.file) {
+        return;
+    }
+    let thread_safe_runtime = thread_safe_runtime_names(&builtin.file);
+    let globals = modules
+        .values()
+        .filter(|module| module.mod_name != "builtin")
+        .map(|module| (module.mod_name.clone(), shared_global_names(&module.file)))
+        .collect::<HashMap<_, _>>();
+    let lowerable = modules
+        .values()
+        .filter(|module| module.mod_name != "builtin")
+        .all(|module| {
+            globals
+                .get(&module.mod_name)
+                .is_some_and(|names| can_lower(&module.file, names, &thread_safe_runtime))
+        });
+    if !lowerable {
+        return;
+    }
+
+    let mut used = HashSet::new();
+    for module in modules
+        .values_mut()
+        .filter(|module| module.mod_name != "builtin")
+    {
+        lower_file(&mut module.file, &module.mod_name, &globals);
+        used.extend(referenced_runtime_items(&module.file));
+    }
+    if let Some(builtin) = modules
+        .values_mut()
+        .find(|module| module.mod_name == "builtin")
+    {
+        builtin.file.items.extend(
+            runtime_items
+                .into_iter()
+                .filter(|item| item_name(item).is_some_and(|name| used.contains(&name))),
+        );
+    }
+}
+
+fn item_name(item: &syn::Item) -> Option<String> {
+    match item {
+        syn::Item::Fn(item_fn) => Some(item_fn.sig.ident.to_string()),
+        syn::Item::Struct(item_struct) => Some(item_struct.ident.to_string()),
+        syn::Item::Impl(item_impl) if item_impl.trait_.is_none() => {
+            let syn::Type::Path(self_ty) = item_impl.self_ty.as_ref() else {
+                return None;
+            };
+            self_ty
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn starts_goroutines(builtin: &syn::File) -> bool {
+    builtin.items.iter().any(|item| {
+        item_name(item).is_some_and(|name| CONCURRENT_RUNTIME_ITEMS.contains(&name.as_str()))
+    })
+}
+
+/// Names runtime functions whose signatures require `Arc`, `Mutex` or
+/// unwind safety, which lowered values cannot be passed to.
+fn thread_safe_runtime_names(builtin: &syn::File) -> HashSet<String> {
+    struct Collector(HashSet<String>);
+
+    impl Collector {
+        fn record(&mut self, sig: &syn::Signature) {
+            if sig.ident != "lock_func" && mentions_ident(sig, &["Arc", "Mutex", "UnwindSafe"]) {
+                self.0.insert(sig.ident.to_string());
+            }
+        }
+    }
+
+    impl<'ast> Visit<'ast> for Collector {
+        fn visit_item_fn(&mut self, item_fn: &'ast syn::ItemFn) {
+            self.record(&item_fn.sig);
+        }
+
+        fn visit_impl_item_fn(&mut self, item_fn: &'ast syn::ImplItemFn) {
+            self.record(&item_fn.sig);
+        }
+
+        fn visit_item_type(&mut self, item_type: &'ast syn::ItemType) {
+            if mentions_ident(&item_type.ty, &["Arc", "Mutex"]) {
+                self.0.insert(item_type.ident.to_string());
+            }
+        }
+    }
+
+    let mut collector = Collector(HashSet::new());
+    collector.visit_file(builtin);
+    collector.0
+}
+
+fn mentions_ident(tokens: &impl quote::ToTokens, names: &[&str]) -> bool {
+    fn walk(stream: proc_macro2::TokenStream, names: &[&str]) -> bool {
+        stream.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => names.iter().any(|name| ident == name),
+            proc_macro2::TokenTree::Group(group) => walk(group.stream(), names),
+            _ => false,
+        })
+    }
+    walk(tokens.to_token_stream(), names)
+}
+
+/// Names the package variables whose values would change type.
+fn shared_global_names(file: &syn::File) -> HashSet<String> {
+    file.items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Static(item_static) if is_shared_type(&item_static.ty) => {
+                Some(item_static.ident.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_shared_type(ty: &syn::Type) -> bool {
+    struct Finder(bool);
+
+    impl<'ast> Visit<'ast> for Finder {
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            self.0 |= is_thread_safe_path(path);
+            syn::visit::visit_path(self, path);
+        }
+
+        fn visit_type_trait_object(&mut self, object: &'ast syn::TypeTraitObject) {
+            self.0 |= is_fn_trait_object(object) && object.bounds.iter().any(is_send_or_sync_bound);
+            syn::visit::visit_type_trait_object(self, object);
+        }
+    }
+
+    let mut finder = Finder(false);
+    finder.visit_type(ty);
+    finder.0
+}
+
+fn is_thread_safe_path(path: &syn::Path) -> bool {
+    path_starts_with(path, &["std", "sync", "Arc"])
+        || path_starts_with(path, &["std", "sync", "Mutex"])
+}
+
+fn is_fn_trait_object(object: &syn::TypeTraitObject) -> bool {
+    object.bounds.iter().any(|bound| {
+        matches!(bound, syn::TypeParamBound::Trait(bound)
+        if bound.path.segments.last().is_some_and(|segment| {
+            segment.ident == "Fn" || segment.ident == "FnMut" || segment.ident == "FnOnce"
+        }))
+    })
+}
+
+fn is_send_or_sync_bound(bound: &syn::TypeParamBound) -> bool {
+    matches!(bound, syn::TypeParamBound::Trait(bound)
+        if path_is(&bound.path, &["Send"]) || path_is(&bound.path, &["Sync"]))
+}
+
+/// Reports whether every use of a cell, function value or package variable
+/// in `file` keeps working once its type is single-threaded.
+fn can_lower(file: &syn::File, globals: &HashSet<String>, runtime: &HashSet<String>) -> bool {
+    struct Checker<'a> {
+        globals: &'a HashSet<String>,
+        runtime: &'a HashSet<String>,
+        lowerable: bool,
+    }
+
+    impl<'ast> Visit<'ast> for Checker<'_> {
+        fn visit_item(&mut self, item: &'ast syn::Item) {
+            match item {
+                syn::Item::Struct(syn::ItemStruct { fields, .. }) => {
+                    self.lowerable &= !fields.iter().any(|field| is_shared_type(&field.ty));
+                }
+                syn::Item::Enum(item_enum) => {
+                    self.lowerable &= !item_enum
+                        .variants
+                        .iter()
+                        .flat_map(|variant| &variant.fields)
+                        .any(|field| is_shared_type(&field.ty));
+                }
+                syn::Item::Union(_) => self.lowerable = false,
+                syn::Item::Type(item_type) => self.lowerable &= !is_shared_type(&item_type.ty),
+                syn::Item::Static(item_static) if is_shared_type(&item_static.ty) => {
+                    self.lowerable &= self.globals.contains(&item_static.ident.to_string())
+                        && lazy_lock_parts(item_static).is_some();
+                }
+                syn::Item::Use(item_use) => {
+                    self.lowerable &= !mentions_ident(&item_use.tree, &["sync"])
+                        && !mentions_any_global(&item_use.tree, self.globals);
+                }
+                _ => {}
+            }
+            syn::visit::visit_item(self, item);
+        }
+
+        fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+            self.lowerable &= !self.globals.contains(&pat.ident.to_string());
+            syn::visit::visit_pat_ident(self, pat);
+        }
+
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            let guard_or_alias = path.segments.iter().any(|segment| {
+                ["Arc", "Mutex", "MutexGuard", "LockResult", "PoisonError"]
+                    .iter()
+                    .any(|name| segment.ident == name)
+            }) && !is_thread_safe_path(path);
+            let runtime_call = path_starts_with(path, &["crate", "builtin"])
+                && path
+                    .segments
+                    .iter()
+                    .skip(2)
+                    .any(|segment| self.runtime.contains(&segment.ident.to_string()));
+            self.lowerable &= !guard_or_alias && !runtime_call;
+            syn::visit::visit_path(self, path);
+        }
+
+        fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+            let method = call.method.to_string();
+            self.lowerable &= !self.runtime.contains(&method)
+                && !UNSUPPORTED_MUTEX_METHODS.contains(&method.as_str());
+            syn::visit::visit_expr_method_call(self, call);
+        }
+
+        fn visit_type_trait_object(&mut self, object: &'ast syn::TypeTraitObject) {
+            self.lowerable &=
+                is_fn_trait_object(object) || !object.bounds.iter().any(is_send_or_sync_bound);
+            syn::visit::visit_type_trait_object(self, object);
+        }
+
+        fn visit_generics(&mut self, generics: &'ast syn::Generics) {
+            self.lowerable &= !mentions_ident(generics, &["Send", "Sync"])
+                && !mentions_ident(&generics.where_clause, &["Send", "Sync"]);
+            syn::visit::visit_generics(self, generics);
+        }
+
+        fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+            self.lowerable &= !mentions_ident(&mac.tokens, &["sync"])
+                && !mentions_any_global(&mac.tokens, self.globals);
+            syn::visit::visit_macro(self, mac);
+        }
+    }
+
+    let mut checker = Checker {
+        globals,
+        runtime,
+        lowerable: true,
+    };
+    checker.visit_file(file);
+    checker.lowerable
+}
+
+fn mentions_any_global(tokens: &impl quote::ToTokens, globals: &HashSet<String>) -> bool {
+    let names = globals.iter().map(String::as_str).collect::<Vec<_>>();
+    mentions_ident(tokens, &names)
+}
+
+/// Splits `static X: LazyLock<T> = LazyLock::new(|| value)` into `T` and
+/// `value`.
+fn lazy_lock_parts(item_static: &syn::ItemStatic) -> Option<(&syn::Type, &syn::Expr)> {
+    if !matches!(item_static.mutability, syn::StaticMutability::None) {
+        return None;
+    }
+    let syn::Type::Path(ty) = item_static.ty.as_ref() else {
+        return None;
+    };
+    if ty.qself.is_some() || !path_starts_with(&ty.path, &["std", "sync", "LazyLock"]) {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &ty.path.segments.last()?.arguments else {
+        return None;
+    };
+    let [syn::GenericArgument::Type(inner)] = args.args.iter().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let syn::Expr::Call(call) = item_static.expr.as_ref() else {
+        return None;
+    };
+    if !is_path_call_expr(&call.func, &["std", "sync", "LazyLock", "new"]) {
+        return None;
+    }
+    let [syn::Expr::Closure(init)] = call.args.iter().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    init.inputs
+        .is_empty()
+        .then_some((inner, init.body.as_ref()))
+}
+
+fn lower_file(file: &mut syn::File, mod_name: &str, globals: &HashMap<String, HashSet<String>>) {
+    struct Lowering<'a> {
+        mod_name: &'a str,
+        globals: &'a HashMap<String, HashSet<String>>,
+    }
+
+    impl Lowering<'_> {
+        fn is_global(&self, path: &syn::Path) -> bool {
+            let segments = path.segments.iter().collect::<Vec<_>>();
+            let (module, name) = match segments[..] {
+                [name] => (self.mod_name.to_string(), name),
+                [module, name] => (module.ident.to_string(), name),
+                [root, module, name] if root.ident == "crate" => (module.ident.to_string(), name),
+                _ => return false,
+            };
+            name.arguments.is_none()
+                && self
+                    .globals
+                    .get(&module)
+                    .is_some_and(|names| names.contains(&name.ident.to_string()))
+        }
+    }
+
+    impl VisitMut for Lowering<'_> {
+        fn visit_block_mut(&mut self, block: &mut syn::Block) {
+            block.stmts.retain(|stmt| {
+                !matches!(stmt, syn::Stmt::Expr(syn::Expr::Call(call), Some(_))
+                    if call.args.is_empty()
+                        && is_path_call_expr(&call.func, &["crate", "builtin", "yield_point"]))
+            });
+            syn::visit_mut::visit_block_mut(self, block);
+        }
+
+        fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+            if let syn::Expr::Path(path) = expr
+                && path.qself.is_none()
+                && self.is_global(&path.path)
+            {
+                *expr = syn::parse_quote!(crate::builtin::thread_global(&#path));
+                return;
+            }
+            if let syn::Expr::Call(call) = expr
+                && let syn::Expr::Path(func) = call.func.as_mut()
+                && path_is(&func.path, &["crate", "builtin", "lock_func"])
+            {
+                func.path = syn::parse_quote!(crate::builtin::lock_local_func);
+            }
+            syn::visit_mut::visit_expr_mut(self, expr);
+        }
+
+        fn visit_field_value_mut(&mut self, field: &mut syn::FieldValue) {
+            if field.colon_token.is_none()
+                && let syn::Expr::Path(path) = &field.expr
+                && self.is_global(&path.path)
+            {
+                field.colon_token = Some(syn::parse_quote!(:));
+            }
+            syn::visit_mut::visit_field_value_mut(self, field);
+        }
+
+        fn visit_path_mut(&mut self, path: &mut syn::Path) {
+            let replacement: Option<[&str; 3]> = if path_starts_with(path, &["std", "sync", "Arc"])
+            {
+                Some(["std", "rc", "Rc"])
+            } else if path_starts_with(path, &["std", "sync", "Mutex"]) {
+                Some(["crate", "builtin", "LocalMutex"])
+            } else {
+                None
+            };
+            if let Some(replacement) = replacement {
+                for (segment, ident) in path.segments.iter_mut().zip(replacement) {
+                    segment.ident = syn::Ident::new(ident, segment.ident.span());
+                }
+            }
+            syn::visit_mut::visit_path_mut(self, path);
+        }
+
+        fn visit_type_trait_object_mut(&mut self, object: &mut syn::TypeTraitObject) {
+            if is_fn_trait_object(object) {
+                object.bounds = std::mem::take(&mut object.bounds)
+                    .into_iter()
+                    .filter(|bound| !is_send_or_sync_bound(bound))
+                    .collect();
+            }
+            syn::visit_mut::visit_type_trait_object_mut(self, object);
+        }
+    }
+
+    Lowering { mod_name, globals }.visit_file_mut(file);
+
+    let Some(names) = globals.get(mod_name) else {
+        return;
+    };
+    for item in &mut file.items {
+        let syn::Item::Static(item_static) = item else {
+            continue;
+        };
+        if !names.contains(&item_static.ident.to_string()) {
+            continue;
+        }
+        let Some((ty, value)) = lazy_lock_parts(item_static) else {
+            continue;
+        };
+        let attrs = &item_static.attrs;
+        let vis = &item_static.vis;
+        let ident = &item_static.ident;
+        *item = syn::parse_quote! {
+            thread_local! {
+                #(#attrs)*
+                #vis static #ident: &'static #ty = Box::leak(Box::new(#value));
+            }
+        };
+    }
+}
+
+fn referenced_runtime_items(file: &syn::File) -> HashSet<String> {
+    struct Collector(HashSet<String>);
+
+    impl<'ast> Visit<'ast> for Collector {
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            if path_starts_with(path, &["crate", "builtin"])
+                && let Some(segment) = path.segments.iter().nth(2)
+                && LOCAL_RUNTIME_ITEMS.contains(&segment.ident.to_string().as_str())
+            {
+                self.0.insert(segment.ident.to_string());
+            }
+            syn::visit::visit_path(self, path);
+        }
+
+        fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+            for name in LOCAL_RUNTIME_ITEMS {
+                if mentions_ident(&mac.tokens, &[name]) {
+                    self.0.insert((*name).to_string());
+                }
+            }
+            syn::visit::visit_macro(self, mac);
+        }
+    }
+
+    let mut collector = Collector(HashSet::new());
+    collector.visit_file(file);
+    collector.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quote::quote;
+    use syn::parse_quote as rust;
+
+    use super::super::CompiledModule;
+
+    fn module(mod_name: &str, file: syn::File) -> (String, CompiledModule) {
+        (
+            mod_name.to_string(),
+            CompiledModule {
+                mod_name: mod_name.to_string(),
+                import_path: mod_name.to_string(),
+                file,
+                filename: format!("{mod_name}.rs"),
+                content_hash: String::new(),
+                is_main: mod_name == "__main__",
+                is_stdlib: false,
+            },
+        )
+    }
+
+    fn program(builtin: syn::File, main: syn::File) -> BTreeMap<String, CompiledModule> {
+        let runtime: syn::File = rust! {
+            #[derive(Default)]
+            pub struct LocalMutex<T>(std::cell::RefCell<T>);
+            impl<T> LocalMutex<T> {
+                pub const fn new(value: T) -> Self {
+                    Self(std::cell::RefCell::new(value))
+                }
+            }
+            pub fn lock_local_func<T>(func: &std::rc::Rc<LocalMutex<T>>) -> std::cell::RefMut<'_, T> {
+                func.0.borrow_mut()
+            }
+            pub fn thread_global<T: ?Sized>(global: &'static std::thread::LocalKey<&'static T>) -> &'static T {
+                global.with(|value| *value)
+            }
+        };
+        let mut modules = BTreeMap::from([
+            module("builtin", runtime),
+            module("__main__", main),
+            module(
+                "counter",
+                rust! {
+                    pub fn Bump() {
+                        *(Count).lock().unwrap() += 1;
+                    }
+                    pub static Count: std::sync::LazyLock<std::sync::Arc<std::sync::Mutex<isize>>> =
+                        std::sync::LazyLock::new(|| std::sync::Arc::new(std::sync::Mutex::new(0)));
+                },
+            ),
+        ]);
+        let runtime_items = super::local_runtime_items(&modules);
+        if let Some(module) = modules.get_mut("builtin") {
+            module.file = builtin;
+        }
+        super::lower_single_threaded_program(&mut modules, runtime_items);
+        modules
+    }
+
+    fn tokens(modules: &BTreeMap<String, CompiledModule>, key: &str) -> String {
+        let file = &modules[key].file;
+        quote!(#file).to_string()
+    }
+
+    #[test]
+    fn lowers_cells_function_values_and_globals_without_goroutines() {
+        let modules = program(
+            rust! {
+                pub fn yield_point() {}
+            },
+            rust! {
+                pub fn main() {
+                    let mut sum = std::sync::Arc::new(std::sync::Mutex::new(0));
+                    let mut add = {
+                        let __gors_func: std::sync::Arc<dyn Fn(isize) + Send + Sync> =
+                            std::sync::Arc::new({
+                                let sum = sum.clone();
+                                move |x: isize| *sum.lock().unwrap() += x
+                            });
+                        std::sync::Arc::new(std::sync::Mutex::new(Some(__gors_func)))
+                    };
+                    while *sum.lock().unwrap() < 10 {
+                        crate::builtin::yield_point();
+                        let f = crate::builtin::lock_func(&add).clone();
+                        counter::Bump();
+                    }
+                }
+            },
+        );
+        let main = tokens(&modules, "__main__");
+        let counter = tokens(&modules, "counter");
+        let builtin = tokens(&modules, "builtin");
+
+        assert!(!main.contains("std :: sync"), "{main}");
+        assert!(
+            main.contains("std :: rc :: Rc < dyn Fn (isize) >"),
+            "{main}"
+        );
+        assert!(
+            main.contains("std :: rc :: Rc :: new (crate :: builtin :: LocalMutex :: new (0))"),
+            "{main}"
+        );
+        assert!(
+            main.contains("crate :: builtin :: lock_local_func (& add)"),
+            "{main}"
+        );
+        assert!(!main.contains("yield_point"), "{main}");
+        assert!(
+            counter.contains("* (crate :: builtin :: thread_global (& Count)) . lock ()"),
+            "{counter}"
+        );
+        assert!(counter.contains("thread_local !"), "{counter}");
+        assert!(
+            counter.contains(
+                "pub static Count : & 'static std :: rc :: Rc < crate :: builtin :: LocalMutex < isize > >"
+            ),
+            "{counter}"
+        );
+        assert!(builtin.contains("pub struct LocalMutex"), "{builtin}");
+        assert!(builtin.contains("fn lock_local_func"), "{builtin}");
+        assert!(builtin.contains("fn thread_global"), "{builtin}");
+    }
+
+    #[test]
+    fn keeps_programs_that_start_goroutines_or_store_function_values() {
+        let main: syn::File = rust! {
+            pub fn main() {
+                let mut sum = std::sync::Arc::new(std::sync::Mutex::new(0));
+                counter::Bump();
+            }
+        };
+        let concurrent = program(
+            rust! {
+                pub fn go<F: FnOnce() + Send + 'static>(f: F) {
+                    f();
+                }
+            },
+            main.clone(),
+        );
+        assert!(tokens(&concurrent, "__main__").contains("std :: sync :: Mutex"));
+        assert!(tokens(&concurrent, "counter").contains("LazyLock"));
+        assert!(!tokens(&concurrent, "builtin").contains("LocalMutex"));
+
+        let mut stored = main;
+        stored.items.push(rust! {
+            pub struct Handler {
+                pub run: std::sync::Arc<std::sync::Mutex<Option<std::sync::Arc<dyn Fn() + Send + Sync>>>>,
+            }
+        });
+        let stored = program(rust! {}, stored);
+        assert!(tokens(&stored, "__main__").contains("std :: sync :: Mutex"));
+        assert!(tokens(&stored, "counter").contains("LazyLock"));
+    }
+}
@@ -42,6 +42,16 @@ pub(super) fn is_active() -> bool {
     TRACKER.with(|tracker| tracker.borrow().is_active())
 }
 
+/// Stop tracking, and hand over the mappings tracked so far.
+pub(super) fn take() -> SourceMapTracker {
+    TRACKER.with(|tracker| std::mem::take(&mut *tracker.borrow_mut()))
+}
+
+/// Track on with a tracker [`take`] handed over.
+pub(super) fn restore(tracker: SourceMapTracker) {
+    TRACKER.with(|current| *current.borrow_mut() = tracker);
+}
+
 pub(super) fn clear() {
     TRACKER.with(|tracker| {
         tracker.borrow_mut().clear();
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use super::CompiledModule;
+
+/// Lets programs report stack overflows like Go.
+///
+/// Every function and closure of a program that calls something starts with
+/// a `crate::builtin::check_stack` call, which crashes the program when the
+/// goroutine running it is near the end of its stack. Functions that call
+/// nothing cannot recurse, and fit in the headroom the check leaves.
+pub(super) fn add_stack_checks(modules: &mut BTreeMap<String, CompiledModule>) {
+    let has_runtime_support =
+        modules.values().any(|module| {
+            module.mod_name == "builtin"
+                && module.file.items.iter().any(
+                    |item| matches!(item, syn::Item::Fn(func) if func.sig.ident == "check_stack"),
+                )
+        });
+    let has_main = modules.get("__main__").is_some_and(|module| {
+        module
+            .file
+            .items
+            .iter()
+            .any(|item| matches!(item, syn::Item::Fn(func) if func.sig.ident == "main"))
+    });
+    if !has_runtime_support || !has_main {
+        return;
+    }
+    for module in modules.values_mut() {
+        if module.mod_name != "builtin" {
+            add_stack_checks_for_file(&mut module.file);
+        }
+    }
+}
+
+fn add_stack_checks_for_file(file: &mut syn::File) {
+    use syn::visit_mut::VisitMut;
+
+    struct StackChecks;
+
+    impl VisitMut for StackChecks {
+        fn visit_item_const_mut(&mut self, _: &mut syn::ItemConst) {}
+
+        fn visit_item_fn_mut(&mut self, func: &mut syn::ItemFn) {
+            syn::visit_mut::visit_item_fn_mut(self, func);
+            if func.sig.constness.is_none() {
+                check_stack_in_block(&mut func.block);
+            }
+        }
+
+        fn visit_impl_item_fn_mut(&mut self, func: &mut syn::ImplItemFn) {
+            syn::visit_mut::visit_impl_item_fn_mut(self, func);
+            if func.sig.constness.is_none() {
+                check_stack_in_block(&mut func.block);
+            }
+        }
+
+        fn visit_trait_item_fn_mut(&mut self, func: &mut syn::TraitItemFn) {
+            syn::visit_mut::visit_trait_item_fn_mut(self, func);
+            if let (Some(block), None) = (&mut func.default, func.sig.constness) {
+                check_stack_in_block(block);
+            }
+        }
+
+        fn visit_expr_closure_mut(&mut self, closure: &mut syn::ExprClosure) {
+            syn::visit_mut::visit_expr_closure_mut(self, closure);
+            if !makes_calls(&closure.body) {
+                return;
+            }
+            match closure.body.as_mut() {
+                syn::Expr::Block(block) if block.label.is_none() => {
+                    block.block.stmts.insert(0, check_stack_stmt());
+                }
+                body => {
+                    let stmt = check_stack_stmt();
+                    *body = syn::parse_quote!({ #stmt #body });
+                }
+            }
+        }
+    }
+
+    StackChecks.visit_file_mut(file);
+}
+
+fn check_stack_in_block(block: &mut syn::Block) {
+    if block_makes_calls(block) {
+        block.stmts.insert(0, check_stack_stmt());
+    }
+}
+
+fn check_stack_stmt() -> syn::Stmt {
+    syn::parse_quote! {
+        crate::builtin::check_stack();
+    }
+}
+
+fn makes_calls(expr: &syn::Expr) -> bool {
+    let mut finder = CallFinder(false);
+    syn::visit::Visit::visit_expr(&mut finder, expr);
+    finder.0
+}
+
+fn block_makes_calls(block: &syn::Block) -> bool {
+    let mut finder = CallFinder(false);
+    syn::visit::Visit::visit_block(&mut finder, block);
+    finder.0
+}
+
+struct CallFinder(bool);
+
+impl<'ast> syn::visit::Visit<'ast> for CallFinder {
+    fn visit_expr_call(&mut self, _: &'ast syn::ExprCall) {
+        self.0 = true;
+    }
+
+    fn visit_expr_method_call(&mut self, _: &'ast syn::ExprMethodCall) {
+        self.0 = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote as rust;
+
+    #[test]
+    fn checks_the_stack_on_entry_to_functions_and_closures_that_call() {
+        let mut file: syn::File = rust! {
+            pub fn fib(n: isize) -> isize {
+                if n < 2 {
+                    return n;
+                }
+                fib(n - 1) + fib(n - 2)
+            }
+
+            pub fn add(a: isize, b: isize) -> isize {
+                a + b
+            }
+
+            pub fn apply(f: isize) -> isize {
+                let g = |n: isize| add(n, f);
+                g(1)
+            }
+
+            impl Point {
+                pub fn norm(&self) -> isize {
+                    self.x.abs()
+                }
+            }
+        };
+
+        super::add_stack_checks_for_file(&mut file);
+        let tokens = quote!(#file).to_string();
+
+        assert!(
+            tokens
+                .contains("pub fn fib (n : isize) -> isize { crate :: builtin :: check_stack () ;")
+        );
+        assert!(tokens.contains("pub fn add (a : isize , b : isize) -> isize { a + b }"));
+        assert!(
+            tokens.contains("| n : isize | { crate :: builtin :: check_stack () ; add (n , f) }")
+        );
+        assert!(
+            tokens.contains("pub fn norm (& self) -> isize { crate :: builtin :: check_stack () ;")
+        );
+    }
+}
@@ -140,10 +140,39 @@ pub(super) fn expr_is_ident(expr: &syn::Expr, ident: &syn::Ident) -> bool {
 pub(super) fn is_slice_range_index_expr(expr: &syn::Expr) -> bool {
     matches!(
         expr,
-        syn::Expr::Index(index) if matches!(&*index.index, syn::Expr::Range(_))
+        syn::Expr::Index(index) if slice_index_range(&index.index).is_some()
     )
 }
 
+/// The range of a slice expression's index: `lo..hi`, or the one checked by
+/// `crate::builtin::bounds(lo..hi)`.
+pub(super) fn slice_index_range(index: &syn::Expr) -> Option<&syn::ExprRange> {
+    match index {
+        syn::Expr::Range(range) => Some(range),
+        syn::Expr::Call(call)
+            if call.args.len() == 1
+                && is_path_call_expr(&call.func, &["crate", "builtin", "bounds"]) =>
+        {
+            call.args.first().and_then(slice_index_range)
+        }
+        _ => None,
+    }
+}
+
+/// [`slice_index_range`], mutably.
+pub(super) fn slice_index_range_mut(index: &mut syn::Expr) -> Option<&mut syn::ExprRange> {
+    match index {
+        syn::Expr::Range(range) => Some(range),
+        syn::Expr::Call(call)
+            if call.args.len() == 1
+                && is_path_call_expr(&call.func, &["crate", "builtin", "bounds"]) =>
+        {
+            call.args.first_mut().and_then(slice_index_range_mut)
+        }
+        _ => None,
+    }
+}
+
 pub(super) fn strip_paren_or_group(mut expr: &syn::Expr) -> &syn::Expr {
     loop {
         match expr {
@@ -650,6 +679,22 @@ pub(super) fn box_dyn_any_cast_source_expr(expr: &syn::Expr) -> Option<syn::Expr
     }
 }
 
+pub(super) fn is_gors_any_nil_expr(expr: &syn::Expr) -> bool {
+    match strip_paren_or_group(expr) {
+        syn::Expr::Path(path) => {
+            path.qself.is_none() && path_is(&path.path, &["crate", "builtin", "GorsAny", "Nil"])
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn gors_any_new_arg(expr: &syn::Expr) -> Option<&syn::Expr> {
+    single_call_arg_for_path(
+        strip_paren_or_group(expr),
+        &["crate", "builtin", "GorsAny", "new"],
+    )
+}
+
 pub(super) fn arc_mutex_new_inner_expr(expr: &syn::Expr) -> Option<syn::Expr> {
     let mutex_call = single_call_arg_for_path(expr, &["std", "sync", "Arc", "new"])?;
     single_call_arg_for_path(mutex_call, &["std", "sync", "Mutex", "new"]).cloned()
@@ -787,6 +832,12 @@ pub(super) fn is_box_dyn_any_type(ty: &syn::Type) -> bool {
     trait_object_has_any_bound(trait_object)
 }
 
+pub(super) fn is_gors_any_type(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(type_path)
+        if type_path.qself.is_none()
+            && path_is(&type_path.path, &["crate", "builtin", "GorsAny"]))
+}
+
 pub(super) fn is_box_type_with_any_bound(ty: &syn::Type) -> bool {
     let syn::Type::Path(type_path) = ty else {
         return false;
@@ -925,18 +976,35 @@ pub(super) fn macro_token_item_names(
         item_names: &std::collections::HashSet<String>,
         names: &mut std::collections::HashSet<String>,
     ) {
+        // Segments of `std::string::String` and the like name library items,
+        // not the local items they may share a name with.
+        let mut in_external_path = false;
+        let mut after_path_sep = false;
         for token in tokens {
             match token {
                 proc_macro2::TokenTree::Ident(ident) => {
                     let name = ident.to_string();
-                    if item_names.contains(&name) {
-                        names.insert(name);
+                    if !(in_external_path && after_path_sep) {
+                        in_external_path = matches!(name.as_str(), "std" | "core" | "alloc");
+                        if item_names.contains(&name) {
+                            names.insert(name);
+                        }
                     }
+                    after_path_sep = false;
+                }
+                proc_macro2::TokenTree::Punct(punct) => {
+                    after_path_sep = punct.as_char() == ':';
+                    in_external_path &= after_path_sep;
                 }
                 proc_macro2::TokenTree::Group(group) => {
                     collect(group.stream(), item_names, names);
+                    in_external_path = false;
+                    after_path_sep = false;
+                }
+                proc_macro2::TokenTree::Literal(_) => {
+                    in_external_path = false;
+                    after_path_sep = false;
                 }
-                proc_macro2::TokenTree::Literal(_) | proc_macro2::TokenTree::Punct(_) => {}
             }
         }
     }
@@ -1090,6 +1158,7 @@ mod tests {
         let other_expr: syn::Expr = parse_quote! { other };
         let value_ident = syn::Ident::new("value", proc_macro2::Span::call_site());
         let slice_range: syn::Expr = parse_quote! { values[1..] };
+        let checked_slice_range: syn::Expr = parse_quote! { values[crate::builtin::bounds(..2)] };
         let slice_index: syn::Expr = parse_quote! { values[1] };
         let vec_ty: syn::Type = parse_quote! { Vec<u8> };
 
@@ -1103,6 +1172,7 @@ mod tests {
         assert!(!expr_is_ident(&other_expr, &value_ident));
         assert!(!expr_is_ident(&field_expr, &value_ident));
         assert!(is_slice_range_index_expr(&slice_range));
+        assert!(is_slice_range_index_expr(&checked_slice_range));
         assert!(!is_slice_range_index_expr(&slice_index));
         assert!(!is_path_ident(&qualified_expr, "value"));
         assert_eq!(type_path_ident_name(&vec_ty).as_deref(), Some("Vec"));
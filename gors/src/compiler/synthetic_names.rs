@@ -183,6 +183,14 @@ pub(super) fn next_select_recv_idents() -> (syn::Ident, syn::Ident) {
     )
 }
 
+pub(super) fn select_chosen_ident() -> syn::Ident {
+    syn::Ident::new("__gors_select_chosen", Span::mixed_site())
+}
+
+pub(super) fn select_case_ident(index: usize) -> syn::Ident {
+    syn::Ident::new(&format!("__gors_select_case_{index}"), Span::mixed_site())
+}
+
 pub(super) fn next_goto_state_names() -> (syn::Ident, syn::Lifetime) {
     let n = next_id(&GOTO_STATE_COUNTER);
     (
@@ -357,6 +365,8 @@ mod tests {
             "__gors_type_switch_value_1"
         );
         assert_eq!(next_select_label().ident.to_string(), "__gors_select_0");
+        assert_eq!(select_chosen_ident().to_string(), "__gors_select_chosen");
+        assert_eq!(select_case_ident(1).to_string(), "__gors_select_case_1");
         assert_eq!(next_defer_id(), 0);
         assert_eq!(range_assign_temp_ident(2).to_string(), "__gors_range_2");
         assert_eq!(
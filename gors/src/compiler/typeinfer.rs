@@ -617,7 +617,7 @@ fn const_integer_value_i128(expr: &ast::Expr<'_>, env: &TypeEnv) -> Option<i128>
     }
 }
 
-fn parse_integer_literal_i128(value: &str) -> Option<i128> {
+pub(crate) fn parse_integer_literal_i128(value: &str) -> Option<i128> {
     let cleaned = value.replace('_', "");
     let (radix, digits) = if let Some(rest) = cleaned
         .strip_prefix("0b")
@@ -3370,6 +3370,50 @@ impl TypeEnv {
         promoted
     }
 
+    /// The function key of `method` on `type_name`, looking through embedded
+    /// struct fields. `None` when the method is missing or is promoted from an
+    /// embedded interface.
+    pub fn named_type_method_func_key(
+        &self,
+        type_name: &str,
+        method: &str,
+    ) -> Option<std::string::String> {
+        self.promoted_method_func_key(type_name, method, &mut HashSet::new())
+    }
+
+    fn promoted_method_func_key(
+        &self,
+        type_name: &str,
+        method: &str,
+        visiting: &mut HashSet<std::string::String>,
+    ) -> Option<std::string::String> {
+        let method_key = format!("{type_name}.{method}");
+        if self.has_func(&method_key) {
+            return Some(method_key);
+        }
+        if !visiting.insert(type_name.to_string()) {
+            return None;
+        }
+        let promoted = self
+            .get_struct_fields(type_name)
+            .iter()
+            .filter(|(field_name, _)| self.is_struct_embedded_field(type_name, field_name))
+            .find_map(|(_, field_ty)| {
+                let field_ty = match self.resolve_alias(field_ty) {
+                    GoType::Pointer(inner) => *inner,
+                    field_ty => field_ty,
+                };
+                match field_ty {
+                    GoType::Named(name) | GoType::Instantiated { name, .. } => {
+                        self.promoted_method_func_key(&name, method, visiting)
+                    }
+                    _ => None,
+                }
+            });
+        visiting.remove(type_name);
+        promoted
+    }
+
     fn embedded_type_has_method(
         &self,
         field_ty: &GoType,
@@ -4340,10 +4384,14 @@ impl TypeEnv {
                     let method_key = format!("{}.{}", name.name, method_name);
                     self.set_func_params(&method_key, params);
                     self.set_func(&method_key, returns);
-                    self.set_borrowed_slice_params(
-                        &method_key,
-                        borrowed_slice_indices_from_params(&self.get_func_params(&method_key)),
-                    );
+                    // A variadic parameter is lowered to a Vec, like in
+                    // function declarations.
+                    let mut borrowed =
+                        borrowed_slice_indices_from_params(&self.get_func_params(&method_key));
+                    if let Some(start) = variadic_start {
+                        borrowed.remove(&start);
+                    }
+                    self.set_borrowed_slice_params(&method_key, borrowed);
                     if let Some(start) = variadic_start {
                         self.set_func_variadic_start(&method_key, start);
                     }
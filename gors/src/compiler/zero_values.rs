@@ -1,4 +1,4 @@
-use super::syn_inspect::is_box_type_with_any_bound;
+use super::syn_inspect::{is_box_type_with_any_bound, is_gors_any_type};
 use super::{ast, typeinfer};
 
 pub(super) fn expr_for_type(expr: &ast::Expr) -> syn::Expr {
@@ -11,7 +11,7 @@ pub(super) fn expr_for_type(expr: &ast::Expr) -> syn::Expr {
             super::boxed_noop_interface_expr(&interface_name)
         }
         ast::Expr::InterfaceType(_) => {
-            syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any> }
+            syn::parse_quote! { crate::builtin::GorsAny::Nil }
         }
         ast::Expr::FuncType(func_type) => expr_for_func_type(func_type),
         ast::Expr::ArrayType(array_type) if array_type.len.is_some() => {
@@ -26,7 +26,7 @@ pub(super) fn expr_for_optional_type(type_expr: Option<&ast::Expr>) -> syn::Expr
         Some(expr) if super::interface_name_from_type_expr(expr).is_some() => expr_for_type(expr),
         Some(ast::Expr::Ident(ident)) => expr_for_type_name(Some(ident.name)),
         Some(ast::Expr::InterfaceType(_)) => {
-            syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any> }
+            syn::parse_quote! { crate::builtin::GorsAny::Nil }
         }
         Some(ast::Expr::FuncType(func_type)) => expr_for_func_type(func_type),
         Some(ast::Expr::ArrayType(array_type)) => expr_for_array_type(array_type),
@@ -49,7 +49,7 @@ pub(super) fn expr_for_type_name(type_name: Option<&str>) -> syn::Expr {
         Some("error") => syn::parse_quote! {
             Box::new(crate::builtin::__GorsNooperror::default()) as Box<dyn crate::builtin::error>
         },
-        Some("any") => syn::parse_quote! { Box::new(()) as Box<dyn std::any::Any> },
+        Some("any") => syn::parse_quote! { crate::builtin::GorsAny::Nil },
         _ => syn::parse_quote! { Default::default() },
     }
 }
@@ -68,9 +68,12 @@ pub(super) fn expr_for_go_type(go_type: &typeinfer::GoType) -> Option<syn::Expr>
 
 pub(super) fn expr_for_syn_type(ty: Option<&syn::Type>) -> syn::Expr {
     if let Some(ty) = ty {
-        if is_box_type_with_any_bound(ty) {
+        if is_gors_any_type(ty) {
             return expr_for_type_name(Some("any"));
         }
+        if is_box_type_with_any_bound(ty) {
+            return syn::parse_quote! { Box::new(()) as #ty };
+        }
     }
     if matches!(ty, Some(syn::Type::Array(_))) {
         return syn::parse_quote! { std::array::from_fn(|_| Default::default()) };
@@ -134,12 +137,11 @@ mod tests {
     }
 
     #[test]
-    fn any_zero_value_is_boxed_unit() -> Result<(), String> {
+    fn any_zero_value_is_nil_interface() -> Result<(), String> {
         let expr = first_type_expr("package p\ntype T any\n")?;
         let zero = expr_for_type(&expr).to_token_stream().to_string();
 
-        assert!(zero.contains("Box :: new (())"), "{zero}");
-        assert!(zero.contains("Box < dyn std :: any :: Any >"), "{zero}");
+        assert_eq!(zero, "crate :: builtin :: GorsAny :: Nil");
         Ok(())
     }
 
@@ -160,7 +162,7 @@ mod tests {
         let zero = expr_for_type(&expr).to_token_stream().to_string();
 
         assert!(zero.contains("std :: array :: from_fn"), "{zero}");
-        assert!(zero.contains("Box :: new (())"), "{zero}");
+        assert!(zero.contains("GorsAny :: Nil"), "{zero}");
         Ok(())
     }
 
@@ -0,0 +1,366 @@
+//! `//go:generate` directives, as run by `go generate`.
+//!
+//! A directive is a line comment starting at the first column with
+//! `//go:generate` followed by a space or tab. Its arguments are split on
+//! spaces and tabs, with double-quoted Go strings kept as one word, then
+//! `$NAME` and `${NAME}` are expanded from the generator environment
+//! (`$GOFILE`, `$GOLINE`, `$GOPACKAGE`, `$DOLLAR`, ...) and the process
+//! environment. `//go:generate -command NAME ARGS...` defines a shorthand
+//! for the rest of the file.
+
+use std::collections::HashMap;
+
+use crate::ast;
+
+pub mod stringer;
+
+const DIRECTIVE_PREFIX: &str = "//go:generate";
+
+/// One `//go:generate` directive of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// Line of the directive, starting at 1.
+    pub line: usize,
+    /// The whole comment, `//go:generate` included. `-run` matches it.
+    pub text: String,
+}
+
+/// The `//go:generate` directives of `file`, in source order.
+pub fn file_directives(file: &ast::File) -> Vec<Directive> {
+    file.comments
+        .iter()
+        .flat_map(|group| &group.list)
+        .filter(|comment| comment.slash.column == 1)
+        .filter(|comment| {
+            comment
+                .text
+                .strip_prefix(DIRECTIVE_PREFIX)
+                .is_some_and(|rest| rest.starts_with([' ', '\t']))
+        })
+        .map(|comment| Directive {
+            line: comment.slash.line,
+            text: comment.text.trim_end_matches('\r').to_string(),
+        })
+        .collect()
+}
+
+/// Expands the directives of one source file into commands.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    file: String,
+    package: String,
+    env: Vec<(String, String)>,
+    commands: HashMap<String, Vec<String>>,
+}
+
+impl Generator {
+    /// A generator for the file at `path`, of package `package`.
+    ///
+    /// `env` holds the variables set for every command on top of the
+    /// per-directive ones, such as `GOOS` and `GOARCH`.
+    pub fn new(path: &str, package: &str, env: Vec<(String, String)>) -> Self {
+        let file = std::path::Path::new(path).file_name().map_or_else(
+            || path.to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Self {
+            file,
+            package: package.to_string(),
+            env,
+            commands: HashMap::new(),
+        }
+    }
+
+    /// Variables set in the environment of the command of `directive`.
+    pub fn env(&self, directive: &Directive) -> Vec<(String, String)> {
+        let mut env = self.env.clone();
+        env.extend([
+            ("GOFILE".to_string(), self.file.clone()),
+            ("GOLINE".to_string(), directive.line.to_string()),
+            ("GOPACKAGE".to_string(), self.package.clone()),
+            ("DOLLAR".to_string(), "$".to_string()),
+        ]);
+        env
+    }
+
+    /// The expanded command words of `directive`.
+    ///
+    /// Returns `Ok(None)` for `-command` definitions, which only record a
+    /// shorthand for the directives after it.
+    pub fn command(&mut self, directive: &Directive) -> Result<Option<Vec<String>>, String> {
+        let arguments = directive
+            .text
+            .strip_prefix(DIRECTIVE_PREFIX)
+            .unwrap_or_default();
+        let mut words = split_words(arguments)?;
+        if let Some(definition) = words.first().and_then(|word| self.commands.get(word)) {
+            words.splice(..1, definition.iter().cloned());
+        }
+        let env = self.env(directive);
+        let words = words
+            .iter()
+            .map(|word| expand(word, &env))
+            .collect::<Vec<_>>();
+
+        match words.split_first() {
+            None => Err("no arguments to directive".to_string()),
+            Some((first, rest)) if first == "-command" => {
+                let Some((name, definition)) = rest.split_first() else {
+                    return Err("no command specified for -command".to_string());
+                };
+                if self.commands.contains_key(name) {
+                    return Err(format!("command {name:?} multiply defined"));
+                }
+                self.commands.insert(name.clone(), definition.to_vec());
+                Ok(None)
+            }
+            Some(_) => Ok(Some(words)),
+        }
+    }
+}
+
+/// Split directive arguments into words, unquoting double-quoted words.
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut rest = line.trim_start_matches([' ', '\t']);
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted_end(quoted)?;
+            let (word, after) = rest.split_at(end + 2);
+            words.push(unquote(word, '"')?);
+            if !after.is_empty() && !after.starts_with([' ', '\t']) {
+                return Err("expect space after quoted argument".to_string());
+            }
+            rest = after;
+        } else {
+            let end = rest.find([' ', '\t']).unwrap_or(rest.len());
+            let (word, after) = rest.split_at(end);
+            words.push(word.to_string());
+            rest = after;
+        }
+        rest = rest.trim_start_matches([' ', '\t']);
+    }
+    Ok(words)
+}
+
+/// Byte offset of the closing quote in `quoted`, which follows an opening `"`.
+fn quoted_end(quoted: &str) -> Result<usize, String> {
+    let mut bytes = quoted.bytes().enumerate();
+    while let Some((i, byte)) = bytes.next() {
+        match byte {
+            b'\\' => {
+                if bytes.next().is_none() {
+                    return Err("bad backslash".to_string());
+                }
+            }
+            b'"' => return Ok(i),
+            _ => {}
+        }
+    }
+    Err("mismatched quoted string".to_string())
+}
+
+/// Unquote a Go string or rune literal delimited by `quote`.
+fn unquote(word: &str, quote: char) -> Result<String, String> {
+    let bad = || "bad quoted string".to_string();
+    let inner = word
+        .strip_prefix(quote)
+        .and_then(|word| word.strip_suffix(quote))
+        .ok_or_else(bad)?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let escaped = chars.next().ok_or_else(bad)?;
+        let decoded = match escaped {
+            'a' => '\x07',
+            'b' => '\x08',
+            'f' => '\x0c',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\x0b',
+            '\\' => escaped,
+            _ if escaped == quote => escaped,
+            'x' | 'u' | 'U' | '0'..='7' => {
+                let (radix, len) = match escaped {
+                    'x' => (16, 2),
+                    'u' => (16, 4),
+                    'U' => (16, 8),
+                    _ => (8, 2),
+                };
+                let digits = chars.by_ref().take(len).collect::<String>();
+                let digits = if radix == 8 {
+                    format!("{escaped}{digits}")
+                } else {
+                    digits
+                };
+                u32::from_str_radix(&digits, radix)
+                    .ok()
+                    .filter(|_| digits.len() == len + usize::from(radix == 8))
+                    .and_then(char::from_u32)
+                    .ok_or_else(bad)?
+            }
+            _ => return Err(bad()),
+        };
+        out.push(decoded);
+    }
+    Ok(out)
+}
+
+/// Expand `$NAME` and `${NAME}` like Go's `os.Expand`, looking names up in
+/// `env` first and then in the process environment.
+fn expand(word: &str, env: &[(String, String)]) -> String {
+    let lookup = |name: &str| {
+        env.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var(name).ok())
+            .unwrap_or_default()
+    };
+
+    let mut out = String::new();
+    let mut rest = word;
+    while let Some(dollar) = rest.find('$') {
+        let (before, after) = rest.split_at(dollar);
+        out.push_str(before);
+        let after = after.strip_prefix('$').unwrap_or(after);
+        let (name, width) = shell_name(after);
+        if name.is_empty() && width == 0 {
+            out.push('$');
+        } else if !name.is_empty() {
+            out.push_str(&lookup(name));
+        }
+        rest = after.split_at(width).1;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The variable name at the start of `s`, after a `$`, and the number of
+/// bytes it spans. Malformed `${...}` has an empty name and a non-zero
+/// width, so it is dropped.
+fn shell_name(s: &str) -> (&str, usize) {
+    if let Some(braced) = s.strip_prefix('{') {
+        let mut chars = braced.chars();
+        if let (Some(c), Some('}')) = (chars.next(), chars.next())
+            && is_shell_special(c)
+        {
+            return (braced.split_at(1).0, 3);
+        }
+        return match braced.find('}') {
+            None => ("", 1),
+            Some(0) => ("", 2),
+            Some(end) => (braced.split_at(end).0, end + 2),
+        };
+    }
+    if s.starts_with(is_shell_special) {
+        return (s.split_at(1).0, 1);
+    }
+    let end = s
+        .find(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+        .unwrap_or(s.len());
+    (s.split_at(end).0, end)
+}
+
+fn is_shell_special(c: char) -> bool {
+    matches!(c, '*' | '#' | '$' | '@' | '!' | '?' | '-' | '0'..='9')
+}
+
+#[cfg(test)]
+#[allow(clippy::panic, clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn directive(line: usize, text: &str) -> Directive {
+        Directive {
+            line,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn file_directives_only_match_first_column_go_generate_comments() {
+        let source = "package p\n\n//go:generate stringer -type=Pill\n  //go:generate indented\n//go:generatex no\n// go:generate spaced\n//go:generate\techo tab\n";
+        let file = crate::parser::parse_file("pill.go", source).unwrap();
+
+        assert_eq!(
+            file_directives(&file),
+            vec![
+                directive(3, "//go:generate stringer -type=Pill"),
+                directive(7, "//go:generate\techo tab"),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_expands_variables_and_quoted_words() {
+        let mut generator = Generator::new("dir/pill.go", "painkiller", Vec::new());
+        let words = generator
+            .command(&directive(
+                12,
+                r#"//go:generate echo $GOFILE ${GOPACKAGE}:$GOLINE "a b\tc" $DOLLAR{x} ${} $"#,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            words,
+            Some(vec![
+                "echo".to_string(),
+                "pill.go".to_string(),
+                "painkiller:12".to_string(),
+                "a b\tc".to_string(),
+                "${x}".to_string(),
+                String::new(),
+                "$".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn command_substitutes_shorthands_defined_by_dash_command() {
+        let mut generator = Generator::new("pill.go", "painkiller", Vec::new());
+
+        assert_eq!(
+            generator.command(&directive(1, "//go:generate -command yacc go tool yacc")),
+            Ok(None)
+        );
+        assert_eq!(
+            generator.command(&directive(2, "//go:generate yacc -o gopher.go gopher.y")),
+            Ok(Some(
+                ["go", "tool", "yacc", "-o", "gopher.go", "gopher.y"]
+                    .map(String::from)
+                    .to_vec()
+            ))
+        );
+        assert_eq!(
+            generator.command(&directive(3, "//go:generate -command yacc other")),
+            Err("command \"yacc\" multiply defined".to_string())
+        );
+        assert_eq!(
+            generator.command(&directive(4, "//go:generate -command")),
+            Err("no command specified for -command".to_string())
+        );
+    }
+
+    #[test]
+    fn command_rejects_malformed_quotes() {
+        let mut generator = Generator::new("pill.go", "painkiller", Vec::new());
+
+        assert_eq!(
+            generator.command(&directive(1, r#"//go:generate echo "open"#)),
+            Err("mismatched quoted string".to_string())
+        );
+        assert_eq!(
+            generator.command(&directive(1, r#"//go:generate echo "a"b"#)),
+            Err("expect space after quoted argument".to_string())
+        );
+        assert_eq!(
+            generator.command(&directive(1, "//go:generate \t")),
+            Err("no arguments to directive".to_string())
+        );
+    }
+}
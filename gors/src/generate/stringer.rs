@@ -0,0 +1,704 @@
+//! A `stringer` equivalent: generates `String` methods for integer types
+//! with named constants, like `golang.org/x/tools/cmd/stringer`.
+//!
+//! Constant types come from [`TypeEnv`]; values are evaluated here, so
+//! `iota`, implicit repetition and constant arithmetic are supported. The
+//! output matches the layout of the upstream tool: one run of consecutive
+//! values is a name string sliced by an index array, up to ten runs become
+//! a `switch`, and more runs a map.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::ast;
+use crate::compiler::typeinfer::{GoType, TypeEnv, parse_integer_literal_i128};
+use crate::token::Token;
+
+/// More runs than this are looked up in a map instead of a `switch`.
+const MAX_SWITCH_RUNS: usize = 10;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    types: Vec<String>,
+    output: Option<String>,
+    trim_prefix: String,
+    line_comment: bool,
+    tags: Option<String>,
+    args: Vec<String>,
+}
+
+/// A named constant of one of the requested types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Value {
+    original_name: String,
+    name: String,
+    value: i128,
+}
+
+/// Run `stringer` with command-line `args` from the directory `dir`.
+pub fn run(args: &[String], dir: &Path) -> Result<(), String> {
+    let options = parse_flags(args)?;
+    let inputs = if options.args.is_empty() {
+        vec![".".to_string()]
+    } else {
+        options.args.clone()
+    };
+    let (package_dir, files) = match inputs.as_slice() {
+        [input] if dir.join(input).is_dir() => {
+            let package_dir = dir.join(input);
+            let files = crate::parser::scan_package_dir(&package_dir.to_string_lossy())
+                .map_err(|err| err.to_string())?
+                .go_files;
+            (package_dir, files)
+        }
+        _ => {
+            if options.tags.is_some() {
+                return Err(
+                    "-tags option applies only to directories, not when files are specified"
+                        .to_string(),
+                );
+            }
+            let files = inputs
+                .iter()
+                .map(|input| dir.join(input).to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            let package_dir = dir
+                .join(inputs.first().map(String::as_str).unwrap_or_default())
+                .parent()
+                .map_or_else(|| dir.to_path_buf(), Path::to_path_buf);
+            (package_dir, files)
+        }
+    };
+
+    let sources = files
+        .iter()
+        .map(|path| {
+            std::fs::read_to_string(path)
+                .map(|source| (path.clone(), source))
+                .map_err(|err| format!("cannot read '{path}': {err}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let parsed = sources
+        .iter()
+        .map(|(path, source)| crate::parser::parse_file(path, source))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+
+    let header = std::iter::once("stringer")
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let source = generate(&options, &header, &parsed)?;
+    let output = options.output.as_ref().map_or_else(
+        || {
+            let first = options
+                .types
+                .first()
+                .map(String::as_str)
+                .unwrap_or_default();
+            package_dir.join(format!("{first}_string.go").to_lowercase())
+        },
+        |output| dir.join(output),
+    );
+    std::fs::write(&output, source).map_err(|err| format!("writing output: {err}"))
+}
+
+/// Parse flags like Go's `flag` package: `-name value`, `-name=value` or
+/// `--name`, stopping at the first argument that is not a flag.
+fn parse_flags(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        let Some(flag) = arg
+            .strip_prefix("--")
+            .or_else(|| arg.strip_prefix('-'))
+            .filter(|flag| !flag.is_empty())
+        else {
+            options.args.push(arg.clone());
+            break;
+        };
+        let (name, inline_value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (flag, None),
+        };
+        if name == "linecomment" {
+            options.line_comment = match inline_value.as_deref() {
+                None | Some("true" | "1" | "t" | "T" | "TRUE" | "True") => true,
+                Some("false" | "0" | "f" | "F" | "FALSE" | "False") => false,
+                Some(value) => {
+                    return Err(format!("invalid boolean value {value:?} for -linecomment"));
+                }
+            };
+            continue;
+        }
+        let Some(value) = inline_value.or_else(|| args.next().cloned()) else {
+            return Err(format!("flag needs an argument: -{name}"));
+        };
+        match name {
+            "type" => options.types = value.split(',').map(str::to_string).collect(),
+            "output" => options.output = Some(value),
+            "trimprefix" => options.trim_prefix = value,
+            "tags" => options.tags = Some(value),
+            _ => return Err(format!("flag provided but not defined: -{name}")),
+        }
+    }
+    options.args.extend(args.cloned());
+    if options.types.is_empty() || options.types.iter().any(String::is_empty) {
+        return Err("usage: stringer [flags] -type T [directory]".to_string());
+    }
+    Ok(options)
+}
+
+/// The generated source for the requested types of the package `files`.
+fn generate(options: &Options, header: &str, files: &[ast::File]) -> Result<String, String> {
+    let package = files
+        .first()
+        .map(|file| file.name.name)
+        .ok_or_else(|| "no buildable Go source files".to_string())?;
+    let mut env = TypeEnv::new();
+    env.scan_files(&files.iter().collect::<Vec<_>>());
+    let constants = package_constants(files, &env);
+
+    let mut out = format!(
+        "// Code generated by \"{header}\"; DO NOT EDIT.\n\npackage {package}\n\nimport \"strconv\"\n"
+    );
+    for type_name in &options.types {
+        let underlying = env.resolve_alias(&GoType::Named(type_name.clone()));
+        let values = constants
+            .iter()
+            .filter(|constant| {
+                constant.name != "_" && constant.type_name.as_deref() == Some(type_name.as_str())
+            })
+            .map(|constant| {
+                let name = match &constant.line_comment {
+                    Some(comment) if options.line_comment => comment.clone(),
+                    _ => constant
+                        .name
+                        .strip_prefix(options.trim_prefix.as_str())
+                        .unwrap_or(&constant.name)
+                        .to_string(),
+                };
+                constant
+                    .value
+                    .map(|value| Value {
+                        original_name: constant.name.clone(),
+                        name,
+                        value,
+                    })
+                    .ok_or_else(|| format!("can't handle non-integer constant type {type_name}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.is_empty() {
+            return Err(format!("no values defined for type {type_name}"));
+        }
+        if !underlying.is_integer() {
+            return Err(format!(
+                "can't handle non-integer constant type {type_name}"
+            ));
+        }
+        write_type(&mut out, type_name, underlying.is_signed_int(), values);
+    }
+    Ok(out)
+}
+
+/// A package-level constant with its evaluated value, if it is an integer.
+struct Constant {
+    name: String,
+    type_name: Option<String>,
+    value: Option<i128>,
+    line_comment: Option<String>,
+}
+
+fn package_constants(files: &[ast::File], env: &TypeEnv) -> Vec<Constant> {
+    let mut values = HashMap::new();
+    let mut constants = Vec::new();
+    for file in files {
+        for decl in &file.decls {
+            let ast::Decl::GenDecl(decl) = decl else {
+                continue;
+            };
+            if decl.tok != Token::CONST {
+                continue;
+            }
+            let mut repeated: Option<&Vec<ast::Expr>> = None;
+            for (iota, spec) in decl.specs.iter().enumerate() {
+                let ast::Spec::ValueSpec(spec) = spec else {
+                    continue;
+                };
+                if spec.values.is_some() {
+                    repeated = spec.values.as_ref();
+                }
+                for (index, name) in spec.names.iter().enumerate() {
+                    let value = repeated
+                        .and_then(|exprs| exprs.get(index))
+                        .and_then(|expr| evaluate(expr, iota as i128, &values, env));
+                    if let Some(value) = value {
+                        values.insert(name.name.to_string(), value);
+                    }
+                    let type_name = match env.get_var(name.name) {
+                        Some(GoType::Named(type_name)) => Some(type_name),
+                        _ => None,
+                    };
+                    constants.push(Constant {
+                        name: name.name.to_string(),
+                        type_name,
+                        value,
+                        line_comment: spec.comment.as_ref().and_then(comment_text),
+                    });
+                }
+            }
+        }
+    }
+    constants
+}
+
+/// Text of a line comment group of exactly one comment, like stringer's
+/// `-linecomment`.
+fn comment_text(group: &ast::CommentGroup) -> Option<String> {
+    let [comment] = group.list.as_slice() else {
+        return None;
+    };
+    let text = comment.text.strip_prefix("//").or_else(|| {
+        comment
+            .text
+            .strip_prefix("/*")
+            .and_then(|text| text.strip_suffix("*/"))
+    })?;
+    Some(text.trim().to_string())
+}
+
+/// Evaluate an integer constant expression.
+fn evaluate(
+    expr: &ast::Expr,
+    iota: i128,
+    values: &HashMap<String, i128>,
+    env: &TypeEnv,
+) -> Option<i128> {
+    let eval = |expr: &ast::Expr| evaluate(expr, iota, values, env);
+    match expr {
+        ast::Expr::BasicLit(lit) => match lit.kind {
+            Token::INT => parse_integer_literal_i128(lit.value),
+            Token::CHAR => super::unquote(lit.value, '\'')
+                .ok()
+                .and_then(|text| text.chars().next())
+                .map(|c| i128::from(u32::from(c))),
+            _ => None,
+        },
+        ast::Expr::Ident(ident) if ident.name == "iota" => Some(iota),
+        ast::Expr::Ident(ident) => values
+            .get(ident.name)
+            .copied()
+            .or_else(|| env.get_const_integer_value(ident.name)),
+        ast::Expr::ParenExpr(paren) => eval(&paren.x),
+        ast::Expr::CallExpr(call) => match call.args.as_deref() {
+            Some([arg]) => eval(arg),
+            _ => None,
+        },
+        ast::Expr::UnaryExpr(unary) => {
+            let x = eval(&unary.x)?;
+            match unary.op {
+                Token::ADD => Some(x),
+                Token::SUB => x.checked_neg(),
+                Token::XOR => Some(!x),
+                _ => None,
+            }
+        }
+        ast::Expr::BinaryExpr(binary) => {
+            let x = eval(&binary.x)?;
+            let y = eval(&binary.y)?;
+            match binary.op {
+                Token::ADD => x.checked_add(y),
+                Token::SUB => x.checked_sub(y),
+                Token::MUL => x.checked_mul(y),
+                Token::QUO => x.checked_div(y),
+                Token::REM => x.checked_rem(y),
+                Token::AND => Some(x & y),
+                Token::OR => Some(x | y),
+                Token::XOR => Some(x ^ y),
+                Token::AND_NOT => Some(x & !y),
+                Token::SHL => u32::try_from(y).ok().and_then(|y| x.checked_shl(y)),
+                Token::SHR => u32::try_from(y).ok().and_then(|y| x.checked_shr(y)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Append the declarations and `String` method for `type_name`.
+fn write_type(out: &mut String, type_name: &str, signed: bool, mut values: Vec<Value>) {
+    out.push_str("\nfunc _() {\n");
+    out.push_str("\t// An \"invalid array index\" compiler error signifies that the constant values have changed.\n");
+    out.push_str("\t// Re-run the stringer command to generate them again.\n");
+    out.push_str("\tvar x [1]struct{}\n");
+    for value in &values {
+        let separator = if value.value < 0 { " - " } else { "-" };
+        out.push_str(&format!(
+            "\t_ = x[{}{separator}{}]\n",
+            value.original_name, value.value
+        ));
+    }
+    out.push_str("}\n");
+
+    // The stable sort keeps the first name of duplicated values.
+    values.sort_by_key(|value| value.value);
+    values.dedup_by_key(|value| value.value);
+    let runs = split_into_runs(&values);
+    match runs.as_slice() {
+        [run] => write_one_run(out, type_name, signed, run),
+        runs if runs.len() <= MAX_SWITCH_RUNS => write_multiple_runs(out, type_name, signed, runs),
+        runs => write_map(out, type_name, runs),
+    }
+}
+
+/// Split sorted, deduplicated values into runs of consecutive values.
+fn split_into_runs(values: &[Value]) -> Vec<&[Value]> {
+    let mut runs = Vec::new();
+    let mut rest = values;
+    while !rest.is_empty() {
+        let len = rest
+            .windows(2)
+            .position(|pair| match pair {
+                [a, b] => b.value != a.value + 1,
+                _ => true,
+            })
+            .map_or(rest.len(), |index| index + 1);
+        let (run, after) = rest.split_at(len);
+        runs.push(run);
+        rest = after;
+    }
+    runs
+}
+
+/// The name constant and index array of a run, without `const`/`var`.
+fn name_and_index_decls(type_name: &str, suffix: &str, run: &[Value]) -> (String, String) {
+    let mut names = String::new();
+    let mut indexes = vec!["0".to_string()];
+    for value in run {
+        names.push_str(&value.name);
+        indexes.push(names.len().to_string());
+    }
+    let name = format!("_{type_name}_name{suffix} = {}", go_quote(&names));
+    let index = format!(
+        "_{type_name}_index{suffix} = [...]uint{}{{{}}}",
+        index_bits(names.len()),
+        indexes.join(", ")
+    );
+    (name, index)
+}
+
+fn index_bits(len: usize) -> u32 {
+    match len {
+        0..0x100 => 8,
+        0x100..0x1_0000 => 16,
+        _ => 32,
+    }
+}
+
+fn out_of_range(type_name: &str, value: &str) -> String {
+    format!("\"{type_name}(\" + strconv.FormatInt(int64({value}), 10) + \")\"")
+}
+
+fn write_one_run(out: &mut String, type_name: &str, signed: bool, run: &[Value]) {
+    let (name, index) = name_and_index_decls(type_name, "", run);
+    let first = run.first().map_or(0, |value| value.value);
+    let less_than_zero = if signed { "i < 0 || " } else { "" };
+    out.push_str(&format!("\nconst {name}\n\nvar {index}\n\n"));
+    out.push_str(&format!("func (i {type_name}) String() string {{\n"));
+    let original = if first == 0 {
+        "i".to_string()
+    } else {
+        out.push_str(&format!("\ti -= {first}\n"));
+        if first < 0 {
+            format!("i - {}", -first)
+        } else {
+            format!("i+{first}")
+        }
+    };
+    out.push_str(&format!(
+        "\tif {less_than_zero}i >= {type_name}(len(_{type_name}_index)-1) {{\n"
+    ));
+    out.push_str(&format!(
+        "\t\treturn {}\n\t}}\n",
+        out_of_range(type_name, &original)
+    ));
+    out.push_str(&format!(
+        "\treturn _{type_name}_name[_{type_name}_index[i]:_{type_name}_index[i+1]]\n}}\n"
+    ));
+}
+
+fn write_multiple_runs(out: &mut String, type_name: &str, signed: bool, runs: &[&[Value]]) {
+    let mut names = Vec::new();
+    let mut indexes = Vec::new();
+    for (i, run) in runs.iter().enumerate() {
+        let (name, index) = name_and_index_decls(type_name, &format!("_{i}"), run);
+        names.push(name);
+        if run.len() != 1 {
+            indexes.push(index);
+        }
+    }
+    out.push_str("\nconst (\n");
+    for name in &names {
+        out.push_str(&format!("\t{name}\n"));
+    }
+    out.push_str(")\n");
+    if !indexes.is_empty() {
+        out.push_str("\nvar (\n");
+        for index in &indexes {
+            out.push_str(&format!("\t{index}\n"));
+        }
+        out.push_str(")\n");
+    }
+
+    out.push_str(&format!(
+        "\nfunc (i {type_name}) String() string {{\n\tswitch {{\n"
+    ));
+    for (i, run) in runs.iter().enumerate() {
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            continue;
+        };
+        if run.len() == 1 {
+            out.push_str(&format!(
+                "\tcase i == {}:\n\t\treturn _{type_name}_name_{i}\n",
+                first.value
+            ));
+            continue;
+        }
+        if first.value == 0 && !signed {
+            out.push_str(&format!("\tcase i <= {}:\n", last.value));
+        } else {
+            out.push_str(&format!(
+                "\tcase {} <= i && i <= {}:\n",
+                first.value, last.value
+            ));
+        }
+        if first.value != 0 {
+            out.push_str(&format!("\t\ti -= {}\n", first.value));
+        }
+        out.push_str(&format!(
+            "\t\treturn _{type_name}_name_{i}[_{type_name}_index_{i}[i]:_{type_name}_index_{i}[i+1]]\n"
+        ));
+    }
+    out.push_str(&format!(
+        "\tdefault:\n\t\treturn {}\n\t}}\n}}\n",
+        out_of_range(type_name, "i")
+    ));
+}
+
+fn write_map(out: &mut String, type_name: &str, runs: &[&[Value]]) {
+    let values = runs.iter().flat_map(|run| run.iter()).collect::<Vec<_>>();
+    let names = values
+        .iter()
+        .map(|value| value.name.as_str())
+        .collect::<String>();
+    out.push_str(&format!(
+        "\nconst _{type_name}_name = {}\n",
+        go_quote(&names)
+    ));
+
+    let key_width = values
+        .iter()
+        .map(|value| value.value.to_string().len())
+        .max()
+        .unwrap_or_default();
+    out.push_str(&format!(
+        "\nvar _{type_name}_map = map[{type_name}]string{{\n"
+    ));
+    let mut start = 0;
+    for value in values {
+        let end = start + value.name.len();
+        let key = format!("{}:", value.value);
+        out.push_str(&format!(
+            "\t{key:<width$} _{type_name}_name[{start}:{end}],\n",
+            width = key_width + 1
+        ));
+        start = end;
+    }
+    out.push_str("}\n\n");
+    out.push_str(&format!(
+        "func (i {type_name}) String() string {{\n\tif str, ok := _{type_name}_map[i]; ok {{\n\t\treturn str\n\t}}\n\treturn {}\n}}\n",
+        out_of_range(type_name, "i")
+    ));
+}
+
+/// Quote `s` as a Go interpreted string literal, like `strconv.Quote`.
+fn go_quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\x07' => quoted.push_str("\\a"),
+            '\x08' => quoted.push_str("\\b"),
+            '\x0c' => quoted.push_str("\\f"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\x0b' => quoted.push_str("\\v"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+#[allow(clippy::panic, clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn generated(args: &[&str], source: &str) -> Result<String, String> {
+        let args = args
+            .iter()
+            .map(|arg| (*arg).to_string())
+            .collect::<Vec<_>>();
+        let options = parse_flags(&args)?;
+        let file = crate::parser::parse_file("pill.go", source).unwrap();
+        generate(&options, &format!("stringer {}", args.join(" ")), &[file])
+    }
+
+    #[test]
+    fn parse_flags_accepts_go_flag_forms() {
+        let args = [
+            "-type",
+            "A,B",
+            "--output=out.go",
+            "-linecomment",
+            "-trimprefix=Pill",
+            "dir",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            parse_flags(&args),
+            Ok(Options {
+                types: vec!["A".to_string(), "B".to_string()],
+                output: Some("out.go".to_string()),
+                trim_prefix: "Pill".to_string(),
+                line_comment: true,
+                tags: None,
+                args: vec!["dir".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_flags(&["dir".to_string()]),
+            Err("usage: stringer [flags] -type T [directory]".to_string())
+        );
+    }
+
+    #[test]
+    fn generates_one_run_like_upstream_stringer() {
+        let source = "package painkiller\n\ntype Pill int\n\nconst (\n\tPlacebo Pill = iota\n\tAspirin\n\tIbuprofen\n\tParacetamol\n\tAcetaminophen = Paracetamol\n)\n";
+
+        assert_eq!(
+            generated(&["-type=Pill"], source).unwrap(),
+            r#"// Code generated by "stringer -type=Pill"; DO NOT EDIT.
+
+package painkiller
+
+import "strconv"
+
+func _() {
+	// An "invalid array index" compiler error signifies that the constant values have changed.
+	// Re-run the stringer command to generate them again.
+	var x [1]struct{}
+	_ = x[Placebo-0]
+	_ = x[Aspirin-1]
+	_ = x[Ibuprofen-2]
+	_ = x[Paracetamol-3]
+	_ = x[Acetaminophen-3]
+}
+
+const _Pill_name = "PlaceboAspirinIbuprofenParacetamol"
+
+var _Pill_index = [...]uint8{0, 7, 14, 23, 34}
+
+func (i Pill) String() string {
+	if i < 0 || i >= Pill(len(_Pill_index)-1) {
+		return "Pill(" + strconv.FormatInt(int64(i), 10) + ")"
+	}
+	return _Pill_name[_Pill_index[i]:_Pill_index[i+1]]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn generates_offset_runs_with_trimmed_and_line_comment_names() {
+        let source = "package p\n\ntype Day uint8\n\nconst (\n\t_ Day = iota\n\tDayMon // monday\n\tDayTue\n)\n";
+
+        let trimmed = generated(&["-type=Day", "-trimprefix=Day"], source).unwrap();
+        assert!(
+            trimmed.contains("const _Day_name = \"MonTue\"\n"),
+            "{trimmed}"
+        );
+        assert!(
+            trimmed.contains("\ti -= 1\n\tif i >= Day(len(_Day_index)-1) {\n"),
+            "{trimmed}"
+        );
+        assert!(
+            trimmed.contains("strconv.FormatInt(int64(i+1), 10)"),
+            "{trimmed}"
+        );
+
+        let commented = generated(&["-type=Day", "-linecomment"], source).unwrap();
+        assert!(
+            commented.contains("const _Day_name = \"mondayDayTue\"\n"),
+            "{commented}"
+        );
+    }
+
+    #[test]
+    fn generates_switch_for_few_runs_and_map_for_many() {
+        let source =
+            "package p\n\ntype Flag int\n\nconst (\n\tA Flag = 1 << iota\n\tB\n\tC\n\tD\n)\n";
+        let switch = generated(&["-type=Flag"], source).unwrap();
+        assert!(
+            switch.contains("\tcase 1 <= i && i <= 2:\n\t\ti -= 1\n"),
+            "{switch}"
+        );
+        assert!(
+            switch.contains("\tcase i == 4:\n\t\treturn _Flag_name_1\n"),
+            "{switch}"
+        );
+        assert!(
+            switch.contains("\tcase i == 8:\n\t\treturn _Flag_name_2\n"),
+            "{switch}"
+        );
+
+        let consts = (0..12)
+            .map(|i| format!("\tF{i} Flag = {}\n", i * 10))
+            .collect::<String>();
+        let map = generated(
+            &["-type=Flag"],
+            &format!("package p\n\ntype Flag int\n\nconst (\n{consts})\n"),
+        )
+        .unwrap();
+        assert!(
+            map.contains("var _Flag_map = map[Flag]string{\n\t0:   _Flag_name[0:2],\n"),
+            "{map}"
+        );
+        assert!(map.contains("\t110: _Flag_name[23:26],\n"), "{map}");
+    }
+
+    #[test]
+    fn rejects_types_without_integer_constants() {
+        let source =
+            "package p\n\ntype Name string\n\nconst Bob Name = \"bob\"\n\ntype Empty int\n";
+
+        assert_eq!(
+            generated(&["-type=Empty"], source),
+            Err("no values defined for type Empty".to_string())
+        );
+        assert_eq!(
+            generated(&["-type=Name"], source),
+            Err("can't handle non-integer constant type Name".to_string())
+        );
+    }
+}
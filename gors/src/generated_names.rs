@@ -2,6 +2,8 @@ pub const AS_ANY_METHOD: &str = "__gors_as_any";
 pub const CLONE_BOX_METHOD: &str = "__gors_clone_box";
 pub const INTERFACE_KEY_METHOD: &str = "__gors_interface_key";
 pub const PACKAGE_INIT_FN: &str = "__gors_init";
+pub const LINE_TABLE_STATIC: &str = "__GORS_LINE_TABLE";
+pub const LINE_TABLES_STATIC: &str = "__GORS_LINE_TABLES";
 pub const ERROR_EXT_TRAIT: &str = "__GorsErrorExt";
 pub const FMT_FLUSH_HOOK: &str = "__gors_flush_fmt";
 pub const FMT_FLUSH_METHOD_DOC_PREFIX: &str = "gors:fmt-flush-method=";
@@ -30,6 +32,10 @@ pub fn package_init_ident() -> syn::Ident {
     ident(PACKAGE_INIT_FN)
 }
 
+pub fn line_tables_ident() -> syn::Ident {
+    ident(LINE_TABLES_STATIC)
+}
+
 pub fn error_ext_trait_ident() -> syn::Ident {
     ident(ERROR_EXT_TRAIT)
 }
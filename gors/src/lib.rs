@@ -100,6 +100,18 @@ pub mod scanner;
 /// positions in Go source code and generated Rust output.
 pub mod mapping;
 
+/// `//go:generate` directives and the built-in generators.
+///
+/// Splits and expands directives the way `go generate` does, and provides a
+/// `stringer` equivalent that needs no Go toolchain.
+pub mod generate;
+
+/// Package metadata listing.
+///
+/// Reports local and embedded standard library packages in the shape of
+/// `go list -json`.
+pub mod packages;
+
 /// Go package resolution.
 ///
 /// Resolves Go packages, including embedded Go SDK source packages, into Rust
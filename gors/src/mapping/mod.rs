@@ -6,6 +6,7 @@
 
 pub use sourcemap::{SourceMap, SourceMapBuilder};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// A pending mapping collected during compilation.
@@ -23,7 +24,7 @@ pub struct PendingMapping {
 }
 
 /// Tracker for collecting source mappings during compilation.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct SourceMapTracker {
     /// Pending mappings collected during compilation
     pending: Vec<PendingMapping>,
@@ -112,47 +113,51 @@ impl SourceMapTracker {
         // Extract tokens from the Rust source
         let tokens = extract_tokens(rust_source);
 
-        // Build a map of name -> tokens for matching
-        let mut name_to_tokens: HashMap<&str, Vec<&TokenInfo>> = HashMap::new();
-        for token in &tokens {
-            name_to_tokens.entry(&token.text).or_default().push(token);
-        }
-
-        // Track which token index we've used for each Rust token name
-        let mut name_indices: HashMap<String, usize> = HashMap::new();
-
-        // Match pending mappings to Rust tokens
-        // Go names are stored in pending.name, but we need to find the corresponding Rust token
-        for pending in &self.pending {
-            if let Some(ref go_name) = pending.name {
-                // Get the Rust token name to search for
-                let rust_name = go_name_to_rust_name(go_name);
-
-                if let Some(matching_tokens) = name_to_tokens.get(rust_name) {
-                    let idx = name_indices.entry(rust_name.to_string()).or_insert(0);
-                    if let Some(token) = matching_tokens.get(*idx) {
-                        // Store the Go name in the source map (not the Rust name)
-                        let name_idx = builder.add_name(go_name);
-                        let src_idx = pending
-                            .source
-                            .as_ref()
-                            .and_then(|source| source_indices.get(source))
-                            .copied()
-                            .or(fallback_source_idx);
-                        builder.add_raw(
-                            token.start_line.saturating_sub(1),   // generated line (0-based)
-                            token.start_column.saturating_sub(1), // generated column (0-based)
-                            pending.orig_line.saturating_sub(1),  // original line (0-based)
-                            pending.orig_col.saturating_sub(1),   // original column (0-based)
-                            src_idx,
-                            Some(name_idx),
-                            false, // is_range: false for point mappings
-                        );
-                        *idx += 1;
-                    }
+        let mut matches = Vec::new();
+        let mut unmatched = Vec::new();
+        let mut spans = function_spans(&tokens);
+        for segment in function_segments(&self.pending) {
+            let span = segment
+                .function
+                .and_then(|name| spans.get_mut(go_name_to_rust_name(name).as_ref()))
+                .and_then(|spans| (!spans.is_empty()).then(|| spans.remove(0)));
+            match span {
+                Some(span) => {
+                    matches.extend(align_function(&self.pending, &segment, &tokens, span))
                 }
+                None => unmatched.extend(segment.entries),
             }
         }
+        matches.extend(match_by_occurrence(&self.pending, &unmatched, &tokens));
+        matches.sort_by_key(|&(_, token)| token);
+
+        for (pending_idx, token_idx) in matches {
+            let (Some(pending), Some(token)) =
+                (self.pending.get(pending_idx), tokens.get(token_idx))
+            else {
+                continue;
+            };
+            let Some(go_name) = pending.name.as_deref() else {
+                continue;
+            };
+            // Store the Go name in the source map (not the Rust name)
+            let name_idx = builder.add_name(go_name);
+            let src_idx = pending
+                .source
+                .as_ref()
+                .and_then(|source| source_indices.get(source))
+                .copied()
+                .or(fallback_source_idx);
+            builder.add_raw(
+                token.start_line.saturating_sub(1),   // generated line (0-based)
+                token.start_column.saturating_sub(1), // generated column (0-based)
+                pending.orig_line.saturating_sub(1),  // original line (0-based)
+                pending.orig_col.saturating_sub(1),   // original column (0-based)
+                src_idx,
+                Some(name_idx),
+                false, // is_range: false for point mappings
+            );
+        }
 
         builder.into_sourcemap()
     }
@@ -165,13 +170,224 @@ impl SourceMapTracker {
     }
 }
 
+/// The pending mappings recorded while compiling a Go function, which start
+/// with its `func` keyword. Compilation records the name of the function
+/// last, on the line of the keyword.
+struct FunctionSegment<'a> {
+    function: Option<&'a str>,
+    /// Indices of the pending mappings, the `func` keyword first.
+    entries: Vec<usize>,
+}
+
+fn function_segments(pending: &[PendingMapping]) -> Vec<FunctionSegment<'_>> {
+    let mut segments: Vec<FunctionSegment<'_>> = Vec::new();
+    let mut keyword_line = None;
+    for (idx, mapping) in pending.iter().enumerate() {
+        if mapping.name.as_deref() == Some("func") {
+            keyword_line = Some((mapping.source.as_deref(), mapping.orig_line));
+            segments.push(FunctionSegment {
+                function: None,
+                entries: Vec::new(),
+            });
+        }
+        match segments.last_mut() {
+            Some(segment) if keyword_line.is_some() => segment.entries.push(idx),
+            _ => segments.push(FunctionSegment {
+                function: None,
+                entries: vec![idx],
+            }),
+        }
+    }
+    for segment in &mut segments {
+        let first = segment.entries.first().and_then(|&idx| pending.get(idx));
+        let last = segment.entries.last().and_then(|&idx| pending.get(idx));
+        if let (Some(first), Some(last)) = (first, last)
+            && first.name.as_deref() == Some("func")
+            && segment.entries.len() > 1
+            && (last.source.as_deref(), last.orig_line)
+                == (first.source.as_deref(), first.orig_line)
+        {
+            segment.function = last.name.as_deref();
+        }
+    }
+    segments
+}
+
+/// The token ranges of the Rust functions, by name, in output order. Each
+/// runs from its `fn` keyword up to the next function.
+fn function_spans(tokens: &[TokenInfo]) -> HashMap<&str, Vec<std::ops::Range<usize>>> {
+    let starts: Vec<(usize, &str)> = tokens
+        .windows(2)
+        .enumerate()
+        .filter_map(|(idx, pair)| match pair {
+            [keyword, name] if keyword.text == "fn" && is_identifier(&name.text) => {
+                Some((idx, name.text.as_str()))
+            }
+            _ => None,
+        })
+        .collect();
+    let mut spans: HashMap<&str, Vec<std::ops::Range<usize>>> = HashMap::new();
+    for (i, &(start, name)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(tokens.len(), |&(end, _)| end);
+        spans.entry(name).or_default().push(start..end);
+    }
+    spans
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+}
+
+/// Match the mappings of a Go function to the tokens of its Rust function.
+/// The keyword and the name map to the head of the function, and the body
+/// is aligned in order, skipping the names the compiler drops or repeats.
+fn align_function(
+    pending: &[PendingMapping],
+    segment: &FunctionSegment<'_>,
+    tokens: &[TokenInfo],
+    span: std::ops::Range<usize>,
+) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut body: Vec<usize> = segment.entries.clone();
+    if segment.function.is_some()
+        && let (Some(keyword), Some(name)) = (body.first().copied(), body.pop())
+    {
+        body.remove(0);
+        matches.push((keyword, span.start));
+        matches.push((name, span.start + 1));
+    }
+    body.retain(|&idx| {
+        pending
+            .get(idx)
+            .is_some_and(|mapping| mapping.name.is_some())
+    });
+    body.sort_by_key(|&idx| {
+        pending
+            .get(idx)
+            .map(|mapping| (mapping.orig_line, mapping.orig_col))
+    });
+    let rust_names: Vec<Cow<'_, str>> = body
+        .iter()
+        .filter_map(|&idx| pending.get(idx)?.name.as_deref())
+        .map(go_name_to_rust_name)
+        .collect();
+    let names: Vec<&str> = rust_names.iter().map(AsRef::as_ref).collect();
+    let wanted: std::collections::HashSet<&str> = names.iter().copied().collect();
+    let candidates: Vec<usize> = span
+        .skip(2)
+        .filter(|&idx| {
+            tokens
+                .get(idx)
+                .is_some_and(|token| wanted.contains(token.text.as_str()))
+        })
+        .collect();
+    let texts: Vec<&str> = candidates
+        .iter()
+        .filter_map(|&idx| tokens.get(idx).map(|token| token.text.as_str()))
+        .collect();
+    for (name_pos, text_pos) in align_in_order(&names, &texts) {
+        if let (Some(&entry), Some(&token)) = (body.get(name_pos), candidates.get(text_pos)) {
+            matches.push((entry, token));
+        }
+    }
+    matches
+}
+
+/// The longest in-order matching of equal items of `a` and `b`, as index
+/// pairs. Long functions fall back to a greedy matching.
+fn align_in_order(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    const MAX_CELLS: usize = 4_000_000;
+    if a.len().saturating_mul(b.len()) > MAX_CELLS {
+        let mut pairs = Vec::new();
+        let mut next = 0;
+        for (i, item) in a.iter().enumerate() {
+            if let Some(offset) = b.iter().skip(next).position(|other| other == item) {
+                pairs.push((i, next + offset));
+                next += offset + 1;
+            }
+        }
+        return pairs;
+    }
+    // lengths[i][j] is the longest matching of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+    let at = |i: usize, j: usize| i * width + j;
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            let length = if a.get(i) == b.get(j) {
+                lengths.get(at(i + 1, j + 1)).map_or(0, |length| length + 1)
+            } else {
+                let skip_a = lengths.get(at(i + 1, j)).copied().unwrap_or(0);
+                let skip_b = lengths.get(at(i, j + 1)).copied().unwrap_or(0);
+                skip_a.max(skip_b)
+            };
+            if let Some(cell) = lengths.get_mut(at(i, j)) {
+                *cell = length;
+            }
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let length = |i, j| lengths.get(at(i, j)).copied().unwrap_or(0);
+        if a.get(i) == b.get(j) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if length(i + 1, j) >= length(i, j + 1) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Match each mapping to the next token with its name, in recording order.
+fn match_by_occurrence(
+    pending: &[PendingMapping],
+    entries: &[usize],
+    tokens: &[TokenInfo],
+) -> Vec<(usize, usize)> {
+    // Build a map of name -> tokens for matching
+    let mut name_to_tokens: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        name_to_tokens.entry(&token.text).or_default().push(idx);
+    }
+
+    // Track which token index we've used for each Rust token name
+    let mut name_indices: HashMap<Cow<'_, str>, usize> = HashMap::new();
+    let mut matches = Vec::new();
+    for &entry in entries {
+        let Some(go_name) = pending
+            .get(entry)
+            .and_then(|mapping| mapping.name.as_deref())
+        else {
+            continue;
+        };
+        // Get the Rust token name to search for
+        let rust_name = go_name_to_rust_name(go_name);
+        if let Some(matching_tokens) = name_to_tokens.get(rust_name.as_ref()) {
+            let idx = name_indices.entry(rust_name).or_insert(0);
+            if let Some(&token) = matching_tokens.get(*idx) {
+                matches.push((entry, token));
+                *idx += 1;
+            }
+        }
+    }
+    matches
+}
+
 /// Map Go token name to the corresponding Rust token name for matching.
 /// This is used during source map building to find Rust tokens that correspond to Go tokens.
 /// The actual Go name is still stored in the source map for highlighting.
-fn go_name_to_rust_name(go_name: &str) -> &str {
+fn go_name_to_rust_name(go_name: &str) -> Cow<'_, str> {
     match go_name {
-        "func" => "fn",
-        _ => go_name,
+        "func" => Cow::Borrowed("fn"),
+        _ if crate::compiler::is_rust_keyword(go_name) => Cow::Owned(format!("{go_name}_")),
+        _ => Cow::Borrowed(go_name),
     }
 }
 
@@ -340,6 +556,32 @@ fn extract_tokens(rust_source: &str) -> Vec<TokenInfo> {
             continue;
         }
 
+        // Lifetime or label
+        if ch == '\''
+            && chars
+                .get(i + 1)
+                .is_some_and(|next| next.is_alphabetic() || *next == '_')
+            && chars.get(i + 2).is_some_and(|next| *next != '\'')
+        {
+            let mut text = String::from(ch);
+            column += 1;
+            i += 1;
+            while let Some(current) = chars.get(i).copied() {
+                if !(current.is_alphanumeric() || current == '_') {
+                    break;
+                }
+                text.push(current);
+                column += 1;
+                i += 1;
+            }
+            tokens.push(TokenInfo {
+                text,
+                start_line,
+                start_column,
+            });
+            continue;
+        }
+
         // Character literal
         if ch == '\'' {
             let mut text = String::new();
@@ -438,6 +680,42 @@ mod tests {
         assert!(parsed.get_token_count() >= 2);
     }
 
+    #[test]
+    fn test_source_map_tracker_follows_reordered_functions() {
+        let mut tracker = SourceMapTracker::new();
+        tracker.start("main.go", "main.rs", None);
+
+        // func worker(ch chan int) { v := <-ch }
+        tracker.record(3, 1, Some("func"));
+        tracker.record(3, 13, Some("ch"));
+        tracker.record(4, 2, Some("v"));
+        tracker.record(4, 9, Some("ch"));
+        tracker.record(3, 6, Some("worker"));
+        // func main() { ch := ...; go worker(ch); <-ch }
+        tracker.record(8, 1, Some("func"));
+        tracker.record(9, 2, Some("ch"));
+        tracker.record(10, 5, Some("worker"));
+        tracker.record(10, 12, Some("ch"));
+        tracker.record(11, 4, Some("ch"));
+        tracker.record(8, 6, Some("main"));
+
+        let rust_source = "fn main() {\n    let ch = make();\n    { let ch = ch.clone(); go(move || worker(ch.clone())) };\n    'label: { ch.recv() };\n}\nfn worker(ch: Chan) {\n    let v = ch.recv();\n}\n";
+        let sm = tracker.build_source_map(rust_source);
+        let original_line = |line: u32, col: u32| {
+            sm.lookup_token(line, col).map(|token| {
+                (
+                    token.get_src_line() + 1,
+                    token.get_name().map(str::to_string),
+                )
+            })
+        };
+
+        assert_eq!(original_line(0, 3), Some((8, Some("main".to_string()))));
+        assert_eq!(original_line(3, 15), Some((11, Some("ch".to_string()))));
+        assert_eq!(original_line(5, 3), Some((3, Some("worker".to_string()))));
+        assert_eq!(original_line(6, 12), Some((4, Some("ch".to_string()))));
+    }
+
     #[test]
     fn test_source_map_tracker_inactive() {
         let mut tracker = SourceMapTracker::new();
@@ -0,0 +1,571 @@
+//! Package metadata in the shape of `go list -json`.
+//!
+//! Local packages come from a [`ParsedProgram`]; standard library packages
+//! are the ones gors embeds (see [`resolve::list_packages`]).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::parser::{self, ParsedPackage, ParsedProgram, PathParseError};
+use crate::resolve;
+
+/// Import path `go list` reports for packages named by `.go` files.
+pub const COMMAND_LINE_ARGUMENTS: &str = "command-line-arguments";
+
+/// One package as reported by `gors list -json`. Empty fields are omitted,
+/// like `go list`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Package {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub dir: String,
+    pub import_path: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub standard: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dep_only: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub go_files: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignored_go_files: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deps: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub test_go_files: Vec<String>,
+    #[serde(rename = "XTestGoFiles", skip_serializing_if = "Vec::is_empty")]
+    pub xtest_go_files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<PackageError>,
+}
+
+/// Why a package could not be loaded, reported in the `Error` field like
+/// `go list -e` does.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PackageError {
+    pub import_stack: Option<Vec<String>>,
+    pub pos: String,
+    pub err: String,
+}
+
+/// List the packages named by `patterns`.
+///
+/// Patterns are expanded by [`expand_patterns`]; `std` names every standard
+/// library package gors embeds, and `.go` files form one
+/// `command-line-arguments` package. With `deps`, every dependency is listed
+/// too, dependencies before dependents.
+///
+/// A package that fails to load is still listed, with its [`Package::error`]
+/// set, so one broken package does not hide the others.
+pub fn list(patterns: &[String], deps: bool) -> Vec<Package> {
+    let mut graph = ImportGraph::default();
+    let mut roots = Vec::new();
+    let (files, dirs): (Vec<_>, Vec<_>) = expand_patterns(patterns)
+        .into_iter()
+        .partition(|pattern| pattern.ends_with(".go"));
+    if !files.is_empty() {
+        roots.push(match parser::parse_program_files(&files) {
+            Ok(program) => graph.add_program(&program, Some(&files)),
+            Err(err) => graph.add_broken(None, Some(&files), &err),
+        });
+    }
+    for dir in dirs {
+        if dir == "std" {
+            roots.extend(resolve::list_packages());
+            continue;
+        }
+        roots.push(match parser::parse_program(&dir) {
+            Ok(program) => graph.add_program(&program, None),
+            Err(err) => graph.add_broken(Some(&dir), None, &err),
+        });
+    }
+    graph.finish(&roots, deps)
+}
+
+/// Expand package patterns into the package paths they name.
+///
+/// `...` matches any string (so `./...` is the current directory and every
+/// package below it, and `./cmd/...` also matches `./cmd`). Patterns may be
+/// relative or absolute directories, or import paths inside the main module
+/// found from the current directory. `.go` files, `std` and paths without a
+/// wildcard pass through unchanged. Like the `go` command, walks skip
+/// `testdata`, `vendor`, hidden and `_`-prefixed directories, and nested
+/// modules.
+pub fn expand_patterns(patterns: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    for pattern in patterns {
+        let Some(local) = local_pattern(pattern) else {
+            out.push(pattern.clone());
+            continue;
+        };
+        if !local.contains("...") {
+            out.push(local);
+            continue;
+        }
+        let walk_root = match local.find("...") {
+            Some(idx) => local.get(..idx).unwrap_or_default(),
+            None => local.as_str(),
+        };
+        let walk_root = match walk_root.rfind('/') {
+            Some(idx) => walk_root.get(..idx).unwrap_or_default(),
+            None => ".",
+        };
+        let walk_root = if walk_root.is_empty() { "/" } else { walk_root };
+        let mut dirs = Vec::new();
+        walk_package_dirs(Path::new(walk_root), true, &mut dirs);
+        for dir in dirs {
+            let dir = dir.to_string_lossy().into_owned();
+            if pattern_matches(&local, &dir) && !out.contains(&dir) {
+                out.push(dir);
+            }
+        }
+    }
+    out
+}
+
+/// Rewrite a pattern into a filesystem pattern, resolving import paths of
+/// the main module. Returns `None` for patterns that are not local paths.
+fn local_pattern(pattern: &str) -> Option<String> {
+    if pattern == "std" || pattern.ends_with(".go") {
+        return None;
+    }
+    if pattern == "."
+        || pattern == ".."
+        || pattern.starts_with("./")
+        || pattern.starts_with("../")
+        || pattern.starts_with('/')
+    {
+        return Some(pattern.to_string());
+    }
+    let module_root = relative_module_root()?;
+    let module_path = parser::package_import_path(&module_root)?;
+    let rest = pattern.strip_prefix(module_path.as_str())?;
+    if !(rest.is_empty() || rest.starts_with('/')) {
+        return None;
+    }
+    Some(format!("{module_root}{rest}"))
+}
+
+/// The main module root relative to the current directory, e.g. `..`.
+fn relative_module_root() -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    let depth = cwd
+        .ancestors()
+        .position(|dir| dir.join("go.mod").is_file())?;
+    Some(if depth == 0 {
+        ".".to_string()
+    } else {
+        vec![".."; depth].join("/")
+    })
+}
+
+fn walk_package_dirs(dir: &Path, is_root: bool, out: &mut Vec<std::path::PathBuf>) {
+    if !is_root && dir.join("go.mod").is_file() {
+        return;
+    }
+    let dir_str = dir.to_string_lossy();
+    if parser::scan_package_dir(&dir_str).is_ok_and(|files| !files.go_files.is_empty()) {
+        out.push(dir.to_path_buf());
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut children = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    !name.starts_with('.')
+                        && !name.starts_with('_')
+                        && name != "testdata"
+                        && name != "vendor"
+                })
+        })
+        .collect::<Vec<_>>();
+    children.sort();
+    for child in children {
+        walk_package_dirs(&child, false, out);
+    }
+}
+
+/// Match a slash-separated path against a pattern where `...` matches any
+/// string, with Go's special case that `x/...` also matches `x`.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    if pattern == "..." {
+        return true;
+    }
+    if let Some(prefix) = pattern.strip_suffix("/...")
+        && !prefix.contains("...")
+        && path == prefix
+    {
+        return true;
+    }
+    wildcard_matches(pattern.as_bytes(), path.as_bytes())
+}
+
+fn wildcard_matches(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.strip_prefix(b"...") {
+        Some(rest) => (0..=path.len()).any(|skip| {
+            path.get(skip..)
+                .is_some_and(|tail| wildcard_matches(rest, tail))
+        }),
+        None => match (pattern.split_first(), path.split_first()) {
+            (None, None) => true,
+            (Some((p, pattern)), Some((c, path))) if p == c => wildcard_matches(pattern, path),
+            _ => false,
+        },
+    }
+}
+
+#[derive(Default)]
+struct ImportGraph {
+    packages: BTreeMap<String, Package>,
+}
+
+impl ImportGraph {
+    fn add_program(&mut self, program: &ParsedProgram, files: Option<&[String]>) -> String {
+        for pkg in &program.imports {
+            let package = local_package(pkg, pkg.import_path.clone(), None);
+            self.packages.insert(package.import_path.clone(), package);
+        }
+        let main = &program.main_package;
+        let import_path = match files {
+            Some(_) => COMMAND_LINE_ARGUMENTS.to_string(),
+            None => package_dir(main)
+                .and_then(|dir| parser::package_import_path(&dir))
+                .unwrap_or_else(|| COMMAND_LINE_ARGUMENTS.to_string()),
+        };
+        let package = local_package(main, import_path.clone(), files);
+        self.packages.insert(import_path.clone(), package);
+        import_path
+    }
+
+    /// Record a package that failed to load, named by its directory or by
+    /// its `.go` files.
+    fn add_broken(
+        &mut self,
+        dir: Option<&str>,
+        files: Option<&[String]>,
+        err: &PathParseError,
+    ) -> String {
+        let (import_path, dir_files) = match (dir, files) {
+            (Some(dir), _) => (
+                parser::package_import_path(dir).unwrap_or_else(|| dir.to_string()),
+                parser::scan_package_dir(dir).unwrap_or_default(),
+            ),
+            (None, files) => (
+                COMMAND_LINE_ARGUMENTS.to_string(),
+                parser::PackageDirFiles {
+                    go_files: files.unwrap_or_default().to_vec(),
+                    ..Default::default()
+                },
+            ),
+        };
+        let name = dir_files
+            .go_files
+            .iter()
+            .find_map(|file| {
+                let source = std::fs::read_to_string(file).ok()?;
+                parser::package_clause_name(&source).map(str::to_string)
+            })
+            .unwrap_or_default();
+        let dir = dir
+            .map(|dir| {
+                std::fs::canonicalize(dir).map_or_else(
+                    |_| dir.to_string(),
+                    |dir| dir.to_string_lossy().into_owned(),
+                )
+            })
+            .unwrap_or_default();
+        let package = Package {
+            dir,
+            import_path: import_path.clone(),
+            name,
+            go_files: base_names(&dir_files.go_files),
+            ignored_go_files: base_names(&dir_files.ignored_go_files),
+            test_go_files: base_names(&dir_files.test_go_files),
+            xtest_go_files: base_names(&dir_files.xtest_go_files),
+            error: Some(PackageError {
+                err: err.to_string(),
+                ..PackageError::default()
+            }),
+            ..Package::default()
+        };
+        self.packages.insert(import_path.clone(), package);
+        import_path
+    }
+
+    fn imports_of(&self, import_path: &str) -> Vec<String> {
+        match self.packages.get(import_path) {
+            Some(package) => package.imports.clone(),
+            None => resolve::collect_transitive_imports(import_path),
+        }
+    }
+
+    fn finish(self, roots: &[String], deps: bool) -> Vec<Package> {
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::new();
+        for root in roots {
+            self.visit(root, &mut visited, &mut order);
+        }
+
+        let mut listed = Vec::new();
+        for import_path in order {
+            let is_root = roots.contains(&import_path);
+            if !deps && !is_root {
+                continue;
+            }
+            let mut package = self
+                .packages
+                .get(&import_path)
+                .cloned()
+                .unwrap_or_else(|| standard_package(&import_path));
+            package.deps = self.deps_of(&import_path);
+            package.dep_only = !is_root;
+            listed.push(package);
+        }
+        listed
+    }
+
+    fn visit(&self, import_path: &str, visited: &mut BTreeSet<String>, order: &mut Vec<String>) {
+        if !visited.insert(import_path.to_string()) {
+            return;
+        }
+        for import in self.imports_of(import_path) {
+            self.visit(&import, visited, order);
+        }
+        order.push(import_path.to_string());
+    }
+
+    fn deps_of(&self, import_path: &str) -> Vec<String> {
+        let mut deps = BTreeSet::new();
+        let mut pending = self.imports_of(import_path);
+        while let Some(import) = pending.pop() {
+            if deps.insert(import.clone()) {
+                pending.extend(self.imports_of(&import));
+            }
+        }
+        deps.into_iter().collect()
+    }
+}
+
+fn package_dir(pkg: &ParsedPackage) -> Option<String> {
+    let (file, _) = pkg.files.first()?;
+    let dir = Path::new(file).parent()?;
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    Some(
+        std::fs::canonicalize(dir)
+            .unwrap_or_else(|_| dir.to_path_buf())
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn local_package(pkg: &ParsedPackage, import_path: String, files: Option<&[String]>) -> Package {
+    let dir = package_dir(pkg).unwrap_or_default();
+    let dir_files = match files {
+        Some(files) => parser::PackageDirFiles {
+            go_files: files.to_vec(),
+            ..Default::default()
+        },
+        None => parser::scan_package_dir(&dir).unwrap_or_default(),
+    };
+    let imports = pkg
+        .ast
+        .imports()
+        .into_iter()
+        .map(|spec| spec.path.value.trim_matches('"').to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    Package {
+        dir,
+        import_path,
+        name: pkg.name.clone(),
+        go_files: base_names(&dir_files.go_files),
+        ignored_go_files: base_names(&dir_files.ignored_go_files),
+        imports,
+        test_go_files: base_names(&dir_files.test_go_files),
+        xtest_go_files: base_names(&dir_files.xtest_go_files),
+        ..Package::default()
+    }
+}
+
+fn standard_package(import_path: &str) -> Package {
+    let files = resolve::package_files(import_path);
+    let files = files.as_deref().map(Vec::as_slice).unwrap_or_default();
+    let name = files
+        .iter()
+        .find_map(|(_, source)| parser::package_clause_name(source))
+        .or_else(|| import_path.rsplit('/').next())
+        .unwrap_or(import_path)
+        .to_string();
+    let known = resolve::is_known(import_path);
+    Package {
+        import_path: import_path.to_string(),
+        name,
+        standard: known,
+        go_files: files
+            .iter()
+            .map(|(file, _)| base_name(file).to_string())
+            .collect(),
+        imports: resolve::collect_transitive_imports(import_path),
+        ..Package::default()
+    }
+}
+
+fn base_names(paths: &[String]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| base_name(path).to_string())
+        .collect()
+}
+
+fn base_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+#[cfg(test)]
+#[allow(clippy::panic, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn lists_module_packages_with_files_and_deps() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        write(root, "go.mod", "module example.com/app\n\ngo 1.22\n");
+        write(
+            root,
+            "main.go",
+            "package main\n\nimport \"example.com/app/util\"\n\nfunc main() { util.Hello() }\n",
+        );
+        write(root, "main_test.go", "package main\n");
+        write(root, "main_ext_test.go", "package main_test\n");
+        write(root, "gen.go", "//go:build ignore\n\npackage main\n");
+        write(root, "util/util.go", "package util\n\nfunc Hello() {}\n");
+
+        let dir = root.to_string_lossy().into_owned();
+        let packages = list(std::slice::from_ref(&dir), false);
+        let [main] = packages.as_slice() else {
+            panic!("expected one package, got {packages:?}");
+        };
+        assert_eq!(main.import_path, "example.com/app");
+        assert_eq!(main.name, "main");
+        assert_eq!(main.go_files, ["main.go"]);
+        assert_eq!(main.ignored_go_files, ["gen.go"]);
+        assert_eq!(main.test_go_files, ["main_test.go"]);
+        assert_eq!(main.xtest_go_files, ["main_ext_test.go"]);
+        assert_eq!(main.imports, ["example.com/app/util"]);
+        assert_eq!(main.deps, ["example.com/app/util"]);
+        assert!(!main.standard && !main.dep_only);
+
+        let packages = list(&[dir], true);
+        let import_paths = packages
+            .iter()
+            .map(|package| package.import_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(import_paths, ["example.com/app/util", "example.com/app"]);
+        assert!(packages.first().unwrap().dep_only);
+
+        let json = serde_json::to_value(packages.first().unwrap()).unwrap();
+        assert_eq!(
+            json.get("ImportPath"),
+            Some(&serde_json::json!("example.com/app/util"))
+        );
+        assert_eq!(json.get("GoFiles"), Some(&serde_json::json!(["util.go"])));
+        assert!(json.get("Deps").is_none());
+    }
+
+    #[test]
+    fn lists_broken_packages_with_an_error_alongside_the_others() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        write(root, "go.mod", "module example.com/app\n\ngo 1.22\n");
+        write(root, "main.go", "package main\n\nfunc main() {}\n");
+        write(root, "broken/broken.go", "package broken\n\nfunc {\n");
+        write(root, "util/util.go", "package util\n\nfunc Hello() {}\n");
+
+        let root_str = root.to_string_lossy().into_owned();
+        let packages = list(&[format!("{root_str}/...")], false);
+        let import_paths = packages
+            .iter()
+            .map(|package| package.import_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            import_paths,
+            [
+                "example.com/app",
+                "example.com/app/broken",
+                "example.com/app/util"
+            ]
+        );
+
+        let broken = packages.get(1).unwrap();
+        assert_eq!(broken.name, "broken");
+        assert_eq!(broken.go_files, ["broken.go"]);
+        let error = broken.error.as_ref().unwrap();
+        assert!(!error.err.is_empty());
+        let json = serde_json::to_value(broken).unwrap();
+        assert_eq!(
+            json.pointer("/Error/Err"),
+            Some(&serde_json::json!(error.err))
+        );
+        assert!(packages.first().unwrap().error.is_none());
+        assert!(packages.get(2).unwrap().error.is_none());
+    }
+
+    #[test]
+    fn expands_recursive_patterns_skipping_testdata_and_nested_modules() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        write(root, "go.mod", "module example.com/app\n\ngo 1.22\n");
+        write(root, "main.go", "package main\n\nfunc main() {}\n");
+        write(root, "cmd/tool/main.go", "package main\n\nfunc main() {}\n");
+        write(root, "cmd/tool/testdata/x.go", "package x\n");
+        write(root, "internal/only_test.go", "package internal\n");
+        write(root, "_scratch/a.go", "package a\n");
+        write(root, "nested/go.mod", "module example.com/nested\n");
+        write(root, "nested/n.go", "package nested\n");
+
+        let root_str = root.to_string_lossy().into_owned();
+        let expanded = expand_patterns(&[format!("{root_str}/...")]);
+        assert_eq!(expanded, [root_str.clone(), format!("{root_str}/cmd/tool")]);
+
+        let expanded = expand_patterns(&[format!("{root_str}/cmd/...")]);
+        assert_eq!(expanded, [format!("{root_str}/cmd/tool")]);
+
+        let passthrough = vec!["std".to_string(), "main.go".to_string()];
+        assert_eq!(expand_patterns(&passthrough), passthrough);
+    }
+
+    #[test]
+    fn wildcard_patterns_follow_go_matching() {
+        assert!(pattern_matches("./...", "."));
+        assert!(pattern_matches("./cmd/...", "./cmd"));
+        assert!(pattern_matches("./cmd/...", "./cmd/tool"));
+        assert!(!pattern_matches("./cmd/...", "./cmdline"));
+        assert!(pattern_matches("./.../tool", "./cmd/tool"));
+        assert!(!pattern_matches("./.../tool", "./cmd/tools"));
+    }
+}
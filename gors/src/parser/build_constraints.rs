@@ -0,0 +1,258 @@
+//! Build constraint evaluation for package directories.
+//!
+//! Mirrors `go/build` file selection: `_GOOS`/`_GOARCH` filename suffixes and
+//! `//go:build` expressions, evaluated for the host platform with the `gc`
+//! compiler tag and release tags up to the pinned toolchain.
+
+const KNOWN_OS: &[&str] = &[
+    "aix",
+    "android",
+    "darwin",
+    "dragonfly",
+    "freebsd",
+    "hurd",
+    "illumos",
+    "ios",
+    "js",
+    "linux",
+    "nacl",
+    "netbsd",
+    "openbsd",
+    "plan9",
+    "solaris",
+    "wasip1",
+    "windows",
+    "zos",
+];
+
+const KNOWN_ARCH: &[&str] = &[
+    "386", "amd64", "arm", "arm64", "loong64", "mips", "mips64", "mips64le", "mipsle", "ppc64",
+    "ppc64le", "riscv64", "s390x", "sparc64", "wasm",
+];
+
+const UNIX_OS: &[&str] = &[
+    "aix",
+    "android",
+    "darwin",
+    "dragonfly",
+    "freebsd",
+    "hurd",
+    "illumos",
+    "ios",
+    "linux",
+    "netbsd",
+    "openbsd",
+    "solaris",
+];
+
+fn goos() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
+fn goarch() -> &'static str {
+    match std::env::consts::ARCH {
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "x86_64" => "amd64",
+        arch => arch,
+    }
+}
+
+/// Whether `file_name` with contents `source` is part of the build for the
+/// host platform.
+pub(super) fn file_matches(file_name: &str, source: &str) -> bool {
+    file_name_matches(file_name) && source_constraint(source).is_none_or(eval)
+}
+
+fn file_name_matches(file_name: &str) -> bool {
+    let stem = file_name.strip_suffix(".go").unwrap_or(file_name);
+    let stem = stem.strip_suffix("_test").unwrap_or(stem);
+    let mut parts = stem.split('_').skip(1).collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        return true;
+    };
+    if KNOWN_ARCH.contains(&last) {
+        if let Some(os) = parts.pop().filter(|os| KNOWN_OS.contains(os)) {
+            return tag_matches(os) && tag_matches(last);
+        }
+        return tag_matches(last);
+    }
+    !KNOWN_OS.contains(&last) || tag_matches(last)
+}
+
+/// The `//go:build` expression of a file, if any. Only the header before the
+/// package clause is considered.
+fn source_constraint(source: &str) -> Option<&str> {
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("package ") {
+            break;
+        }
+        if let Some(expr) = trimmed.strip_prefix("//go:build ") {
+            return Some(expr.trim());
+        }
+    }
+    None
+}
+
+fn tag_matches(tag: &str) -> bool {
+    if tag == goos() || tag == goarch() || tag == "gc" {
+        return true;
+    }
+    if tag == "unix" {
+        return UNIX_OS.contains(&goos());
+    }
+    // `android` implies `linux`, `ios` implies `darwin`, `illumos` implies `solaris`.
+    if matches!(
+        (goos(), tag),
+        ("android", "linux") | ("ios", "darwin") | ("illumos", "solaris")
+    ) {
+        return true;
+    }
+    release_tag_matches(tag)
+}
+
+fn release_tag_matches(tag: &str) -> bool {
+    let Some(minor) = tag
+        .strip_prefix("go1.")
+        .and_then(|minor| minor.parse::<u32>().ok())
+    else {
+        return false;
+    };
+    let toolchain_minor = crate::GO_VERSION
+        .split('.')
+        .nth(1)
+        .and_then(|minor| minor.parse::<u32>().ok())
+        .unwrap_or(u32::MAX);
+    minor <= toolchain_minor
+}
+
+/// Evaluate a build constraint expression. Malformed expressions exclude
+/// the file, matching `go build`, which refuses to use it.
+fn eval(expr: &str) -> bool {
+    let mut parser = ExprParser {
+        tokens: tokenize(expr),
+        pos: 0,
+    };
+    let value = parser.or();
+    value.filter(|_| parser.pos == parser.tokens.len()) == Some(true)
+}
+
+fn tokenize(expr: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+    while let Some(ch) = rest.chars().next() {
+        let len = match ch {
+            '(' | ')' | '!' => 1,
+            '&' | '|' => 2,
+            _ => rest
+                .find(|ch: char| ch.is_whitespace() || "()!&|".contains(ch))
+                .unwrap_or(rest.len()),
+        };
+        let (token, tail) = rest.split_at(len.min(rest.len()));
+        tokens.push(token);
+        rest = tail.trim_start();
+    }
+    tokens
+}
+
+struct ExprParser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn or(&mut self) -> Option<bool> {
+        let mut value = self.and()?;
+        while self.peek() == Some("||") {
+            self.pos += 1;
+            value |= self.and()?;
+        }
+        Some(value)
+    }
+
+    fn and(&mut self) -> Option<bool> {
+        let mut value = self.not()?;
+        while self.peek() == Some("&&") {
+            self.pos += 1;
+            value &= self.not()?;
+        }
+        Some(value)
+    }
+
+    fn not(&mut self) -> Option<bool> {
+        if self.peek() == Some("!") {
+            self.pos += 1;
+            return self.not().map(|value| !value);
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Option<bool> {
+        let token = self.peek()?;
+        self.pos += 1;
+        if token == "(" {
+            let value = self.or()?;
+            (self.peek() == Some(")")).then_some(())?;
+            self.pos += 1;
+            return Some(value);
+        }
+        token
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '.')
+            .then(|| tag_matches(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_go_build_expressions() {
+        assert!(!eval("ignore"));
+        assert!(eval("gc"));
+        assert!(eval(&format!("{} && !ignore", goos())));
+        assert!(eval("(ignore || go1.1) && !go1.999"));
+        assert!(!eval("gc &&"));
+        assert!(!eval("(gc"));
+    }
+
+    #[test]
+    fn honors_platform_file_name_suffixes() {
+        let other_os = if goos() == "plan9" {
+            "windows"
+        } else {
+            "plan9"
+        };
+        assert!(file_name_matches("main.go"));
+        assert!(file_name_matches("string_helpers.go"));
+        assert!(file_name_matches(&format!("sys_{}.go", goos())));
+        assert!(file_name_matches(&format!(
+            "sys_{}_{}.go",
+            goos(),
+            goarch()
+        )));
+        assert!(!file_name_matches(&format!("sys_{other_os}.go")));
+        assert!(!file_name_matches(&format!("sys_{other_os}_test.go")));
+    }
+
+    #[test]
+    fn reads_constraint_from_file_header_only() {
+        assert!(!file_matches(
+            "gen.go",
+            "//go:build ignore\n\npackage main\n"
+        ));
+        assert!(file_matches(
+            "main.go",
+            "package main\n\n//go:build ignore\n"
+        ));
+    }
+}
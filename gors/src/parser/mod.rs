@@ -5,6 +5,8 @@ use crate::scanner;
 use crate::token::{Position, Token};
 use std::fmt;
 
+mod build_constraints;
+
 fn make_basic_lit<'a>((value_pos, kind, value): scanner::Step<'a>) -> ast::BasicLit<'a> {
     let hide_column = value_pos.column == 0;
     let mut end = value_pos;
@@ -286,6 +288,78 @@ impl std::fmt::Display for PathParseError {
 
 impl std::error::Error for PathParseError {}
 
+/// Go source files of a package directory, classified like `go list`.
+///
+/// Paths are sorted. Hidden and `_`-prefixed files are skipped entirely;
+/// files excluded by build constraints land in `ignored_go_files`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PackageDirFiles {
+    pub go_files: Vec<String>,
+    pub ignored_go_files: Vec<String>,
+    pub test_go_files: Vec<String>,
+    pub xtest_go_files: Vec<String>,
+}
+
+/// Classify the `.go` files of a package directory for the host platform.
+pub fn scan_package_dir(dir_path: &str) -> std::result::Result<PackageDirFiles, PathParseError> {
+    let entries = std::fs::read_dir(dir_path).map_err(|e| {
+        PathParseError::IoError(format!("cannot read directory '{}': {}", dir_path, e))
+    })?;
+
+    let mut paths: Vec<std::path::PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.ends_with(".go") && !name.starts_with('.') && !name.starts_with('_')
+                })
+                && path.is_file()
+        })
+        .collect();
+    paths.sort();
+
+    let mut files = PackageDirFiles::default();
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let buffer = std::fs::read_to_string(&path).map_err(|e| {
+            PathParseError::IoError(format!("cannot read '{}': {}", path.display(), e))
+        })?;
+        let path_str = path.to_string_lossy().into_owned();
+        if !build_constraints::file_matches(file_name, &buffer) {
+            files.ignored_go_files.push(path_str);
+        } else if !file_name.ends_with("_test.go") {
+            files.go_files.push(path_str);
+        } else if package_clause_name(&buffer).is_some_and(|name| name.ends_with("_test")) {
+            files.xtest_go_files.push(path_str);
+        } else {
+            files.test_go_files.push(path_str);
+        }
+    }
+    Ok(files)
+}
+
+/// The package name from a source file's package clause, without parsing
+/// the rest of the file.
+pub fn package_clause_name(source: &str) -> Option<&str> {
+    let scanner = scanner::Scanner::new("", source);
+    let mut saw_package = false;
+    for step in scanner {
+        let (_, token, literal) = step.ok()?;
+        match token {
+            Token::COMMENT => {}
+            Token::PACKAGE => saw_package = true,
+            Token::IDENT if saw_package => return Some(literal),
+            _ => return None,
+        }
+    }
+    None
+}
+
 /// Parse all Go files in a directory into a single merged AST.
 ///
 /// This function reads all `.go` files in the specified directory (excluding
@@ -413,6 +487,8 @@ pub struct ParsedPackage {
     pub import_path: String,
     pub ast: ast::File<'static>,
     pub files: Vec<(String, String)>,
+    /// Version from the module's go.mod `go` directive, if any.
+    pub go_version: Option<String>,
 }
 
 /// A fully resolved program: main package plus all local imports.
@@ -446,6 +522,7 @@ pub fn parse_program(path: &str) -> std::result::Result<ParsedProgram, PathParse
     let module_name = module_root
         .as_ref()
         .and_then(|root| parse_go_mod(root).ok());
+    let go_version = module_root.as_deref().and_then(parse_go_mod_go_version);
 
     let mut imports = Vec::new();
     let mut stdlib_imports = Vec::new();
@@ -455,6 +532,7 @@ pub fn parse_program(path: &str) -> std::result::Result<ParsedProgram, PathParse
             &main_ast,
             root,
             mod_name,
+            go_version.as_deref(),
             &mut imports,
             &mut stdlib_imports,
             &mut visited,
@@ -470,6 +548,7 @@ pub fn parse_program(path: &str) -> std::result::Result<ParsedProgram, PathParse
             import_path: String::new(),
             ast: main_ast,
             files,
+            go_version,
         },
         imports,
         stdlib_imports,
@@ -512,6 +591,7 @@ pub fn parse_program_files(
     let module_name = module_root
         .as_ref()
         .and_then(|root| parse_go_mod(root).ok());
+    let go_version = module_root.as_deref().and_then(parse_go_mod_go_version);
 
     let mut imports = Vec::new();
     let mut stdlib_imports = Vec::new();
@@ -521,6 +601,7 @@ pub fn parse_program_files(
             &main_ast,
             root,
             mod_name,
+            go_version.as_deref(),
             &mut imports,
             &mut stdlib_imports,
             &mut visited,
@@ -536,6 +617,7 @@ pub fn parse_program_files(
             import_path: String::new(),
             ast: main_ast,
             files,
+            go_version,
         },
         imports,
         stdlib_imports,
@@ -565,6 +647,7 @@ pub fn parse_program_from_source(
             import_path: String::new(),
             ast,
             files: vec![(filename.to_string(), source.to_string())],
+            go_version: None,
         },
         imports: vec![],
         stdlib_imports,
@@ -611,6 +694,25 @@ fn parse_explicit_files(
     Ok((merged, files_content))
 }
 
+/// The import path of the package in `dir`, derived from the enclosing
+/// module's go.mod, e.g. `example.com/m/cmd/tool`.
+pub fn package_import_path(dir: &str) -> Option<String> {
+    let dir = std::fs::canonicalize(dir).ok()?;
+    let module_root = find_module_root(&dir.to_string_lossy())?;
+    let module_name = parse_go_mod(&module_root).ok()?;
+    let rel = dir.strip_prefix(&module_root).ok()?;
+    let rel = rel
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(if rel.is_empty() {
+        module_name
+    } else {
+        format!("{module_name}/{rel}")
+    })
+}
+
 fn find_module_root(start_dir: &str) -> Option<String> {
     let mut dir = std::path::PathBuf::from(start_dir);
     loop {
@@ -640,6 +742,23 @@ fn parse_go_mod(module_root: &str) -> std::result::Result<String, PathParseError
     ))
 }
 
+/// Read the `go` directive of a module's go.mod, e.g. `1.22` for `go 1.22`.
+fn parse_go_mod_go_version(module_root: &str) -> Option<String> {
+    let go_mod_path = std::path::Path::new(module_root).join("go.mod");
+    let content = std::fs::read_to_string(go_mod_path).ok()?;
+    content.lines().find_map(|line| {
+        let version = line.trim().strip_prefix("go")?;
+        version.starts_with(char::is_whitespace).then(|| {
+            version
+                .split("//")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+    })
+}
+
 fn collect_stdlib_imports(file: &ast::File<'_>, stdlib_imports: &mut Vec<String>) {
     for import_spec in file.imports() {
         let import_path = import_spec.path.value.trim_matches('"');
@@ -655,6 +774,7 @@ fn resolve_imports_recursive(
     file: &ast::File<'static>,
     module_root: &str,
     module_name: &str,
+    go_version: Option<&str>,
     imports: &mut Vec<ParsedPackage>,
     stdlib_imports: &mut Vec<String>,
     visited: &mut std::collections::HashSet<String>,
@@ -697,6 +817,7 @@ fn resolve_imports_recursive(
             &pkg_ast,
             module_root,
             module_name,
+            go_version,
             imports,
             stdlib_imports,
             visited,
@@ -708,6 +829,7 @@ fn resolve_imports_recursive(
             import_path: import_path.to_string(),
             ast: pkg_ast,
             files: pkg_files,
+            go_version: go_version.map(str::to_string),
         });
     }
 
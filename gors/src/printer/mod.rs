@@ -445,8 +445,21 @@ pub fn generate_multi(
     let mut mod_decls = Vec::new();
 
     let dependency_modules = ordered_dependency_modules(&program);
+    let mut package_line_tables = Vec::new();
     for module in &dependency_modules {
-        let source = generated_source(generate(module.file.clone())?);
+        let mut source = generated_source(generate(module.file.clone())?);
+        if program.has_main
+            && let Some(tracker) = program.package_source_maps.get(&module.import_path)
+        {
+            let source_map = tracker.build_source_map(&source);
+            // Appended last, so that the positions it maps stay put.
+            source.push_str(&line_table_source(
+                &source_map,
+                &module.filename,
+                &module.import_path,
+            ));
+            package_line_tables.push(module.mod_name.as_str());
+        }
         files.insert(module.filename.clone(), source);
 
         if module.filename == format!("{}.rs", module.mod_name) {
@@ -471,20 +484,48 @@ pub fn generate_multi(
             .iter()
             .map(|module| module.mod_name.as_str())
             .collect();
+        let line_table = crate::generated_names::LINE_TABLE_STATIC;
+        let mut line_tables = vec![line_table.to_string()];
         if !dependency_mods.is_empty() {
             let wrapper_mod = main_wrapper_module_name(&dependency_mods);
             main_parts.push(format!(
                 "#[path = \"lib.rs\"]\nmod {wrapper_mod};\nuse {wrapper_mod}::*;",
             ));
+            line_tables.extend(
+                package_line_tables
+                    .iter()
+                    .map(|mod_name| format!("{wrapper_mod}::{mod_name}::{line_table}")),
+            );
         }
 
         let main_body = generate(main_module.file.clone())?;
         main_parts.push(main_body);
 
-        files.insert(
-            "main.rs".to_string(),
-            generated_source(main_parts.join("\n\n") + "\n"),
-        );
+        let mut main_rs = generated_source(main_parts.join("\n\n") + "\n");
+        if crate::compiler::source_map_tracker_is_active() {
+            let source_map = crate::compiler::build_source_map(&main_rs);
+            // Appended last, so that the positions it maps stay put.
+            if program.has_main {
+                main_rs.push_str(&line_table_source(&source_map, "main.rs", "main"));
+                main_rs.push_str(&format!(
+                    "\nstatic {}: [&crate::builtin::LineTable; {}] = [{}];\n",
+                    crate::generated_names::LINE_TABLES_STATIC,
+                    line_tables.len(),
+                    line_tables
+                        .iter()
+                        .map(|table| format!("&{table}"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ));
+            }
+            let mut source_map_json = Vec::new();
+            source_map.to_writer(&mut source_map_json)?;
+            files.insert(
+                "main.rs.map".to_string(),
+                String::from_utf8(source_map_json)?,
+            );
+        }
+        files.insert("main.rs".to_string(), main_rs);
     }
 
     drop(timer);
@@ -499,11 +540,112 @@ pub fn generate_multi(
     Ok(output)
 }
 
+/// The line table the runtime symbolizes stacks with: the Go line of the
+/// positions of the generated `rust_file` the source line changes at, and
+/// the functions the sources of `package` declare.
+fn line_table_source(source_map: &sourcemap::SourceMap, rust_file: &str, package: &str) -> String {
+    let sources: Vec<String> = source_map
+        .sources()
+        .map(|source| format!("{source:?}"))
+        .collect();
+    let mut lines = Vec::new();
+    let mut last = None;
+    for token in source_map.tokens() {
+        let source = (token.get_src_id(), token.get_src_line());
+        if token.get_source().is_none() || last == Some(source) {
+            continue;
+        }
+        last = Some(source);
+        lines.push(format!(
+            "({}, {}, {}, {})",
+            token.get_dst_line() + 1,
+            token.get_dst_col() + 1,
+            source.0,
+            source.1 + 1,
+        ));
+    }
+    let functions: Vec<String> = declared_functions(source_map, package)
+        .into_iter()
+        .map(|(source, first, last, name)| format!("({source}, {first}, {last}, {name:?})"))
+        .collect();
+    let line_table = crate::generated_names::LINE_TABLE_STATIC;
+    format!(
+        "\npub static {line_table}: crate::builtin::LineTable = crate::builtin::LineTable {{\n    file: {rust_file:?},\n    package: {package:?},\n    sources: &[{}],\n    lines: &[\n{}    ],\n    functions: &[\n{}    ],\n}};\n",
+        sources.join(", "),
+        lines
+            .chunks(6)
+            .map(|chunk| format!("        {},\n", chunk.join(", ")))
+            .collect::<String>(),
+        functions
+            .iter()
+            .map(|function| format!("        {function},\n"))
+            .collect::<String>(),
+    )
+}
+
+/// The source index, the lines of the `func` keyword and of the closing
+/// brace, and the Go name of the functions declared in the sources of a
+/// source map, which belong to `package`. Goroutine dumps name the frames
+/// they only know the position of after them.
+fn declared_functions(
+    source_map: &sourcemap::SourceMap,
+    package: &str,
+) -> Vec<(u32, usize, usize, String)> {
+    let mut functions = Vec::new();
+    for (source, name) in (0..).zip(source_map.sources()) {
+        let Some(contents) = source_map.get_source_contents(source) else {
+            continue;
+        };
+        let Ok(file) = crate::parser::parse_file(name, contents) else {
+            continue;
+        };
+        for decl in &file.decls {
+            let crate::ast::Decl::FuncDecl(func) = decl else {
+                continue;
+            };
+            let Some(body) = &func.body else {
+                continue;
+            };
+            let first = func.type_.func.as_ref().unwrap_or(&func.name.name_pos).line;
+            let name = match func.recv.as_ref().and_then(|recv| recv.list.first()) {
+                Some(recv) => format!(
+                    "{package}.{}.{}",
+                    receiver_type_name(recv.type_.as_ref()),
+                    func.name.name
+                ),
+                None => format!("{package}.{}", func.name.name),
+            };
+            functions.push((source, first, body.rbrace.line, name));
+        }
+    }
+    functions
+}
+
+/// A receiver type as Go names methods after it: `T`, `(*T)`, or
+/// `(*T[...])` for generic types.
+fn receiver_type_name(expr: Option<&crate::ast::Expr<'_>>) -> String {
+    use crate::ast::Expr;
+    match expr {
+        Some(Expr::StarExpr(star)) => format!("(*{})", receiver_type_name(Some(&star.x))),
+        Some(Expr::ParenExpr(paren)) => receiver_type_name(Some(&paren.x)),
+        Some(Expr::IndexExpr(index)) => format!("{}[...]", receiver_type_name(Some(&index.x))),
+        Some(Expr::IndexListExpr(index)) => {
+            format!("{}[...]", receiver_type_name(Some(&index.x)))
+        }
+        Some(Expr::Ident(ident)) => ident.name.to_string(),
+        _ => "?".to_string(),
+    }
+}
+
 fn multi_codegen_cache_key(program: &crate::compiler::CompiledProgram) -> String {
     let mut hasher = Sha256::new();
     hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
     hasher.update(b"\0multi\0");
-    hasher.update([u8::from(program.has_main)]);
+    hasher.update([
+        u8::from(program.has_main),
+        u8::from(crate::compiler::source_map_tracker_is_active()),
+        u8::from(!program.package_source_maps.is_empty()),
+    ]);
     for (key, module) in &program.modules {
         hasher.update(key.as_bytes());
         hasher.update(b"\0");
@@ -638,6 +780,37 @@ mod tests {
         crate::compiler::clear_source_map_tracker();
     }
 
+    #[test]
+    fn line_table_lists_the_positions_the_go_line_changes_at() {
+        let mut builder = sourcemap::SourceMapBuilder::new(None);
+        let main_go = builder.add_source("./main.go");
+        builder.set_source_contents(
+            main_go,
+            Some("package main\n\ntype T struct{}\n\nfunc (t *T) Run() {\n}\n\nfunc main() {\n\tgo func() {}()\n\tselect {}\n}\n"),
+        );
+        for (dst_line, dst_col, src_line) in [(8, 4, 9), (8, 7, 9), (12, 8, 10), (20, 0, 4)] {
+            builder.add_raw(dst_line, dst_col, src_line, 0, Some(main_go), None, false);
+        }
+
+        let table = super::line_table_source(&builder.into_sourcemap(), "main.rs", "main");
+
+        assert!(
+            table.contains("pub static __GORS_LINE_TABLE: crate::builtin::LineTable"),
+            "{table}"
+        );
+        assert!(table.contains("file: \"main.rs\","), "{table}");
+        assert!(table.contains("package: \"main\","), "{table}");
+        assert!(table.contains("sources: &[\"./main.go\"]"), "{table}");
+        assert!(
+            table.contains("(9, 5, 0, 10), (13, 9, 0, 11), (21, 1, 0, 5),"),
+            "{table}"
+        );
+        assert!(
+            table.contains("(0, 5, 6, \"main.(*T).Run\"),\n        (0, 8, 11, \"main.main\"),"),
+            "{table}"
+        );
+    }
+
     #[test]
     fn generated_output_orders_macro_rules_before_invocations() {
         let file: syn::File = syn::parse_quote! {
@@ -696,6 +869,8 @@ mod tests {
         let program = crate::compiler::CompiledProgram {
             modules,
             has_main: true,
+            escapes: Vec::new(),
+            package_source_maps: BTreeMap::new(),
         };
 
         let multi = super::generate_multi(program.clone()).unwrap();
@@ -739,6 +914,8 @@ mod tests {
         let program = crate::compiler::CompiledProgram {
             modules,
             has_main: true,
+            escapes: Vec::new(),
+            package_source_maps: BTreeMap::new(),
         };
         let output = super::generate_multi(program).unwrap();
         let expected_prefix = format!("{}{}\n\n", super::GENERATED_HEADER, super::GENERATED_LINTS);
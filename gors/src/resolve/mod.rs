@@ -89,7 +89,7 @@ fn embedded_package(import_path: &str) -> Option<&'static EmbeddedGoPackage> {
 }
 
 pub fn is_known(import_path: &str) -> bool {
-    package_exists(import_path)
+    package_exists(import_path) || runtime_primitives::provides_package(import_path)
 }
 
 pub fn package_exists(import_path: &str) -> bool {
@@ -1108,7 +1108,9 @@ fn type_env_cell(import_path: &str) -> Option<TypeEnvCell> {
 }
 
 fn scan_type_env_uncached(import_path: &str) -> Option<(String, TypeEnv)> {
-    let files = package_files(import_path)?;
+    let Some(files) = package_files(import_path) else {
+        return runtime_primitives::fallback_type_env(import_path);
+    };
     let mut env = TypeEnv::new();
     let mut package_name = None;
     let mut parsed_files = Vec::new();
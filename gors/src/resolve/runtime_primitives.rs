@@ -1,11 +1,13 @@
 use std::collections::HashSet;
 
+mod embed;
 mod reflectlite;
 mod runtime;
 mod syscall;
 
 pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Option<syn::ItemMod> {
     match import_path {
+        embed::IMPORT_PATH => embed::module(import_path, roots),
         reflectlite::IMPORT_PATH => reflectlite::module(import_path, roots),
         runtime::IMPORT_PATH => runtime::module(import_path, roots),
         _ => None,
@@ -26,11 +28,32 @@ pub(super) fn supplement_type_env(
     import_path: &str,
     env: &mut crate::compiler::typeinfer::TypeEnv,
 ) {
-    if import_path == syscall::IMPORT_PATH {
-        syscall::supplement_type_env(env);
+    match import_path {
+        runtime::IMPORT_PATH => runtime::supplement_type_env(env),
+        syscall::IMPORT_PATH => syscall::supplement_type_env(env),
+        _ => {}
     }
 }
 
+/// Whether the primitives stand in for the whole package, so that it
+/// compiles even with an SDK that ships without its sources.
+pub(super) fn provides_package(import_path: &str) -> bool {
+    import_path == runtime::IMPORT_PATH
+}
+
+/// The type env of a package the primitives stand in for, when its sources
+/// are missing: the primitives only need their own supplements.
+pub(super) fn fallback_type_env(
+    import_path: &str,
+) -> Option<(String, crate::compiler::typeinfer::TypeEnv)> {
+    if !provides_package(import_path) {
+        return None;
+    }
+    let mut env = crate::compiler::typeinfer::TypeEnv::new();
+    runtime::supplement_type_env(&mut env);
+    Some((runtime::IMPORT_PATH.to_string(), env))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,6 +110,139 @@ mod tests {
         assert!(tokens.contains("pub struct Frames"), "{tokens}");
         assert!(tokens.contains("pub fn Next"), "{tokens}");
         assert!(tokens.contains("Frame :: default"), "{tokens}");
+        assert!(
+            tokens.contains("crate :: builtin :: callers (skip , pc)"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("crate :: builtin :: caller_frame (pc)"),
+            "{tokens}"
+        );
+        assert!(!tokens.contains("pub fn Caller ("), "{tokens}");
+    }
+
+    #[test]
+    fn runtime_module_emits_caller_location() {
+        let tokens = required_tokens_for("runtime", &["Caller"]);
+
+        assert!(
+            tokens.contains("pub fn Caller (mut skip : isize) -> (usize , String , isize , bool)"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("crate :: builtin :: caller (skip)"),
+            "{tokens}"
+        );
+    }
+
+    #[test]
+    fn runtime_module_emits_goroutine_stack_dump() {
+        let tokens = required_tokens_for("runtime", &["Stack"]);
+
+        assert!(tokens.contains("pub fn Stack"), "{tokens}");
+        assert!(
+            tokens.contains("crate :: builtin :: goroutine_stack (buf , all)"),
+            "{tokens}"
+        );
+        assert!(!tokens.contains("pub fn Callers"), "{tokens}");
+    }
+
+    #[test]
+    fn runtime_module_emits_scheduler_and_gc_roots() {
+        let tokens = required_tokens_for(
+            "runtime",
+            &[
+                "NumGoroutine",
+                "Gosched",
+                "Goexit",
+                "GC",
+                "KeepAlive",
+                "SetFinalizer",
+            ],
+        );
+
+        assert!(
+            tokens.contains("crate :: builtin :: num_goroutine ()"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("crate :: builtin :: gosched ()"),
+            "{tokens}"
+        );
+        assert!(tokens.contains("crate :: builtin :: goexit ()"), "{tokens}");
+        assert!(tokens.contains("crate :: builtin :: gc ()"), "{tokens}");
+        assert!(
+            tokens.contains("crate :: builtin :: keep_alive (x)"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("crate :: builtin :: set_finalizer (& obj , finalizer)"),
+            "{tokens}"
+        );
+        assert!(!tokens.contains("global_allocator"), "{tokens}");
+    }
+
+    #[test]
+    fn runtime_module_emits_mem_stats_with_counting_allocator() {
+        let tokens = required_tokens_for("runtime", &["MemStats", "ReadMemStats"]);
+
+        assert!(tokens.contains("pub struct MemStats"), "{tokens}");
+        assert!(tokens.contains("pub TotalAlloc : u64"), "{tokens}");
+        assert!(tokens.contains("pub NumGC : u32"), "{tokens}");
+        assert!(tokens.contains("pub fn ReadMemStats"), "{tokens}");
+        assert!(tokens.contains("# [global_allocator]"), "{tokens}");
+        assert!(
+            tokens.contains("crate :: builtin :: record_alloc (layout . size ())"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("crate :: builtin :: record_free (layout . size ())"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("crate :: builtin :: record_realloc (layout . size () , new_size)"),
+            "{tokens}"
+        );
+        assert!(!tokens.contains("repr"), "{tokens}");
+    }
+
+    #[test]
+    fn runtime_type_env_leaves_set_finalizer_arguments_untyped() {
+        let mut env = TypeEnv::new();
+        env.set_func_params("SetFinalizer", vec![GoType::Any, GoType::Any]);
+        supplement_type_env("runtime", &mut env);
+
+        assert_eq!(
+            env.get_func_params("SetFinalizer"),
+            vec![GoType::Unknown, GoType::Unknown]
+        );
+    }
+
+    #[test]
+    fn runtime_fallback_type_env_leaves_set_finalizer_arguments_untyped() {
+        let fallback = fallback_type_env("runtime");
+
+        assert_eq!(
+            fallback
+                .as_ref()
+                .map(|(name, env)| (name.as_str(), env.get_func_params("SetFinalizer"))),
+            Some(("runtime", vec![GoType::Unknown, GoType::Unknown]))
+        );
+        assert!(fallback_type_env("syscall").is_none());
+    }
+
+    #[test]
+    fn embed_module_emits_fs_only_for_fs_roots() {
+        let tokens = required_tokens_for("embed", &["FS", "FS::ReadFile"]);
+
+        assert!(tokens.contains("pub struct FS"), "{tokens}");
+        assert!(tokens.contains("pub fn __gors_embedded"), "{tokens}");
+        assert!(tokens.contains("pub fn ReadFile"), "{tokens}");
+        assert!(
+            tokens.contains("impl crate :: io__fs :: FS for FS"),
+            "{tokens}"
+        );
+        assert!(tokens_for("embed", &["init"]).is_none());
     }
 
     #[test]
@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+
+pub(super) const IMPORT_PATH: &str = "embed";
+const FS_TYPE: &str = "FS";
+
+/// `embed.FS` over the files a `//go:embed` directive resolved at compile
+/// time. The compiler initializes embedded variables through
+/// `FS::__gors_embedded`; the zero value is an empty file system.
+pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Option<syn::ItemMod> {
+    let roots = roots?;
+    if !roots
+        .iter()
+        .any(|root| root == FS_TYPE || root.starts_with("FS::"))
+    {
+        return None;
+    }
+
+    let mut items = fs_items();
+    items.extend(file_items());
+    Some(super::super::item_mod_for(import_path, items))
+}
+
+fn fs_items() -> Vec<syn::Item> {
+    vec![
+        syn::parse_quote! {
+            #[derive(Clone, Copy, Default)]
+            pub struct FS {
+                files: crate::builtin::GorsEmbedFs,
+            }
+        },
+        syn::parse_quote! {
+            impl FS {
+                pub fn __gors_embedded(files: &'static [(&'static str, &'static [u8])]) -> Self {
+                    Self {
+                        files: crate::builtin::GorsEmbedFs::new(files),
+                    }
+                }
+
+                pub fn Open(
+                    &self,
+                    name: String,
+                ) -> (Box<dyn crate::io__fs::File>, Box<dyn crate::builtin::error>) {
+                    match self.files.lookup(&name) {
+                        Some(entry) => (
+                            Box::new(openFile {
+                                entry: Some(entry),
+                                offset: 0,
+                            }) as Box<dyn crate::io__fs::File>,
+                            Box::new(crate::builtin::__GorsNooperror::default())
+                                as Box<dyn crate::builtin::error>,
+                        ),
+                        None => (
+                            Box::new(openFile::default()) as Box<dyn crate::io__fs::File>,
+                            path_error(format!("open {name}: file does not exist")),
+                        ),
+                    }
+                }
+
+                pub fn ReadDir(
+                    &self,
+                    name: String,
+                ) -> (Vec<Box<dyn crate::io__fs::DirEntry>>, Box<dyn crate::builtin::error>) {
+                    match self.files.read_dir(&name) {
+                        Ok(entries) => (
+                            entries
+                                .into_iter()
+                                .map(|entry| Box::new(file { entry }) as Box<dyn crate::io__fs::DirEntry>)
+                                .collect(),
+                            Box::new(crate::builtin::__GorsNooperror::default())
+                                as Box<dyn crate::builtin::error>,
+                        ),
+                        Err(message) => (Vec::new(), path_error(message)),
+                    }
+                }
+
+                pub fn ReadFile(&self, name: String) -> (Vec<u8>, Box<dyn crate::builtin::error>) {
+                    match self.files.read_file(&name) {
+                        Ok(data) => (
+                            data.to_vec(),
+                            Box::new(crate::builtin::__GorsNooperror::default())
+                                as Box<dyn crate::builtin::error>,
+                        ),
+                        Err(message) => (Vec::new(), path_error(message)),
+                    }
+                }
+            }
+        },
+        syn::parse_quote! {
+            impl crate::io__fs::FS for FS {
+                fn __gors_as_any(&self) -> Option<&dyn std::any::Any> {
+                    Some(self)
+                }
+
+                fn __gors_interface_key(&self) -> crate::builtin::GorsInterfaceKey {
+                    crate::builtin::GorsInterfaceKey::non_comparable()
+                }
+
+                fn __gors_clone_box(&self) -> Box<dyn crate::io__fs::FS> {
+                    Box::new(*self) as Box<dyn crate::io__fs::FS>
+                }
+
+                fn Open(
+                    &mut self,
+                    name: String,
+                ) -> (Box<dyn crate::io__fs::File>, Box<dyn crate::builtin::error>) {
+                    FS::Open(self, name)
+                }
+            }
+        },
+        syn::parse_quote! {
+            fn path_error(message: String) -> Box<dyn crate::builtin::error> {
+                Box::new(crate::builtin::__GorsStringError(message)) as Box<dyn crate::builtin::error>
+            }
+        },
+    ]
+}
+
+/// `file` is both the `fs.DirEntry` and the `fs.FileInfo` of an embedded
+/// file or directory; `openFile` is the `fs.File` returned by `Open`, nil
+/// when it has no entry.
+fn file_items() -> Vec<syn::Item> {
+    vec![
+        syn::parse_quote! {
+            #[derive(Clone, Copy, Default)]
+            struct file {
+                entry: crate::builtin::GorsEmbedEntry,
+            }
+        },
+        syn::parse_quote! {
+            impl file {
+                fn mode(&self) -> crate::io__fs::FileMode {
+                    if self.entry.is_dir() {
+                        crate::io__fs::FileMode((1 << 31) | 0o555)
+                    } else {
+                        crate::io__fs::FileMode(0o444)
+                    }
+                }
+            }
+        },
+        syn::parse_quote! {
+            impl crate::io__fs::DirEntry for file {
+                fn __gors_as_any(&self) -> Option<&dyn std::any::Any> {
+                    Some(self)
+                }
+
+                fn __gors_interface_key(&self) -> crate::builtin::GorsInterfaceKey {
+                    crate::builtin::GorsInterfaceKey::non_comparable()
+                }
+
+                fn __gors_clone_box(&self) -> Box<dyn crate::io__fs::DirEntry> {
+                    Box::new(*self) as Box<dyn crate::io__fs::DirEntry>
+                }
+
+                fn Name(&mut self) -> String {
+                    self.entry.name().to_string()
+                }
+
+                fn IsDir(&mut self) -> bool {
+                    self.entry.is_dir()
+                }
+
+                fn Type(&mut self) -> crate::io__fs::FileMode {
+                    crate::io__fs::FileMode(self.mode().0 & (1 << 31))
+                }
+
+                fn Info(
+                    &mut self,
+                ) -> (Box<dyn crate::io__fs::FileInfo>, Box<dyn crate::builtin::error>) {
+                    (
+                        Box::new(*self) as Box<dyn crate::io__fs::FileInfo>,
+                        Box::new(crate::builtin::__GorsNooperror::default())
+                            as Box<dyn crate::builtin::error>,
+                    )
+                }
+            }
+        },
+        syn::parse_quote! {
+            impl crate::io__fs::FileInfo for file {
+                fn __gors_as_any(&self) -> Option<&dyn std::any::Any> {
+                    Some(self)
+                }
+
+                fn __gors_interface_key(&self) -> crate::builtin::GorsInterfaceKey {
+                    crate::builtin::GorsInterfaceKey::non_comparable()
+                }
+
+                fn __gors_clone_box(&self) -> Box<dyn crate::io__fs::FileInfo> {
+                    Box::new(*self) as Box<dyn crate::io__fs::FileInfo>
+                }
+
+                fn Name(&mut self) -> String {
+                    self.entry.name().to_string()
+                }
+
+                fn Size(&mut self) -> i64 {
+                    self.entry.data().len() as i64
+                }
+
+                fn Mode(&mut self) -> crate::io__fs::FileMode {
+                    self.mode()
+                }
+
+                fn ModTime(&mut self) -> crate::time::Time {
+                    Default::default()
+                }
+
+                fn IsDir(&mut self) -> bool {
+                    self.entry.is_dir()
+                }
+
+                fn Sys(&mut self) -> crate::builtin::GorsAny {
+                    crate::builtin::GorsAny::Nil
+                }
+            }
+        },
+        syn::parse_quote! {
+            #[derive(Clone, Copy, Default)]
+            struct openFile {
+                entry: Option<crate::builtin::GorsEmbedEntry>,
+                offset: usize,
+            }
+        },
+        syn::parse_quote! {
+            impl crate::io__fs::File for openFile {
+                fn __gors_as_any(&self) -> Option<&dyn std::any::Any> {
+                    self.entry.is_some().then_some(self as &dyn std::any::Any)
+                }
+
+                fn __gors_interface_key(&self) -> crate::builtin::GorsInterfaceKey {
+                    if self.entry.is_some() {
+                        crate::builtin::GorsInterfaceKey::non_comparable()
+                    } else {
+                        crate::builtin::GorsInterfaceKey::nil()
+                    }
+                }
+
+                fn __gors_clone_box(&self) -> Box<dyn crate::io__fs::File> {
+                    Box::new(*self) as Box<dyn crate::io__fs::File>
+                }
+
+                fn Stat(
+                    &mut self,
+                ) -> (Box<dyn crate::io__fs::FileInfo>, Box<dyn crate::builtin::error>) {
+                    (
+                        Box::new(file {
+                            entry: self.entry.unwrap_or_default(),
+                        }) as Box<dyn crate::io__fs::FileInfo>,
+                        Box::new(crate::builtin::__GorsNooperror::default())
+                            as Box<dyn crate::builtin::error>,
+                    )
+                }
+
+                fn Read(&mut self, b: &mut [u8]) -> (isize, Box<dyn crate::builtin::error>) {
+                    let entry = self.entry.unwrap_or_default();
+                    if entry.is_dir() {
+                        return (
+                            0,
+                            path_error(format!("read {}: is a directory", entry.path())),
+                        );
+                    }
+                    let n = entry.read_at(&mut self.offset, b);
+                    if n == 0 && !b.is_empty() {
+                        return (0, path_error("EOF".to_string()));
+                    }
+                    (
+                        n as isize,
+                        Box::new(crate::builtin::__GorsNooperror::default())
+                            as Box<dyn crate::builtin::error>,
+                    )
+                }
+
+                fn Close(&mut self) -> Box<dyn crate::builtin::error> {
+                    Box::new(crate::builtin::__GorsNooperror::default())
+                        as Box<dyn crate::builtin::error>
+                }
+            }
+        },
+    ]
+}
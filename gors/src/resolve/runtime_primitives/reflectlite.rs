@@ -49,7 +49,7 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
         items.extend([
             syn::parse_quote! {
                 pub struct Value {
-                    value: Box<dyn std::any::Any>,
+                    value: crate::builtin::GorsAny,
                 }
             },
             syn::parse_quote! {
@@ -65,7 +65,7 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
                 impl Default for Value {
                     fn default() -> Self {
                         Self {
-                            value: Box::new(()) as Box<dyn std::any::Any>,
+                            value: crate::builtin::GorsAny::Nil,
                         }
                     }
                 }
@@ -82,7 +82,7 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
                 }
             },
             syn::parse_quote! {
-                pub fn ValueOf(i: Box<dyn std::any::Any>) -> Value {
+                pub fn ValueOf(i: crate::builtin::GorsAny) -> Value {
                     Value { value: i }
                 }
             },
@@ -129,7 +129,7 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
                 }
             },
             syn::parse_quote! {
-                pub fn TypeOf(i: Box<dyn std::any::Any>) -> Type {
+                pub fn TypeOf(i: crate::builtin::GorsAny) -> Type {
                     Type {
                         value: crate::builtin::clone_any_send_sync(i.as_ref()),
                     }
@@ -140,7 +140,7 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
     if needs_swapper {
         items.push(syn::parse_quote! {
             pub fn Swapper(
-                slice: Box<dyn std::any::Any>,
+                slice: crate::builtin::GorsAny,
             ) -> std::sync::Arc<
                 std::sync::Mutex<
                     Option<std::sync::Arc<dyn Fn(isize, isize) -> () + Send + Sync>>
@@ -1,14 +1,26 @@
 use std::collections::HashSet;
 
+use crate::compiler::typeinfer::{GoType, TypeEnv};
+
 pub(super) const IMPORT_PATH: &str = "runtime";
+const CALLER_FUNC: &str = "Caller";
 const CALLERS_FUNC: &str = "Callers";
 const CALLERS_FRAMES_FUNC: &str = "CallersFrames";
 const FRAME_TYPE: &str = "Frame";
 const FRAMES_TYPE: &str = "Frames";
+const GC_FUNC: &str = "GC";
+const GOEXIT_FUNC: &str = "Goexit";
 const GOMAXPROCS_FUNC: &str = "GOMAXPROCS";
 const GOARCH_FUNC: &str = "GOARCH";
 const GOROOT_FUNC: &str = "GOROOT";
 const GOOS_FUNC: &str = "GOOS";
+const GOSCHED_FUNC: &str = "Gosched";
+const KEEP_ALIVE_FUNC: &str = "KeepAlive";
+const MEM_STATS_TYPE: &str = "MemStats";
+const NUM_GOROUTINE_FUNC: &str = "NumGoroutine";
+const READ_MEM_STATS_FUNC: &str = "ReadMemStats";
+const SET_FINALIZER_FUNC: &str = "SetFinalizer";
+const STACK_FUNC: &str = "Stack";
 const STRINGER_TRAIT: &str = "stringer";
 
 pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Option<syn::ItemMod> {
@@ -18,10 +30,25 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
     }
 
     let mut items = Vec::new();
+    if roots.contains(CALLER_FUNC) {
+        items.push(syn::parse_quote! {
+            #[track_caller]
+            pub fn Caller(mut skip: isize) -> (usize, String, isize, bool) {
+                crate::builtin::caller(skip)
+            }
+        });
+    }
     if roots.contains(CALLERS_FUNC) {
         items.push(syn::parse_quote! {
             pub fn Callers(mut skip: isize, mut pc: &mut [usize]) -> isize {
-                0
+                crate::builtin::callers(skip, pc)
+            }
+        });
+    }
+    if roots.contains(STACK_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn Stack(mut buf: &mut [u8], mut all: bool) -> isize {
+                crate::builtin::goroutine_stack(buf, all)
             }
         });
     }
@@ -31,17 +58,58 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
     if roots.contains(GOMAXPROCS_FUNC) {
         items.push(syn::parse_quote! {
             pub fn GOMAXPROCS(mut n: isize) -> isize {
-                let current = std::thread::available_parallelism()
-                    .map(|parallelism| parallelism.get() as isize)
-                    .unwrap_or(1)
-                    .max(1);
-                if n < 1 {
-                    return current;
-                }
-                current
+                crate::builtin::gomaxprocs(n)
+            }
+        });
+    }
+    if roots.contains(NUM_GOROUTINE_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn NumGoroutine() -> isize {
+                crate::builtin::num_goroutine()
+            }
+        });
+    }
+    if roots.contains(GOSCHED_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn Gosched() {
+                crate::builtin::gosched()
+            }
+        });
+    }
+    if roots.contains(GOEXIT_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn Goexit() {
+                crate::builtin::goexit()
+            }
+        });
+    }
+    if roots.contains(GC_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn GC() {
+                crate::builtin::gc()
+            }
+        });
+    }
+    if roots.contains(KEEP_ALIVE_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn KeepAlive<T>(mut x: T) {
+                crate::builtin::keep_alive(x)
+            }
+        });
+    }
+    if roots.contains(SET_FINALIZER_FUNC) {
+        items.push(syn::parse_quote! {
+            pub fn SetFinalizer<T: Send + 'static>(
+                mut obj: crate::builtin::GorsPtr<T>,
+                mut finalizer: impl crate::builtin::FinalizerFunc<T>,
+            ) {
+                crate::builtin::set_finalizer(&obj, finalizer)
             }
         });
     }
+    if needs_mem_stats(roots) {
+        items.extend(mem_stats_items());
+    }
     if roots.contains(GOARCH_FUNC) {
         items.push(syn::parse_quote! {
             pub fn GOARCH() -> String {
@@ -79,6 +147,14 @@ pub(super) fn module(import_path: &str, roots: Option<&HashSet<String>>) -> Opti
     (!items.is_empty()).then(|| super::super::item_mod_for(import_path, items))
 }
 
+/// `SetFinalizer` takes its arguments as `any` in Go, but the primitive
+/// needs the pointer and the function as they are: leaving them untyped
+/// keeps call sites from boxing them into interface values.
+pub(super) fn supplement_type_env(env: &mut TypeEnv) {
+    env.set_func(SET_FINALIZER_FUNC, Vec::new());
+    env.set_func_params(SET_FINALIZER_FUNC, vec![GoType::Unknown, GoType::Unknown]);
+}
+
 fn needs_frames(roots: &HashSet<String>) -> bool {
     roots.contains(CALLERS_FRAMES_FUNC)
         || roots.contains(FRAME_TYPE)
@@ -88,6 +164,95 @@ fn needs_frames(roots: &HashSet<String>) -> bool {
             .any(|root| root == "Frames::Next" || root == "Frame::clone" || root == "Frames::clone")
 }
 
+fn needs_mem_stats(roots: &HashSet<String>) -> bool {
+    roots.contains(MEM_STATS_TYPE)
+        || roots.contains(READ_MEM_STATS_FUNC)
+        || roots.iter().any(|root| root == "MemStats::clone")
+}
+
+/// `MemStats` and `ReadMemStats`, along with the global allocator counting
+/// the allocations of the program for them.
+fn mem_stats_items() -> Vec<syn::Item> {
+    vec![
+        syn::parse_quote! {
+            #[derive(Clone, Default)]
+            pub struct MemStats {
+                pub Alloc: u64,
+                pub TotalAlloc: u64,
+                pub Sys: u64,
+                pub Mallocs: u64,
+                pub Frees: u64,
+                pub HeapAlloc: u64,
+                pub HeapSys: u64,
+                pub HeapInuse: u64,
+                pub HeapObjects: u64,
+                pub NumGC: u32,
+            }
+        },
+        syn::parse_quote! {
+            pub fn ReadMemStats(mut m: crate::builtin::GorsPtr<MemStats>) {
+                let stats = crate::builtin::read_mem_stats();
+                *m.lock().unwrap() = MemStats {
+                    Alloc: stats.heap_alloc,
+                    TotalAlloc: stats.total_alloc,
+                    Mallocs: stats.mallocs,
+                    Frees: stats.frees,
+                    HeapAlloc: stats.heap_alloc,
+                    HeapInuse: stats.heap_alloc,
+                    HeapObjects: stats.mallocs.saturating_sub(stats.frees),
+                    NumGC: stats.num_gc,
+                    ..MemStats::default()
+                };
+            }
+        },
+        syn::parse_quote! {
+            struct CountingAllocator;
+        },
+        syn::parse_quote! {
+            #[allow(unsafe_code)]
+            unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+                unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+                    let ptr = unsafe { std::alloc::System.alloc(layout) };
+                    if !ptr.is_null() {
+                        crate::builtin::record_alloc(layout.size());
+                    }
+                    ptr
+                }
+
+                unsafe fn alloc_zeroed(&self, layout: std::alloc::Layout) -> *mut u8 {
+                    let ptr = unsafe { std::alloc::System.alloc_zeroed(layout) };
+                    if !ptr.is_null() {
+                        crate::builtin::record_alloc(layout.size());
+                    }
+                    ptr
+                }
+
+                unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+                    unsafe { std::alloc::System.dealloc(ptr, layout) };
+                    crate::builtin::record_free(layout.size());
+                }
+
+                unsafe fn realloc(
+                    &self,
+                    ptr: *mut u8,
+                    layout: std::alloc::Layout,
+                    new_size: usize,
+                ) -> *mut u8 {
+                    let new_ptr = unsafe { std::alloc::System.realloc(ptr, layout, new_size) };
+                    if !new_ptr.is_null() {
+                        crate::builtin::record_realloc(layout.size(), new_size);
+                    }
+                    new_ptr
+                }
+            }
+        },
+        syn::parse_quote! {
+            #[global_allocator]
+            static ALLOCATOR: CountingAllocator = CountingAllocator;
+        },
+    ]
+}
+
 fn frames_items() -> Vec<syn::Item> {
     vec![
         syn::parse_quote! {
@@ -117,10 +282,20 @@ fn frames_items() -> Vec<syn::Item> {
         },
         syn::parse_quote! {
             pub fn CallersFrames(mut callers: Vec<usize>) -> crate::builtin::GorsPtr<Frames> {
-                crate::builtin::GorsPtr::new(Frames {
-                    frames: Vec::new(),
-                    next: 0,
-                })
+                let frames = callers
+                    .iter()
+                    .filter_map(|&pc| {
+                        let (function, file, line) = crate::builtin::caller_frame(pc)?;
+                        Some(Frame {
+                            PC: pc,
+                            Function: function,
+                            File: file,
+                            Line: line,
+                            ..Frame::default()
+                        })
+                    })
+                    .collect();
+                crate::builtin::GorsPtr::new(Frames { frames, next: 0 })
             }
         },
         syn::parse_quote! {
@@ -241,7 +241,7 @@ fn run_generated_rust_program(
         }
     };
 
-    let Some(go_stdout) = finish_go_reference_stdout(go_run, abort, metrics, &name) else {
+    let Some(go_out) = finish_go_reference_stdout(go_run, abort, metrics, &name) else {
         return ProgramRunResult {
             name,
             passed: false,
@@ -251,21 +251,42 @@ fn run_generated_rust_program(
     };
 
     let rust_stdout = String::from_utf8_lossy(&rust_out.stdout);
-    let result = if rust_out.status.success() && rust_stdout == go_stdout.as_str() {
+    let rust_stderr = String::from_utf8_lossy(&rust_out.stderr);
+    let crashed_like_go =
+        go_out.exit_code == 0 || crash_report(&rust_stderr) == crash_report(&go_out.stderr);
+    let result = if rust_out.status.code() == Some(go_out.exit_code)
+        && rust_stdout == go_out.stdout.as_str()
+        && crashed_like_go
+    {
         ProgramRunResult {
             name,
             passed: true,
             skipped: false,
             error: None,
         }
-    } else if rust_out.status.success() {
+    } else if rust_out.status.success() && go_out.exit_code == 0 {
         ProgramRunResult {
             name,
             passed: false,
             skipped: false,
             error: Some(format!(
                 "Output mismatch:\nExpected: {:?}\nGot: {:?}",
-                go_stdout, rust_stdout
+                go_out.stdout, rust_stdout
+            )),
+        }
+    } else if go_out.exit_code != 0 {
+        ProgramRunResult {
+            name,
+            passed: false,
+            skipped: false,
+            error: Some(format!(
+                "Crash mismatch:\nExpected: exit status {}, {:?}, stdout {:?}\nGot: {}, {:?}, stdout {:?}",
+                go_out.exit_code,
+                crash_report(&go_out.stderr),
+                go_out.stdout,
+                rust_out.status,
+                crash_report(&rust_stderr),
+                rust_stdout
             )),
         }
     } else {
@@ -300,16 +321,37 @@ fn run_generated_rust_program(
 
 fn spawn_go_program(dir: &Path, abort: &AtomicBool) -> Result<Option<RunningCommand>, String> {
     let mut go_cmd = go_command();
-    go_cmd.args(["run", "."]).current_dir(dir);
+    go_cmd.arg("run");
+    if detects_races(dir) {
+        go_cmd.arg("-race");
+    }
+    go_cmd.arg(".").current_dir(dir);
     spawn_command_abortable(go_cmd, abort)
 }
 
+/// Programs named `race_*` are built with the race detector, like
+/// `go run -race`.
+fn detects_races(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("race_"))
+}
+
+/// What the Go program printed, and how it exited.
+struct GoReference {
+    stdout: String,
+    /// The standard error of the program, without the line `go run` adds
+    /// when it fails.
+    stderr: String,
+    exit_code: i32,
+}
+
 fn finish_go_reference_stdout(
     go_run: Option<RunningCommand>,
     abort: &AtomicBool,
     metrics: &RunMetrics,
     name: &str,
-) -> Option<String> {
+) -> Option<GoReference> {
     let Some(go_run) = go_run else {
         eprintln!("Skipping {name} - go run failed");
         return None;
@@ -317,14 +359,61 @@ fn finish_go_reference_stdout(
     let before = go_run.started;
     let output = wait_command_output_abortable(go_run, abort, Some(go_run_timeout()));
     RunMetrics::add_duration(&metrics.go, before.elapsed());
-    match output {
-        Ok(Some(o)) if o.status.success() => Some(String::from_utf8_lossy(&o.stdout).to_string()),
-        Ok(None) => None,
-        _ => {
+    let output = match output {
+        Ok(Some(o)) => o,
+        Ok(None) => return None,
+        Err(_) => {
             eprintln!("Skipping {name} - go run failed");
-            None
+            return None;
         }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status.success() {
+        return Some(GoReference {
+            stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: 0,
+        });
+    }
+    let Some((stderr, exit_code)) = program_exit_code(&output.stderr) else {
+        eprintln!("Skipping {name} - go run failed");
+        return None;
+    };
+    Some(GoReference {
+        stdout,
+        stderr: stderr.to_string(),
+        exit_code,
+    })
+}
+
+/// Split the `exit status N` line `go run` ends its standard error with
+/// when the program it built fails, from what the program printed. A
+/// program that did not build has no such line.
+fn program_exit_code(stderr: &[u8]) -> Option<(&str, i32)> {
+    let stderr = std::str::from_utf8(stderr).ok()?;
+    let stderr = stderr.trim_end_matches('\n');
+    let (program, last) = stderr.rsplit_once('\n').unwrap_or(("", stderr));
+    let code = last.strip_prefix("exit status ")?.parse().ok()?;
+    Some((program, code))
+}
+
+/// The line a crashing program reports its error with: the first one of its
+/// standard error, past the separators of race reports. Addresses differ
+/// from run to run, so they are masked.
+fn crash_report(stderr: &str) -> String {
+    let line = stderr
+        .lines()
+        .find(|line| !line.is_empty() && !line.bytes().all(|byte| byte == b'='))
+        .unwrap_or("");
+    let mut masked = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("0x") {
+        masked.push_str(&rest[..start + 2]);
+        rest = rest[start + 2..].trim_start_matches(|ch: char| ch.is_ascii_hexdigit());
+        masked.push('?');
     }
+    masked.push_str(rest);
+    masked
 }
 
 fn compile_and_run_generated_rust(
@@ -340,8 +429,12 @@ fn compile_and_run_generated_rust(
     RunMetrics::add_duration(&metrics.parse, before.elapsed());
 
     let before = Instant::now();
-    let compiled = gors::compiler::compile_program_multi(program)
-        .map_err(|e| format!("compile failed: {e}"))?;
+    let compiled = if detects_races(dir) {
+        gors::compiler::compile_program_multi_with_race_detection(program)
+    } else {
+        gors::compiler::compile_program_multi(program)
+    }
+    .map_err(|e| format!("compile failed: {e}"))?;
     RunMetrics::add_duration(&metrics.compile, before.elapsed());
 
     let before = Instant::now();
@@ -0,0 +1 @@
+module channel_close_closed_panic
@@ -0,0 +1,38 @@
+package main
+
+import "fmt"
+
+func message(r any) string {
+	switch r := r.(type) {
+	case error:
+		return r.Error()
+	case string:
+		return r
+	default:
+		return "unexpected panic"
+	}
+}
+
+func closeTwice(ch chan int) {
+	defer func() {
+		if r := recover(); r != nil {
+			fmt.Println("recovered:", message(r))
+		}
+	}()
+	close(ch)
+	fmt.Println("closed once")
+	close(ch)
+	fmt.Println("closed twice")
+}
+
+func main() {
+	unbuffered := make(chan int)
+	closeTwice(unbuffered)
+
+	buffered := make(chan int, 2)
+	buffered <- 1
+	closeTwice(buffered)
+	for v := range buffered {
+		fmt.Println("received", v)
+	}
+}
@@ -0,0 +1 @@
+module channel_close_nil_panic
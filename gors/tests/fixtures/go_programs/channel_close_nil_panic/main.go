@@ -0,0 +1,33 @@
+package main
+
+import "fmt"
+
+func message(r any) string {
+	switch r := r.(type) {
+	case error:
+		return r.Error()
+	case string:
+		return r
+	default:
+		return "unexpected panic"
+	}
+}
+
+func closeChan(ch chan string) {
+	defer func() {
+		if r := recover(); r != nil {
+			fmt.Println("recovered:", message(r))
+		}
+	}()
+	close(ch)
+	fmt.Println("closed")
+}
+
+func main() {
+	var ch chan string
+	closeChan(ch)
+	fmt.Println("nil:", ch == nil)
+
+	ch = make(chan string)
+	closeChan(ch)
+}
@@ -0,0 +1,3 @@
+module channel_deadlock
+
+go 1.21
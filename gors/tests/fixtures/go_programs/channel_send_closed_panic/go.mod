@@ -0,0 +1 @@
+module channel_send_closed_panic
@@ -0,0 +1,81 @@
+package main
+
+import "fmt"
+
+func message(r any) string {
+	switch r := r.(type) {
+	case error:
+		return r.Error()
+	case string:
+		return r
+	default:
+		return "unexpected panic"
+	}
+}
+
+func try(name string, f func()) {
+	fmt.Println(name)
+	defer func() {
+		if r := recover(); r != nil {
+			fmt.Println("recovered:", message(r))
+		}
+	}()
+	f()
+	fmt.Println("did not panic")
+}
+
+func main() {
+	try("buffered", func() {
+		ch := make(chan int, 1)
+		close(ch)
+		ch <- 1
+	})
+
+	try("unbuffered", func() {
+		ch := make(chan int)
+		close(ch)
+		ch <- 1
+	})
+
+	try("blocked", func() {
+		ch := make(chan int)
+		ready := make(chan bool)
+		go func() {
+			<-ready
+			close(ch)
+		}()
+		ready <- true
+		ch <- 1
+	})
+
+	try("select", func() {
+		ch := make(chan int)
+		close(ch)
+		select {
+		case ch <- 1:
+			fmt.Println("sent")
+		default:
+			fmt.Println("default")
+		}
+	})
+
+	try("blocked select", func() {
+		ch := make(chan int)
+		never := make(chan int)
+		go close(ch)
+		select {
+		case ch <- 1:
+			fmt.Println("sent")
+		case v := <-never:
+			fmt.Println("received", v)
+		}
+	})
+
+	ch := make(chan int, 1)
+	ch <- 7
+	close(ch)
+	v, ok := <-ch
+	fmt.Println("drained", v, ok)
+	v, ok = <-ch
+	fmt.Println("drained", v, ok)
+}
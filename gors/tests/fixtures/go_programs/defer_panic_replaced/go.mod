@@ -0,0 +1 @@
+module defer_panic_replaced
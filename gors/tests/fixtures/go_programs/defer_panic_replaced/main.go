@@ -0,0 +1,99 @@
+package main
+
+import "fmt"
+
+type log struct {
+	events []string
+}
+
+func (l *log) note(event string) {
+	l.events = append(l.events, event)
+}
+
+// recovered is deferred directly, so its call to recover returns the panic
+// of the function which deferred it.
+func (l *log) recovered(who string) {
+	r := recover()
+	if s, ok := r.(string); ok {
+		l.note(who + " recovered " + s)
+	} else if r == nil {
+		l.note(who + " recovered nil")
+	} else {
+		l.note(who + " recovered unexpected value")
+	}
+}
+
+func (l *log) panicWith(value string) {
+	panic(value)
+}
+
+// replaced panics in a deferred call while already panicking: the later
+// panic replaces the first one, and the remaining deferred calls still run.
+func replaced(l *log) {
+	defer l.recovered("replaced")
+	defer l.note("still deferred")
+	defer l.panicWith("second")
+	panic("first")
+}
+
+// panicsOnReturn returns normally, but one of its deferred calls panics.
+func panicsOnReturn(l *log) {
+	defer l.recovered("panicsOnReturn")
+	defer l.panicWith("deferred")
+	l.note("returning")
+}
+
+func handleOwnPanic(l *log) {
+	defer l.recovered("handleOwnPanic")
+	panic("helper")
+}
+
+// nestedRecover defers a helper which recovers its own panic, without
+// taking the panic of its caller.
+func nestedRecover(l *log) {
+	defer l.recovered("nestedRecover")
+	defer handleOwnPanic(l)
+	panic("outer")
+}
+
+func recoverTwice(l *log) {
+	defer l.recovered("second")
+	defer l.recovered("first")
+	panic("once")
+}
+
+func notPanicking(l *log) {
+	defer l.recovered("notPanicking")
+	l.note("returning")
+}
+
+func report(name string, l *log) {
+	fmt.Println(name)
+	for _, event := range l.events {
+		fmt.Println("  " + event)
+	}
+}
+
+func inGoroutine(name string, f func(*log)) {
+	l := &log{}
+	done := make(chan bool)
+	go func() {
+		f(l)
+		done <- true
+	}()
+	<-done
+	report(name, l)
+}
+
+func main() {
+	inGoroutine("replaced", replaced)
+	inGoroutine("panics on return", panicsOnReturn)
+	inGoroutine("nested recover", nestedRecover)
+	inGoroutine("recover twice", recoverTwice)
+	inGoroutine("not panicking", notPanicking)
+
+	l := &log{}
+	replaced(l)
+	nestedRecover(l)
+	report("main goroutine", l)
+}
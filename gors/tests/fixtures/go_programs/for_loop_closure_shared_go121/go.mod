@@ -0,0 +1,3 @@
+module for_loop_closure_shared_go121
+
+go 1.21
@@ -0,0 +1,32 @@
+package main
+
+import "fmt"
+
+func main() {
+	var prints []func()
+	for i := 0; i < 3; i++ {
+		prints = append(prints, func() { fmt.Println("closure", i) })
+	}
+	for _, print := range prints {
+		print()
+	}
+
+	var ptrs []*int
+	for j := 0; j < 3; j++ {
+		ptrs = append(ptrs, &j)
+	}
+	for _, p := range ptrs {
+		fmt.Println("pointer", *p)
+	}
+
+	done := make(chan int, 3)
+	for k := 0; k < 3; k++ {
+		func() {
+			done <- k
+		}()
+	}
+	close(done)
+	for v := range done {
+		fmt.Println("immediate", v)
+	}
+}
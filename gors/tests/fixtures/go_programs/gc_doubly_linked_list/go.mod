@@ -0,0 +1,3 @@
+module gc_doubly_linked_list
+
+go 1.21
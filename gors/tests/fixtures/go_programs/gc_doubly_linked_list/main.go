@@ -0,0 +1,74 @@
+package main
+
+import (
+	"fmt"
+	"runtime"
+)
+
+type Node struct {
+	Prev, Next *Node
+	Value      int
+	Payload    [16]int
+}
+
+type List struct {
+	Head, Tail *Node
+	Len        int
+}
+
+func (l *List) PushBack(v int) {
+	n := &Node{Prev: l.Tail, Value: v}
+	if l.Tail == nil {
+		l.Head = n
+	} else {
+		l.Tail.Next = n
+	}
+	l.Tail = n
+	l.Len++
+}
+
+func (l *List) Sum() int {
+	total := 0
+	for n := l.Head; n != nil; n = n.Next {
+		total += n.Value
+	}
+	return total
+}
+
+func build(n int) int {
+	l := &List{}
+	for i := 0; i < n; i++ {
+		l.PushBack(i)
+	}
+	return l.Sum()
+}
+
+func main() {
+	done := make(chan bool)
+	spins := make(chan int)
+	go func() {
+		count := 0
+		for {
+			select {
+			case <-done:
+				spins <- count
+				return
+			default:
+				count++
+			}
+		}
+	}()
+
+	total := 0
+	for round := 0; round < 40; round++ {
+		total += build(10000)
+	}
+	done <- true
+	<-spins
+
+	var m runtime.MemStats
+	runtime.ReadMemStats(&m)
+	fmt.Println("total:", total)
+	fmt.Println("collected:", m.NumGC > 0)
+	fmt.Println("bounded:", m.HeapAlloc < 32<<20)
+}
@@ -0,0 +1 @@
+module goroutine_recover_stress
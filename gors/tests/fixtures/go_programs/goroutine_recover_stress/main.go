@@ -0,0 +1,59 @@
+package main
+
+import "fmt"
+
+type result struct {
+	id    int
+	value int
+}
+
+// work panics, then waits in a deferred call until every goroutine has
+// panicked before recovering, so that all the panics are in flight at once.
+func work(id int, arrived chan<- bool, release <-chan bool, results chan<- result) {
+	defer func() {
+		arrived <- true
+		<-release
+		value, _ := recover().(int)
+		results <- result{id, value}
+	}()
+	defer func() {
+		if id%2 == 0 {
+			panic(id * 1000)
+		}
+	}()
+	panic(id)
+}
+
+func main() {
+	const n = 64
+	for round := 0; round < 4; round++ {
+		arrived := make(chan bool)
+		release := make(chan bool)
+		results := make(chan result, n)
+		for id := 1; id <= n; id++ {
+			go work(id, arrived, release, results)
+		}
+		for i := 0; i < n; i++ {
+			<-arrived
+		}
+		close(release)
+
+		got := make([]int, n+1)
+		for i := 0; i < n; i++ {
+			r := <-results
+			got[r.id] = r.value
+		}
+		mismatches := 0
+		for id := 1; id <= n; id++ {
+			want := id
+			if id%2 == 0 {
+				want = id * 1000
+			}
+			if got[id] != want {
+				mismatches++
+				fmt.Println("goroutine", id, "recovered", got[id], "want", want)
+			}
+		}
+		fmt.Println("round", round, "goroutines", n, "mismatches", mismatches)
+	}
+}
@@ -0,0 +1 @@
+module nil_map
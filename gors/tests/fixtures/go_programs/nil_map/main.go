@@ -0,0 +1,89 @@
+package main
+
+import "fmt"
+
+type Counts map[string]int
+
+type index struct {
+	byName map[string]int
+}
+
+func report() {
+	if r := recover(); r != nil {
+		fmt.Println("  recovered:", r.(error).Error())
+	}
+}
+
+func describe(name string, m map[string]int) {
+	fmt.Println(name, len(m), m == nil, m != nil, m["missing"])
+}
+
+func store() {
+	defer report()
+	var m map[string]int
+	m["a"] = 1
+	fmt.Println("  unreachable", len(m))
+}
+
+func increment() {
+	defer report()
+	var m map[string]int
+	m["a"]++
+	fmt.Println("  unreachable", m["a"])
+}
+
+func namedStore() {
+	defer report()
+	var counts Counts
+	fmt.Println("named", len(counts), counts == nil)
+	counts["x"] = 1
+	fmt.Println("  unreachable", len(counts))
+}
+
+func fieldStore() {
+	defer report()
+	var idx index
+	fmt.Println("field", len(idx.byName), idx.byName == nil)
+	idx.byName["x"] = 1
+	fmt.Println("  unreachable", len(idx.byName))
+}
+
+func main() {
+	var m map[string]int
+	describe("declared", m)
+	v, ok := m["a"]
+	fmt.Println("comma ok", v, ok)
+	for k, v := range m {
+		fmt.Println("unreachable", k, v)
+	}
+	delete(m, "a")
+	clear(m)
+	describe("after delete and clear", m)
+
+	fmt.Println("store")
+	store()
+	fmt.Println("increment")
+	increment()
+	namedStore()
+	fieldStore()
+
+	m = make(map[string]int)
+	describe("made", m)
+	m["a"] = 1
+	m["a"]++
+	describe("stored", m)
+	fmt.Println("value", m["a"])
+	m = nil
+	describe("reset", m)
+
+	literal := map[string]int{}
+	describe("literal", literal)
+
+	counts := Counts{"x": 1}
+	counts["y"] = 2
+	fmt.Println("named made", len(counts), counts == nil)
+
+	idx := index{byName: map[string]int{}}
+	idx.byName["x"] = 1
+	fmt.Println("field made", len(idx.byName), idx.byName == nil)
+}
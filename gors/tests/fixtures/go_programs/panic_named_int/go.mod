@@ -0,0 +1,3 @@
+module panic_named_int
+
+go 1.21
@@ -0,0 +1,18 @@
+package main
+
+import "fmt"
+
+type Code int
+
+func run(step int) {
+	if step == 3 {
+		panic(Code(7))
+	}
+	fmt.Println("step", step)
+}
+
+func main() {
+	for step := 1; step <= 5; step++ {
+		run(step)
+	}
+}
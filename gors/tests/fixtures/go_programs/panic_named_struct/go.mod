@@ -0,0 +1,3 @@
+module panic_named_struct
+
+go 1.21
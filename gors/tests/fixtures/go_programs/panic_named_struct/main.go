@@ -0,0 +1,19 @@
+package main
+
+import "fmt"
+
+type Pair struct {
+	A, B int
+}
+
+func divide(p Pair) int {
+	if p.B == 0 {
+		panic(p)
+	}
+	return p.A / p.B
+}
+
+func main() {
+	fmt.Println(divide(Pair{A: 6, B: 3}))
+	fmt.Println(divide(Pair{A: 1, B: 0}))
+}
@@ -0,0 +1,3 @@
+module panic_stringer_pointer
+
+go 1.21
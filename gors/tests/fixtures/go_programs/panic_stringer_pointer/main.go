@@ -0,0 +1,25 @@
+package main
+
+import "fmt"
+
+type Point struct {
+	X, Y int
+}
+
+func (p *Point) String() string {
+	return fmt.Sprintf("point(%d, %d)", p.X, p.Y)
+}
+
+func move(p *Point, dx int) {
+	p.X += dx
+	if p.X > 10 {
+		panic(p)
+	}
+}
+
+func main() {
+	p := &Point{X: 1, Y: 2}
+	move(p, 4)
+	fmt.Println(p.X, p.Y)
+	move(p, 8)
+}
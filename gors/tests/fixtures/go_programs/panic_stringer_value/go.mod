@@ -0,0 +1,3 @@
+module panic_stringer_value
+
+go 1.21
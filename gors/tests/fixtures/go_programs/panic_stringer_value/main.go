@@ -0,0 +1,29 @@
+package main
+
+import "fmt"
+
+type Celsius float64
+
+func (c Celsius) String() string {
+	return fmt.Sprintf("%.1fC", float64(c))
+}
+
+func check(t Celsius) {
+	if t > 100 {
+		panic(t)
+	}
+	fmt.Println("ok:", t)
+}
+
+func main() {
+	func() {
+		defer func() {
+			if c, ok := recover().(Celsius); ok {
+				fmt.Println("recovered:", float64(c))
+			}
+		}()
+		check(120)
+	}()
+	check(21.5)
+	check(150)
+}
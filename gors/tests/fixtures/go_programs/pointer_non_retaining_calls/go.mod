@@ -0,0 +1,3 @@
+module pointer_non_retaining_calls
+
+go 1.21
@@ -0,0 +1,68 @@
+package main
+
+import "fmt"
+
+type Stack struct {
+	items []int
+}
+
+func (s *Stack) Push(v int) { s.items = append(s.items, v) }
+
+func (s *Stack) Pop() int {
+	v := s.items[len(s.items)-1]
+	s.items = s.items[:len(s.items)-1]
+	return v
+}
+
+func (s Stack) Len() int { return len(s.items) }
+
+type Counter struct {
+	hits  int
+	total int
+}
+
+func (c *Counter) Add(v int) {
+	c.hits++
+	c.total += v
+}
+
+func (c Counter) Mean() int { return c.total / c.hits }
+
+type Point struct{ X, Y int }
+
+func scale(p *Point, k int) {
+	p.X *= k
+	p.Y *= k
+}
+
+func swap(a, b *int) { *a, *b = *b, *a }
+
+func addTo(p *int, v int) int {
+	*p += v
+	return *p
+}
+
+func main() {
+	var s Stack
+	s.Push(1)
+	s.Push(2)
+	top := s.Pop()
+	fmt.Println(top, s.Len())
+
+	pt := Point{1, 2}
+	scale(&pt, 3)
+	fmt.Println(pt.X, pt.Y)
+
+	var c Counter
+	c.Add(4)
+	c.Add(8)
+	fmt.Println(c.hits, c.Mean())
+
+	a, b := 1, 2
+	swap(&a, &b)
+	fmt.Println(a, b)
+
+	total := 5
+	total = addTo(&total, total)
+	fmt.Println(total)
+}
@@ -0,0 +1,3 @@
+module race_mutex_counter
+
+go 1.21
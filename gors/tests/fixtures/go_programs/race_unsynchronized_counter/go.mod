@@ -0,0 +1,3 @@
+module race_unsynchronized_counter
+
+go 1.21
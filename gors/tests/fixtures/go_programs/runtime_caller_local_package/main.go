@@ -0,0 +1,34 @@
+package main
+
+import (
+	"fmt"
+	"runtime"
+
+	"example/site"
+)
+
+func base(path string) string {
+	for i := len(path) - 1; i >= 0; i-- {
+		if path[i] == '/' {
+			return path[i+1:]
+		}
+	}
+	return path
+}
+
+func report(label string, file string, line int, ok bool) {
+	fmt.Println(label, base(file), line, ok)
+}
+
+func main() {
+	_, file, line, ok := runtime.Caller(0)
+	report("main:", file, line, ok)
+
+	file, line, ok = site.Here()
+	report("here:", file, line, ok)
+
+	file, line, ok = site.Caller()
+	report("caller:", file, line, ok)
+
+	fmt.Println("func:", site.FuncName())
+}
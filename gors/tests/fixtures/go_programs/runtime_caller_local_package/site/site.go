@@ -0,0 +1,23 @@
+package site
+
+import "runtime"
+
+// Here reports its own position.
+func Here() (string, int, bool) {
+	_, file, line, ok := runtime.Caller(0)
+	return file, line, ok
+}
+
+// Caller reports the position it was called from.
+func Caller() (string, int, bool) {
+	_, file, line, ok := runtime.Caller(1)
+	return file, line, ok
+}
+
+// FuncName reports its own name.
+func FuncName() string {
+	pc := make([]uintptr, 1)
+	runtime.Callers(1, pc)
+	frame, _ := runtime.CallersFrames(pc).Next()
+	return frame.Function
+}
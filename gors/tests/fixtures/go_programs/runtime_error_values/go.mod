@@ -0,0 +1 @@
+module runtime_error_values
@@ -0,0 +1,96 @@
+package main
+
+import "fmt"
+
+type point struct{ x int }
+
+func message(r any) string {
+	switch r := r.(type) {
+	case error:
+		return "error: " + r.Error()
+	case string:
+		return "string: " + r
+	default:
+		return "unexpected panic"
+	}
+}
+
+func check(name string, f func()) {
+	fmt.Print(name, ": ")
+	defer func() {
+		if r := recover(); r != nil {
+			fmt.Println(message(r))
+		}
+	}()
+	f()
+	fmt.Println("no panic")
+}
+
+func at(s []int, i int) int {
+	return s[i]
+}
+
+func store(s []int, i int) {
+	s[i] = 1
+}
+
+func byteAt(s string, i int) byte {
+	return s[i]
+}
+
+func window(s []int, lo, hi int) []int {
+	return s[lo:hi]
+}
+
+func prefix(s []int, hi int) []int {
+	return s[:hi]
+}
+
+func suffix(s []int, lo int) []int {
+	return s[lo:]
+}
+
+func substring(s string, lo, hi int) string {
+	return s[lo:hi]
+}
+
+func asInt(v any) int {
+	return v.(int)
+}
+
+func divide(a, b int) int {
+	return a / b
+}
+
+func remainder(a, b int) int {
+	return a % b
+}
+
+func field(p *point) int {
+	return p.x
+}
+
+func main() {
+	s := []int{1, 2, 3}
+	check("index", func() { fmt.Println(at(s, 5)) })
+	check("negative index", func() { fmt.Println(at(s, -1)) })
+	check("store", func() { store(s, 3) })
+	check("string index", func() { fmt.Println(byteAt("hello", 7)) })
+	check("slice high", func() { fmt.Println(len(prefix(s, 4))) })
+	check("slice low", func() { fmt.Println(len(suffix(s, 4))) })
+	check("slice inverted", func() { fmt.Println(len(window(s, 2, 1))) })
+	check("substring high", func() { fmt.Println(substring("hello", 1, 9)) })
+	check("substring inverted", func() { fmt.Println(substring("hello", 3, 2)) })
+	check("reslice", func() {
+		t := s
+		t = t[1:5]
+		fmt.Println(len(t))
+	})
+	check("type assertion", func() { fmt.Println(asInt("text")) })
+	check("nil type assertion", func() { fmt.Println(asInt(nil)) })
+	check("divide", func() { fmt.Println(divide(1, 0)) })
+	check("remainder", func() { fmt.Println(remainder(1, 0)) })
+	check("nil pointer", func() { fmt.Println(field(nil)) })
+	check("in range", func() { fmt.Println(at(s, 2), len(window(s, 1, 2))) })
+	check("explicit", func() { panic("boom") })
+}
@@ -0,0 +1,3 @@
+module runtime_goroutines_and_finalizers
+
+go 1.21
@@ -0,0 +1,53 @@
+package main
+
+import (
+	"fmt"
+	"runtime"
+)
+
+type resource struct {
+	id   int
+	name string
+}
+
+func open(id int, label string, finalized chan string) {
+	r := &resource{id: id, name: "file"}
+	runtime.SetFinalizer(r, func(r *resource) {
+		finalized <- fmt.Sprint(label, " ", r.name, " ", r.id)
+	})
+}
+
+func report(steps chan int, step int) {
+	steps <- step
+}
+
+func exit(steps chan int) {
+	defer report(steps, 2)
+	steps <- 1
+	runtime.Goexit()
+	steps <- 3
+}
+
+func main() {
+	fmt.Println("goroutines:", runtime.NumGoroutine())
+
+	started := make(chan bool)
+	release := make(chan bool)
+	go func() {
+		started <- true
+		<-release
+	}()
+	<-started
+	fmt.Println("goroutines:", runtime.NumGoroutine())
+	close(release)
+
+	steps := make(chan int, 2)
+	go exit(steps)
+	fmt.Println("step", <-steps)
+	fmt.Println("step", <-steps)
+
+	finalized := make(chan string)
+	open(7, "closed", finalized)
+	runtime.GC()
+	fmt.Println(<-finalized)
+}
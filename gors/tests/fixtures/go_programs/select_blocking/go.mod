@@ -0,0 +1 @@
+module select_blocking
@@ -0,0 +1,58 @@
+package main
+
+import "fmt"
+
+func producer(ch chan<- int, quit <-chan bool) {
+	for i := 0; ; i++ {
+		select {
+		case ch <- i:
+		case <-quit:
+			close(ch)
+			return
+		}
+	}
+}
+
+func main() {
+	ch := make(chan int)
+	quit := make(chan bool)
+	go producer(ch, quit)
+
+	sum := 0
+	for i := 0; i < 100; i++ {
+		select {
+		case v := <-ch:
+			sum += v
+		}
+	}
+	quit <- true
+	for range ch {
+	}
+	fmt.Println("sum", sum)
+
+	evens := make(chan int)
+	odds := make(chan int)
+	done := make(chan bool)
+	go func() {
+		for i := 0; i < 10; i++ {
+			if i%2 == 0 {
+				evens <- i
+			} else {
+				odds <- i
+			}
+		}
+		done <- true
+	}()
+	nEvens, nOdds := 0, 0
+	for running := true; running; {
+		select {
+		case v := <-evens:
+			nEvens += v
+		case v := <-odds:
+			nOdds += v
+		case <-done:
+			running = false
+		}
+	}
+	fmt.Println("evens", nEvens, "odds", nOdds)
+}
@@ -0,0 +1,3 @@
+module stack_overflow_recursion
+
+go 1.21
@@ -0,0 +1,3 @@
+module type_assertion_crash
+
+go 1.21
@@ -0,0 +1,12 @@
+package main
+
+import "fmt"
+
+func describe(v any) string {
+	return fmt.Sprint("count: ", v.(int))
+}
+
+func main() {
+	fmt.Println(describe(3))
+	fmt.Println(describe("three"))
+}
@@ -18,6 +18,10 @@ func (a *Accumulator) Add(nums ...int) {
 	}
 }
 
+type Counter struct {
+	Accumulator
+}
+
 func main() {
 	acc := Accumulator{base: 2}
 	values := []int{4, 5}
@@ -42,4 +46,12 @@ func main() {
 		zero := len("")
 		_ = 1 / zero
 	}
+
+	counter := &Counter{}
+	counter.Add(3, 4)
+	counter.Add()
+	if counter.base != 7 {
+		zero := len("")
+		_ = 1 / zero
+	}
 }
@@ -0,0 +1,3 @@
+module waitgroup_deadlock
+
+go 1.21
@@ -0,0 +1,23 @@
+package main
+
+import (
+	"fmt"
+	"sync"
+)
+
+func square(wg *sync.WaitGroup, results chan int, n int) {
+	results <- n * n
+	wg.Done()
+}
+
+func main() {
+	var wg sync.WaitGroup
+	results := make(chan int, 2)
+	wg.Add(3)
+	for i := 1; i <= 2; i++ {
+		go square(&wg, results, i)
+	}
+	fmt.Println(<-results + <-results)
+	wg.Wait()
+	fmt.Println("unreachable")
+}
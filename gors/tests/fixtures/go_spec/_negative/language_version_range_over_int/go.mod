@@ -0,0 +1,3 @@
+module go_spec_negative_language_version_range_over_int
+
+go 1.21
@@ -0,0 +1,3 @@
+module closure_calls
+
+go 1.21
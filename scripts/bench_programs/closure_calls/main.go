@@ -0,0 +1,14 @@
+package main
+
+import "fmt"
+
+func main() {
+	total := 0
+	add := func(n int) { total += n }
+	sub := func(n int) { total -= n / 2 }
+	for i := 0; i < 30000000; i++ {
+		add(i)
+		sub(i)
+	}
+	fmt.Println(total)
+}
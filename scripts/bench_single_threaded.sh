@@ -0,0 +1,69 @@
+#!/usr/bin/env bash
+# Compares the run time of Go programs compiled with and without the
+# single-threaded lowering, which applies to programs that never start a
+# goroutine. Programs the lowering leaves unchanged are skipped.
+#
+# usage: scripts/bench_single_threaded.sh [program-dir...]
+#
+# Defaults to the go_programs fixtures and scripts/bench_programs. Each
+# program is built in release mode and timed as the best of RUNS runs
+# (default 5). GORS_GO_SDK_PATH is passed through to gors, and GORS names a
+# gors binary to use instead of building one.
+set -euo pipefail
+
+root=$(cd "$(dirname "$0")/.." && pwd)
+runs=${RUNS:-5}
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+gors=${GORS:-}
+if [ -z "$gors" ]; then
+	cargo build --quiet --release --package gors-cli --manifest-path "$root/Cargo.toml"
+	gors=$root/target/release/gors
+fi
+
+if [ $# -gt 0 ]; then
+	programs=("$@")
+else
+	programs=("$root"/gors/tests/fixtures/go_programs/*/ "$root"/scripts/bench_programs/*/)
+fi
+
+# Prints the best wall time of a program over $runs runs, in milliseconds.
+best_ms() {
+	local best= start end ms
+	for _ in $(seq "$runs"); do
+		start=$(date +%s%N)
+		(cd "$2" && timeout 60 "$1" </dev/null >/dev/null 2>&1) || true
+		end=$(date +%s%N)
+		ms=$(((end - start) / 1000000))
+		if [ -z "$best" ] || [ "$ms" -lt "$best" ]; then
+			best=$ms
+		fi
+	done
+	echo "$best"
+}
+
+printf '%-40s %10s %10s %8s\n' program threaded single speedup
+for dir in "${programs[@]}"; do
+	name=$(basename "$dir")
+	built=true
+	for mode in 0 1; do
+		out=$work/$name-$mode
+		flags=()
+		if [ "$mode" = 0 ]; then
+			flags=(--thread-safe)
+		fi
+		if ! (cd "$dir" && "$gors" build "${flags[@]}" -o "$out" . >/dev/null 2>&1) ||
+			! rustc --edition 2024 -O -C overflow-checks=off "$out/main.rs" -o "$out/prog" 2>/dev/null; then
+			built=false
+			break
+		fi
+	done
+	if ! $built || diff -rq "$work/$name-0" "$work/$name-1" --exclude=prog >/dev/null; then
+		continue
+	fi
+	threaded=$(best_ms "$work/$name-0/prog" "$dir")
+	single=$(best_ms "$work/$name-1/prog" "$dir")
+	speedup=$(awk -v t="$threaded" -v s="$single" 'BEGIN { printf "%.2fx", t / (s > 0 ? s : 1) }')
+	printf '%-40s %8sms %8sms %8s\n' "$name" "$threaded" "$single" "$speedup"
+done